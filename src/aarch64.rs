@@ -0,0 +1,764 @@
+use core::{cmp, fmt};
+use std::collections::HashSet;
+
+// Reference: Arm Architecture Reference Manual for A-profile architecture - Chapter C4 (A64
+// Instruction Set Encoding)
+
+// register number 31 is either the stack pointer or the zero register depending on the instruction
+const SP: u32 = 31;
+const XZR: u32 = 31;
+
+/// Analyzes a subroutine and returns all the `BL` and `B` instructions in it, plus whether this
+/// function performs an indirect function call or not
+///
+/// Encodings that can't be decoded are reported as an `Error`; the caller decides whether that's
+/// fatal or not
+pub fn analyze(bytes: &[u8], address: u64) -> Result<Analysis, Error> {
+    // we want to know if any of the instructions modifies the SP (stack pointer). We use this
+    // information to determine if the subroutine uses stack space or not. We want to detect the
+    // following instructions:
+    // - a9bf7bfd      stp     x29, x30, [sp, #-16]!
+    // - f81f0ffe      str     x30, [sp, #-16]!
+    // - d10083ff      sub     sp, sp, #32
+    // - cb2963ff      sub     sp, sp, x9
+    let mut modifies_sp = false;
+
+    // same as in the Thumb analysis: we only compute the stack usage of subroutines that contain
+    // no intra-branches. `sp` is the running offset of the SP from its value on entry; `stack` is
+    // the lowest offset seen so far, i.e. minus the peak stack usage
+    let mut sp = 0i64;
+    let mut stack = Some(0i64);
+
+    let mut bls = vec![];
+    let mut bs = vec![];
+    let mut indirect = false;
+
+    // lightweight constant propagation used to resolve `adrp` + `add` + `blr` sequences and to
+    // bound `mov` + `sub sp, sp, xN` stack allocations. A register keeps its constant until an
+    // instruction writes to it. The SP is never tracked. Values known on one path may not hold on
+    // the others so the constants are also forgotten after unconditional branches and returns, and
+    // at the targets of intra-function branches
+    let mut consts: [Option<u64>; 32] = [None; 32];
+    let mut blrs = vec![];
+
+    let mut insns = vec![];
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let pc = address.wrapping_add(offset as u64);
+
+        let raw = match bytes.get(offset..offset + 4) {
+            Some(word) => u32::from_le_bytes([word[0], word[1], word[2], word[3]]),
+            None => return Err(Error::unknown(&bytes[offset..], pc)),
+        };
+        match decode(raw) {
+            Some((op, writes)) => insns.push((offset, op, writes)),
+            None => return Err(Error::unknown(&bytes[offset..], pc)),
+        }
+
+        offset += 4;
+    }
+
+    let targets = insns
+        .iter()
+        .filter_map(|(offset, op, _)| match op {
+            Op::B(imm) | Op::Bcc(imm) => Some(*offset as i64 + i64::from(*imm)),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    for (offset, op, writes) in insns {
+        let pc = address.wrapping_add(offset as u64);
+
+        if targets.contains(&(offset as i64)) {
+            consts = [None; 32];
+        }
+
+        let known = consts;
+        for (reg, value) in consts.iter_mut().enumerate() {
+            if writes & (1 << reg) != 0 {
+                *value = None;
+            }
+        }
+
+        // the value of the register written by a constant-producing instruction, if known
+        let constant = match op {
+            Op::Bl(imm) => {
+                bls.push(offset as i64 + i64::from(imm));
+                None
+            }
+
+            Op::B(imm) | Op::Bcc(imm) => {
+                let target = offset as i64 + i64::from(imm);
+
+                if target >= 0 && (target as usize) < bytes.len() {
+                    // this is an `if` or `loop`; give up the stack usage analysis
+                    stack = None;
+                }
+
+                bs.push(target);
+                None
+            }
+
+            Op::Blr(rn) | Op::Br(rn) => {
+                if let Some(target) = known[rn as usize] {
+                    // `BR` is a tail call
+                    blrs.push(target);
+                } else {
+                    indirect = true;
+
+                    if let Op::Br(_) = op {
+                        // either a jump table or an indirect tail call
+                        stack = None;
+                    }
+                }
+                None
+            }
+
+            Op::AddImm(rd, rn, imm) => {
+                if rd == SP {
+                    modifies_sp = true;
+
+                    if rn == SP {
+                        sp += imm;
+                        if let Some(stack) = stack.as_mut() {
+                            *stack = cmp::min(*stack, sp);
+                        }
+                    }
+                }
+
+                if rd == SP || rn == SP {
+                    None
+                } else {
+                    known[rn as usize].map(|base| (rd, base.wrapping_add(imm as u64)))
+                }
+            }
+
+            Op::SubSpRegister(rm) => {
+                modifies_sp = true;
+
+                match known[rm as usize] {
+                    Some(value) => {
+                        sp = sp.wrapping_sub(value as i64);
+                        if let Some(stack) = stack.as_mut() {
+                            *stack = cmp::min(*stack, sp);
+                        }
+                    }
+
+                    None => stack = None,
+                }
+                None
+            }
+
+            Op::Push(n) => {
+                modifies_sp = true;
+
+                sp -= n as i64;
+                if let Some(stack) = stack.as_mut() {
+                    *stack = cmp::min(*stack, sp);
+                }
+                None
+            }
+
+            Op::Pop(n) => {
+                modifies_sp = true;
+
+                sp += n as i64;
+                None
+            }
+
+            Op::WritesSp => {
+                modifies_sp = true;
+                None
+            }
+
+            Op::UnknownSp => {
+                modifies_sp = true;
+                stack = None;
+                None
+            }
+
+            Op::Adr(rd, imm) => Some((rd, pc.wrapping_add(imm as u64))),
+
+            Op::Adrp(rd, imm) => Some((rd, (pc & !0xfff).wrapping_add(imm as u64))),
+
+            Op::Movz(rd, imm) => Some((rd, imm)),
+
+            Op::Movk(rd, shift, imm) => {
+                known[rd as usize].map(|value| (rd, (value & !(0xffff << shift)) | (imm << shift)))
+            }
+
+            Op::Mov(rd, rm) => known[rm as usize].map(|value| (rd, value)),
+
+            Op::Return | Op::Other => None,
+        };
+
+        if let Some((reg, value)) = constant {
+            if reg != XZR {
+                consts[reg as usize] = Some(value);
+            }
+        }
+
+        // the next instruction can only be reached through a branch
+        if let Op::B(_) | Op::Br(_) | Op::Return = op {
+            consts = [None; 32];
+        }
+    }
+
+    Ok(Analysis {
+        bls,
+        bs,
+        blrs,
+        indirect,
+        modifies_sp,
+        stack: stack.map(|min| (-min) as u64),
+    })
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Analysis {
+    /// Offsets (relative to the start of the subroutine) of the targets of `BL` instructions
+    pub bls: Vec<i64>,
+
+    /// Offsets (relative to the start of the subroutine) of the targets of `B`, `B.cond`, `CBZ`,
+    /// `CBNZ`, `TBZ` and `TBNZ` instructions
+    pub bs: Vec<i64>,
+
+    /// Absolute addresses of the targets of `BLR` and `BR` instructions that were resolved using
+    /// constant propagation (`adrp` + `add` + `blr`)
+    pub blrs: Vec<u64>,
+
+    /// Whether the subroutine performs an indirect function call we couldn't resolve
+    pub indirect: bool,
+
+    /// Whether the subroutine modifies the SP register
+    pub modifies_sp: bool,
+
+    /// Stack usage of the subroutine; `None` if the subroutine contains intra-branches
+    pub stack: Option<u64>,
+}
+
+// What an instruction does, as far as the analysis is concerned. Register operands are register
+// numbers; immediates are already scaled and sign extended
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    // `BL label`
+    Bl(i32),
+
+    // `B label`
+    B(i32),
+
+    // `B.cond`, `CBZ`, `CBNZ`, `TBZ` and `TBNZ`
+    Bcc(i32),
+
+    // `BLR Xn` and its pointer authentication variants
+    Blr(u32),
+
+    // `BR Xn` and its pointer authentication variants
+    Br(u32),
+
+    // `RET`, `RETAA`, `RETAB` and `ERET`
+    Return,
+
+    // `ADD Xd|SP, Xn|SP, #imm` and `SUB Xd|SP, Xn|SP, #imm`; the immediate is negated for `SUB`
+    AddImm(u32, u32, i64),
+
+    // `SUB SP, SP, Xm`
+    SubSpRegister(u32),
+
+    // store with pre-index writeback to the SP; number of bytes allocated on the stack
+    Push(u64),
+
+    // load or store with writeback to the SP that increments it; e.g. `ldp x29, x30, [sp], #16`
+    Pop(u64),
+
+    // any other instruction that modifies the SP; e.g. `mov sp, x29`
+    WritesSp,
+
+    // an instruction that modifies the SP by an unknown amount; e.g.
+    // `and sp, x9, #0xffffffffffffffe0`
+    UnknownSp,
+
+    // `ADR Xd, label`; offset relative to this instruction
+    Adr(u32, i64),
+
+    // `ADRP Xd, label`; offset relative to the 4 KiB page of this instruction
+    Adrp(u32, i64),
+
+    // `MOVZ Xd, #imm, LSL #shift` and `MOVN`; `imm` is already shifted (and inverted)
+    Movz(u32, u64),
+
+    // `MOVK Xd, #imm, LSL #shift`
+    Movk(u32, u32, u64),
+
+    // `MOV Xd, Xm` (`ORR Xd, XZR, Xm`)
+    Mov(u32, u32),
+
+    Other,
+}
+
+// Decodes an instruction. Returns the operation and a bit mask of the general purpose registers it
+// writes to
+fn decode(raw: u32) -> Option<(Op, u32)> {
+    const ALL: u32 = u32::MAX;
+
+    let rd = bits(raw, 4, 0);
+    let rn = bits(raw, 9, 5);
+    let rm = bits(raw, 20, 16);
+    let sf = bits(raw, 31, 31) == 1;
+    let rd_mask = 1 << rd;
+
+    // C4.1 A64 instruction set encoding; top level encoding (`op0`, bits 28:25)
+    match bits(raw, 28, 25) {
+        // reserved (e.g. `UDF`), unallocated and SVE encodings
+        0b0000..=0b0011 => None,
+
+        // C4.1.86 Data Processing -- Immediate
+        0b1000 | 0b1001 => {
+            let op = match bits(raw, 25, 23) {
+                // PC-rel. addressing
+                0b000 | 0b001 => {
+                    let imm = (bits(raw, 23, 5) << 2) | bits(raw, 30, 29);
+                    let imm = i64::from(sign_extend(imm, 21));
+
+                    if bits(raw, 31, 31) == 1 {
+                        Op::Adrp(rd, imm << 12)
+                    } else {
+                        Op::Adr(rd, imm)
+                    }
+                }
+
+                // Add/subtract (immediate)
+                0b010 => {
+                    let setflags = bits(raw, 29, 29) == 1;
+                    let imm = i64::from(bits(raw, 21, 0) >> 10) << (12 * bits(raw, 22, 22));
+                    let imm = if bits(raw, 30, 30) == 1 { -imm } else { imm };
+
+                    if setflags {
+                        // `Rd` is the zero register; `CMP` and `CMN`
+                        Op::Other
+                    } else if sf {
+                        Op::AddImm(rd, rn, imm)
+                    } else if rd == SP {
+                        Op::UnknownSp
+                    } else {
+                        Op::Other
+                    }
+                }
+
+                // Logical (immediate); `Rd` is the SP unless the instruction sets flags (`ANDS`)
+                0b100 if rd == SP && bits(raw, 30, 29) != 0b11 => Op::UnknownSp,
+
+                // Move wide (immediate)
+                0b101 => {
+                    let shift = 16 * bits(raw, 22, 21);
+                    let imm = u64::from(bits(raw, 20, 5));
+
+                    if !sf && shift >= 32 {
+                        return None;
+                    }
+
+                    let mask = if sf { u64::MAX } else { u64::from(u32::MAX) };
+                    match bits(raw, 30, 29) {
+                        // MOVN
+                        0b00 => Op::Movz(rd, !(imm << shift) & mask),
+                        // MOVZ
+                        0b10 => Op::Movz(rd, imm << shift),
+                        // MOVK
+                        0b11 => Op::Movk(rd, shift, imm),
+                        _ => return None,
+                    }
+                }
+
+                _ => Op::Other,
+            };
+
+            let writes = match op {
+                Op::AddImm(SP, ..) | Op::UnknownSp => 0,
+                _ if rd == XZR => 0,
+                _ => rd_mask,
+            };
+
+            Some((op, writes))
+        }
+
+        // C4.1.87 Branches, Exception Generating and System instructions
+        0b1010 | 0b1011 => {
+            let op0 = bits(raw, 31, 29);
+
+            if bits(raw, 30, 26) == 0b00101 {
+                // Unconditional branch (immediate)
+                let imm = sign_extend(bits(raw, 25, 0), 26) << 2;
+
+                if op0 == 0b100 {
+                    // the callee may clobber any register
+                    Some((Op::Bl(imm), ALL))
+                } else {
+                    Some((Op::B(imm), 0))
+                }
+            } else if bits(raw, 30, 25) == 0b011010 {
+                // Compare and branch (immediate)
+                Some((Op::Bcc(sign_extend(bits(raw, 23, 5), 19) << 2), 0))
+            } else if bits(raw, 30, 25) == 0b011011 {
+                // Test and branch (immediate)
+                Some((Op::Bcc(sign_extend(bits(raw, 18, 5), 14) << 2), 0))
+            } else if bits(raw, 31, 25) == 0b0101010 {
+                // Conditional branch (immediate)
+                Some((Op::Bcc(sign_extend(bits(raw, 23, 5), 19) << 2), 0))
+            } else if bits(raw, 31, 25) == 0b1101011 {
+                // Unconditional branch (register)
+                let opc = bits(raw, 24, 21);
+
+                if bits(raw, 20, 16) != 0b11111 {
+                    return None;
+                }
+
+                match opc {
+                    0b0000 | 0b1000 => Some((Op::Br(rn), 0)),
+                    0b0001 | 0b1001 => Some((Op::Blr(rn), ALL)),
+                    0b0010 | 0b0100 => Some((Op::Return, 0)),
+                    _ => None,
+                }
+            } else if bits(raw, 31, 24) == 0b1101_0101 {
+                // System instructions; only `SYSL` and `MRS` write to a general purpose register
+                let writes = if bits(raw, 21, 21) == 1 && rd != XZR {
+                    rd_mask
+                } else {
+                    0
+                };
+
+                Some((Op::Other, writes))
+            } else {
+                // Exception generation (e.g. `SVC`, `BRK`)
+                Some((Op::Other, 0))
+            }
+        }
+
+        // C4.1.88 Loads and Stores
+        0b0100 | 0b0110 | 0b1100 | 0b1110 => {
+            let simd = bits(raw, 26, 26) == 1;
+            let pair = bits(raw, 29, 27) == 0b101;
+            // Load/store register (unscaled, pre- / post-indexed, unprivileged, register offset
+            // and unsigned immediate)
+            let single = bits(raw, 29, 27) == 0b111;
+
+            let store = if pair || single && simd {
+                bits(raw, 22, 22) == 0
+            } else if single {
+                bits(raw, 23, 22) == 0b00
+            } else {
+                // conservatively treat everything else (e.g. `LDR (literal)`) as a load
+                false
+            };
+
+            let (writeback, pre, imm) = if pair {
+                // Load/store register pair
+                let scale = if simd {
+                    2 + bits(raw, 31, 30)
+                } else {
+                    2 + bits(raw, 31, 31)
+                };
+                let imm = i64::from(sign_extend(bits(raw, 21, 15), 7)) << scale;
+
+                match bits(raw, 24, 23) {
+                    // post-indexed
+                    0b01 => (true, false, imm),
+                    // pre-indexed
+                    0b11 => (true, true, imm),
+                    _ => (false, false, 0),
+                }
+            } else if single
+                && bits(raw, 25, 24) == 0b00
+                && bits(raw, 21, 21) == 0
+                && bits(raw, 10, 10) == 1
+            {
+                // Load/store register (immediate post-indexed) and (immediate pre-indexed)
+                let imm = i64::from(sign_extend(bits(raw, 20, 12), 9));
+
+                (true, bits(raw, 11, 11) == 1, imm)
+            } else {
+                (false, false, 0)
+            };
+
+            // `Rt` and `Rt2` are written by loads (except prefetches, but that's harmless)
+            let mut writes = 0;
+            if !store && !simd {
+                writes |= rd_mask;
+
+                if pair {
+                    writes |= 1 << bits(raw, 14, 10);
+                }
+            }
+
+            // exclusive and atomic memory operations write a status register or the old value.
+            // This also catches the register offset forms, which is merely conservative
+            if bits(raw, 29, 24) == 0b001000 || single && bits(raw, 21, 21) == 1 {
+                writes = ALL;
+            }
+
+            let op = if writeback && rn == SP {
+                if pre && store && imm < 0 {
+                    Op::Push(imm.unsigned_abs())
+                } else if imm > 0 {
+                    Op::Pop(imm as u64)
+                } else {
+                    Op::WritesSp
+                }
+            } else {
+                if writeback {
+                    writes |= 1 << rn;
+                }
+
+                Op::Other
+            };
+
+            Some((op, writes & !(1 << XZR)))
+        }
+
+        // C4.1.89 Data Processing -- Register
+        0b0101 | 0b1101 => {
+            let op = if raw & 0x7fe0_ffe0 == 0x2a00_03e0 && sf {
+                // `MOV Xd, Xm`
+                Op::Mov(rd, rm)
+            } else if bits(raw, 28, 21) == 0b0101_1001 && bits(raw, 30, 29) == 0b10 && rd == SP {
+                // Add/subtract (extended register); `SUB SP, Xn|SP, Xm`
+                if rn == SP && sf && bits(raw, 12, 10) == 0 && bits(raw, 15, 13) & 0b011 == 0b011 {
+                    Op::SubSpRegister(rm)
+                } else {
+                    Op::UnknownSp
+                }
+            } else if bits(raw, 28, 21) == 0b0101_1001 && bits(raw, 29, 29) == 0 && rd == SP {
+                // `ADD SP, Xn|SP, Xm`
+                Op::UnknownSp
+            } else {
+                Op::Other
+            };
+
+            let writes = match op {
+                Op::SubSpRegister(_) | Op::UnknownSp => 0,
+                _ if rd == XZR => 0,
+                _ => rd_mask,
+            };
+
+            Some((op, writes))
+        }
+
+        // C4.1.90 Data Processing -- Scalar Floating-Point and Advanced SIMD; some of these (e.g.
+        // `FMOV Xd, Dn` and `UMOV`) write to a general purpose register
+        _ => Some((Op::Other, if rd == XZR { 0 } else { rd_mask })),
+    }
+}
+
+fn bits(raw: u32, hi: u32, lo: u32) -> u32 {
+    (raw >> lo) & ((1 << (hi - lo + 1)) - 1)
+}
+
+// sign extends the lowest `width` bits of `x`
+fn sign_extend(x: u32, width: u32) -> i32 {
+    ((x << (32 - width)) as i32) >> (32 - width)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    // the instruction at `address` could not be decoded
+    UnknownInstruction { address: u64, encoding: String },
+}
+
+impl Error {
+    fn unknown(bytes: &[u8], address: u64) -> Self {
+        let encoding = bytes
+            .iter()
+            .take(4)
+            .rev()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        Error::UnknownInstruction { address, encoding }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownInstruction { address, encoding } => write!(
+                f,
+                "unknown instruction {} at address {:#010x}",
+                encoding, address
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn sanity() {
+        // 94000004        bl      #16
+        // 17fffffe        b       #-8
+        // 54000201        b.ne    #64
+        // b4000040        cbz     x0, #8
+        // 371fffe1        tbnz    w1, #3, #-4
+        // d65f03c0        ret
+        let branches = super::analyze(
+            &[
+                0x04, 0x00, 0x00, 0x94, 0xfe, 0xff, 0xff, 0x17, 0x01, 0x02, 0x00, 0x54, 0x40, 0x00,
+                0x00, 0xb4, 0xe1, 0xff, 0x1f, 0x37, 0xc0, 0x03, 0x5f, 0xd6,
+            ],
+            0,
+        )
+        .unwrap();
+        assert_eq!(branches.bls, vec![16]);
+        assert_eq!(branches.bs, vec![4 - 8, 8 + 64, 12 + 8, 16 - 4]);
+        // some of the branches are intra-branches
+        assert_eq!(branches.stack, None);
+
+        // d65f0bff        retaa
+        let ret = super::analyze(&[0xff, 0x0b, 0x5f, 0xd6], 0).unwrap();
+        assert_eq!(
+            ret,
+            super::Analysis {
+                bls: vec![],
+                bs: vec![],
+                blrs: vec![],
+                indirect: false,
+                modifies_sp: false,
+                stack: Some(0),
+            }
+        );
+
+        // d503201f        nop
+        // 00000000        udf     #0
+        assert_eq!(
+            super::analyze(&[0x1f, 0x20, 0x03, 0xd5, 0x00, 0x00, 0x00, 0x00], 0x100),
+            Err(super::Error::UnknownInstruction {
+                address: 0x104,
+                encoding: "00000000".to_owned(),
+            })
+        );
+
+        // truncated instruction
+        assert!(super::analyze(&[0x1f, 0x20], 0).is_err());
+    }
+
+    #[test]
+    fn constants() {
+        // f0000008        adrp    x8, #12288
+        // 91004108        add     x8, x8, #16
+        // d63f0100        blr     x8
+        let adrp = super::analyze(
+            &[
+                0x08, 0x00, 0x00, 0xf0, 0x08, 0x41, 0x00, 0x91, 0x00, 0x01, 0x3f, 0xd6,
+            ],
+            0x1234,
+        )
+        .unwrap();
+        assert!(!adrp.indirect);
+        assert_eq!(adrp.blrs, vec![0x4010]);
+
+        // d2840009        mov     x9, #8192
+        // f2a00029        movk    x9, #1, lsl #16
+        // aa0903ea        mov     x10, x9
+        // d61f0140        br      x10
+        let movk = super::analyze(
+            &[
+                0x09, 0x00, 0x84, 0xd2, 0x29, 0x00, 0xa0, 0xf2, 0xea, 0x03, 0x09, 0xaa, 0x40, 0x01,
+                0x1f, 0xd6,
+            ],
+            0,
+        )
+        .unwrap();
+        assert!(!movk.indirect);
+        assert_eq!(movk.blrs, vec![0x1_2000]);
+        assert_eq!(movk.stack, Some(0));
+
+        // f9400008        ldr     x8, [x0]
+        // d63f0100        blr     x8
+        let clobbered =
+            super::analyze(&[0x08, 0x00, 0x40, 0xf9, 0x00, 0x01, 0x3f, 0xd6], 0).unwrap();
+        assert!(clobbered.indirect);
+        assert!(clobbered.blrs.is_empty());
+
+        // d2840008        mov     x8, #8192
+        // b4000040        cbz     x0, #8
+        // d2860008        mov     x8, #12288
+        // d63f0100        blr     x8
+        let join = super::analyze(
+            &[
+                0x08, 0x00, 0x84, 0xd2, 0x40, 0x00, 0x00, 0xb4, 0x08, 0x00, 0x86, 0xd2, 0x00, 0x01,
+                0x3f, 0xd6,
+            ],
+            0,
+        )
+        .unwrap();
+        assert!(join.indirect);
+        assert!(join.blrs.is_empty());
+
+        // d61f0100        br      x8
+        let jump_table = super::analyze(&[0x00, 0x01, 0x1f, 0xd6], 0).unwrap();
+        assert!(jump_table.indirect);
+        assert_eq!(jump_table.stack, None);
+    }
+
+    #[test]
+    fn modifies_sp() {
+        // d503201f        nop
+        // aa0903ea        mov     x10, x9
+        let nop = super::analyze(&[0x1f, 0x20, 0x03, 0xd5, 0xea, 0x03, 0x09, 0xaa], 0).unwrap();
+        assert!(!nop.modifies_sp);
+        assert_eq!(nop.stack, Some(0));
+
+        // a9bf7bfd        stp     x29, x30, [sp, #-16]!
+        // 6dbe27e8        stp     d8, d9, [sp, #-32]!
+        // d10083ff        sub     sp, sp, #32
+        // 910083ff        add     sp, sp, #32
+        // a8c17bfd        ldp     x29, x30, [sp], #16
+        // d65f03c0        ret
+        let frame = super::analyze(
+            &[
+                0xfd, 0x7b, 0xbf, 0xa9, 0xe8, 0x27, 0xbe, 0x6d, 0xff, 0x83, 0x00, 0xd1, 0xff, 0x83,
+                0x00, 0x91, 0xfd, 0x7b, 0xc1, 0xa8, 0xc0, 0x03, 0x5f, 0xd6,
+            ],
+            0,
+        )
+        .unwrap();
+        assert!(frame.modifies_sp);
+        assert_eq!(frame.stack, Some(16 + 32 + 32));
+
+        // a9bf7bfd        stp     x29, x30, [sp, #-16]!
+        // a8c17bfd        ldp     x29, x30, [sp], #16
+        // d10083ff        sub     sp, sp, #32
+        let peak = super::analyze(
+            &[
+                0xfd, 0x7b, 0xbf, 0xa9, 0xfd, 0x7b, 0xc1, 0xa8, 0xff, 0x83, 0x00, 0xd1,
+            ],
+            0,
+        )
+        .unwrap();
+        assert_eq!(peak.stack, Some(32));
+
+        // f81f0ffe        str     x30, [sp, #-16]!
+        // d14007ff        sub     sp, sp, #1, lsl #12
+        let str = super::analyze(&[0xfe, 0x0f, 0x1f, 0xf8, 0xff, 0x07, 0x40, 0xd1], 0).unwrap();
+        assert_eq!(str.stack, Some(16 + 4096));
+
+        // d2840009        mov     x9, #8192
+        // cb2963ff        sub     sp, sp, x9
+        let register =
+            super::analyze(&[0x09, 0x00, 0x84, 0xd2, 0xff, 0x63, 0x29, 0xcb], 0).unwrap();
+        assert_eq!(register.stack, Some(8192));
+
+        // cb2963ff        sub     sp, sp, x9
+        let dynamic = super::analyze(&[0xff, 0x63, 0x29, 0xcb], 0).unwrap();
+        assert!(dynamic.modifies_sp);
+        assert_eq!(dynamic.stack, None);
+
+        // 927be93f        and     sp, x9, #0xffffffffffffffe0
+        let realign = super::analyze(&[0x3f, 0xe9, 0x7b, 0x92], 0).unwrap();
+        assert!(realign.modifies_sp);
+        assert_eq!(realign.stack, None);
+
+        // 910003bf        mov     sp, x29
+        let epilogue = super::analyze(&[0xbf, 0x03, 0x00, 0x91], 0).unwrap();
+        assert!(epilogue.modifies_sp);
+        assert_eq!(epilogue.stack, Some(0));
+    }
+}
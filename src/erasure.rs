@@ -0,0 +1,259 @@
+//! Type-erasure patterns
+//!
+//! Some libraries erase the type of a value by pairing a pointer to it with a function pointer
+//! that knows the real type of the value; `core::fmt::Argument` is the best known example. In the
+//! LLVM-IR these pairs are called through function pointers whose signature, e.g. `i1 (ptr,
+//! ptr)`, is shared by many unrelated functions so going by the signature alone would add plenty
+//! of impossible edges to the call graph. A pattern recognizes these calls by the function that
+//! performs them (according to the debug info) and restricts their callees to the functions the
+//! library could have stored in the function pointer.
+
+use core::str::FromStr;
+
+use crate::ir::{FnSig, Type};
+
+/// Name of the `core::fmt` pattern in `Pattern::builtin`
+pub const CORE_FMT: &str = "core::fmt";
+
+/// A type-erasure pattern
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    /// Label of the fictitious node that represents the erased calls
+    pub name: String,
+    // signature of the function pointer, see `shape`
+    shape: Option<String>,
+    // functions that perform the erased calls; the code of these functions may have been inlined
+    callers: Vec<String>,
+    // functions that can be called through the erased function pointer
+    callees: Vec<String>,
+}
+
+impl Pattern {
+    /// The patterns used by the Rust standard library and other well known crates
+    pub fn builtin() -> Vec<Pattern> {
+        vec![
+            // `ArgumentV1.formatter` (older `core`) and `rt::Argument` (newer `core`)
+            Pattern::new(
+                CORE_FMT,
+                Some("i1 (ptr, ptr)"),
+                &[
+                    "core::fmt::write",
+                    "core::fmt::ArgumentV1::fmt",
+                    "<core::fmt::ArgumentV1>::fmt",
+                    "core::fmt::rt::Argument::fmt",
+                    "<core::fmt::rt::Argument>::fmt",
+                ],
+                &["<* as core::fmt::*>::fmt", "core::fmt::*"],
+            ),
+            Pattern::new(
+                "defmt",
+                Some("void (ptr)"),
+                &["defmt::export::*"],
+                &["<* as defmt::*Format>::format"],
+            ),
+            // `uDisplay::fmt(&self, &mut Formatter<'_, dyn uWrite>) -> Result<(), E>` where `E` is
+            // a fieldless error
+            Pattern::new(
+                "ufmt",
+                Some("i1 (ptr, ptr)"),
+                &["ufmt::*"],
+                &["<* as ufmt::u*>::fmt"],
+            ),
+        ]
+    }
+
+    fn new(name: &str, shape: Option<&str>, callers: &[&str], callees: &[&str]) -> Self {
+        Pattern {
+            name: name.to_owned(),
+            shape: shape.map(|shape| shape.to_owned()),
+            callers: callers.iter().map(|s| s.to_string()).collect(),
+            callees: callees.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Whether a function pointer, or a function, with signature `sig` fits this pattern
+    pub fn fits(&self, sig: &FnSig) -> bool {
+        self.shape
+            .as_ref()
+            .map(|shape| *shape == self::shape(sig))
+            .unwrap_or(true)
+    }
+
+    /// Whether the erased calls are performed by `function`
+    ///
+    /// `function` is a demangled name without hash (`{:#}`)
+    pub fn is_caller(&self, function: &str) -> bool {
+        self.callers.iter().any(|pattern| glob(pattern, function))
+    }
+
+    /// Whether `function` can be called through the erased function pointer
+    ///
+    /// `function` is a demangled name without hash (`{:#}`)
+    pub fn is_callee(&self, function: &str) -> bool {
+        self.callees.iter().any(|pattern| glob(pattern, function))
+    }
+}
+
+/// Parses a user-defined pattern: `CALLER => CALLEE`
+///
+/// Both sides are demangled function names (without hash) where `*` matches any sequence of
+/// characters, e.g. `app::Callback::call => <* as app::Handler>::handle`
+impl FromStr for Pattern {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, failure::Error> {
+        let mut parts = s.splitn(2, "=>").map(str::trim);
+        match (parts.next(), parts.next()) {
+            (Some(caller), Some(callee)) if !caller.is_empty() && !callee.is_empty() => {
+                Ok(Pattern::new(caller, None, &[caller], &[callee]))
+            }
+
+            _ => Err(failure::format_err!(
+                "malformed type-erasure pattern `{}`; expected `CALLER => CALLEE`",
+                s
+            )),
+        }
+    }
+}
+
+/// Whether `sig` is `fn(&T, &mut core::fmt::Formatter) -> core::fmt::Result`
+///
+/// Only typed pointers name the `Formatter`; this lets the `core::fmt` calls and callees be
+/// recognized in programs without debug info. The type of the first argument varies (e.g.
+/// `%fmt::Void`, `%core::fmt::Void.12` or some unrelated type) so it's ignored
+pub fn is_formatter(sig: &FnSig) -> bool {
+    match (&sig.inputs[..], sig.output.as_ref()) {
+        ([Type::Pointer(_), Type::Pointer(fmt)], Some(output)) => {
+            **fmt == Type::Alias("core::fmt::Formatter") && **output == Type::Integer(1)
+        }
+        _ => false,
+    }
+}
+
+/// The signature `sig` with all its pointers turned into `ptr`
+///
+/// The erased pointer is `*const ()` in the callers but `&T` in the callees; besides, typed
+/// pointers can't be trusted (see `fmt::Void`)
+pub fn shape<'a>(sig: &FnSig<'a>) -> String {
+    let erase = |ty: &Type<'a>| match ty {
+        Type::Pointer(_) => Type::OpaquePointer,
+        _ => ty.clone(),
+    };
+
+    FnSig {
+        inputs: sig.inputs.iter().map(erase).collect(),
+        output: sig.output.as_ref().map(|output| Box::new(erase(output))),
+        variadic: false,
+    }
+    .to_string()
+}
+
+/// Whether `s` matches `pattern`, where `*` matches any sequence of characters, including the
+/// empty one
+pub fn glob(pattern: &str, s: &str) -> bool {
+    let mut parts = pattern.split('*').collect::<Vec<_>>();
+    let first = parts.remove(0);
+    let last = match parts.pop() {
+        Some(last) => last,
+        // no wildcard
+        None => return pattern == s,
+    };
+
+    if !s.starts_with(first) {
+        return false;
+    }
+
+    let mut rest = &s[first.len()..];
+    for part in parts {
+        if let Some(start) = rest.find(part) {
+            rest = &rest[start + part.len()..];
+        } else {
+            return false;
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pattern;
+    use crate::ir::{FnSig, Type};
+
+    #[test]
+    fn glob() {
+        assert!(super::glob("core::fmt::write", "core::fmt::write"));
+        assert!(!super::glob("core::fmt::write", "core::fmt::write_str"));
+        assert!(super::glob(
+            "<* as core::fmt::*>::fmt",
+            "<u8 as core::fmt::Debug>::fmt"
+        ));
+        assert!(super::glob(
+            "<* as core::fmt::*>::fmt",
+            "<&T as core::fmt::Display>::fmt"
+        ));
+        assert!(!super::glob(
+            "<* as core::fmt::*>::fmt",
+            "<u8 as core::fmt::Debug>::fmt::inner"
+        ));
+        // the parts can't overlap
+        assert!(!super::glob("ab*ba", "aba"));
+        assert!(super::glob("*", ""));
+    }
+
+    #[test]
+    fn builtin() {
+        let patterns = Pattern::builtin();
+        let fmt = patterns
+            .iter()
+            .find(|pattern| pattern.name == super::CORE_FMT)
+            .unwrap();
+
+        assert!(fmt.is_caller("core::fmt::write"));
+        assert!(fmt.is_caller("<core::fmt::rt::Argument>::fmt"));
+        assert!(fmt.is_caller("core::fmt::ArgumentV1::fmt"));
+        assert!(!fmt.is_caller("<core::fmt::Arguments as core::fmt::Display>::fmt"));
+
+        assert!(fmt.is_callee("<i32 as core::fmt::Display>::fmt"));
+        assert!(fmt.is_callee("<str as core::fmt::Debug>::fmt"));
+        assert!(!fmt.is_callee("<app::Foo as app::Bar>::fmt"));
+    }
+
+    #[test]
+    fn shape() {
+        let formatter = FnSig {
+            inputs: vec![
+                Type::Pointer(Box::new(Type::Alias("fmt::Void"))),
+                Type::Pointer(Box::new(Type::Alias("core::fmt::Formatter"))),
+            ],
+            output: Some(Box::new(Type::Integer(1))),
+            variadic: false,
+        };
+        assert_eq!(super::shape(&formatter), "i1 (ptr, ptr)");
+        assert!(Pattern::builtin()[0].fits(&formatter));
+        assert!(super::is_formatter(&formatter));
+
+        let write_str = FnSig {
+            inputs: vec![Type::OpaquePointer, Type::OpaquePointer, Type::Integer(64)],
+            output: Some(Box::new(Type::Integer(1))),
+            variadic: false,
+        };
+        assert!(!Pattern::builtin()[0].fits(&write_str));
+        assert!(!super::is_formatter(&write_str));
+    }
+
+    #[test]
+    fn from_str() {
+        let pattern = "app::dispatch => <* as app::Handler>::handle"
+            .parse::<Pattern>()
+            .unwrap();
+
+        assert_eq!(pattern.name, "app::dispatch");
+        assert!(pattern.is_caller("app::dispatch"));
+        assert!(pattern.is_callee("<app::Led as app::Handler>::handle"));
+        assert!(!pattern.is_callee("<app::Led as app::Other>::handle"));
+
+        assert!("app::dispatch".parse::<Pattern>().is_err());
+        assert!("app::dispatch =>".parse::<Pattern>().is_err());
+    }
+}
@@ -0,0 +1,90 @@
+//! Async tasks
+//!
+//! An async executor polls each of its tasks through a function pointer stored in the task, e.g.
+//! `TaskHeader.poll_fn` in `embassy-executor`. The function behind that pointer (a trampoline)
+//! calls the `Future::poll` method of the state machine that the compiler generates for an
+//! `async fn` or `async` block. The waker vtables (`RawWakerVTable`) are called through function
+//! pointers of the same signature as the trampolines, `fn(*const ())`, so going by the signature
+//! alone a task that wakes another one would poll every task. A future that's being polled doesn't
+//! poll other tasks so the function pointer calls made while polling a task can't reach the
+//! trampolines.
+
+use crate::symbol;
+
+// the trampolines of the executors we know about, without their generic arguments
+const TRAMPOLINES: &[&str] = &["<embassy_executor::raw::TaskStorage>::poll"];
+
+/// Whether `function`, a function stored in a function pointer, looks like the trampoline of an
+/// executor: the one of a known executor or a `poll` function instantiated for the state machine
+/// of an `async fn`, which may have been inlined into it
+///
+/// `function` is a demangled name without hash (`{:#}`)
+pub fn is_trampoline(function: &str) -> bool {
+    let (generic, arguments) = symbol::generic(function);
+    TRAMPOLINES.contains(&&*generic)
+        || (generic.ends_with("::poll") && arguments.iter().any(|args| async_fn(args).is_some()))
+}
+
+/// If `function` is the `Future::poll` method of the state machine of an `async fn` or `async`
+/// block, returns the path of the function that contains it
+///
+/// `function` is a demangled name without hash (`{:#}`)
+pub fn state_machine(function: &str) -> Option<&str> {
+    let (self_ty, _, _) = symbol::split_impl(function)?;
+    match symbol::trait_method(function) {
+        Some(("core::future::future::Future", "poll")) => async_fn(self_ty),
+        _ => None,
+    }
+}
+
+/// The path of the first `async fn` or `async` block that appears in `s`, e.g. `app::blink` for
+/// `<embassy_executor::raw::TaskStorage<app::blink::{closure#0}>>::poll`
+pub fn async_fn(s: &str) -> Option<&str> {
+    // v0 and legacy symbol names
+    let end = s.find("::{closure#").or_else(|| s.find("::{{closure}}"))?;
+    let start = s[..end]
+        .rfind(&['<', ' ', ',', '(', '[', '&', '*'][..])
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    Some(&s[start..end])
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn state_machine() {
+        assert_eq!(
+            super::state_machine("<app::blink::{closure#0} as core::future::future::Future>::poll"),
+            Some("app::blink")
+        );
+        assert_eq!(
+            super::state_machine(
+                "<core::future::from_generator::GenFuture<app::blink::{{closure}}> as \
+                 core::future::future::Future>::poll"
+            ),
+            Some("app::blink")
+        );
+        assert_eq!(
+            super::state_machine("<app::Timer as core::future::future::Future>::poll"),
+            None
+        );
+        assert_eq!(
+            super::state_machine("<app::blink::{closure#0} as core::ops::FnOnce<()>>::call_once"),
+            None
+        );
+    }
+
+    #[test]
+    fn trampoline() {
+        let poll = "<embassy_executor::raw::TaskStorage<app::__blink_task::{closure#0}>>::poll";
+        assert!(super::is_trampoline(poll));
+        assert_eq!(super::async_fn(poll), Some("app::__blink_task"));
+        assert!(!super::is_trampoline(
+            "<embassy_executor::raw::TaskStorage<app::__blink_task::{closure#0}>>::spawn"
+        ));
+        assert!(super::is_trampoline(
+            "<app::executor::Task<app::blink::{closure#0}>>::poll"
+        ));
+        assert!(!super::is_trampoline("<app::executor::Task<u8>>::poll"));
+    }
+}
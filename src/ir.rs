@@ -1,31 +1,38 @@
-use core::fmt;
+use core::{cmp, fmt};
+use std::{
+    collections::{HashMap, VecDeque},
+    thread,
+};
 
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_until, take_while1},
-    character::complete::{char, digit1, line_ending, not_line_ending, space1},
-    combinator::{map, map_res, opt},
+    bytes::complete::{is_not, tag, take_until, take_while, take_while1},
+    character::complete::{char, digit1, not_line_ending, space1},
+    combinator::{map, map_res, opt, recognize},
     error::ErrorKind,
-    multi::{many0, many1, separated_list},
-    sequence::delimited,
+    multi::{many0, many1},
+    sequence::{delimited, pair},
     IResult,
 };
 
+pub mod bitcode;
 mod define;
 mod item;
 mod ty;
 
-use crate::ir::ty::type_;
 pub use crate::ir::{
-    define::Stmt,
-    item::{Declare, Item},
+    define::{Define, Origin, Stmt},
+    item::{Declare, Item, Metadata},
     ty::Type,
 };
+use crate::ir::{item::Malformed, ty::type_};
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct FnSig<'a> {
     pub inputs: Vec<Type<'a>>,
     pub output: Option<Box<Type<'a>>>,
+    // e.g. `i32 (ptr, ...)`
+    pub variadic: bool,
 }
 
 impl<'a> fmt::Display for FnSig<'a> {
@@ -47,26 +54,206 @@ impl<'a> fmt::Display for FnSig<'a> {
 
             write!(f, "{}", input)?;
         }
+
+        if self.variadic {
+            f.write_str(if is_first { "..." } else { ", ..." })?;
+        }
         f.write_str(")")
     }
 }
 
-pub fn parse(ll: &str) -> Result<Vec<Item>, failure::Error> {
-    items(ll).map(|t| t.1).map_err(|e| {
-        failure::format_err!(
-            "BUG: failed to parse .ll file; please submit a bug report with Rust source code. Details (include the _first_ LLVM item, e.g. `define .. {{ .. }}`, in the report):\n{:?}",
-            e
-        )
+/// Parses a LLVM module, using as many threads as there are CPUs
+///
+/// The items of the module are returned in the order they appear in it. They are parsed lazily, a
+/// batch at a time, so only the items that are still to be consumed are held in memory. Attribute
+/// groups and the metadata nodes the analysis doesn't use are skipped. Items the parser doesn't
+/// understand are returned as `Item::Malformed`; the rest of the module is still parsed.
+pub fn parse(ll: &str) -> Parse<'_> {
+    Parse {
+        ll,
+        chunks: Split { rest: ll },
+        threads: thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1),
+        parsed: VecDeque::new(),
+    }
+}
+
+// number of items each thread parses at a time
+const BATCH: usize = 1_024;
+
+/// Iterator over the items of a LLVM module; see `parse`
+pub struct Parse<'a> {
+    ll: &'a str,
+    chunks: Split<'a>,
+    threads: usize,
+    parsed: VecDeque<Item<'a>>,
+}
+
+impl<'a> Iterator for Parse<'a> {
+    type Item = Item<'a>;
+
+    fn next(&mut self) -> Option<Item<'a>> {
+        if self.parsed.is_empty() {
+            // splitting the module is cheap; parsing its items, in particular function bodies, is
+            // not
+            let ll = self.ll;
+            let chunks = self
+                .chunks
+                .by_ref()
+                .take(BATCH * self.threads)
+                .collect::<Vec<_>>();
+
+            if self.threads == 1 {
+                self.parsed
+                    .extend(chunks.iter().map(|chunk| item(ll, chunk)));
+            } else {
+                let per_thread = cmp::max(chunks.len().div_ceil(self.threads), 1);
+                let parsed = &mut self.parsed;
+                thread::scope(|s| {
+                    let handles = chunks
+                        .chunks(per_thread)
+                        .map(|chunks| {
+                            s.spawn(move || {
+                                chunks
+                                    .iter()
+                                    .map(|chunk| item(ll, chunk))
+                                    .collect::<Vec<_>>()
+                            })
+                        })
+                        .collect::<Vec<_>>();
+
+                    for handle in handles {
+                        parsed.extend(handle.join().expect("BUG: parser thread panicked"));
+                    }
+                });
+            }
+        }
+
+        self.parsed.pop_front()
+    }
+}
+
+// `chunk` is a slice of the module `ll`; the module is used to locate parse errors
+fn item<'a>(ll: &'a str, chunk: &'a str) -> Item<'a> {
+    match item::item(chunk) {
+        Ok((rest, item)) if rest.trim().is_empty() => return item,
+        _ => {}
+    }
+
+    // report the first token the parser didn't understand
+    let rest = item::failure(chunk).trim_start_matches([' ', '\t']);
+    let offset = rest.as_ptr() as usize - ll.as_ptr() as usize;
+    let start = ll[..offset].rfind('\n').map(|pos| pos + 1).unwrap_or(0);
+    let found = rest.lines().next().unwrap_or("");
+
+    // the function or global the item defines, e.g. `define void @foo() {`
+    let first = chunk.lines().next().unwrap_or("");
+    let name = first
+        .find('@')
+        .and_then(|pos| function(&first[pos..]).ok())
+        .map(|(_, name)| name.0);
+
+    Item::Malformed(Malformed {
+        name,
+        define: chunk.starts_with("define "),
+        line: ll[..offset].matches('\n').count() + 1,
+        column: ll[start..offset].chars().count() + 1,
+        found,
     })
 }
 
-fn items(i: &str) -> IResult<&str, Vec<Item>> {
-    let (i, items) = separated_list(many1(line_ending), crate::ir::item::item)(i)?;
-    let i = many0(line_ending)(i)?.0;
-    if i.is_empty() {
-        Ok(("", items))
-    } else {
-        Err(nom::Err::Failure((i, ErrorKind::Eof)))
+// Splits a LLVM module into its top-level items without parsing them. Items start at the beginning
+// of a line and span a single line, except for function definitions, which end with a line that
+// only contains `}`
+struct Split<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Split<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        loop {
+            if self.rest.is_empty() {
+                return None;
+            }
+
+            let end = if self.rest.starts_with("define ") {
+                self.rest
+                    .find("\n}")
+                    .map(|pos| pos + "\n}".len())
+                    .unwrap_or(self.rest.len())
+            } else {
+                self.rest.find('\n').unwrap_or(self.rest.len())
+            };
+
+            let (chunk, rest) = self.rest.split_at(end);
+            // also skips empty lines
+            self.rest = rest.trim_start_matches(['\r', '\n']);
+
+            let chunk = chunk.trim_end_matches('\r');
+            if !is_skipped(chunk) {
+                return Some(chunk);
+            }
+        }
+    }
+}
+
+// whether the item can be skipped without parsing it; this is the case of most metadata, which
+// dominates the size of modules compiled with debug info
+fn is_skipped(chunk: &str) -> bool {
+    if chunk.starts_with("attributes ") {
+        return true;
+    }
+
+    if chunk.starts_with('!') {
+        // see `item::node`
+        let node = chunk
+            .find(" = ")
+            .map(|pos| chunk[pos + " = ".len()..].trim_start_matches("distinct "))
+            .unwrap_or("");
+
+        return ![
+            "!DILocation(",
+            "!DILexicalBlock",
+            "!DISubprogram(",
+            "!DIGlobalVariable",
+        ]
+        .iter()
+        .any(|kind| node.starts_with(kind));
+    }
+
+    false
+}
+
+/// Returns the linkage name of the function a `DILocation` belongs to
+///
+/// If the code at that location was inlined this is the inlined function
+pub fn subprogram<'a>(metadata: &HashMap<u32, Metadata<'a>>, dbg: u32) -> Option<&'a str> {
+    let mut id = dbg;
+    // bounded to not loop forever on malformed metadata
+    for _ in 0..64 {
+        match metadata.get(&id)? {
+            Metadata::Location { scope } | Metadata::LexicalBlock { scope } => id = *scope,
+            Metadata::Subprogram { linkage_name } => return *linkage_name,
+            _ => return None,
+        }
+    }
+
+    None
+}
+
+/// Returns the name of the global variable a global's `!dbg` attachment describes, e.g.
+/// `<app::Foo as app::Trait>::{vtable}`
+pub fn global_variable<'a>(metadata: &HashMap<u32, Metadata<'a>>, dbg: u32) -> Option<&'a str> {
+    match metadata.get(&dbg)? {
+        Metadata::GlobalVariableExpression { var } => match metadata.get(var)? {
+            Metadata::GlobalVariable { name } => *name,
+            _ => None,
+        },
+        Metadata::GlobalVariable { name } => *name,
+        _ => None,
     }
 }
 
@@ -109,13 +296,82 @@ fn global(i: &str) -> IResult<&str, Global> {
     Ok((i, Global(s)))
 }
 
+// the names of all the globals (functions and statics) that appear in `i`
+fn globals(i: &str) -> Vec<&str> {
+    i.match_indices('@')
+        .filter_map(|(start, _)| global(&i[start..]).ok().and_then(|(_, global)| global.0))
+        .collect()
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
-struct Local;
+struct Local<'a>(&'a str);
 
 fn local(i: &str) -> IResult<&str, Local> {
     let i = char('%')(i)?.0;
-    let i = alt((map(digit1, drop), map(ident, drop)))(i)?.0;
-    Ok((i, Local))
+    let (i, name) = alt((digit1, map(ident, |i| i.0), map(string, |s| s.0)))(i)?;
+    Ok((i, Local(name)))
+}
+
+// `42`, `-1`, `0.000000e+00`, `0x7FF0000000000000`, `true`, `null`, etc.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Literal;
+
+fn literal(i: &str) -> IResult<&str, Literal> {
+    let i = alt((
+        recognize(pair(
+            opt(char('-')),
+            pair(
+                digit1,
+                take_while(|c: char| c.is_ascii_alphanumeric() || c == '.' || c == '+' || c == '-'),
+            ),
+        )),
+        tag("true"),
+        tag("false"),
+        tag("null"),
+        tag("undef"),
+        tag("poison"),
+        tag("zeroinitializer"),
+    ))(i)?
+    .0;
+    Ok((i, Literal))
+}
+
+// the value of an instruction operand; constant expressions are reduced to the global they
+// operate on
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operand<'a> {
+    // `@foo`, `getelementptr inbounds (i8, ptr @foo, i64 8)`
+    Global(&'a str),
+
+    // `%0`
+    Local(&'a str),
+
+    // `null`, `42`, etc.
+    Constant,
+}
+
+fn operand(i: &str) -> IResult<&str, Operand> {
+    alt((
+        map(global, |global| {
+            global.0.map(Operand::Global).unwrap_or(Operand::Constant)
+        }),
+        map(local, |local| Operand::Local(local.0)),
+        // constant expression
+        map(
+            recognize(pair(
+                take_while1(|c: char| c.is_ascii_alphabetic() || c == ' '),
+                parens,
+            )),
+            |expr| {
+                globals(expr)
+                    .first()
+                    .cloned()
+                    .map(Operand::Global)
+                    .unwrap_or(Operand::Constant)
+            },
+        ),
+        map(literal, |_| Operand::Constant),
+    ))(i)
 }
 
 // `internal`, `fastcc`, `dereferenceable(4)`, etc.
@@ -139,7 +395,7 @@ fn attribute(i: &str) -> IResult<&str, Attribute> {
         }
 
         // have this branch always error because this is not an attribute but part of a type
-        "double" | "float" | "void" => {
+        "double" | "float" | "void" | "ptr" => {
             return Err(nom::Err::Error((i, ErrorKind::Switch)));
         }
 
@@ -153,12 +409,22 @@ fn attribute(i: &str) -> IResult<&str, Attribute> {
             return Err(nom::Err::Error((i, ErrorKind::Switch)));
         }
 
-        _ => i,
+        // e.g. `captures(none)`, `range(i8 0, 2)` or `memory(argmem: read)`
+        _ => opt(parens)(i)?.0,
     };
 
     Ok((i, Attribute))
 }
 
+// a balanced parenthesized expression
+fn parens(i: &str) -> IResult<&str, &str> {
+    recognize(delimited(
+        char('('),
+        many0(alt((is_not("()"), parens))),
+        char(')'),
+    ))(i)
+}
+
 // NOTE constant operation
 #[derive(Clone, Copy, Debug, PartialEq)]
 struct Bitcast<'a>(Option<&'a str>);
@@ -189,8 +455,8 @@ struct GetElementPtr;
 fn getelementptr(i: &str) -> IResult<&str, GetElementPtr> {
     let i = tag("getelementptr")(i)?.0;
     let i = space1(i)?.0;
-    let i = opt(|i| {
-        let i = tag("inbounds")(i)?.0;
+    let i = many0(|i| {
+        let i = alt((tag("inbounds"), tag("nuw"), tag("nusw")))(i)?.0;
         space1(i)
     })(i)?
     .0;
@@ -208,7 +474,7 @@ fn getelementptr(i: &str) -> IResult<&str, GetElementPtr> {
                 let i = space1(i)?.0;
                 let i = type_(i)?.0;
                 let i = space1(i)?.0;
-                digit1(i)
+                literal(i)
             })(i)
         },
         char(')'),
@@ -253,7 +519,16 @@ fn string(i: &str) -> IResult<&str, String> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Alias, Comment, FnSig, GetElementPtr, Ident, Local, String, Type};
+    use super::{
+        Alias, Attribute, Comment, FnSig, GetElementPtr, Ident, Literal, Local, Operand, String,
+        Type,
+    };
+    use crate::ir::{item::Item, Split};
+
+    // the sequential reference for `parse`: the items of the module, one at a time
+    fn sequential(ll: &str) -> impl Iterator<Item = Item<'_>> {
+        Split { rest: ll }.map(move |chunk| super::item(ll, chunk))
+    }
 
     #[test]
     fn alias() {
@@ -273,6 +548,10 @@ mod tests {
     #[test]
     fn attribute() {
         assert!(super::attribute("void").is_err());
+        assert!(super::attribute("ptr").is_err());
+
+        assert_eq!(super::attribute("captures(none)"), Ok(("", Attribute)));
+        assert_eq!(super::attribute("range(i32 0, 4097)"), Ok(("", Attribute)));
     }
 
     #[test]
@@ -294,6 +573,50 @@ mod tests {
             super::getelementptr("getelementptr inbounds (<{ [0 x i8] }>, <{ [0 x i8] }>* @anon.3751ff68b49c735a867036886cf6a576.71, i32 0, i32 0)"),
             Ok(("", GetElementPtr)),
         );
+
+        assert_eq!(
+            super::getelementptr("getelementptr inbounds nuw (i8, ptr @_RNvNtNtCsjrHSEGnQ3l9_3std2io5stdio6STDOUT, i64 8)"),
+            Ok(("", GetElementPtr)),
+        );
+    }
+
+    #[test]
+    fn globals() {
+        assert_eq!(
+            super::globals(
+                "  store ptr @foo, ptr getelementptr inbounds (i8, ptr @BAR, i64 8), align 4"
+            ),
+            vec!["foo", "BAR"]
+        );
+
+        assert_eq!(
+            super::globals("  %1 = load i32, ptr @0, align 4"),
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn operand() {
+        assert_eq!(super::operand("@foo"), Ok(("", Operand::Global("foo"))));
+        assert_eq!(super::operand("%1"), Ok(("", Operand::Local("1"))));
+        assert_eq!(super::operand("null"), Ok(("", Operand::Constant)));
+        assert_eq!(
+            super::operand("getelementptr inbounds (i8, ptr @BAR, i64 8), align 4"),
+            Ok((", align 4", Operand::Global("BAR")))
+        );
+        assert_eq!(
+            super::operand("bitcast (void ()* @foo to i8*)"),
+            Ok(("", Operand::Global("foo")))
+        );
+    }
+
+    #[test]
+    fn literal() {
+        assert_eq!(super::literal("42"), Ok(("", Literal)));
+        assert_eq!(super::literal("-1"), Ok(("", Literal)));
+        assert_eq!(super::literal("0x3FF19999A0000000"), Ok(("", Literal)));
+        assert_eq!(super::literal("1.000000e+00"), Ok(("", Literal)));
+        assert_eq!(super::literal("poison"), Ok(("", Literal)));
     }
 
     #[test]
@@ -316,11 +639,11 @@ mod tests {
 
     #[test]
     fn local() {
-        assert_eq!(super::local("%113"), Ok(("", Local)));
+        assert_eq!(super::local("%113"), Ok(("", Local("113"))));
 
-        assert_eq!(super::local("%."), Ok(("", Local)));
+        assert_eq!(super::local("%."), Ok(("", Local("."))));
 
-        assert_eq!(super::local("%.9"), Ok(("", Local)));
+        assert_eq!(super::local("%.9"), Ok(("", Local(".9"))));
     }
 
     #[test]
@@ -329,6 +652,7 @@ mod tests {
             FnSig {
                 inputs: vec![],
                 output: None,
+                variadic: false,
             }
             .to_string(),
             "void ()"
@@ -338,6 +662,7 @@ mod tests {
             FnSig {
                 inputs: vec![Type::Integer(8)],
                 output: None,
+                variadic: false,
             }
             .to_string(),
             "void (i8)"
@@ -347,10 +672,21 @@ mod tests {
             FnSig {
                 inputs: vec![Type::Integer(8)],
                 output: Some(Box::new(Type::Integer(8))),
+                variadic: false,
             }
             .to_string(),
             "i8 (i8)"
         );
+
+        assert_eq!(
+            FnSig {
+                inputs: vec![Type::OpaquePointer],
+                output: Some(Box::new(Type::Integer(32))),
+                variadic: true,
+            }
+            .to_string(),
+            "i32 (ptr, ...)"
+        );
     }
 
     #[test]
@@ -377,4 +713,105 @@ mod tests {
         // NOTE trailing space
         assert_eq!(super::string(r#""Hello" "#), Ok((" ", String("Hello"))));
     }
+
+    #[test]
+    fn split() {
+        let ll = "; ModuleID = 'app'\r\n\
+                  \n\
+                  define void @foo() {\n\
+                  start:\n\
+                  \x20 ret void\n\
+                  }\n\
+                  \n\
+                  attributes #0 = { nounwind }\n\
+                  !0 = !DIFile(filename: \"app.rs\", directory: \"/app\")\n\
+                  !1 = distinct !DISubprogram(name: \"foo\", linkageName: \"foo\")\n\
+                  !2 = !DILocation(line: 1, column: 2, scope: !1)";
+
+        assert_eq!(
+            super::Split { rest: ll }.collect::<Vec<_>>(),
+            vec![
+                "; ModuleID = 'app'",
+                "define void @foo() {\nstart:\n  ret void\n}",
+                "!1 = distinct !DISubprogram(name: \"foo\", linkageName: \"foo\")",
+                "!2 = !DILocation(line: 1, column: 2, scope: !1)",
+            ]
+        );
+
+        // the parallel parser returns the items in order
+        let items = super::parse(ll).collect::<Vec<_>>();
+        assert_eq!(items, sequential(ll).collect::<Vec<_>>());
+        assert_eq!(items.len(), 4);
+    }
+
+    #[test]
+    fn malformed() {
+        use super::{item::Malformed, Item};
+
+        let ll = "define void @foo() {\n\
+                  start:\n\
+                  \x20 call void @bar()\n\
+                  \x20 call spir_func void bogus\n\
+                  \x20 ret void\n\
+                  }\n\
+                  \n\
+                  declare void @bar()\n\
+                  declare void @baz(i32, ???)";
+
+        let items = super::parse(ll).collect::<Vec<_>>();
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[0],
+            Item::Malformed(Malformed {
+                name: Some("foo"),
+                define: true,
+                line: 4,
+                column: 3,
+                found: "call spir_func void bogus",
+            })
+        );
+        assert!(matches!(items[1], Item::Declare(_)));
+        assert_eq!(
+            items[2],
+            Item::Malformed(Malformed {
+                name: Some("baz"),
+                define: false,
+                line: 9,
+                column: 22,
+                found: ", ???)",
+            })
+        );
+    }
+
+    // the parallel parser returns the same items, in the same order, as the sequential one; the
+    // fixtures are repeated so that they span several batches. Set `LL` to the path of a `.ll`
+    // file to also check it
+    #[test]
+    fn parallel() {
+        use std::{env, fs};
+
+        let mut fixtures = [
+            include_str!("ir/define/parse1.ll"),
+            include_str!("ir/define/parse2.ll"),
+            include_str!("ir/define/parse3.ll"),
+            include_str!("ir/define/parse4.ll"),
+            include_str!("ir/define/parse5.ll"),
+            include_str!("ir/define/parse6.ll"),
+            include_str!("ir/define/parse7.ll"),
+            include_str!("ir/define/parse8.ll"),
+        ]
+        .iter()
+        .map(|fixture| fixture.repeat(1_000))
+        .collect::<Vec<_>>();
+        if let Ok(path) = env::var("LL") {
+            fixtures.push(fs::read_to_string(path).unwrap());
+        }
+
+        for ll in &fixtures {
+            assert_eq!(
+                super::parse(ll).collect::<Vec<_>>(),
+                sequential(ll).collect::<Vec<_>>()
+            );
+        }
+    }
 }
@@ -0,0 +1,1540 @@
+//! LLVM bitcode reader
+//!
+//! An alternative to parsing the textual LLVM-IR (`--emit=llvm-ir`). The records of the binary
+//! format (`--emit=llvm-bc`) change less often, and more gracefully, between LLVM releases than
+//! the grammar of the textual IR does: new fields are appended to existing records and obsolete
+//! records keep their codes. Only what the analysis needs is decoded -- the functions and the calls
+//! they make, the globals and their initializers and the debug info nodes that lead from a call
+//! site to its enclosing function -- and turned into the same `Item`s that `ir::parse` produces.
+//!
+//! Only the bitcode produced by LLVM 5 and newer, which keeps the names of globals in a string
+//! table, is supported. See <https://llvm.org/docs/BitCodeFormat.html>
+
+use core::str;
+use std::collections::{HashMap, HashSet};
+
+use typed_arena::Arena;
+
+use crate::ir::{
+    define::{self, Define, Origin, Slot, Stmt},
+    item::{self, Declare, Global, Item, Metadata, Vtable},
+    FnSig, Type,
+};
+
+use self::stream::{Entry, Reader, Record};
+
+mod stream;
+
+// block IDs
+const MODULE_BLOCK: u64 = 8;
+const CONSTANTS_BLOCK: u64 = 11;
+const FUNCTION_BLOCK: u64 = 12;
+const METADATA_BLOCK: u64 = 15;
+const METADATA_ATTACHMENT_BLOCK: u64 = 16;
+const TYPE_BLOCK: u64 = 17;
+const METADATA_KIND_BLOCK: u64 = 22;
+const STRTAB_BLOCK: u64 = 23;
+
+// MODULE_BLOCK records
+const MODULE_CODE_VERSION: u64 = 1;
+const MODULE_CODE_GLOBALVAR: u64 = 7;
+const MODULE_CODE_FUNCTION: u64 = 8;
+const MODULE_CODE_ALIAS: u64 = 14;
+const MODULE_CODE_IFUNC: u64 = 15;
+
+const STRTAB_BLOB: u64 = 1;
+
+// TYPE_BLOCK records
+const TYPE_CODE_NUMENTRY: u64 = 1;
+const TYPE_CODE_VOID: u64 = 2;
+const TYPE_CODE_FLOAT: u64 = 3;
+const TYPE_CODE_DOUBLE: u64 = 4;
+const TYPE_CODE_LABEL: u64 = 5;
+const TYPE_CODE_OPAQUE: u64 = 6;
+const TYPE_CODE_INTEGER: u64 = 7;
+const TYPE_CODE_POINTER: u64 = 8;
+const TYPE_CODE_FUNCTION_OLD: u64 = 9;
+const TYPE_CODE_ARRAY: u64 = 11;
+const TYPE_CODE_VECTOR: u64 = 12;
+const TYPE_CODE_METADATA: u64 = 16;
+const TYPE_CODE_STRUCT_ANON: u64 = 18;
+const TYPE_CODE_STRUCT_NAME: u64 = 19;
+const TYPE_CODE_STRUCT_NAMED: u64 = 20;
+const TYPE_CODE_FUNCTION: u64 = 21;
+const TYPE_CODE_OPAQUE_POINTER: u64 = 25;
+
+// CONSTANTS_BLOCK records
+const CST_CODE_SETTYPE: u64 = 1;
+const CST_CODE_INTEGER: u64 = 4;
+const CST_CODE_AGGREGATE: u64 = 7;
+const CST_CODE_CE_BINOP: u64 = 10;
+const CST_CODE_CE_CAST: u64 = 11;
+const CST_CODE_CE_GEP_OLD: u64 = 12;
+const CST_CODE_CE_SELECT: u64 = 13;
+const CST_CODE_INLINEASM_OLD: u64 = 18;
+const CST_CODE_CE_INBOUNDS_GEP: u64 = 20;
+const CST_CODE_INLINEASM_OLD2: u64 = 23;
+const CST_CODE_CE_GEP_WITH_INRANGE_INDEX_OLD: u64 = 24;
+const CST_CODE_DSO_LOCAL_EQUIVALENT: u64 = 27;
+const CST_CODE_INLINEASM_OLD3: u64 = 28;
+const CST_CODE_NO_CFI_VALUE: u64 = 29;
+const CST_CODE_INLINEASM: u64 = 30;
+const CST_CODE_CE_GEP_WITH_INRANGE: u64 = 31;
+const CST_CODE_CE_GEP: u64 = 32;
+const CST_CODE_PTRAUTH: u64 = 33;
+const CST_CODE_PTRAUTH2: u64 = 34;
+
+// FUNCTION_BLOCK records
+const FUNC_CODE_DECLAREBLOCKS: u64 = 1;
+const FUNC_CODE_INST_INVOKE: u64 = 13;
+const FUNC_CODE_INST_PHI: u64 = 16;
+const FUNC_CODE_INST_LOAD: u64 = 20;
+const FUNC_CODE_DEBUG_LOC_AGAIN: u64 = 33;
+const FUNC_CODE_INST_CALL: u64 = 34;
+const FUNC_CODE_DEBUG_LOC: u64 = 35;
+const FUNC_CODE_INST_RESUME: u64 = 39;
+const FUNC_CODE_INST_LOADATOMIC: u64 = 41;
+const FUNC_CODE_INST_GEP: u64 = 43;
+const FUNC_CODE_INST_STORE: u64 = 44;
+const FUNC_CODE_INST_STOREATOMIC: u64 = 45;
+const FUNC_CODE_OPERAND_BUNDLE: u64 = 55;
+const FUNC_CODE_INST_CALLBR: u64 = 57;
+const FUNC_CODE_BLOCKADDR_USERS: u64 = 60;
+// `#dbg_value` and friends
+const FUNC_CODE_DEBUG_RECORDS: [u64; 5] = [61, 62, 63, 64, 65];
+
+// instructions, other than calls, that produce a value
+const FUNC_CODE_VALUES: [u64; 30] = [
+    2, 3, 4, 5, 6, 7, 8, 9, 16, 19, 20, 23, 26, 27, 28, 29, 30, 37, 38, 40, 41, 43, 46, 47, 50, 51,
+    52, 56, 58, 59,
+];
+
+// flags of the calling convention field of calls
+const CALL_EXPLICIT_TYPE: u64 = 1 << 15;
+const CALL_FMF: u64 = 1 << 17;
+const INVOKE_EXPLICIT_TYPE: u64 = 1 << 13;
+
+// METADATA_BLOCK records that don't define a node
+const METADATA_CODE_NAME: u64 = 4;
+const METADATA_CODE_KIND: u64 = 6;
+const METADATA_CODE_NAMED_NODE: u64 = 10;
+const METADATA_CODE_ATTACHMENT: u64 = 11;
+const METADATA_CODE_GLOBAL_DECL_ATTACHMENT: u64 = 36;
+const METADATA_CODE_INDEX_OFFSET: u64 = 38;
+const METADATA_CODE_INDEX: u64 = 39;
+// METADATA_BLOCK records that do
+const METADATA_CODE_SUBPROGRAM: u64 = 21;
+const METADATA_CODE_LEXICAL_BLOCK: u64 = 22;
+const METADATA_CODE_LEXICAL_BLOCK_FILE: u64 = 23;
+const METADATA_CODE_GLOBAL_VAR: u64 = 27;
+const METADATA_CODE_STRINGS: u64 = 35;
+const METADATA_CODE_GLOBAL_VAR_EXPR: u64 = 37;
+
+/// Parses a LLVM bitcode file
+///
+/// The items are returned in the order the globals and functions are declared in the module,
+/// followed by the metadata nodes
+pub fn parse<'a>(bc: &'a [u8], strings: &'a Strings) -> Result<Vec<Item<'a>>, failure::Error> {
+    let bc = unwrap(bc)?;
+
+    // the string table, which holds the names of the globals, comes after the module
+    let mut reader = Reader::new(&bc[4..]);
+    let mut module = None;
+    let mut strtab = None;
+    loop {
+        match reader.next()? {
+            Entry::Block(MODULE_BLOCK) if module.is_none() => {
+                module = Some(reader.clone());
+                reader.skip()?;
+            }
+
+            Entry::Block(STRTAB_BLOCK) => {
+                reader.enter()?;
+                loop {
+                    match reader.next()? {
+                        Entry::Record(Record {
+                            code: STRTAB_BLOB,
+                            blob: Some(blob),
+                            ..
+                        }) => strtab = Some(blob),
+                        Entry::Record(_) => {}
+                        Entry::Block(_) => reader.skip()?,
+                        Entry::End => break,
+                    }
+                }
+            }
+
+            Entry::Block(_) => reader.skip()?,
+            Entry::Record(_) => {}
+            Entry::End => break,
+        }
+    }
+
+    let mut reader = module.ok_or_else(|| failure::err_msg("LLVM bitcode contains no module"))?;
+    let strtab = strtab.ok_or_else(|| {
+        failure::err_msg("LLVM bitcode has no string table; LLVM 5 or newer is required")
+    })?;
+    reader.enter()?;
+    Module::new(strtab, strings).read(&mut reader)
+}
+
+/// The strings of the items that are not stored as a contiguous sequence of bytes in the bitcode:
+/// the names of types and the templates of `asm!` blocks
+///
+/// The items borrow their other strings from the bitcode; this must live as long as it does
+#[derive(Default)]
+pub struct Strings(Arena<String>);
+
+impl Strings {
+    fn alloc(&self, s: String) -> &str {
+        self.0.alloc(s)
+    }
+}
+
+// strips the wrapper header used on Darwin
+fn unwrap(bc: &[u8]) -> Result<&[u8], failure::Error> {
+    let word = |i: usize| {
+        bc.get(i..i + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    };
+
+    let bc = if word(0) == Some(0x0B17_C0DE) {
+        match (word(8), word(12)) {
+            (Some(offset), Some(size)) => bc.get(offset..offset + size).unwrap_or(&[]),
+            _ => &[],
+        }
+    } else {
+        bc
+    };
+
+    if bc.starts_with(b"BC\xC0\xDE") {
+        Ok(bc)
+    } else {
+        Err(failure::err_msg("not a LLVM bitcode file"))
+    }
+}
+
+#[derive(Clone, Debug)]
+enum RawType<'a> {
+    Void,
+    Label,
+    Metadata,
+    Integer(usize),
+    Float,
+    Double,
+    Pointer(u64),
+    OpaquePointer,
+    Array(usize, u64),
+    Vector(usize, u64),
+    Struct {
+        packed: bool,
+        fields: Vec<u64>,
+    },
+    // named struct
+    Alias(&'a str),
+    Fn {
+        inputs: Vec<u64>,
+        output: u64,
+        variadic: bool,
+    },
+    // `half`, `token`, etc.
+    Other(&'static str),
+}
+
+#[derive(Clone, Debug)]
+enum Value<'a> {
+    // named function, global variable or alias
+    Global(&'a str),
+    Int(i64),
+    // inline assembly; the template escaped as in the textual IR
+    // template and constraints
+    Asm(&'a str, &'a str),
+    // `bitcast (@foo to ..)` and the like
+    Cast(u32),
+    // other constant expressions and aggregates; their operands
+    Expr(Vec<u32>),
+    Aggregate(Vec<u32>),
+    // arguments; instructions are not recorded
+    Local,
+    // `null`, strings, unnamed globals, etc.
+    Other,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Node<'a> {
+    String(Option<&'a str>),
+    Node(Option<Metadata<'a>>),
+}
+
+struct Function<'a> {
+    name: &'a str,
+    ty: u64,
+    internal: bool,
+    declaration: bool,
+    personality: Option<u32>,
+}
+
+struct Variable<'a> {
+    // value ID
+    id: u32,
+    name: Option<&'a str>,
+    ty: u64,
+    internal: bool,
+    constant: bool,
+    init: Option<u32>,
+}
+
+struct Module<'a> {
+    strtab: &'a [u8],
+    strings: &'a Strings,
+    types: Vec<RawType<'a>>,
+    // functions, global variables, aliases and constants by value ID
+    values: Vec<Value<'a>>,
+    functions: Vec<Function<'a>>,
+    variables: Vec<Variable<'a>>,
+    aliases: Vec<(&'a str, u32)>,
+    // metadata nodes by metadata ID
+    metadata: Vec<Node<'a>>,
+    // number of module-level metadata nodes; function-level nodes are renumbered after them
+    module_metadata: u32,
+    next_metadata: u32,
+    // `Location` nodes made up for the debug locations of indirect calls, by scope
+    locations: HashMap<u32, u32>,
+    invariant_load: Option<u64>,
+    dbg: Option<u64>,
+    // `!dbg` attachments of the global variables, by value ID
+    attachments: HashMap<u32, u32>,
+    items: Vec<Item<'a>>,
+}
+
+impl<'a> Module<'a> {
+    fn new(strtab: &'a [u8], strings: &'a Strings) -> Self {
+        Module {
+            strtab,
+            strings,
+            types: vec![],
+            values: vec![],
+            functions: vec![],
+            variables: vec![],
+            aliases: vec![],
+            metadata: vec![],
+            module_metadata: 0,
+            next_metadata: 0,
+            locations: HashMap::new(),
+            invariant_load: None,
+            dbg: None,
+            attachments: HashMap::new(),
+            items: vec![],
+        }
+    }
+
+    fn read(mut self, reader: &mut Reader<'a>) -> Result<Vec<Item<'a>>, failure::Error> {
+        // function bodies appear in the same order as the functions they belong to
+        let mut bodies = vec![];
+        let mut defines = HashMap::new();
+        loop {
+            match reader.next()? {
+                Entry::Record(record) => match record.code {
+                    MODULE_CODE_VERSION if record.ops.first().cloned().unwrap_or(0) < 2 => {
+                        return Err(failure::err_msg(
+                            "unsupported LLVM bitcode version; LLVM 5 or newer is required",
+                        ));
+                    }
+
+                    // [strtab offset, strtab size, type, flags, initid, linkage, ..]
+                    MODULE_CODE_GLOBALVAR => {
+                        let ops = &record.ops;
+                        let name = self.name(ops)?;
+                        let flags = op(ops, 3)?;
+                        let ty = op(ops, 2)?;
+                        // the type was the pointer type before explicit types
+                        let ty = match self.types.get(ty as usize) {
+                            Some(RawType::Pointer(pointee)) if flags & 2 == 0 => *pointee,
+                            _ => ty,
+                        };
+                        let id = self.values.len() as u32;
+                        self.values
+                            .push(name.map(Value::Global).unwrap_or(Value::Other));
+                        self.variables.push(Variable {
+                            id,
+                            name,
+                            ty,
+                            internal: internal(op(ops, 5)?),
+                            constant: flags & 1 != 0,
+                            init: op(ops, 4)?.checked_sub(1).map(|id| id as u32),
+                        });
+                    }
+
+                    // [strtab offset, strtab size, type, callingconv, isproto, linkage,
+                    //  paramattrs, alignment, section, visibility, gc, unnamed_addr, prologuedata,
+                    //  dllstorageclass, comdat, prefixdata, personalityfn, ..]
+                    MODULE_CODE_FUNCTION => {
+                        let ops = &record.ops;
+                        let name = self.name(ops)?;
+                        let declaration = op(ops, 4)? != 0;
+                        self.values
+                            .push(name.map(Value::Global).unwrap_or(Value::Other));
+                        if !declaration {
+                            bodies.push(self.functions.len());
+                        }
+                        self.functions.push(Function {
+                            name: name.unwrap_or(""),
+                            ty: op(ops, 2)?,
+                            internal: internal(op(ops, 5)?),
+                            declaration,
+                            personality: ops
+                                .get(16)
+                                .and_then(|id| id.checked_sub(1))
+                                .map(|id| id as u32),
+                        });
+                    }
+
+                    // [strtab offset, strtab size, type, addrspace, aliasee, linkage, ..]
+                    MODULE_CODE_ALIAS => {
+                        let name = self.name(&record.ops)?;
+                        self.values
+                            .push(name.map(Value::Global).unwrap_or(Value::Other));
+                        if let Some(name) = name {
+                            self.aliases.push((name, op(&record.ops, 4)? as u32));
+                        }
+                    }
+
+                    MODULE_CODE_IFUNC => {
+                        let name = self.name(&record.ops)?;
+                        self.values
+                            .push(name.map(Value::Global).unwrap_or(Value::Other));
+                    }
+
+                    _ => {}
+                },
+
+                Entry::Block(TYPE_BLOCK) => {
+                    reader.enter()?;
+                    self.types(reader)?;
+                }
+
+                Entry::Block(CONSTANTS_BLOCK) => {
+                    reader.enter()?;
+                    self.constants(reader)?;
+                }
+
+                Entry::Block(METADATA_BLOCK) => {
+                    reader.enter()?;
+                    self.metadata(reader)?;
+                    self.module_metadata = self.metadata.len() as u32;
+                    self.next_metadata = self.module_metadata;
+                }
+
+                Entry::Block(METADATA_KIND_BLOCK) => {
+                    reader.enter()?;
+                    self.kinds(reader)?;
+                }
+
+                Entry::Block(FUNCTION_BLOCK) => {
+                    let index = *bodies
+                        .get(defines.len())
+                        .ok_or_else(|| bug("function body without a function"))?;
+                    reader.enter()?;
+                    let define = self.function(reader, index)?;
+                    defines.insert(index, define);
+                }
+
+                Entry::Block(_) => reader.skip()?,
+
+                Entry::End => break,
+            }
+        }
+
+        let mut items = vec![];
+        for variable in &self.variables {
+            let mut references = vec![];
+            if let Some(init) = variable.init {
+                self.globals(init, &mut references);
+            }
+
+            items.push(Item::Global(Global {
+                name: variable.name,
+                internal: variable.internal,
+                references,
+                vtable: if variable.constant {
+                    self.vtable(variable)
+                } else {
+                    None
+                },
+            }));
+        }
+
+        for (name, aliasee) in &self.aliases {
+            if let Some(aliasee) = self.global(*aliasee) {
+                items.push(Item::Alias(name, aliasee));
+            }
+        }
+
+        for (index, function) in self.functions.iter().enumerate() {
+            if let Some(define) = defines.remove(&index) {
+                items.push(Item::Define(define));
+            } else if function.declaration {
+                let sig = if function.name.starts_with("llvm.") {
+                    // llvm intrinsic; we don't care about these
+                    None
+                } else {
+                    self.sig(function.ty)
+                };
+
+                items.push(Item::Declare(Declare {
+                    name: function.name,
+                    sig,
+                }));
+            }
+        }
+
+        for (id, node) in self.metadata[..self.module_metadata as usize]
+            .iter()
+            .enumerate()
+        {
+            if let Node::Node(Some(md)) = node {
+                items.push(Item::Metadata(Some((id as u32, *md))));
+            }
+        }
+        items.append(&mut self.items);
+
+        Ok(items)
+    }
+
+    // the name of a global; `None` if it's unnamed
+    fn name(&self, ops: &[u64]) -> Result<Option<&'a str>, failure::Error> {
+        let (offset, size) = (op(ops, 0)? as usize, op(ops, 1)? as usize);
+        if size == 0 {
+            return Ok(None);
+        }
+
+        let bytes = self
+            .strtab
+            .get(offset..offset + size)
+            .ok_or_else(|| bug("name out of the bounds of the string table"))?;
+        str::from_utf8(bytes)
+            .map(Some)
+            .map_err(|_| bug("name is not valid UTF-8"))
+    }
+
+    fn types(&mut self, reader: &mut Reader<'a>) -> Result<(), failure::Error> {
+        let mut name = None;
+        loop {
+            let record = match reader.next()? {
+                Entry::Record(record) => record,
+                Entry::Block(_) => {
+                    reader.skip()?;
+                    continue;
+                }
+                Entry::End => return Ok(()),
+            };
+
+            let ops = &record.ops;
+            let ty = match record.code {
+                TYPE_CODE_NUMENTRY => continue,
+                TYPE_CODE_VOID => RawType::Void,
+                TYPE_CODE_FLOAT => RawType::Float,
+                TYPE_CODE_DOUBLE => RawType::Double,
+                TYPE_CODE_LABEL => RawType::Label,
+                TYPE_CODE_METADATA => RawType::Metadata,
+                TYPE_CODE_INTEGER => RawType::Integer(op(ops, 0)? as usize),
+                TYPE_CODE_POINTER => RawType::Pointer(op(ops, 0)?),
+                TYPE_CODE_OPAQUE_POINTER => RawType::OpaquePointer,
+                TYPE_CODE_ARRAY => RawType::Array(op(ops, 0)? as usize, op(ops, 1)?),
+                TYPE_CODE_VECTOR => RawType::Vector(op(ops, 0)? as usize, op(ops, 1)?),
+                TYPE_CODE_STRUCT_ANON => RawType::Struct {
+                    packed: op(ops, 0)? != 0,
+                    fields: ops[1..].to_vec(),
+                },
+                TYPE_CODE_STRUCT_NAME => {
+                    name = Some(
+                        self.strings
+                            .alloc(ops.iter().map(|c| *c as u8 as char).collect()),
+                    );
+                    continue;
+                }
+                TYPE_CODE_STRUCT_NAMED | TYPE_CODE_OPAQUE => {
+                    RawType::Alias(name.take().unwrap_or(""))
+                }
+                // [vararg, retty, paramty..]
+                TYPE_CODE_FUNCTION => RawType::Fn {
+                    output: op(ops, 1)?,
+                    inputs: ops[2..].to_vec(),
+                    variadic: op(ops, 0)? != 0,
+                },
+                // [vararg, attrid, retty, paramty..]
+                TYPE_CODE_FUNCTION_OLD => RawType::Fn {
+                    output: op(ops, 2)?,
+                    inputs: ops[3..].to_vec(),
+                    variadic: op(ops, 0)? != 0,
+                },
+                10 => RawType::Other("half"),
+                13 => RawType::Other("x86_fp80"),
+                14 => RawType::Other("fp128"),
+                15 => RawType::Other("ppc_fp128"),
+                17 => RawType::Other("x86_mmx"),
+                22 => RawType::Other("token"),
+                23 => RawType::Other("bfloat"),
+                24 => RawType::Other("x86_amx"),
+                _ => RawType::Other("?"),
+            };
+            self.types.push(ty);
+        }
+    }
+
+    fn ty(&self, id: u64) -> Option<Type<'a>> {
+        Some(match self.types.get(id as usize)? {
+            RawType::Void | RawType::Label => return None,
+            RawType::Metadata => Type::Alias("metadata"),
+            RawType::Integer(bits) => Type::Integer(*bits),
+            RawType::Float => Type::Float,
+            RawType::Double => Type::Double,
+            RawType::Pointer(pointee) => Type::Pointer(Box::new(self.ty(*pointee)?)),
+            RawType::OpaquePointer => Type::OpaquePointer,
+            RawType::Array(count, elem) => Type::Array(*count, Box::new(self.ty(*elem)?)),
+            RawType::Vector(count, elem) => Type::Vector(*count, Box::new(self.ty(*elem)?)),
+            RawType::Struct { packed, fields } => {
+                let fields = fields
+                    .iter()
+                    .map(|field| self.ty(*field))
+                    .collect::<Option<Vec<_>>>()?;
+                if *packed {
+                    Type::PackedStruct(fields)
+                } else {
+                    Type::Struct(fields)
+                }
+            }
+            RawType::Alias(name) => Type::Alias(name),
+            RawType::Fn { .. } => Type::Fn(self.sig(id)?),
+            RawType::Other(name) => Type::Alias(name),
+        })
+    }
+
+    fn sig(&self, id: u64) -> Option<FnSig<'a>> {
+        match self.types.get(id as usize)? {
+            RawType::Fn {
+                inputs,
+                output,
+                variadic,
+            } => Some(FnSig {
+                inputs: inputs
+                    .iter()
+                    .map(|input| self.ty(*input))
+                    .collect::<Option<_>>()?,
+                output: self.ty(*output).map(Box::new),
+                variadic: *variadic,
+            }),
+            // the type of functions was a pointer type before explicit types
+            RawType::Pointer(pointee) => self.sig(*pointee),
+            _ => None,
+        }
+    }
+
+    fn constants(&mut self, reader: &mut Reader<'a>) -> Result<(), failure::Error> {
+        loop {
+            let record = match reader.next()? {
+                Entry::Record(record) => record,
+                Entry::Block(_) => {
+                    reader.skip()?;
+                    continue;
+                }
+                Entry::End => return Ok(()),
+            };
+
+            // operands are absolute value IDs
+            let ops = &record.ops;
+            let id = |i: usize| op(ops, i).map(|id| id as u32);
+            let value = match record.code {
+                CST_CODE_SETTYPE => continue,
+                CST_CODE_INTEGER => Value::Int(signed(op(ops, 0)?)),
+                CST_CODE_AGGREGATE => Value::Aggregate(ops.iter().map(|id| *id as u32).collect()),
+                // [opcode, lhs, rhs, ..]
+                CST_CODE_CE_BINOP => Value::Expr(vec![id(1)?, id(2)?]),
+                // [opcode, opty, opval]
+                CST_CODE_CE_CAST => Value::Cast(id(2)?),
+                CST_CODE_CE_SELECT => Value::Expr(vec![id(0)?, id(1)?, id(2)?]),
+                // [pointee type, (flags), (inrange), (type, value)..]
+                CST_CODE_CE_GEP_OLD
+                | CST_CODE_CE_INBOUNDS_GEP
+                | CST_CODE_CE_GEP_WITH_INRANGE_INDEX_OLD
+                | CST_CODE_CE_GEP_WITH_INRANGE
+                | CST_CODE_CE_GEP => {
+                    let start = match record.code {
+                        CST_CODE_CE_GEP_OLD | CST_CODE_CE_INBOUNDS_GEP => 2 - ops.len() % 2,
+                        CST_CODE_CE_GEP_WITH_INRANGE => 5,
+                        _ => 2,
+                    };
+                    Value::Expr(
+                        ops.get(start..)
+                            .unwrap_or(&[])
+                            .chunks(2)
+                            .filter_map(|pair| pair.get(1).map(|id| *id as u32))
+                            .collect(),
+                    )
+                }
+                // [type, value]
+                CST_CODE_DSO_LOCAL_EQUIVALENT | CST_CODE_NO_CFI_VALUE => Value::Cast(id(1)?),
+                // [pointer, key, discriminator, address discriminator, ..]
+                CST_CODE_PTRAUTH | CST_CODE_PTRAUTH2 => Value::Cast(id(0)?),
+                // [(fnty), flags, asmstrsize, asmstr.., constraintsize, constraints..]
+                CST_CODE_INLINEASM_OLD
+                | CST_CODE_INLINEASM_OLD2
+                | CST_CODE_INLINEASM_OLD3
+                | CST_CODE_INLINEASM => {
+                    let start = if record.code >= CST_CODE_INLINEASM_OLD3 {
+                        2
+                    } else {
+                        1
+                    };
+                    let len = op(ops, start)? as usize;
+                    let template = ops
+                        .get(start + 1..start + 1 + len)
+                        .ok_or_else(|| bug("truncated inline assembly"))?;
+                    let start = start + 1 + len;
+                    let len = op(ops, start)? as usize;
+                    let constraints = ops
+                        .get(start + 1..start + 1 + len)
+                        .ok_or_else(|| bug("truncated inline assembly"))?;
+                    Value::Asm(
+                        self.strings.alloc(escape(template)),
+                        self.strings.alloc(escape(constraints)),
+                    )
+                }
+                _ => Value::Other,
+            };
+            self.values.push(value);
+        }
+    }
+
+    fn metadata(&mut self, reader: &mut Reader<'a>) -> Result<(), failure::Error> {
+        loop {
+            let record = match reader.next()? {
+                Entry::Record(record) => record,
+                Entry::Block(_) => {
+                    reader.skip()?;
+                    continue;
+                }
+                Entry::End => return Ok(()),
+            };
+
+            let ops = &record.ops;
+            // fields that refer to other nodes hold their ID plus one; zero means "none"
+            let field = |i: usize| {
+                ops.get(i)
+                    .and_then(|id| id.checked_sub(1))
+                    .map(|id| id as u32)
+            };
+            let node = match record.code {
+                // [count, offset, blob]: the lengths of the strings, VBR6 encoded, followed by
+                // the strings
+                METADATA_CODE_STRINGS => {
+                    let (count, offset) = (op(ops, 0)? as usize, op(ops, 1)? as usize);
+                    let blob = record.blob.unwrap_or(&[]);
+                    let (lengths, mut chars) = (blob.get(..offset), blob.get(offset..));
+                    let lengths = stream::vbr6s(
+                        lengths.ok_or_else(|| bug("truncated metadata strings"))?,
+                        count,
+                    )?;
+                    for len in lengths {
+                        let s = chars.and_then(|chars| chars.get(..len as usize));
+                        chars = chars.and_then(|chars| chars.get(len as usize..));
+                        self.metadata
+                            .push(Node::String(s.and_then(|s| str::from_utf8(s).ok())));
+                    }
+                    continue;
+                }
+
+                METADATA_CODE_NAME
+                | METADATA_CODE_KIND
+                | METADATA_CODE_NAMED_NODE
+                | METADATA_CODE_ATTACHMENT
+                | METADATA_CODE_INDEX_OFFSET
+                | METADATA_CODE_INDEX => continue,
+
+                // [value, (kind, node)..]; unlike in the other records, the nodes are not offset by
+                // one
+                METADATA_CODE_GLOBAL_DECL_ATTACHMENT => {
+                    if let Some(pair) = ops
+                        .get(1..)
+                        .unwrap_or(&[])
+                        .chunks(2)
+                        .find(|pair| pair.len() == 2 && Some(pair[0]) == self.dbg)
+                    {
+                        self.attachments.insert(op(ops, 0)? as u32, pair[1] as u32);
+                    }
+                    continue;
+                }
+
+                // [flags, scope, name, linkageName, ..]
+                METADATA_CODE_SUBPROGRAM => Some(Metadata::Subprogram {
+                    linkage_name: field(3).and_then(|id| match self.metadata.get(id as usize) {
+                        Some(Node::String(s)) => *s,
+                        _ => None,
+                    }),
+                }),
+
+                // [distinct, scope, ..]
+                METADATA_CODE_LEXICAL_BLOCK | METADATA_CODE_LEXICAL_BLOCK_FILE => {
+                    field(1).map(|scope| Metadata::LexicalBlock { scope })
+                }
+
+                // [distinct, var, expr]
+                METADATA_CODE_GLOBAL_VAR_EXPR => {
+                    field(1).map(|var| Metadata::GlobalVariableExpression { var })
+                }
+
+                // [distinct, scope, name, ..]
+                METADATA_CODE_GLOBAL_VAR => Some(Metadata::GlobalVariable {
+                    name: field(2).and_then(|id| match self.metadata.get(id as usize) {
+                        Some(Node::String(s)) => *s,
+                        _ => None,
+                    }),
+                }),
+
+                _ => None,
+            };
+            self.metadata.push(Node::Node(node));
+        }
+    }
+
+    fn kinds(&mut self, reader: &mut Reader<'a>) -> Result<(), failure::Error> {
+        loop {
+            match reader.next()? {
+                // [id, name..]
+                Entry::Record(Record {
+                    code: METADATA_CODE_KIND,
+                    ops,
+                    ..
+                }) => {
+                    if ops.len() > 1 && ops[1..].iter().map(|c| *c as u8).eq(*b"invariant.load") {
+                        self.invariant_load = Some(ops[0]);
+                    } else if ops.len() > 1 && ops[1..].iter().map(|c| *c as u8).eq(*b"dbg") {
+                        self.dbg = Some(ops[0]);
+                    }
+                }
+                Entry::Record(_) => {}
+                Entry::Block(_) => reader.skip()?,
+                Entry::End => return Ok(()),
+            }
+        }
+    }
+
+    fn function(
+        &mut self,
+        reader: &mut Reader<'a>,
+        index: usize,
+    ) -> Result<Define<'a>, failure::Error> {
+        let function = &self.functions[index];
+        let (name, internal, personality) =
+            (function.name, function.internal, function.personality);
+        let (inputs, sig) = match (self.types.get(function.ty as usize), self.sig(function.ty)) {
+            (Some(RawType::Fn { inputs, .. }), Some(sig)) => (inputs.len(), sig),
+            _ => return Err(bug("function without a function type")),
+        };
+
+        let values = self.values.len();
+        let metadata = self.metadata.len();
+        self.values.extend((0..inputs).map(|_| Value::Local));
+
+        // the metadata attachments come after the instructions so these are processed once the
+        // whole body has been read
+        let mut records = vec![];
+        let mut invariant = HashSet::new();
+        loop {
+            match reader.next()? {
+                Entry::Record(record) => records.push(record),
+
+                Entry::Block(CONSTANTS_BLOCK) => {
+                    reader.enter()?;
+                    self.constants(reader)?;
+                }
+
+                Entry::Block(METADATA_BLOCK) => {
+                    reader.enter()?;
+                    self.metadata(reader)?;
+                }
+
+                // [instruction, (kind, node)..]
+                Entry::Block(METADATA_ATTACHMENT_BLOCK) => {
+                    reader.enter()?;
+                    loop {
+                        match reader.next()? {
+                            Entry::Record(Record { ops, .. }) if ops.len() % 2 == 1 => {
+                                if ops[1..]
+                                    .chunks(2)
+                                    .any(|pair| Some(pair[0]) == self.invariant_load)
+                                {
+                                    invariant.insert(ops[0]);
+                                }
+                            }
+                            Entry::Record(_) => {}
+                            Entry::Block(_) => reader.skip()?,
+                            Entry::End => break,
+                        }
+                    }
+                }
+
+                Entry::Block(_) => reader.skip()?,
+                Entry::End => break,
+            }
+        }
+
+        // function-level metadata nodes are numbered after the module-level ones in every
+        // function; give them IDs that are unique in the whole module
+        let mut renumbered = HashMap::new();
+        for id in metadata..self.metadata.len() {
+            if let Node::Node(Some(_)) = self.metadata[id] {
+                renumbered.insert(id as u32, self.next_metadata);
+                self.next_metadata += 1;
+            }
+        }
+        let module_metadata = self.module_metadata;
+        let renumber = |id: u32| {
+            if id < module_metadata {
+                Some(id)
+            } else {
+                renumbered.get(&id).cloned()
+            }
+        };
+        for id in metadata..self.metadata.len() {
+            if let Node::Node(Some(md)) = self.metadata[id] {
+                let md = match md {
+                    Metadata::LexicalBlock { scope } => Metadata::LexicalBlock {
+                        scope: renumber(scope).unwrap_or(u32::MAX),
+                    },
+                    md => md,
+                };
+                self.items
+                    .push(Item::Metadata(Some((renumbered[&(id as u32)], md))));
+            }
+        }
+
+        let mut stmts = vec![];
+        let mut references = vec![];
+        let mut stores = vec![];
+        let mut calls = vec![];
+        // values that hold a pointer into an aggregate
+        let mut geps = HashMap::new();
+        // where the values held by locals were loaded from
+        let mut origins = HashMap::new();
+        // ID of the value the next instruction defines; operands are relative to it
+        let mut next = self.values.len() as u32;
+        // index of the next instruction; metadata attachments refer to instructions by index
+        let mut inst = 0;
+        // the debug location records that follow an instruction apply to it; only those of
+        // indirect calls are kept
+        let mut last_call = None;
+        let mut last_scope = None;
+        for record in &records {
+            let ops = &record.ops[..];
+            match record.code {
+                // [line, column, scope, inlinedAt, ..]
+                FUNC_CODE_DEBUG_LOC | FUNC_CODE_DEBUG_LOC_AGAIN => {
+                    if record.code == FUNC_CODE_DEBUG_LOC {
+                        last_scope = ops
+                            .get(2)
+                            .and_then(|id| id.checked_sub(1))
+                            .and_then(|id| renumber(id as u32));
+                    }
+
+                    if let (Some(i), Some(scope)) = (last_call, last_scope) {
+                        let location = self.location(scope);
+                        if let Some(Stmt::IndirectCall(_, dbg, _)) = stmts.get_mut(i) {
+                            *dbg = Some(location);
+                        }
+                    }
+                    continue;
+                }
+
+                FUNC_CODE_DECLAREBLOCKS | FUNC_CODE_OPERAND_BUNDLE | FUNC_CODE_BLOCKADDR_USERS => {
+                    continue
+                }
+
+                code if FUNC_CODE_DEBUG_RECORDS.contains(&code) => continue,
+
+                _ => {}
+            }
+            last_call = None;
+
+            let mut globals = vec![];
+            let defines_value;
+            match record.code {
+                FUNC_CODE_INST_CALL | FUNC_CODE_INST_INVOKE | FUNC_CODE_INST_CALLBR => {
+                    // [paramattrs, cc, ..]
+                    let cc = op(ops, 1)?;
+                    let (mut i, explicit) = match record.code {
+                        // [.., (fmf), fnty, fnid, args..]
+                        FUNC_CODE_INST_CALL => (
+                            if cc & CALL_FMF != 0 { 3 } else { 2 },
+                            cc & CALL_EXPLICIT_TYPE,
+                        ),
+                        // [.., normal bb, unwind bb, fnty, fnid, args..]
+                        FUNC_CODE_INST_INVOKE => (4, cc & INVOKE_EXPLICIT_TYPE),
+                        // [.., default bb, numindirect, indirect bbs.., fnty, fnid, args..]
+                        _ => (4 + op(ops, 3)? as usize, cc & CALL_EXPLICIT_TYPE),
+                    };
+                    if explicit == 0 {
+                        return Err(failure::err_msg(
+                            "unsupported LLVM bitcode: call without explicit function type",
+                        ));
+                    }
+
+                    let fnty = op(ops, i)?;
+                    i += 1;
+                    let callee = value_and_type(ops, &mut i, next)?;
+                    let (inputs, output) = match self.types.get(fnty as usize) {
+                        Some(RawType::Fn { inputs, output, .. }) => (inputs, *output),
+                        _ => return Err(bug("call without a function type")),
+                    };
+                    let mut args = vec![];
+                    for input in inputs {
+                        match self.types.get(*input as usize) {
+                            // basic blocks and metadata are not values
+                            Some(RawType::Label) | Some(RawType::Metadata) => i += 1,
+                            _ => {
+                                let arg = value(ops, &mut i, next)?;
+                                self.globals(arg, &mut globals);
+                                args.push(arg);
+                            }
+                        }
+                    }
+                    // variadic arguments
+                    while i < ops.len() {
+                        let arg = value_and_type(ops, &mut i, next)?;
+                        self.globals(arg, &mut globals);
+                        args.push(arg);
+                    }
+
+                    let stmt = match self.values.get(callee as usize) {
+                        Some(Value::Global(name)) => {
+                            calls.push((*name, globals.clone()));
+                            Stmt::DirectCall(name)
+                        }
+                        Some(Value::Asm(template, constraints)) => {
+                            let args = args.iter().map(|arg| self.global(*arg)).collect::<Vec<_>>();
+                            Stmt::Asm(template, define::asm_operands(constraints, &args))
+                        }
+                        Some(Value::Cast(id)) => Stmt::BitcastCall(self.global(*id)),
+                        _ => {
+                            let mut sig = self.sig(fnty).ok_or_else(|| bug("unknown signature"))?;
+                            let origin = origins.get(&callee).cloned();
+
+                            // see `define::parse`
+                            if let Some(Origin::Vtable(_)) = origin {
+                                if sig.inputs.first() == Some(&Type::OpaquePointer) {
+                                    sig.inputs[0] = Type::erased();
+                                }
+                            }
+
+                            last_call = Some(stmts.len());
+                            Stmt::IndirectCall(sig, None, origin)
+                        }
+                    };
+                    stmts.push(stmt);
+                    defines_value = !matches!(self.types.get(output as usize), Some(RawType::Void));
+                }
+
+                // [address, type, align, volatile, ..]
+                FUNC_CODE_INST_LOAD | FUNC_CODE_INST_LOADATOMIC => {
+                    let address = value_and_type(ops, &mut 0, next)?;
+                    if let Some(name) = self.global(address) {
+                        origins.insert(next, Origin::Static(name));
+                    } else if self.is_local(address) && invariant.contains(&inst) {
+                        // rustc marks the loads from vtables as invariant; no `getelementptr`
+                        // means that the method is at the start of the vtable
+                        let slot = geps.get(&address).cloned().unwrap_or(Slot {
+                            ty: Type::Integer(8),
+                            index: 0,
+                        });
+                        origins.insert(next, Origin::Vtable(slot));
+                    } else {
+                        self.globals(address, &mut globals);
+                    }
+                    stmts.push(Stmt::Other);
+                    defines_value = true;
+                }
+
+                // [address, value, align, volatile, ..]
+                FUNC_CODE_INST_STORE | FUNC_CODE_INST_STOREATOMIC => {
+                    let mut i = 0;
+                    let address = value_and_type(ops, &mut i, next)?;
+                    let value = value_and_type(ops, &mut i, next)?;
+                    self.globals(value, &mut globals);
+
+                    let mut addresses = vec![];
+                    self.globals(address, &mut addresses);
+                    if let Some(name) = self.global(address) {
+                        addresses.remove(0);
+                        if let Some(value) = self.global(value) {
+                            stores.push((name, Some(value)));
+                        } else if self.is_local(value) {
+                            stores.push((name, None));
+                        }
+                    }
+                    globals.extend(addresses);
+                    stmts.push(Stmt::Other);
+                    defines_value = false;
+                }
+
+                // [flags, source element type, base, indices..]
+                FUNC_CODE_INST_GEP => {
+                    let mut i = 2;
+                    let mut operands = vec![];
+                    while i < ops.len() {
+                        operands.push(value_and_type(ops, &mut i, next)?);
+                    }
+                    for operand in &operands {
+                        self.globals(*operand, &mut globals);
+                    }
+
+                    // only the single index form is considered
+                    if let [_, index] = operands[..] {
+                        if let (Some(Value::Int(index)), Some(ty)) =
+                            (self.values.get(index as usize), self.ty(op(ops, 1)?))
+                        {
+                            if *index >= 0 {
+                                geps.insert(
+                                    next,
+                                    Slot {
+                                        ty,
+                                        index: *index as u64,
+                                    },
+                                );
+                            }
+                        }
+                    }
+                    stmts.push(Stmt::Other);
+                    defines_value = true;
+                }
+
+                // [type, (value, bb)..]; values are signed
+                FUNC_CODE_INST_PHI => {
+                    for pair in ops.get(1..).unwrap_or(&[]).chunks_exact(2) {
+                        let id = i64::from(next) - signed(pair[0]);
+                        self.globals(id as u32, &mut globals);
+                    }
+                    stmts.push(Stmt::Other);
+                    defines_value = true;
+                }
+
+                code => {
+                    for operand in operands(code, ops, next)? {
+                        self.globals(operand, &mut globals);
+                    }
+
+                    stmts.push(if code == FUNC_CODE_INST_RESUME {
+                        Stmt::Resume
+                    } else {
+                        Stmt::Other
+                    });
+                    defines_value = FUNC_CODE_VALUES.contains(&code);
+                }
+            }
+
+            references.extend(globals);
+            inst += 1;
+            if defines_value {
+                next += 1;
+            }
+        }
+
+        references.sort();
+        references.dedup();
+
+        let personality = personality.and_then(|id| self.global(id));
+        self.values.truncate(values);
+        self.metadata.truncate(metadata);
+
+        Ok(Define {
+            name,
+            internal,
+            sig,
+            stmts,
+            references,
+            stores,
+            calls,
+            personality,
+        })
+    }
+
+    // a `DILocation` with the given scope
+    fn location(&mut self, scope: u32) -> u32 {
+        if let Some(id) = self.locations.get(&scope) {
+            return *id;
+        }
+
+        let id = self.next_metadata;
+        self.next_metadata += 1;
+        self.locations.insert(scope, id);
+        self.items
+            .push(Item::Metadata(Some((id, Metadata::Location { scope }))));
+        id
+    }
+
+    fn is_local(&self, id: u32) -> bool {
+        match self.values.get(id as usize) {
+            // instructions
+            None => true,
+            Some(value) => matches!(value, Value::Local),
+        }
+    }
+
+    // the global a value is, or the first global a constant expression uses
+    fn global(&self, id: u32) -> Option<&'a str> {
+        match self.values.get(id as usize)? {
+            Value::Global(name) => Some(name),
+            Value::Cast(_) | Value::Expr(_) | Value::Aggregate(_) => {
+                let mut globals = vec![];
+                self.globals(id, &mut globals);
+                globals.first().cloned()
+            }
+            _ => None,
+        }
+    }
+
+    // the globals a value uses
+    fn globals(&self, id: u32, globals: &mut Vec<&'a str>) {
+        match self.values.get(id as usize) {
+            Some(Value::Global(name)) => globals.push(name),
+            Some(Value::Cast(id)) => self.globals(*id, globals),
+            Some(Value::Expr(ids)) | Some(Value::Aggregate(ids)) => {
+                for id in ids {
+                    self.globals(*id, globals);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn vtable(&self, variable: &Variable<'a>) -> Option<Vtable<'a>> {
+        let (fields, elems) = match (
+            self.types.get(variable.ty as usize)?,
+            self.values.get(variable.init? as usize)?,
+        ) {
+            (RawType::Struct { fields, .. }, Value::Aggregate(elems)) => (fields, elems),
+            _ => return None,
+        };
+
+        let fields = fields
+            .iter()
+            .zip(elems)
+            .map(|(ty, elem)| {
+                let function = match self.values.get(*elem as usize)? {
+                    Value::Global(name) => Some(*name),
+                    Value::Cast(id) => match self.values.get(*id as usize)? {
+                        Value::Global(name) => Some(*name),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                Some((self.ty(*ty)?, function))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        if item::is_vtable(variable.name, variable.internal, &fields) {
+            Some(Vtable {
+                fields,
+                dbg: self.attachments.get(&variable.id).cloned(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+fn op(ops: &[u64], i: usize) -> Result<u64, failure::Error> {
+    ops.get(i)
+        .cloned()
+        .ok_or_else(|| bug("record is too short"))
+}
+
+// a value ID relative to the ID of the instruction
+fn value(ops: &[u64], i: &mut usize, next: u32) -> Result<u32, failure::Error> {
+    let relative = op(ops, *i)?;
+    *i += 1;
+    Ok(next.wrapping_sub(relative as u32))
+}
+
+// a relative value ID followed, if the value is a forward reference, by its type
+fn value_and_type(ops: &[u64], i: &mut usize, next: u32) -> Result<u32, failure::Error> {
+    let id = value(ops, i, next)?;
+    if id >= next {
+        *i += 1;
+    }
+    Ok(id)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    // relative value ID followed by its type if the value is a forward reference
+    ValueAndType,
+    // relative value ID
+    Value,
+    // `ValueAndType` until the end of the record
+    ValuesAndTypes,
+    // anything else: types, basic blocks, flags, etc.
+    Skip,
+}
+
+// the values used by instructions other than calls, loads, stores, `getelementptr` and `phi`
+fn operands(code: u64, ops: &[u64], next: u32) -> Result<Vec<u32>, failure::Error> {
+    use self::Field::{Skip, Value as R, ValueAndType as V, ValuesAndTypes as Vs};
+
+    let layout: &[Field] = match code {
+        // binop, cmp, cmp2: [V, R, opcode, ..]; store (old), atomicrmw (old): [V, R, ..]
+        2 | 9 | 28 | 24 | 38 => &[V, R],
+        // cast: [V, ty, opcode]; extractvalue: [V, idx..]; unop: [V, opcode]; freeze: [V]
+        3 | 26 | 56 | 58 => &[V],
+        // extractelement: [V, V]; insertvalue, atomicrmw: [V, V, ..]
+        6 | 27 | 59 => &[V, V],
+        // insertelement, vselect: [V, R, V]
+        7 | 29 => &[V, R, V],
+        // shufflevector: [V, R, R]; cmpxchg (old): [V, R, R, ..]
+        8 | 37 => &[V, R, R],
+        // cmpxchg: [V, V, R, ..]
+        46 => &[V, V, R],
+        // getelementptr (old): [V..]; ret: [V..]; resume: [V]
+        4 | 30 | 10 | 39 => &[Vs],
+        // select (old): [ty, R, R, R]
+        5 => &[Skip, R, R, R],
+        // conditional br: [bb, bb, R]
+        11 if ops.len() == 3 => &[Skip, Skip, R],
+        // switch: [ty, R, bb, (case value, bb)..] where the case values are constant integers;
+        // indirectbr: [ty, R, bb..]; va_arg: [ty, R, ty]
+        12 | 31 | 23 => &[Skip, R],
+        // cleanupret: [R, (bb)]; catchret: [R, bb]; catchswitch: [R, ..]
+        48 | 49 | 52 => &[R],
+        // catchpad, cleanuppad: [R, number of args, V..]
+        50 | 51 => &[R, Skip, Vs],
+        // landingpad: [ty, is cleanup, number of clauses, (clause type, V)..]
+        47 => {
+            let mut values = vec![];
+            let mut i = 3;
+            while i + 1 < ops.len() {
+                i += 1;
+                values.push(value_and_type(ops, &mut i, next)?);
+            }
+            return Ok(values);
+        }
+        // unconditional br; alloca: [ty, ty, size, align] where the size is a constant;
+        // unreachable; fence; landingpad (old)
+        11 | 19 | 15 | 36 | 40 => &[],
+        _ => {
+            return Err(failure::format_err!(
+                "unsupported LLVM bitcode: unknown instruction record {}",
+                code
+            ))
+        }
+    };
+
+    let mut values = vec![];
+    let mut i = 0;
+    for field in layout {
+        match field {
+            V => values.push(value_and_type(ops, &mut i, next)?),
+            R => values.push(value(ops, &mut i, next)?),
+            Vs => {
+                while i < ops.len() {
+                    values.push(value_and_type(ops, &mut i, next)?);
+                }
+            }
+            Skip => i += 1,
+        }
+    }
+    Ok(values)
+}
+
+// signed VBR: the sign is stored in the least significant bit
+fn signed(value: u64) -> i64 {
+    if value & 1 == 0 {
+        (value >> 1) as i64
+    } else if value != 1 {
+        -((value >> 1) as i64)
+    } else {
+        // `-0` encodes the smallest value
+        i64::MIN
+    }
+}
+
+// `internal` and `private` linkages
+fn internal(linkage: u64) -> bool {
+    matches!(linkage, 3 | 9 | 13 | 14)
+}
+
+// prints a string as in the textual IR: `\` followed by two hex digits for anything that isn't
+// printable ASCII
+fn escape(chars: &[u64]) -> String {
+    let mut s = String::with_capacity(chars.len());
+    for c in chars {
+        let c = *c as u8;
+        if c.is_ascii_graphic() && c != b'"' && c != b'\\' || c == b' ' {
+            s.push(char::from(c));
+        } else {
+            s.push_str(&format!("\\{:02X}", c));
+        }
+    }
+    s
+}
+
+fn bug(what: &str) -> failure::Error {
+    failure::format_err!("malformed LLVM bitcode: {}", what)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, env, fs};
+
+    use crate::ir::{
+        self,
+        define::{Origin, Slot},
+        Item, Stmt, Type,
+    };
+
+    use super::Strings;
+
+    // regenerate with `rustc --crate-type=lib -O -C debuginfo=line-tables-only
+    // --emit=llvm-ir,llvm-bc fixture.rs` from within the `bitcode` directory; the symbol hashes
+    // asserted below depend on the compiler version (1.95.0)
+    #[test]
+    fn fixture() {
+        let ll = include_str!("bitcode/fixture.ll");
+        let bc = include_bytes!("bitcode/fixture.bc");
+        compare(ll, bc);
+
+        let strings = Strings::default();
+        let items = super::parse(bc, &strings).unwrap();
+        let define = |name: &str| {
+            items
+                .iter()
+                .find_map(|item| match item {
+                    Item::Define(def) if def.name.contains(name) => Some(def),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        let calls = |name: &str| {
+            define(name)
+                .stmts
+                .iter()
+                .filter(|stmt| !matches!(stmt, Stmt::Other))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(calls("3nop"), [Stmt::Asm("nop", vec![])]);
+
+        // dynamic dispatch
+        let area = calls("4area17hdaf");
+        assert_eq!(area.len(), 2);
+        for (stmt, index) in area.iter().zip(&[24, 32]) {
+            match stmt {
+                Stmt::IndirectCall(sig, Some(_), Some(Origin::Vtable(slot))) => {
+                    assert_eq!(sig.inputs[0], Type::erased());
+                    assert_eq!(
+                        *slot,
+                        Slot {
+                            ty: Type::Integer(8),
+                            index: *index,
+                        }
+                    );
+                }
+                _ => panic!("{:?}", stmt),
+            }
+        }
+
+        // function pointer stored in a static
+        let handler = "_ZN7fixture7HANDLER17h29fdb600c1b1395dE";
+        assert_eq!(
+            define("4init").stores,
+            [(
+                handler,
+                Some("_ZN7fixture15default_handler17h6cd1ff48fca8c069E")
+            )]
+        );
+        match &calls("8dispatch")[..] {
+            [Stmt::IndirectCall(_, _, Some(Origin::Static(name)))] => assert_eq!(*name, handler),
+            stmts => panic!("{:?}", stmts),
+        }
+
+        // `invoke` and `resume`
+        let with_drop = define("9with_drop");
+        assert_eq!(with_drop.personality, Some("rust_eh_personality"));
+        assert!(with_drop.stmts.contains(&Stmt::Resume));
+    }
+
+    // compares the items read from a bitcode file against those parsed from the textual IR of the
+    // same module: `LL=foo.ll BC=foo.bc cargo test -- --ignored textual`
+    #[test]
+    #[ignore]
+    fn textual() {
+        let ll = env::var("LL").expect("set LL to the path of the textual IR (.ll) file");
+        let bc = env::var("BC").expect("set BC to the path of the bitcode (.bc) file");
+        let ll = fs::read_to_string(ll).unwrap();
+        let bc = fs::read(bc).unwrap();
+        compare(&ll, &bc);
+    }
+
+    fn compare(ll: &str, bc: &[u8]) {
+        let text = normalize(ir::parse(ll).collect());
+        let strings = Strings::default();
+        let binary = normalize(super::parse(bc, &strings).unwrap());
+
+        let differences = text
+            .iter()
+            .filter(|(name, item)| binary.get(*name) != Some(item))
+            .map(|(name, item)| format!("{}\n  ll: {}\n  bc: {:?}", name, item, binary.get(name)))
+            .collect::<Vec<_>>();
+        assert!(
+            differences.is_empty(),
+            "{} items differ, e.g.\n{}",
+            differences.len(),
+            differences[..differences.len().min(20)].join("\n")
+        );
+        assert_eq!(text.len(), binary.len());
+    }
+
+    // the items by name; the statements that are not calls are removed and the debug locations
+    // are replaced by the function that contains the call, and the debug info of vtables by the
+    // name of the global variable, which is what they are used for
+    fn normalize(items: Vec<Item>) -> HashMap<String, String> {
+        let metadata = items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Metadata(Some((id, md))) => Some((*id, *md)),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+
+        items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Define(def) => {
+                    let mut def = def.clone();
+                    let mut subprograms = vec![];
+                    def.stmts
+                        .retain(|stmt| !matches!(stmt, Stmt::Label | Stmt::Comment | Stmt::Other));
+                    for stmt in &mut def.stmts {
+                        if let Stmt::IndirectCall(_, dbg, _) = stmt {
+                            subprograms.push(dbg.and_then(|dbg| ir::subprogram(&metadata, dbg)));
+                            *dbg = None;
+                        }
+                    }
+                    Some((def.name.to_owned(), format!("{:?} {:?}", def, subprograms)))
+                }
+                Item::Declare(decl) => Some((decl.name.to_owned(), format!("{:?}", decl))),
+                Item::Global(global) => global.name.map(|name| {
+                    let mut global = global.clone();
+                    let variable = global.vtable.as_mut().and_then(|vtable| {
+                        vtable
+                            .dbg
+                            .take()
+                            .and_then(|dbg| ir::global_variable(&metadata, dbg))
+                    });
+                    (name.to_owned(), format!("{:?} {:?}", global, variable))
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,322 @@
+; ModuleID = 'fixture.7a646b05706873bc-cgu.0'
+source_filename = "fixture.7a646b05706873bc-cgu.0"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-unknown-linux-gnu"
+
+@alloc_49306441e5a5f0c25805e0c836b8d6fb = private unnamed_addr constant [6 x i8] c"square", align 1
+@vtable.0 = private unnamed_addr constant <{ [24 x i8], ptr, ptr }> <{ [24 x i8] c"\00\00\00\00\00\00\00\00\04\00\00\00\00\00\00\00\04\00\00\00\00\00\00\00", ptr @"_ZN50_$LT$fixture..Square$u20$as$u20$fixture..Shape$GT$4area17hf57d1703043c3314E", ptr @"_ZN50_$LT$fixture..Square$u20$as$u20$fixture..Shape$GT$4name17h7a517a637fd0c33eE" }>, align 8
+@alloc_0c812808379efded5a4fb82d2790b556 = private unnamed_addr constant [2 x i8] c"\C0\00", align 1
+@_ZN7fixture7HANDLER17h29fdb600c1b1395dE = local_unnamed_addr global [8 x i8] zeroinitializer, align 8
+
+; <fixture::Square as fixture::Shape>::area
+; Function Attrs: mustprogress nofree norecurse nosync nounwind nonlazybind willreturn memory(argmem: read) uwtable
+define internal noundef i32 @"_ZN50_$LT$fixture..Square$u20$as$u20$fixture..Shape$GT$4area17hf57d1703043c3314E"(ptr noalias noundef readonly align 4 captures(none) dereferenceable(4) %self) unnamed_addr #0 !dbg !7 {
+start:
+  %_2 = load i32, ptr %self, align 4, !dbg !13, !noundef !12
+  %_0 = mul i32 %_2, %_2, !dbg !13
+  ret i32 %_0, !dbg !14
+}
+
+; <fixture::Square as fixture::Shape>::name
+; Function Attrs: mustprogress nofree norecurse nosync nounwind nonlazybind willreturn memory(none) uwtable
+define internal { ptr, i64 } @"_ZN50_$LT$fixture..Square$u20$as$u20$fixture..Shape$GT$4name17h7a517a637fd0c33eE"(ptr noalias readonly align 4 captures(none) %self) unnamed_addr #1 !dbg !15 {
+start:
+  ret { ptr, i64 } { ptr @alloc_49306441e5a5f0c25805e0c836b8d6fb, i64 6 }, !dbg !16
+}
+
+; fixture::square_area
+; Function Attrs: noinline nonlazybind uwtable
+define noundef i32 @_ZN7fixture11square_area17hd58d463a2ac906ccE(i32 noundef %side) unnamed_addr #2 !dbg !17 {
+start:
+  %_4 = alloca [4 x i8], align 4
+  call void @llvm.lifetime.start.p0(ptr nonnull %_4), !dbg !18
+  store i32 %side, ptr %_4, align 4, !dbg !18
+; call fixture::area
+  %_0 = call noundef i32 @_ZN7fixture4area17hdaf53877c00e0efbE(ptr noundef nonnull align 1 %_4, ptr noalias noundef readonly align 8 captures(address, read_provenance) dereferenceable(40) @vtable.0) #10, !dbg !19
+  call void @llvm.lifetime.end.p0(ptr nonnull %_4), !dbg !20
+  ret i32 %_0, !dbg !21
+}
+
+; fixture::default_handler
+; Function Attrs: mustprogress nofree norecurse nosync nounwind nonlazybind willreturn memory(none) uwtable
+define internal void @_ZN7fixture15default_handler17h6cd1ff48fca8c069E() unnamed_addr #1 !dbg !22 {
+start:
+  ret void, !dbg !23
+}
+
+; fixture::nop
+; Function Attrs: noinline nounwind nonlazybind uwtable
+define void @_ZN7fixture3nop17hf97dbd6ad42557f1E() unnamed_addr #3 !dbg !24 {
+start:
+  tail call void asm sideeffect alignstack inteldialect "nop", "~{dirflag},~{fpsr},~{flags},~{memory}"() #11, !dbg !25, !srcloc !26
+  ret void, !dbg !27
+}
+
+; fixture::area
+; Function Attrs: noinline nonlazybind uwtable
+define noundef i32 @_ZN7fixture4area17hdaf53877c00e0efbE(ptr noundef nonnull align 1 %shape.0, ptr noalias noundef readonly align 8 captures(none) dereferenceable(40) %shape.1) unnamed_addr #2 !dbg !28 {
+start:
+  %0 = getelementptr inbounds nuw i8, ptr %shape.1, i64 24, !dbg !29
+  %1 = load ptr, ptr %0, align 8, !dbg !29, !invariant.load !12, !nonnull !12
+  %_2 = tail call noundef i32 %1(ptr noundef nonnull align 1 %shape.0) #12, !dbg !30
+  %2 = getelementptr inbounds nuw i8, ptr %shape.1, i64 32, !dbg !31
+  %3 = load ptr, ptr %2, align 8, !dbg !31, !invariant.load !12, !nonnull !12
+  %4 = tail call { ptr, i64 } %3(ptr noundef nonnull align 1 %shape.0) #12, !dbg !32
+  %_5.1 = extractvalue { ptr, i64 } %4, 1, !dbg !32
+  %_3 = trunc i64 %_5.1 to i32, !dbg !31
+  %_0 = add i32 %_2, %_3, !dbg !29
+  ret i32 %_0, !dbg !33
+}
+
+; fixture::init
+; Function Attrs: mustprogress nofree noinline norecurse nosync nounwind nonlazybind willreturn memory(write, argmem: none, inaccessiblemem: none, target_mem0: none, target_mem1: none) uwtable
+define void @_ZN7fixture4init17h576da0e173e96276E() unnamed_addr #4 !dbg !34 {
+start:
+  store ptr @_ZN7fixture15default_handler17h6cd1ff48fca8c069E, ptr @_ZN7fixture7HANDLER17h29fdb600c1b1395dE, align 8, !dbg !35
+  ret void, !dbg !36
+}
+
+; fixture::format
+; Function Attrs: noinline nonlazybind uwtable
+define void @_ZN7fixture6format17h2399f7e6e4e89f22E(ptr dead_on_unwind noalias noundef writable sret([24 x i8]) align 8 captures(none) dereferenceable(24) %_0, i32 noundef %0) unnamed_addr #2 personality ptr @rust_eh_personality !dbg !37 {
+start:
+  %args = alloca [16 x i8], align 8
+  %x = alloca [4 x i8], align 4
+  store i32 %0, ptr %x, align 4
+  call void @llvm.lifetime.start.p0(ptr nonnull %args), !dbg !38
+  store ptr %x, ptr %args, align 8, !dbg !38
+  %_5.sroa.4.0..sroa_idx = getelementptr inbounds nuw i8, ptr %args, i64 8, !dbg !38
+  store ptr @_RNvXs8_NtNtNtCsgEmfK2I1SDS_4core3fmt3num3impmNtB9_7Display3fmt, ptr %_5.sroa.4.0..sroa_idx, align 8, !dbg !38
+; call alloc::fmt::format::format_inner
+  call void @_RNvNvNtCslNYArtu3iFV_5alloc3fmt6format12format_inner(ptr noalias noundef nonnull sret([24 x i8]) align 8 captures(none) dereferenceable(24) %_0, ptr noundef nonnull @alloc_0c812808379efded5a4fb82d2790b556, ptr noundef nonnull %args), !dbg !42
+  call void @llvm.lifetime.end.p0(ptr nonnull %args), !dbg !58
+  ret void, !dbg !59
+}
+
+; fixture::dispatch
+; Function Attrs: noinline nonlazybind uwtable
+define void @_ZN7fixture8dispatch17h5afbfeb225a9b505E() unnamed_addr #2 !dbg !60 {
+start:
+  %0 = load ptr, ptr @_ZN7fixture7HANDLER17h29fdb600c1b1395dE, align 8, !dbg !61, !noundef !12
+  %.not = icmp eq ptr %0, null, !dbg !63
+  br i1 %.not, label %bb2, label %bb1, !dbg !64
+
+bb1:                                              ; preds = %start
+  tail call void %0(), !dbg !65
+  br label %bb2, !dbg !65
+
+bb2:                                              ; preds = %bb1, %start
+  ret void, !dbg !66
+}
+
+; fixture::with_drop
+; Function Attrs: noinline nonlazybind uwtable
+define noundef range(i64 0, -9223372036854775808) i64 @_ZN7fixture9with_drop17hecb0a53eb21a27c2E(ptr dead_on_return noalias noundef readonly align 8 captures(none) dereferenceable(24) %v, ptr noundef nonnull readonly captures(none) %f) unnamed_addr #2 personality ptr @rust_eh_personality !dbg !67 {
+start:
+  invoke void %f()
+          to label %bb1 unwind label %cleanup, !dbg !68
+
+cleanup:                                          ; preds = %start
+  %0 = landingpad { ptr, i32 }
+          cleanup
+  %v.val = load i64, ptr %v, align 8, !dbg !69
+  %1 = icmp eq i64 %v.val, 0, !dbg !70
+  br i1 %1, label %bb4, label %_RNvXs_NtCslNYArtu3iFV_5alloc5allocNtB4_6GlobalNtNtCsgEmfK2I1SDS_4core5alloc9Allocator10deallocate.exit.i.i.i4.i, !dbg !70
+
+_RNvXs_NtCslNYArtu3iFV_5alloc5allocNtB4_6GlobalNtNtCsgEmfK2I1SDS_4core5alloc9Allocator10deallocate.exit.i.i.i4.i: ; preds = %cleanup
+  %2 = getelementptr inbounds nuw i8, ptr %v, i64 8, !dbg !69
+  %v.val1 = load ptr, ptr %2, align 8, !dbg !69, !nonnull !12, !noundef !12
+; call __rustc::__rust_dealloc
+  tail call void @_RNvCsfLfy6EI15iL_7___rustc14___rust_dealloc(ptr noundef nonnull %v.val1, i64 noundef %v.val, i64 noundef range(i64 1, -9223372036854775807) 1) #11, !dbg !88
+  br label %bb4, !dbg !101
+
+bb1:                                              ; preds = %start
+  %3 = getelementptr inbounds nuw i8, ptr %v, i64 16, !dbg !102
+  %_0 = load i64, ptr %3, align 8, !dbg !102, !noundef !12
+  %_4 = icmp sgt i64 %_0, -1, !dbg !108
+  tail call void @llvm.assume(i1 %_4), !dbg !110
+  %v.val2 = load i64, ptr %v, align 8, !dbg !69
+  %4 = icmp eq i64 %v.val2, 0, !dbg !111
+  br i1 %4, label %"_ZN4core3ptr46drop_in_place$LT$alloc..vec..Vec$LT$u8$GT$$GT$17h32e878ffdd2ac683E.exit5", label %_RNvXs_NtCslNYArtu3iFV_5alloc5allocNtB4_6GlobalNtNtCsgEmfK2I1SDS_4core5alloc9Allocator10deallocate.exit.i.i.i4.i4, !dbg !111
+
+_RNvXs_NtCslNYArtu3iFV_5alloc5allocNtB4_6GlobalNtNtCsgEmfK2I1SDS_4core5alloc9Allocator10deallocate.exit.i.i.i4.i4: ; preds = %bb1
+  %5 = getelementptr inbounds nuw i8, ptr %v, i64 8, !dbg !69
+  %v.val3 = load ptr, ptr %5, align 8, !dbg !69, !nonnull !12, !noundef !12
+; call __rustc::__rust_dealloc
+  tail call void @_RNvCsfLfy6EI15iL_7___rustc14___rust_dealloc(ptr noundef nonnull %v.val3, i64 noundef %v.val2, i64 noundef range(i64 1, -9223372036854775807) 1) #11, !dbg !117
+  br label %"_ZN4core3ptr46drop_in_place$LT$alloc..vec..Vec$LT$u8$GT$$GT$17h32e878ffdd2ac683E.exit5", !dbg !122
+
+"_ZN4core3ptr46drop_in_place$LT$alloc..vec..Vec$LT$u8$GT$$GT$17h32e878ffdd2ac683E.exit5": ; preds = %bb1, %_RNvXs_NtCslNYArtu3iFV_5alloc5allocNtB4_6GlobalNtNtCsgEmfK2I1SDS_4core5alloc9Allocator10deallocate.exit.i.i.i4.i4
+  ret i64 %_0, !dbg !123
+
+bb4:                                              ; preds = %_RNvXs_NtCslNYArtu3iFV_5alloc5allocNtB4_6GlobalNtNtCsgEmfK2I1SDS_4core5alloc9Allocator10deallocate.exit.i.i.i4.i, %cleanup
+  resume { ptr, i32 } %0, !dbg !124
+}
+
+; Function Attrs: mustprogress nocallback nofree nosync nounwind willreturn memory(inaccessiblemem: write)
+declare void @llvm.assume(i1 noundef) #5
+
+; Function Attrs: mustprogress nocallback nofree nosync nounwind willreturn memory(argmem: readwrite)
+declare void @llvm.lifetime.start.p0(ptr captures(none)) #6
+
+; Function Attrs: mustprogress nocallback nofree nosync nounwind willreturn memory(argmem: readwrite)
+declare void @llvm.lifetime.end.p0(ptr captures(none)) #6
+
+; __rustc::__rust_dealloc
+; Function Attrs: nounwind nonlazybind allockind("free") uwtable
+declare void @_RNvCsfLfy6EI15iL_7___rustc14___rust_dealloc(ptr allocptr noundef captures(address), i64 noundef, i64 noundef range(i64 1, -9223372036854775807)) unnamed_addr #7
+
+; Function Attrs: nounwind nonlazybind uwtable
+declare noundef range(i32 0, 10) i32 @rust_eh_personality(i32 noundef, i32 noundef, i64 noundef, ptr noundef, ptr noundef) unnamed_addr #8
+
+; alloc::fmt::format::format_inner
+; Function Attrs: nonlazybind uwtable
+declare void @_RNvNvNtCslNYArtu3iFV_5alloc3fmt6format12format_inner(ptr dead_on_unwind noalias noundef writable sret([24 x i8]) align 8 captures(none) dereferenceable(24), ptr noundef nonnull, ptr noundef nonnull) unnamed_addr #9
+
+; <u32 as core::fmt::Display>::fmt
+; Function Attrs: nonlazybind uwtable
+declare noundef zeroext i1 @_RNvXs8_NtNtNtCsgEmfK2I1SDS_4core3fmt3num3impmNtB9_7Display3fmt(ptr noalias noundef readonly align 4 captures(address, read_provenance) dereferenceable(4), ptr noalias noundef align 8 dereferenceable(24)) unnamed_addr #9
+
+attributes #0 = { mustprogress nofree norecurse nosync nounwind nonlazybind willreturn memory(argmem: read) uwtable "probe-stack"="inline-asm" "target-cpu"="x86-64" }
+attributes #1 = { mustprogress nofree norecurse nosync nounwind nonlazybind willreturn memory(none) uwtable "probe-stack"="inline-asm" "target-cpu"="x86-64" }
+attributes #2 = { noinline nonlazybind uwtable "probe-stack"="inline-asm" "target-cpu"="x86-64" }
+attributes #3 = { noinline nounwind nonlazybind uwtable "probe-stack"="inline-asm" "target-cpu"="x86-64" }
+attributes #4 = { mustprogress nofree noinline norecurse nosync nounwind nonlazybind willreturn memory(write, argmem: none, inaccessiblemem: none, target_mem0: none, target_mem1: none) uwtable "probe-stack"="inline-asm" "target-cpu"="x86-64" }
+attributes #5 = { mustprogress nocallback nofree nosync nounwind willreturn memory(inaccessiblemem: write) }
+attributes #6 = { mustprogress nocallback nofree nosync nounwind willreturn memory(argmem: readwrite) }
+attributes #7 = { nounwind nonlazybind allockind("free") uwtable "alloc-family"="__rust_alloc" "probe-stack"="inline-asm" "target-cpu"="x86-64" }
+attributes #8 = { nounwind nonlazybind uwtable "probe-stack"="inline-asm" "target-cpu"="x86-64" }
+attributes #9 = { nonlazybind uwtable "probe-stack"="inline-asm" "target-cpu"="x86-64" }
+attributes #10 = { noinline }
+attributes #11 = { nounwind }
+attributes #12 = { inlinehint }
+
+!llvm.module.flags = !{!0, !1, !2, !3}
+!llvm.ident = !{!4}
+!llvm.dbg.cu = !{!5}
+
+!0 = !{i32 8, !"PIC Level", i32 2}
+!1 = !{i32 2, !"RtLibUseGOT", i32 1}
+!2 = !{i32 7, !"Dwarf Version", i32 4}
+!3 = !{i32 2, !"Debug Info Version", i32 3}
+!4 = !{!"rustc version 1.95.0 (59807616e 2026-04-14)"}
+!5 = distinct !DICompileUnit(language: DW_LANG_Rust, file: !6, producer: "clang LLVM (rustc version 1.95.0 (59807616e 2026-04-14))", isOptimized: true, runtimeVersion: 0, emissionKind: LineTablesOnly, splitDebugInlining: false, nameTableKind: None)
+!6 = !DIFile(filename: "fixture.rs/@/fixture.7a646b05706873bc-cgu.0", directory: "/root/crate/src/ir/bitcode")
+!7 = distinct !DISubprogram(name: "area", linkageName: "_ZN50_$LT$fixture..Square$u20$as$u20$fixture..Shape$GT$4area17hf57d1703043c3314E", scope: !9, file: !8, line: 9, type: !11, scopeLine: 9, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!8 = !DIFile(filename: "fixture.rs", directory: "/root/crate/src/ir/bitcode", checksumkind: CSK_MD5, checksum: "92890a0e825427ef22d42eacdffd2842")
+!9 = !DINamespace(name: "{impl#0}", scope: !10)
+!10 = !DINamespace(name: "fixture", scope: null)
+!11 = !DISubroutineType(types: !12)
+!12 = !{}
+!13 = !DILocation(line: 10, column: 9, scope: !7)
+!14 = !DILocation(line: 11, column: 6, scope: !7)
+!15 = distinct !DISubprogram(name: "name", linkageName: "_ZN50_$LT$fixture..Square$u20$as$u20$fixture..Shape$GT$4name17h7a517a637fd0c33eE", scope: !9, file: !8, line: 13, type: !11, scopeLine: 13, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!16 = !DILocation(line: 15, column: 6, scope: !15)
+!17 = distinct !DISubprogram(name: "square_area", linkageName: "_ZN7fixture11square_area17hd58d463a2ac906ccE", scope: !10, file: !8, line: 56, type: !11, scopeLine: 56, flags: DIFlagPrototyped, spFlags: DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!18 = !DILocation(line: 57, column: 11, scope: !17)
+!19 = !DILocation(line: 57, column: 5, scope: !17)
+!20 = !DILocation(line: 58, column: 1, scope: !17)
+!21 = !DILocation(line: 58, column: 2, scope: !17)
+!22 = distinct !DISubprogram(name: "default_handler", linkageName: "_ZN7fixture15default_handler17h6cd1ff48fca8c069E", scope: !10, file: !8, line: 25, type: !11, scopeLine: 25, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!23 = !DILocation(line: 25, column: 24, scope: !22)
+!24 = distinct !DISubprogram(name: "nop", linkageName: "_ZN7fixture3nop17hf97dbd6ad42557f1E", scope: !10, file: !8, line: 51, type: !11, scopeLine: 51, flags: DIFlagPrototyped, spFlags: DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!25 = !DILocation(line: 52, column: 14, scope: !24)
+!26 = !{i64 3612067496774}
+!27 = !DILocation(line: 53, column: 2, scope: !24)
+!28 = distinct !DISubprogram(name: "area", linkageName: "_ZN7fixture4area17hdaf53877c00e0efbE", scope: !10, file: !8, line: 19, type: !11, scopeLine: 19, flags: DIFlagPrototyped, spFlags: DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!29 = !DILocation(line: 20, column: 5, scope: !28)
+!30 = !DILocation(line: 20, column: 11, scope: !28)
+!31 = !DILocation(line: 20, column: 20, scope: !28)
+!32 = !DILocation(line: 20, column: 26, scope: !28)
+!33 = !DILocation(line: 21, column: 2, scope: !28)
+!34 = distinct !DISubprogram(name: "init", linkageName: "_ZN7fixture4init17h576da0e173e96276E", scope: !10, file: !8, line: 28, type: !11, scopeLine: 28, flags: DIFlagPrototyped, spFlags: DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!35 = !DILocation(line: 29, column: 14, scope: !34)
+!36 = !DILocation(line: 30, column: 2, scope: !34)
+!37 = distinct !DISubprogram(name: "format", linkageName: "_ZN7fixture6format17h2399f7e6e4e89f22E", scope: !10, file: !8, line: 46, type: !11, scopeLine: 46, flags: DIFlagPrototyped, spFlags: DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!38 = !DILocation(line: 47, column: 5, scope: !39)
+!39 = !DILexicalBlockFile(scope: !40, file: !8, discriminator: 0)
+!40 = distinct !DILexicalBlock(scope: !37, file: !41, line: 114, column: 33)
+!41 = !DIFile(filename: "library/alloc/src/macros.rs", directory: "/rustc/59807616e1fa2540724bfbac14d7976d7e4a3860", checksumkind: CSK_MD5, checksum: "c06fded2c893c2cc0e8ea200156f1086")
+!42 = !DILocation(line: 659, column: 34, scope: !43, inlinedAt: !48)
+!43 = distinct !DISubprogram(name: "{closure#0}", linkageName: "_ZN5alloc3fmt6format28_$u7b$$u7b$closure$u7d$$u7d$17hdd46edd691449cacE", scope: !45, file: !44, line: 659, type: !11, scopeLine: 659, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!44 = !DIFile(filename: "library/alloc/src/fmt.rs", directory: "/rustc/59807616e1fa2540724bfbac14d7976d7e4a3860", checksumkind: CSK_MD5, checksum: "ff238ed6a2e10a790bec34161252a63b")
+!45 = !DINamespace(name: "format", scope: !46)
+!46 = !DINamespace(name: "fmt", scope: !47)
+!47 = !DINamespace(name: "alloc", scope: null)
+!48 = distinct !DILocation(line: 1278, column: 21, scope: !49, inlinedAt: !54)
+!49 = distinct !DISubprogram(name: "map_or_else<&str, alloc::string::String, alloc::fmt::format::{closure_env#0}, fn(&str) -> alloc::string::String>", linkageName: "_ZN4core6option15Option$LT$T$GT$11map_or_else17hc291bbe461775e86E", scope: !51, file: !50, line: 1271, type: !11, scopeLine: 1271, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!50 = !DIFile(filename: "library/core/src/option.rs", directory: "/rustc/59807616e1fa2540724bfbac14d7976d7e4a3860", checksumkind: CSK_MD5, checksum: "3f12dd837a95e960b90752f70f5ddc0e")
+!51 = !DINamespace(name: "Option", scope: !52)
+!52 = !DINamespace(name: "option", scope: !53)
+!53 = !DINamespace(name: "core", scope: null)
+!54 = distinct !DILocation(line: 659, column: 19, scope: !55, inlinedAt: !56)
+!55 = distinct !DISubprogram(name: "format", linkageName: "_RNvNtCslNYArtu3iFV_5alloc3fmt6format", scope: !46, file: !44, line: 649, type: !11, scopeLine: 649, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!56 = !DILocation(line: 47, column: 5, scope: !57)
+!57 = !DILexicalBlockFile(scope: !37, file: !8, discriminator: 4)
+!58 = !DILocation(line: 47, column: 5, scope: !37)
+!59 = !DILocation(line: 48, column: 2, scope: !37)
+!60 = distinct !DISubprogram(name: "dispatch", linkageName: "_ZN7fixture8dispatch17h5afbfeb225a9b505E", scope: !10, file: !8, line: 33, type: !11, scopeLine: 33, flags: DIFlagPrototyped, spFlags: DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!61 = !DILocation(line: 34, column: 37, scope: !62)
+!62 = distinct !DILexicalBlock(scope: !60, file: !8, line: 34, column: 47)
+!63 = !DILocation(line: 34, column: 28, scope: !62)
+!64 = !DILocation(line: 34, column: 12, scope: !62)
+!65 = !DILocation(line: 35, column: 9, scope: !62)
+!66 = !DILocation(line: 37, column: 2, scope: !60)
+!67 = distinct !DISubprogram(name: "with_drop", linkageName: "_ZN7fixture9with_drop17hecb0a53eb21a27c2E", scope: !10, file: !8, line: 40, type: !11, scopeLine: 40, flags: DIFlagPrototyped, spFlags: DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!68 = !DILocation(line: 41, column: 5, scope: !67)
+!69 = !DILocation(line: 43, column: 1, scope: !67)
+!70 = !DILocation(line: 632, column: 39, scope: !71, inlinedAt: !75)
+!71 = distinct !DISubprogram(name: "current_memory<alloc::alloc::Global>", linkageName: "_ZN5alloc7raw_vec20RawVecInner$LT$A$GT$14current_memory17h3661aea613711d2eE", scope: !73, file: !72, line: 631, type: !11, scopeLine: 631, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!72 = !DIFile(filename: "library/alloc/src/raw_vec/mod.rs", directory: "/rustc/59807616e1fa2540724bfbac14d7976d7e4a3860", checksumkind: CSK_MD5, checksum: "78584250230dfe0aae92794a07566ae7")
+!73 = !DINamespace(name: "RawVecInner", scope: !74)
+!74 = !DINamespace(name: "raw_vec", scope: !47)
+!75 = distinct !DILocation(line: 872, column: 52, scope: !76, inlinedAt: !78)
+!76 = distinct !DILexicalBlock(scope: !77, file: !72, line: 872, column: 82)
+!77 = distinct !DISubprogram(name: "deallocate<alloc::alloc::Global>", linkageName: "_ZN5alloc7raw_vec20RawVecInner$LT$A$GT$10deallocate17h6cbf7dcb4fa45fe5E", scope: !73, file: !72, line: 870, type: !11, scopeLine: 870, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!78 = distinct !DILocation(line: 423, column: 29, scope: !79, inlinedAt: !81)
+!79 = distinct !DISubprogram(name: "drop<u8, alloc::alloc::Global>", linkageName: "_ZN77_$LT$alloc..raw_vec..RawVec$LT$T$C$A$GT$$u20$as$u20$core..ops..drop..Drop$GT$4drop17h5b283b5059e07560E", scope: !80, file: !72, line: 421, type: !11, scopeLine: 421, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!80 = !DINamespace(name: "{impl#3}", scope: !74)
+!81 = distinct !DILocation(line: 805, column: 1, scope: !82, inlinedAt: !85)
+!82 = distinct !DISubprogram(name: "drop_in_place<alloc::raw_vec::RawVec<u8, alloc::alloc::Global>>", linkageName: "_ZN4core3ptr53drop_in_place$LT$alloc..raw_vec..RawVec$LT$u8$GT$$GT$17ha39431380c4bd1eaE", scope: !84, file: !83, line: 805, type: !11, scopeLine: 805, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!83 = !DIFile(filename: "library/core/src/ptr/mod.rs", directory: "/rustc/59807616e1fa2540724bfbac14d7976d7e4a3860", checksumkind: CSK_MD5, checksum: "8f706ae0a0620120e6e931983ef127f1")
+!84 = !DINamespace(name: "ptr", scope: !53)
+!85 = distinct !DILocation(line: 805, column: 1, scope: !86, inlinedAt: !87)
+!86 = distinct !DISubprogram(name: "drop_in_place<alloc::vec::Vec<u8, alloc::alloc::Global>>", linkageName: "_ZN4core3ptr46drop_in_place$LT$alloc..vec..Vec$LT$u8$GT$$GT$17h32e878ffdd2ac683E", scope: !84, file: !83, line: 805, type: !11, scopeLine: 805, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!87 = distinct !DILocation(line: 43, column: 1, scope: !67)
+!88 = !DILocation(line: 115, column: 14, scope: !89, inlinedAt: !92)
+!89 = distinct !DISubprogram(name: "dealloc", linkageName: "_RNvNtCslNYArtu3iFV_5alloc5alloc7dealloc", scope: !91, file: !90, line: 114, type: !11, scopeLine: 114, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!90 = !DIFile(filename: "library/alloc/src/alloc.rs", directory: "/rustc/59807616e1fa2540724bfbac14d7976d7e4a3860", checksumkind: CSK_MD5, checksum: "57066d22da38e4b1f3bfd29f3d5ff5ae")
+!91 = !DINamespace(name: "alloc", scope: !47)
+!92 = distinct !DILocation(line: 209, column: 22, scope: !93, inlinedAt: !95)
+!93 = distinct !DISubprogram(name: "deallocate_impl_runtime", linkageName: "_RNvMNtCslNYArtu3iFV_5alloc5allocNtB2_6Global23deallocate_impl_runtime", scope: !94, file: !90, line: 199, type: !11, scopeLine: 199, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!94 = !DINamespace(name: "Global", scope: !91)
+!95 = distinct !DILocation(line: 324, column: 9, scope: !96, inlinedAt: !97)
+!96 = distinct !DISubprogram(name: "deallocate_impl", linkageName: "_RNvMNtCslNYArtu3iFV_5alloc5allocNtB2_6Global15deallocate_impl", scope: !94, file: !90, line: 323, type: !11, scopeLine: 323, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!97 = distinct !DILocation(line: 442, column: 23, scope: !98, inlinedAt: !100)
+!98 = distinct !DISubprogram(name: "deallocate", linkageName: "_RNvXs_NtCslNYArtu3iFV_5alloc5allocNtB4_6GlobalNtNtCsgEmfK2I1SDS_4core5alloc9Allocator10deallocate", scope: !99, file: !90, line: 440, type: !11, scopeLine: 440, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!99 = !DINamespace(name: "{impl#1}", scope: !91)
+!100 = distinct !DILocation(line: 874, column: 28, scope: !76, inlinedAt: !78)
+!101 = !DILocation(line: 872, column: 9, scope: !77, inlinedAt: !78)
+!102 = !DILocation(line: 2997, column: 19, scope: !103, inlinedAt: !107)
+!103 = distinct !DISubprogram(name: "len<u8, alloc::alloc::Global>", linkageName: "_ZN5alloc3vec16Vec$LT$T$C$A$GT$3len17hd2d071cfb7b23137E", scope: !105, file: !104, line: 2996, type: !11, scopeLine: 2996, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition | DISPFlagOptimized, unit: !5, templateParams: !12)
+!104 = !DIFile(filename: "library/alloc/src/vec/mod.rs", directory: "/rustc/59807616e1fa2540724bfbac14d7976d7e4a3860", checksumkind: CSK_MD5, checksum: "561105229399139e451a3aa6309b761e")
+!105 = !DINamespace(name: "Vec", scope: !106)
+!106 = !DINamespace(name: "vec", scope: !47)
+!107 = !DILocation(line: 42, column: 7, scope: !67)
+!108 = !DILocation(line: 3002, column: 37, scope: !109, inlinedAt: !107)
+!109 = distinct !DILexicalBlock(scope: !103, file: !104, line: 2997, column: 9)
+!110 = !DILocation(line: 3002, column: 18, scope: !109, inlinedAt: !107)
+!111 = !DILocation(line: 632, column: 39, scope: !71, inlinedAt: !112)
+!112 = distinct !DILocation(line: 872, column: 52, scope: !76, inlinedAt: !113)
+!113 = distinct !DILocation(line: 423, column: 29, scope: !79, inlinedAt: !114)
+!114 = distinct !DILocation(line: 805, column: 1, scope: !82, inlinedAt: !115)
+!115 = distinct !DILocation(line: 805, column: 1, scope: !86, inlinedAt: !116)
+!116 = distinct !DILocation(line: 43, column: 1, scope: !67)
+!117 = !DILocation(line: 115, column: 14, scope: !89, inlinedAt: !118)
+!118 = distinct !DILocation(line: 209, column: 22, scope: !93, inlinedAt: !119)
+!119 = distinct !DILocation(line: 324, column: 9, scope: !96, inlinedAt: !120)
+!120 = distinct !DILocation(line: 442, column: 23, scope: !98, inlinedAt: !121)
+!121 = distinct !DILocation(line: 874, column: 28, scope: !76, inlinedAt: !113)
+!122 = !DILocation(line: 872, column: 9, scope: !77, inlinedAt: !113)
+!123 = !DILocation(line: 43, column: 2, scope: !67)
+!124 = !DILocation(line: 40, column: 1, scope: !67)
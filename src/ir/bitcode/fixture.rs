@@ -0,0 +1,60 @@
+use std::arch::asm;
+
+pub trait Shape {
+    fn area(&self) -> u32;
+    fn name(&self) -> &'static str;
+}
+
+impl Shape for Square {
+    fn area(&self) -> u32 {
+        self.0 * self.0
+    }
+
+    fn name(&self) -> &'static str {
+        "square"
+    }
+}
+
+#[inline(never)]
+pub fn area(shape: &dyn Shape) -> u32 {
+    shape.area() + shape.name().len() as u32
+}
+
+pub static mut HANDLER: Option<fn()> = None;
+
+fn default_handler() {}
+
+#[inline(never)]
+pub fn init() {
+    unsafe { HANDLER = Some(default_handler) }
+}
+
+#[inline(never)]
+pub fn dispatch() {
+    if let Some(handler) = unsafe { HANDLER } {
+        handler()
+    }
+}
+
+#[inline(never)]
+pub fn with_drop(v: Vec<u8>, f: fn()) -> usize {
+    f();
+    v.len()
+}
+
+#[inline(never)]
+pub fn format(x: u32) -> String {
+    format!("{}", x)
+}
+
+#[inline(never)]
+pub fn nop() {
+    unsafe { asm!("nop") }
+}
+
+#[inline(never)]
+pub fn square_area(side: u32) -> u32 {
+    area(&Square(side))
+}
+
+pub struct Square(pub u32);
@@ -0,0 +1,422 @@
+//! The bitstream container format
+//!
+//! A bitstream is a sequence of blocks that contain records and other blocks. Records are lists of
+//! integers whose encoding is described by "abbreviations" that are defined in the stream itself,
+//! either in the block that uses them or, for all the blocks with the same ID, in the BLOCKINFO
+//! block. See <https://llvm.org/docs/BitCodeFormat.html>
+
+use std::{collections::HashMap, rc::Rc};
+
+// abbreviation IDs with a fixed meaning
+const END_BLOCK: u64 = 0;
+const ENTER_SUBBLOCK: u64 = 1;
+const DEFINE_ABBREV: u64 = 2;
+const UNABBREV_RECORD: u64 = 3;
+
+const BLOCKINFO: u64 = 0;
+// record of the BLOCKINFO block that selects the block the following abbreviations are for
+const BLOCKINFO_CODE_SETBID: u64 = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Op {
+    Literal(u64),
+    Fixed(u32),
+    Vbr(u32),
+    // the element type is the operand that follows
+    Array,
+    Char6,
+    Blob,
+}
+
+type Abbrev = Rc<Vec<Op>>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Entry<'a> {
+    // the start of a block; it must be either `enter`-ed or `skip`-ped
+    Block(u64),
+    Record(Record<'a>),
+    // the end of the current block
+    End,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record<'a> {
+    pub code: u64,
+    pub ops: Vec<u64>,
+    pub blob: Option<&'a [u8]>,
+}
+
+#[derive(Clone)]
+struct Scope {
+    width: u32,
+    abbrevs: Vec<Abbrev>,
+}
+
+#[derive(Clone)]
+pub struct Reader<'a> {
+    bits: Bits<'a>,
+    // the blocks we are in, innermost last
+    scopes: Vec<Scope>,
+    // the block ID, abbreviation width and length (in 32-bit words) of the block that was just
+    // found by `next`
+    pending: Option<(u64, u32, usize)>,
+    blockinfo: HashMap<u64, Vec<Abbrev>>,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Reader {
+            bits: Bits::new(bytes),
+            scopes: vec![],
+            pending: None,
+            blockinfo: HashMap::new(),
+        }
+    }
+
+    pub fn next(&mut self) -> Result<Entry<'a>, failure::Error> {
+        loop {
+            let width = self.scopes.last().map(|scope| scope.width).unwrap_or(2);
+            if self.scopes.is_empty() && self.bits.remaining() < 32 {
+                return Ok(Entry::End);
+            }
+
+            match self.bits.fixed(width)? {
+                END_BLOCK => {
+                    // at the top level this is the padding at the end of the stream
+                    self.bits.align32();
+                    self.scopes.pop();
+                    return Ok(Entry::End);
+                }
+
+                ENTER_SUBBLOCK => {
+                    let id = self.bits.vbr(8)?;
+                    let width = self.bits.vbr(4)? as u32;
+                    self.bits.align32();
+                    let len = self.bits.fixed(32)? as usize;
+                    self.pending = Some((id, width, len));
+
+                    if id == BLOCKINFO {
+                        self.enter()?;
+                        self.blockinfo()?;
+                    } else {
+                        return Ok(Entry::Block(id));
+                    }
+                }
+
+                DEFINE_ABBREV => {
+                    let abbrev = self.define_abbrev()?;
+                    self.scope()?.abbrevs.push(abbrev);
+                }
+
+                id => return self.record(id).map(Entry::Record),
+            }
+        }
+    }
+
+    pub fn enter(&mut self) -> Result<(), failure::Error> {
+        let (id, width, _) = self
+            .pending
+            .take()
+            .ok_or_else(|| bug("no block to enter"))?;
+        self.scopes.push(Scope {
+            width,
+            abbrevs: self.blockinfo.get(&id).cloned().unwrap_or_default(),
+        });
+        Ok(())
+    }
+
+    pub fn skip(&mut self) -> Result<(), failure::Error> {
+        let (_, _, len) = self.pending.take().ok_or_else(|| bug("no block to skip"))?;
+        self.bits.seek(self.bits.pos + len * 32)
+    }
+
+    fn scope(&mut self) -> Result<&mut Scope, failure::Error> {
+        self.scopes
+            .last_mut()
+            .ok_or_else(|| bug("abbreviation defined outside a block"))
+    }
+
+    fn blockinfo(&mut self) -> Result<(), failure::Error> {
+        let mut current = None;
+        loop {
+            let width = self.scope()?.width;
+            match self.bits.fixed(width)? {
+                END_BLOCK => {
+                    self.bits.align32();
+                    self.scopes.pop();
+                    return Ok(());
+                }
+
+                ENTER_SUBBLOCK => {
+                    self.bits.vbr(8)?;
+                    self.bits.vbr(4)?;
+                    self.bits.align32();
+                    let len = self.bits.fixed(32)? as usize;
+                    self.bits.seek(self.bits.pos + len * 32)?;
+                }
+
+                DEFINE_ABBREV => {
+                    let abbrev = self.define_abbrev()?;
+                    let id = current.ok_or_else(|| bug("BLOCKINFO abbreviation without SETBID"))?;
+                    self.blockinfo.entry(id).or_default().push(abbrev);
+                }
+
+                id => {
+                    let record = self.record(id)?;
+                    if record.code == BLOCKINFO_CODE_SETBID {
+                        current = record.ops.first().cloned();
+                    }
+                }
+            }
+        }
+    }
+
+    fn define_abbrev(&mut self) -> Result<Abbrev, failure::Error> {
+        let n = self.bits.vbr(5)?;
+        let mut ops = Vec::with_capacity(n as usize);
+        for _ in 0..n {
+            let op = if self.bits.fixed(1)? == 1 {
+                Op::Literal(self.bits.vbr(8)?)
+            } else {
+                match self.bits.fixed(3)? {
+                    // zero-width fields are always zero
+                    encoding @ (1 | 2) => match self.bits.vbr(5)? as u32 {
+                        0 => Op::Literal(0),
+                        width if width > 64 || (encoding == 2 && width < 2) => {
+                            return Err(bug("invalid abbreviation operand width"))
+                        }
+                        width if encoding == 1 => Op::Fixed(width),
+                        width => Op::Vbr(width),
+                    },
+                    3 => Op::Array,
+                    4 => Op::Char6,
+                    5 => Op::Blob,
+                    _ => return Err(bug("unknown abbreviation operand encoding")),
+                }
+            };
+            ops.push(op);
+        }
+        Ok(Rc::new(ops))
+    }
+
+    fn record(&mut self, id: u64) -> Result<Record<'a>, failure::Error> {
+        if id == UNABBREV_RECORD {
+            let code = self.bits.vbr(6)?;
+            let n = self.bits.vbr(6)?;
+            let mut ops = Vec::with_capacity(n as usize);
+            for _ in 0..n {
+                ops.push(self.bits.vbr(6)?);
+            }
+            return Ok(Record {
+                code,
+                ops,
+                blob: None,
+            });
+        }
+
+        let abbrev = self
+            .scope()?
+            .abbrevs
+            .get(id as usize - 4)
+            .cloned()
+            .ok_or_else(|| bug("undefined abbreviation"))?;
+        let mut ops = vec![];
+        let mut blob = None;
+        let mut iter = abbrev.iter();
+        while let Some(op) = iter.next() {
+            match op {
+                Op::Array => {
+                    let elt = iter
+                        .next()
+                        .ok_or_else(|| bug("array without element type"))?;
+                    let n = self.bits.vbr(6)?;
+                    for _ in 0..n {
+                        ops.push(self.scalar(elt)?);
+                    }
+                }
+
+                Op::Blob => {
+                    let n = self.bits.vbr(6)? as usize;
+                    self.bits.align32();
+                    let start = self.bits.pos / 8;
+                    self.bits.seek(self.bits.pos + n * 8)?;
+                    self.bits.align32();
+                    blob = Some(&self.bits.bytes[start..start + n]);
+                }
+
+                op => ops.push(self.scalar(op)?),
+            }
+        }
+
+        if ops.is_empty() {
+            return Err(bug("record without code"));
+        }
+        let code = ops.remove(0);
+        Ok(Record { code, ops, blob })
+    }
+
+    fn scalar(&mut self, op: &Op) -> Result<u64, failure::Error> {
+        Ok(match *op {
+            Op::Literal(value) => value,
+            Op::Fixed(width) => self.bits.fixed(width)?,
+            Op::Vbr(width) => self.bits.vbr(width)?,
+            Op::Char6 => {
+                let c = self.bits.fixed(6)? as u8;
+                u64::from(match c {
+                    0..=25 => b'a' + c,
+                    26..=51 => b'A' + c - 26,
+                    52..=61 => b'0' + c - 52,
+                    62 => b'.',
+                    _ => b'_',
+                })
+            }
+            Op::Array | Op::Blob => return Err(bug("array or blob used as an element type")),
+        })
+    }
+}
+
+/// Reads `count` VBR6 integers packed into `bytes`, e.g. the lengths of the strings of a
+/// `METADATA_STRINGS` record
+pub fn vbr6s(bytes: &[u8], count: usize) -> Result<Vec<u64>, failure::Error> {
+    let mut bits = Bits::new(bytes);
+    (0..count).map(|_| bits.vbr(6)).collect()
+}
+
+#[derive(Clone)]
+struct Bits<'a> {
+    bytes: &'a [u8],
+    // in bits
+    pos: usize,
+}
+
+impl<'a> Bits<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Bits { bytes, pos: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() * 8 - self.pos
+    }
+
+    fn seek(&mut self, pos: usize) -> Result<(), failure::Error> {
+        if pos > self.bytes.len() * 8 {
+            return Err(eof());
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn align32(&mut self) {
+        self.pos = (self.pos + 31) & !31;
+    }
+
+    // fields are stored starting from their least significant bit
+    fn fixed(&mut self, width: u32) -> Result<u64, failure::Error> {
+        let width = width as usize;
+        if width > self.remaining() {
+            return Err(eof());
+        }
+
+        let mut value = 0;
+        let mut read = 0;
+        while read < width {
+            let offset = self.pos % 8;
+            let n = (8 - offset).min(width - read);
+            let bits = (u64::from(self.bytes[self.pos / 8]) >> offset) & ((1 << n) - 1);
+            value |= bits << read;
+            read += n;
+            self.pos += n;
+        }
+        Ok(value)
+    }
+
+    // variable width integer made of `width`-bit chunks; the top bit of a chunk is set when more
+    // chunks follow
+    fn vbr(&mut self, width: u32) -> Result<u64, failure::Error> {
+        let more = 1 << (width - 1);
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let chunk = self.fixed(width)?;
+            if shift < 64 {
+                value |= (chunk & (more - 1)) << shift;
+            }
+            if chunk & more == 0 {
+                return Ok(value);
+            }
+            shift += width - 1;
+        }
+    }
+}
+
+fn eof() -> failure::Error {
+    failure::err_msg("unexpected end of LLVM bitcode")
+}
+
+fn bug(what: &str) -> failure::Error {
+    failure::format_err!("malformed LLVM bitcode: {}", what)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, Reader, Record};
+
+    #[test]
+    fn bits() {
+        // fields start at the least significant bit of a byte and can span several bytes
+        let mut bits = super::Bits::new(&[0b0110_1001, 0b0000_0010, 0b1111_0000, 0]);
+        assert_eq!(bits.fixed(4).unwrap(), 0b1001);
+        assert_eq!(bits.fixed(6).unwrap(), 0b10_0110);
+        bits.seek(16).unwrap();
+        assert_eq!(bits.vbr(4).unwrap(), 0);
+        assert_eq!(bits.fixed(4).unwrap(), 0b1111);
+        // 8 bits left
+        assert!(bits.fixed(9).is_err());
+
+        // `0b100` with the continuation bit set followed by `0b011`
+        assert_eq!(super::Bits::new(&[0b0011_1100]).vbr(4).unwrap(), 0b011_100);
+    }
+
+    #[test]
+    fn records() {
+        // abbreviation width 2: ENTER_SUBBLOCK(id 8, width 3), UNABBREV_RECORD(code 1, [2]),
+        // END_BLOCK
+        let mut bytes = vec![];
+        let mut acc = 0u64;
+        let mut n = 0;
+        let mut push = |value: u64, width: u32, bytes: &mut Vec<u8>| {
+            acc |= value << n;
+            n += width;
+            while n >= 8 {
+                bytes.push(acc as u8);
+                acc >>= 8;
+                n -= 8;
+            }
+        };
+        push(1, 2, &mut bytes); // ENTER_SUBBLOCK
+        push(8, 8, &mut bytes); // block ID
+        push(3, 4, &mut bytes); // abbreviation width
+        push(0, 18, &mut bytes); // align32
+        push(1, 32, &mut bytes); // length in words
+        push(3, 3, &mut bytes); // UNABBREV_RECORD
+        push(1, 6, &mut bytes); // code
+        push(1, 6, &mut bytes); // number of operands
+        push(2, 6, &mut bytes); // operand
+        push(0, 3, &mut bytes); // END_BLOCK
+        push(0, 8, &mut bytes); // align32
+
+        let mut reader = Reader::new(&bytes);
+        assert_eq!(reader.next().unwrap(), Entry::Block(8));
+        reader.enter().unwrap();
+        assert_eq!(
+            reader.next().unwrap(),
+            Entry::Record(Record {
+                code: 1,
+                ops: vec![2],
+                blob: None,
+            })
+        );
+        assert_eq!(reader.next().unwrap(), Entry::End);
+        assert_eq!(reader.next().unwrap(), Entry::End);
+    }
+}
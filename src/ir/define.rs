@@ -1,41 +1,84 @@
+use std::{collections::HashMap, str::FromStr};
+
 use nom::{
     branch::alt,
     bytes::complete::{is_not, tag},
     character::complete::{char, digit1, line_ending, not_line_ending, space1},
     combinator::{map, map_res, opt},
+    error::ErrorKind,
     multi::{many0, many1, separated_list, separated_nonempty_list},
     sequence::delimited,
     IResult,
 };
 
-use crate::ir::{FnSig, Type};
+use crate::ir::{FnSig, Operand, Type};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Define<'a> {
     pub name: &'a str,
+    // `internal` or `private` linkage
+    pub internal: bool,
     pub sig: FnSig<'a>,
     pub stmts: Vec<Stmt<'a>>,
+    // globals used in any other way than being called directly or loaded from / stored to
+    pub references: Vec<&'a str>,
+    // `(static, value)` pairs; the value is `None` when it's not a global (e.g. a local)
+    pub stores: Vec<(&'a str, Option<&'a str>)>,
+    // direct calls and the globals passed to them as arguments
+    pub calls: Vec<(&'a str, Vec<&'a str>)>,
+    // the routine the unwinder calls to find out what to do with this function's landing pads
+    pub personality: Option<&'a str>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Stmt<'a> {
-    // `  call void asm sideeffect "cpsid i"`
-    Asm(&'a str),
+    // `  call void asm sideeffect "cpsid i"`; the template and, for each of its operands (`$0`,
+    // `${1:c}`, etc.), the global it refers to if it's a constant reference to one (`sym foo`)
+    Asm(&'a str, Vec<Option<&'a str>>),
 
     BitcastCall(Option<&'a str>),
 
     DirectCall(&'a str),
 
-    IndirectCall(FnSig<'a>),
+    // the second field is the debug location (`!dbg !1200`) of the call, if any; the third one is
+    // where the callee was loaded from, if known
+    IndirectCall(FnSig<'a>, Option<u32>, Option<Origin<'a>>),
 
     Comment,
 
     // `start:`
     Label,
 
+    // `resume { ptr, i32 } %5`; continues unwinding after a landing pad
+    Resume,
+
     Other,
 }
 
+// where the callee of an indirect call was loaded from
+#[derive(Clone, Debug, PartialEq)]
+pub enum Origin<'a> {
+    // dynamic dispatch
+    Vtable(Slot<'a>),
+
+    // e.g. `static HANDLER: Option<fn()>`
+    Static(&'a str),
+}
+
+// the position of a method in a vtable: `index` elements of type `ty` away from its start
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slot<'a> {
+    pub ty: Type<'a>,
+    pub index: u64,
+}
+
+impl Slot<'_> {
+    // offset in bytes from the start of the vtable
+    pub fn offset(&self, pointer_size: u64) -> Option<u64> {
+        Some(self.ty.size(pointer_size)? * self.index)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 struct Parameter<'a>(Type<'a>);
 
@@ -48,7 +91,7 @@ fn parameter(i: &str) -> IResult<&str, Parameter> {
     .0;
     let i = opt(|i| {
         let i = space1(i)?.0;
-        super::alias(i)
+        super::local(i)
     })(i)?
     .0;
     Ok((i, Parameter(ty)))
@@ -57,6 +100,7 @@ fn parameter(i: &str) -> IResult<&str, Parameter> {
 pub fn parse(i: &str) -> IResult<&str, Define> {
     let i = tag("define")(i)?.0;
     let i = space1(i)?.0;
+    let internal = i.starts_with("internal ") || i.starts_with("private ");
     let i = many0(|i| {
         let i = super::attribute(i)?.0;
         space1(i)
@@ -75,28 +119,253 @@ pub fn parse(i: &str) -> IResult<&str, Define> {
         },
         map(parameter, |p| p.0),
     )(i)?;
+    let (i, variadic) = opt(|i| {
+        let i = opt(|i| {
+            let i = char(',')(i)?.0;
+            space1(i)
+        })(i)?
+        .0;
+        tag("...")(i)
+    })(i)?;
     let i = char(')')(i)?.0;
 
     // TODO we likely want to parse the metadata (`!dbg !0`) that comes after the parameter list
     // NOTE shortcut
-    let i = not_line_ending(i)?.0;
-    let i = line_ending(i)?.0;
-    let (i, stmts) = separated_nonempty_list(many1(line_ending), super::define::stmt)(i)?;
+    let (i, rest) = not_line_ending(i)?;
+    let personality = rest
+        .find("personality ")
+        .and_then(|start| super::globals(&rest[start..]).first().cloned());
+    let i0 = line_ending(i)?.0;
+
+    let mut stmts = vec![];
+    let mut references = vec![];
+    let mut stores = vec![];
+    let mut calls = vec![];
+    // locals that hold a pointer into an aggregate
+    let mut geps = HashMap::new();
+    // where the values held by locals were loaded from
+    let mut origins = HashMap::new();
+    let (mut i, mut next) = stmt(i0)?;
+    let mut line = &i0[..i0.len() - i.len()];
+    loop {
+        let mut globals = super::globals(line);
+
+        if let Ok((_, (lhs, slot))) = gep(line) {
+            geps.insert(lhs, slot);
+        } else if let Ok((rest, (lhs, address))) = load(line) {
+            match address {
+                // rustc marks the loads from vtables as invariant
+                Operand::Local(address) if rest.contains("!invariant.load") => {
+                    // no `getelementptr` means that the method is at the start of the vtable
+                    let slot = geps.get(address).cloned().unwrap_or(Slot {
+                        ty: Type::Integer(8),
+                        index: 0,
+                    });
+                    origins.insert(lhs, Origin::Vtable(slot));
+                }
+
+                Operand::Global(name) => {
+                    remove(&mut globals, name);
+                    origins.insert(lhs, Origin::Static(name));
+                }
+
+                _ => {}
+            }
+        } else if let Ok((_, (value, Operand::Global(name)))) = store(line) {
+            remove(&mut globals, name);
+            match value {
+                Operand::Global(value) => stores.push((name, Some(value))),
+                Operand::Local(_) => stores.push((name, None)),
+                Operand::Constant => {}
+            }
+        }
+
+        if let Stmt::DirectCall(func) | Stmt::BitcastCall(Some(func)) = next {
+            remove(&mut globals, func);
+            calls.push((func, globals.clone()));
+        }
+        references.extend(globals);
+
+        match next {
+            // the destinations of `invoke` and `callbr` are printed in the line that follows them
+            Stmt::Other if line.trim_start().starts_with("to label ") => {
+                if let Some(Stmt::IndirectCall(_, dbg @ None, _)) = stmts.last_mut() {
+                    *dbg = self::dbg(line);
+                }
+
+                stmts.push(Stmt::Other);
+            }
+
+            Stmt::IndirectCall(mut sig, dbg, _) => {
+                let origin = callee(line).and_then(|c| origins.get(c)).cloned();
+
+                // with opaque pointers the receiver of a trait object method call is a plain
+                // `ptr`; the only thing that gives away the dynamic dispatch is where the callee
+                // came from
+                if let Some(Origin::Vtable(_)) = origin {
+                    if sig.inputs.first() == Some(&Type::OpaquePointer) {
+                        sig.inputs[0] = Type::erased();
+                    }
+                }
+
+                stmts.push(Stmt::IndirectCall(sig, dbg, origin));
+            }
+
+            stmt => stmts.push(stmt),
+        }
+
+        let rest = match many1::<_, _, (&str, ErrorKind), _>(line_ending)(i) {
+            Ok((rest, _)) => rest,
+            Err(_) => break,
+        };
+        match stmt(rest) {
+            Ok((after, stmt)) => {
+                line = &rest[..rest.len() - after.len()];
+                next = stmt;
+                i = after;
+            }
+            Err(nom::Err::Error(_)) => break,
+            Err(e) => return Err(e),
+        }
+    }
+
+    references.sort();
+    references.dedup();
+
     let i = opt(line_ending)(i)?.0;
     let i = tag("}")(i)?.0;
     Ok((
         i,
         Define {
             name: name.0,
+            internal,
             stmts,
             sig: FnSig {
                 inputs,
                 output: output.map(Box::new),
+                variadic: variadic.is_some(),
             },
+            references,
+            stores,
+            calls,
+            personality,
         },
     ))
 }
 
+fn remove(globals: &mut Vec<&str>, name: &str) {
+    if let Some(pos) = globals.iter().position(|global| *global == name) {
+        globals.remove(pos);
+    }
+}
+
+// `%6 = getelementptr inbounds nuw i8, ptr %5, i64 24`
+// `%6 = getelementptr inbounds i1 ({}*)*, i1 ({}*)** %5, i32 3`
+//
+// only the single index form is considered; returns the local that holds the resulting pointer
+fn gep(i: &str) -> IResult<&str, (&str, Slot)> {
+    let i = space1(i)?.0;
+    let (i, lhs) = super::local(i)?;
+    let i = space1(i)?.0;
+    let i = char('=')(i)?.0;
+    let i = space1(i)?.0;
+    let i = tag("getelementptr")(i)?.0;
+    let i = many0(|i| {
+        let i = space1(i)?.0;
+        alt((tag("inbounds"), tag("nuw"), tag("nusw")))(i)
+    })(i)?
+    .0;
+    let i = space1(i)?.0;
+    let (i, ty) = super::type_(i)?;
+    let i = char(',')(i)?.0;
+    let i = space1(i)?.0;
+    let i = super::type_(i)?.0;
+    let i = space1(i)?.0;
+    let i = super::local(i)?.0;
+    let i = char(',')(i)?.0;
+    let i = space1(i)?.0;
+    let i = super::type_(i)?.0;
+    let i = space1(i)?.0;
+    let (i, index) = map_res(digit1, u64::from_str)(i)?;
+
+    if i.is_empty() || i.starts_with(", !") {
+        Ok((i, (lhs.0, Slot { ty, index })))
+    } else {
+        Err(nom::Err::Error((i, ErrorKind::Verify)))
+    }
+}
+
+// `%7 = load ptr, ptr %6, align 8, !invariant.load !38, !nonnull !38`
+//
+// returns the local that holds the loaded value and the address it was loaded from
+fn load(i: &str) -> IResult<&str, (&str, Operand)> {
+    let i = space1(i)?.0;
+    let (i, lhs) = super::local(i)?;
+    let i = space1(i)?.0;
+    let i = char('=')(i)?.0;
+    let i = space1(i)?.0;
+    let i = tag("load")(i)?.0;
+    let i = space1(i)?.0;
+    let i = many0(|i| {
+        let i = alt((tag("atomic"), tag("volatile")))(i)?.0;
+        space1(i)
+    })(i)?
+    .0;
+    let i = super::type_(i)?.0;
+    let i = char(',')(i)?.0;
+    let i = space1(i)?.0;
+    let i = super::type_(i)?.0;
+    let i = space1(i)?.0;
+    let (i, address) = super::operand(i)?;
+    Ok((i, (lhs.0, address)))
+}
+
+// `store ptr @foo, ptr @HANDLER, align 4`
+//
+// returns the stored value and the address it was stored to
+fn store(i: &str) -> IResult<&str, (Operand, Operand)> {
+    let i = space1(i)?.0;
+    let i = tag("store")(i)?.0;
+    let i = space1(i)?.0;
+    let i = many0(|i| {
+        let i = alt((tag("atomic"), tag("volatile")))(i)?.0;
+        space1(i)
+    })(i)?
+    .0;
+    let i = super::type_(i)?.0;
+    let i = space1(i)?.0;
+    let (i, value) = super::operand(i)?;
+    let i = char(',')(i)?.0;
+    let i = space1(i)?.0;
+    let i = super::type_(i)?.0;
+    let i = space1(i)?.0;
+    let (i, address) = super::operand(i)?;
+    Ok((i, (value, address)))
+}
+
+// the local that holds the callee of an indirect call; `%7` in `%8 = tail call i1 %7(ptr %3)`
+fn callee(line: &str) -> Option<&str> {
+    let start = [" call ", " invoke ", " callbr "]
+        .iter()
+        .filter_map(|keyword| line.find(keyword))
+        .min()?;
+    let (_, rhs) = line.split_at(start);
+
+    rhs.match_indices('%').find_map(|(start, _)| {
+        super::local(&rhs[start..])
+            .ok()
+            .filter(|(rest, _)| rest.starts_with('('))
+            .map(|(_, local)| local.0)
+    })
+}
+
+// `!dbg !1200`
+pub(super) fn dbg(i: &str) -> Option<u32> {
+    let (_, rhs) = i.split_at(i.find("!dbg !")? + "!dbg !".len());
+    let end = rhs.find(|c: char| !c.is_ascii_digit()).unwrap_or(rhs.len());
+    u32::from_str(&rhs[..end]).ok()
+}
+
 fn label(i: &str) -> IResult<&str, Stmt> {
     let i = alt((
         map(super::ident, drop),
@@ -118,13 +387,19 @@ fn comment(i: &str) -> IResult<&str, Stmt> {
     Ok((i, Stmt::Comment))
 }
 
-fn asm(i: &str) -> IResult<&str, Stmt> {
+// `call`, `tail call`, `invoke` or `callbr`
+fn call(i: &str) -> IResult<&str, ()> {
     let i = opt(|i| {
-        let i = tag("tail")(i)?.0;
+        let i = alt((tag("tail"), tag("musttail"), tag("notail")))(i)?.0;
         space1(i)
     })(i)?
     .0;
-    let i = tag("call")(i)?.0;
+    let i = alt((tag("callbr"), tag("call"), tag("invoke")))(i)?.0;
+    Ok((i, ()))
+}
+
+fn asm(i: &str) -> IResult<&str, Stmt> {
+    let i = call(i)?.0;
     let i = space1(i)?.0;
     let i = alt((map(super::type_, drop), map(tag("void"), drop)))(i)?.0;
     let i = space1(i)?.0;
@@ -136,9 +411,64 @@ fn asm(i: &str) -> IResult<&str, Stmt> {
     })(i)?
     .0;
     let (i, s) = super::string(i)?;
+    let (i, operands) = opt(|i| {
+        let i = char(',')(i)?.0;
+        let i = space1(i)?.0;
+        let (i, constraints) = super::string(i)?;
+        let (i, args) = delimited(
+            char('('),
+            separated_list(
+                |i| {
+                    let i = char(',')(i)?.0;
+                    space1(i)
+                },
+                asm_argument,
+            ),
+            char(')'),
+        )(i)?;
+        Ok((i, asm_operands(constraints.0, &args)))
+    })(i)?;
     // NOTE shortcut
     let i = not_line_ending(i)?.0;
-    Ok((i, Stmt::Asm(s.0)))
+    Ok((i, Stmt::Asm(s.0, operands.unwrap_or_default())))
+}
+
+// e.g. `ptr nonnull @foo`; only references to globals matter
+fn asm_argument(i: &str) -> IResult<&str, Option<&str>> {
+    let i = super::type_(i)?.0;
+    let i = space1(i)?.0;
+    let i = many0(|i| {
+        let i = super::attribute(i)?.0;
+        space1(i)
+    })(i)?
+    .0;
+    let (i, operand) = super::operand(i)?;
+    match operand {
+        Operand::Global(name) => Ok((i, Some(name))),
+        Operand::Local(_) | Operand::Constant => Ok((i, None)),
+    }
+}
+
+/// Maps the arguments of an inline assembly call to the operands of its template
+///
+/// Operands are numbered in the order of the `constraints`, e.g. `"=r,s,r,~{memory}"`. Outputs
+/// (`=r`) and labels (`!i`) are not passed as arguments; clobbers (`~{memory}`) are not operands
+pub fn asm_operands<'a>(constraints: &str, args: &[Option<&'a str>]) -> Vec<Option<&'a str>> {
+    let mut args = args.iter();
+    constraints
+        .split(',')
+        .filter(|constraint| !constraint.starts_with('~'))
+        .map(|constraint| {
+            // `=*m` is an output written through a pointer argument
+            let is_arg = !(constraint.starts_with('!')
+                || (constraint.starts_with('=') && !constraint.starts_with("=*")));
+            if is_arg {
+                args.next().cloned().flatten()
+            } else {
+                None
+            }
+        })
+        .collect()
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -156,19 +486,15 @@ fn argument(i: &str) -> IResult<&str, Argument> {
         map(super::bitcast, drop),
         map(super::getelementptr, drop),
         map(super::local, drop),
-        map(digit1, drop),
+        map(super::global, drop),
+        map(super::literal, drop),
     ))(i)?
     .0;
     Ok((i, Argument(ty)))
 }
 
 fn bitcast_call(i: &str) -> IResult<&str, Stmt> {
-    let i = opt(|i| {
-        let i = tag("tail")(i)?.0;
-        space1(i)
-    })(i)?
-    .0;
-    let i = tag("call")(i)?.0;
+    let i = call(i)?.0;
     let i = space1(i)?.0;
 
     // not seen in practice (yet?)
@@ -188,12 +514,7 @@ fn bitcast_call(i: &str) -> IResult<&str, Stmt> {
 }
 
 fn direct_call(i: &str) -> IResult<&str, Stmt> {
-    let i = opt(|i| {
-        let i = tag("tail")(i)?.0;
-        space1(i)
-    })(i)?
-    .0;
-    let i = alt((tag("call"), tag("invoke")))(i)?.0;
+    let i = call(i)?.0;
     let i = space1(i)?.0;
     let i = many0(|i| {
         let i = super::attribute(i)?.0;
@@ -211,11 +532,8 @@ fn direct_call(i: &str) -> IResult<&str, Stmt> {
 }
 
 fn indirect_call(i: &str) -> IResult<&str, Stmt> {
-    let i = opt(|i| {
-        let i = tag("tail")(i)?.0;
-        space1(i)
-    })(i)?
-    .0;
+    let i = call(i)?.0;
+    let i = space1(i)?.0;
     let i = many0(|i| {
         let i = super::attribute(i)?.0;
         space1(i)
@@ -223,9 +541,7 @@ fn indirect_call(i: &str) -> IResult<&str, Stmt> {
     .0;
     let (i, output) = alt((map(super::type_, Some), map(tag("void"), |_| None)))(i)?;
     let i = space1(i)?.0;
-    let i = char('%')(i)?.0;
-    let i = opt(char('_'))(i)?.0;
-    let i = digit1(i)?.0;
+    let i = super::local(i)?.0;
     let (i, inputs) = delimited(
         char('('),
         separated_list(
@@ -237,23 +553,34 @@ fn indirect_call(i: &str) -> IResult<&str, Stmt> {
         ),
         char(')'),
     )(i)?;
-    // TODO we likely want to parse the metadata (`!dbg !0`) that comes after the argument list
     // NOTE shortcut
-    let i = not_line_ending(i)?.0;
-    Ok((
-        i,
-        Stmt::IndirectCall(FnSig {
+    let (i, rest) = not_line_ending(i)?;
+    let sig = match output {
+        // variadic callees are called with their full type, e.g. `call i32 (ptr, ...) %0(ptr %1)`;
+        // the arguments include the variadic ones so they don't make up the signature
+        Some(Type::Fn(sig)) => sig,
+        output => FnSig {
             inputs,
             output: output.map(Box::new),
-        }),
-    ))
+            variadic: false,
+        },
+    };
+    Ok((i, Stmt::IndirectCall(sig, dbg(rest), None)))
+}
+
+fn resume(i: &str) -> IResult<&str, Stmt> {
+    let i = tag("resume")(i)?.0;
+    let i = space1(i)?.0;
+    // NOTE shortcut
+    let i = not_line_ending(i)?.0;
+    Ok((i, Stmt::Resume))
 }
 
 fn other(i: &str) -> IResult<&str, Stmt> {
     let i = separated_nonempty_list(
         space1,
         map_res(is_not(" \t\r\n"), |i| {
-            if i == "call" {
+            if i == "call" || i == "invoke" || i == "callbr" {
                 Err(())
             } else {
                 Ok(())
@@ -276,13 +603,21 @@ fn assign(i: &str) -> IResult<&str, Stmt> {
 fn stmt(i: &str) -> IResult<&str, Stmt> {
     alt((label, comment, |i| {
         let i = space1(i)?.0;
-        alt((assign, asm, bitcast_call, direct_call, indirect_call, other))(i)
+        alt((
+            assign,
+            asm,
+            bitcast_call,
+            direct_call,
+            indirect_call,
+            resume,
+            other,
+        ))(i)
     }))(i)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Argument, Define, Parameter};
+    use super::{Argument, Define, Origin, Parameter, Slot};
     use crate::ir::{FnSig, Stmt, Type};
 
     #[test]
@@ -342,14 +677,33 @@ mod tests {
             super::asm(
                 r#"call void asm sideeffect "cpsie i", "~{memory}"() #7, !dbg !5578, !srcloc !5475"#
             ),
-            Ok(("", Stmt::Asm("cpsie i")))
+            Ok(("", Stmt::Asm("cpsie i", vec![])))
         );
 
         assert_eq!(
             super::asm(
                 r#"tail call i32 asm sideeffect "mrs $0, BASEPRI", "=r"() #5, !dbg !1270, !srcloc !1280"#
             ),
-            Ok(("", Stmt::Asm("mrs $0, BASEPRI")))
+            Ok(("", Stmt::Asm("mrs $0, BASEPRI", vec![None])))
+        );
+
+        // `asm!("bl {}", "bl {}", sym foo, in(reg) f)`
+        assert_eq!(
+            super::asm(
+                r#"tail call i32 asm sideeffect "bl ${1:c}\0Abl ${2}", "={r0},s,r,~{memory}"(ptr nonnull @foo, ptr %f) #2, !srcloc !3"#
+            ),
+            Ok((
+                "",
+                Stmt::Asm("bl ${1:c}\\0Abl ${2}", vec![None, Some("foo"), None])
+            ))
+        );
+    }
+
+    #[test]
+    fn callbr() {
+        assert_eq!(
+            super::asm(r#"callbr void asm sideeffect "jmp ${0:l}", "!i"()"#),
+            Ok(("", Stmt::Asm("jmp ${0:l}", vec![None])))
         );
     }
 
@@ -366,14 +720,19 @@ mod tests {
             ),
             Ok((
                 "",
-                Stmt::IndirectCall(FnSig {
-                    inputs: vec![
-                        Type::Pointer(Box::new(Type::Struct(vec![]))),
-                        Type::Pointer(Box::new(Type::Array(0, Box::new(Type::Integer(8))))),
-                        Type::Integer(32),
-                    ],
-                    output: Some(Box::new(Type::Integer(1))),
-                })
+                Stmt::IndirectCall(
+                    FnSig {
+                        inputs: vec![
+                            Type::Pointer(Box::new(Type::Struct(vec![]))),
+                            Type::Pointer(Box::new(Type::Array(0, Box::new(Type::Integer(8))))),
+                            Type::Integer(32),
+                        ],
+                        output: Some(Box::new(Type::Integer(1))),
+                        variadic: false,
+                    },
+                    Some(30714),
+                    None,
+                )
             ))
         );
 
@@ -417,14 +776,35 @@ mod tests {
 
     #[test]
     fn indirect_call() {
+        assert_eq!(
+            super::indirect_call(r#"invoke void %65(ptr noundef nonnull %62)"#),
+            Ok((
+                "",
+                Stmt::IndirectCall(
+                    FnSig {
+                        inputs: vec![Type::OpaquePointer],
+                        output: None,
+                        variadic: false,
+                    },
+                    None,
+                    None,
+                )
+            ))
+        );
+
         assert_eq!(
             super::indirect_call(r#"tail call i32 %0(i32 0) #8, !dbg !1200"#),
             Ok((
                 "",
-                Stmt::IndirectCall(FnSig {
-                    inputs: vec![Type::Integer(32)],
-                    output: Some(Box::new(Type::Integer(32)))
-                })
+                Stmt::IndirectCall(
+                    FnSig {
+                        inputs: vec![Type::Integer(32)],
+                        output: Some(Box::new(Type::Integer(32))),
+                        variadic: false,
+                    },
+                    Some(1200),
+                    None,
+                )
             ))
         );
 
@@ -434,14 +814,19 @@ mod tests {
             ),
             Ok((
                 "",
-                Stmt::IndirectCall(FnSig {
-                    inputs: vec![
-                        Type::Pointer(Box::new(Type::Struct(vec![]))),
-                        Type::Pointer(Box::new(Type::Array(0, Box::new(Type::Integer(8))))),
-                        Type::Integer(64),
-                    ],
-                    output: Some(Box::new(Type::Integer(1)))
-                })
+                Stmt::IndirectCall(
+                    FnSig {
+                        inputs: vec![
+                            Type::Pointer(Box::new(Type::Struct(vec![]))),
+                            Type::Pointer(Box::new(Type::Array(0, Box::new(Type::Integer(8))))),
+                            Type::Integer(64),
+                        ],
+                        output: Some(Box::new(Type::Integer(1))),
+                        variadic: false,
+                    },
+                    Some(4725),
+                    None,
+                )
             ))
         );
 
@@ -451,14 +836,35 @@ mod tests {
             ),
             Ok((
                 "",
-                Stmt::IndirectCall(FnSig {
-                    inputs: vec![
-                        Type::Pointer(Box::new(Type::Struct(vec![]))),
-                        Type::Pointer(Box::new(Type::Array(0, Box::new(Type::Integer(8))))),
-                        Type::Integer(32),
-                    ],
-                    output: Some(Box::new(Type::Integer(1)))
-                })
+                Stmt::IndirectCall(
+                    FnSig {
+                        inputs: vec![
+                            Type::Pointer(Box::new(Type::Struct(vec![]))),
+                            Type::Pointer(Box::new(Type::Array(0, Box::new(Type::Integer(8))))),
+                            Type::Integer(32),
+                        ],
+                        output: Some(Box::new(Type::Integer(1))),
+                        variadic: false,
+                    },
+                    Some(5301),
+                    None,
+                )
+            ))
+        );
+
+        assert_eq!(
+            super::indirect_call(r#"call i32 (ptr, ...) %0(ptr noundef %1, i32 noundef 1)"#),
+            Ok((
+                "",
+                Stmt::IndirectCall(
+                    FnSig {
+                        inputs: vec![Type::OpaquePointer],
+                        output: Some(Box::new(Type::Integer(32))),
+                        variadic: true,
+                    },
+                    None,
+                    None,
+                )
             ))
         );
 
@@ -466,10 +872,15 @@ mod tests {
             super::indirect_call("call zeroext i1 %_8() #7, !dbg !1250"),
             Ok((
                 "",
-                Stmt::IndirectCall(FnSig {
-                    inputs: vec![],
-                    output: Some(Box::new(Type::Integer(1))),
-                })
+                Stmt::IndirectCall(
+                    FnSig {
+                        inputs: vec![],
+                        output: Some(Box::new(Type::Integer(1))),
+                        variadic: false,
+                    },
+                    Some(1250),
+                    None,
+                )
             ))
         );
     }
@@ -490,6 +901,15 @@ mod tests {
     #[test]
     fn other() {
         assert_eq!(super::other("ret void, !dbg !1377"), Ok(("", Stmt::Other)));
+        assert!(super::other("invoke void %0()").is_err());
+    }
+
+    #[test]
+    fn resume() {
+        assert_eq!(
+            super::resume("resume { ptr, i32 } %1, !dbg !1306"),
+            Ok(("", Stmt::Resume))
+        );
     }
 
     #[test]
@@ -523,11 +943,17 @@ mod tests {
                 "",
                 Define {
                     name: "_ZN4core3ptr18real_drop_in_place17h10d0d6d6b26fb8afE",
+                    internal: true,
                     stmts: vec![Stmt::Label, Stmt::Other],
                     sig: FnSig {
                         inputs: vec![Type::Pointer(Box::new(Type::Alias("blue_pill::ItmLogger")))],
                         output: None,
+                        variadic: false,
                     },
+                    references: vec![],
+                    stores: vec![],
+                    calls: vec![],
+                    personality: None,
                 }
             ))
         );
@@ -539,6 +965,7 @@ mod tests {
                 "",
                 Define {
                     name,
+                    internal: true,
                     stmts: vec![Stmt::Label, Stmt::Other],
                     sig: FnSig {
                         inputs: vec![
@@ -546,10 +973,16 @@ mod tests {
                             Type::Pointer(Box::new(Type::Fn(FnSig {
                                 inputs: vec![Type::Pointer(Box::new(Type::Integer(8)))],
                                 output: None,
+                                variadic: false,
                             }))),
                         ],
                         output: None,
+                        variadic: false,
                     },
+                    references: vec![],
+                    stores: vec![],
+                    calls: vec![],
+                    personality: Some("rust_eh_personality"),
                 }
             ))
         );
@@ -560,6 +993,7 @@ mod tests {
                 "",
                 Define {
                     name: "_ZN3std9panicking20rust_panic_with_hook17hac9cf78024704ab4E",
+                    internal: true,
                     stmts: vec![Stmt::Label, Stmt::Other],
                     sig: FnSig {
                         inputs: vec![
@@ -583,7 +1017,12 @@ mod tests {
                             ]))),
                         ],
                         output: None,
+                        variadic: false,
                     },
+                    references: vec![],
+                    stores: vec![],
+                    calls: vec![],
+                    personality: Some("rust_eh_personality"),
                 }
             ))
         );
@@ -594,6 +1033,7 @@ mod tests {
                 "",
                 Define {
                     name: "foo",
+                    internal: false,
                     stmts: vec![Stmt::Label, Stmt::Other],
                     sig: FnSig {
                         inputs: vec![],
@@ -601,9 +1041,15 @@ mod tests {
                             Type::Fn(FnSig {
                                 inputs: vec![],
                                 output: None,
+                                variadic: false,
                             })
                         )))))),
+                        variadic: false,
                     },
+                    references: vec![],
+                    stores: vec![],
+                    calls: vec![],
+                    personality: None,
                 }
             ))
         );
@@ -614,6 +1060,7 @@ mod tests {
                 "",
                 Define {
                     name: "_ZN3app3foo17h3337355bfdc88d96E",
+                    internal: true,
                     stmts: vec![
                         Stmt::Label,
                         Stmt::DirectCall("llvm.dbg.value"),
@@ -623,7 +1070,177 @@ mod tests {
                     sig: FnSig {
                         inputs: vec![Type::Float],
                         output: Some(Box::new(Type::Float)),
+                        variadic: false,
+                    },
+                    references: vec![],
+                    stores: vec![],
+                    calls: vec![("llvm.dbg.value", vec![])],
+                    personality: None,
+                }
+            ))
+        );
+
+        // opaque pointers: only the call through the method loaded from the vtable is dynamic
+        // dispatch
+        assert_eq!(
+            super::parse(include_str!("define/parse6.ll")),
+            Ok((
+                "",
+                Define {
+                    name: "_RNvCs5QGOdkcCnx8_3app3foo",
+                    internal: true,
+                    stmts: vec![
+                        Stmt::Label,
+                        Stmt::Other,
+                        Stmt::Other,
+                        Stmt::IndirectCall(
+                            FnSig {
+                                inputs: vec![Type::erased(), Type::Integer(32)],
+                                output: Some(Box::new(Type::Integer(32))),
+                                variadic: false,
+                            },
+                            Some(1188),
+                            Some(Origin::Vtable(Slot {
+                                ty: Type::Integer(8),
+                                index: 24,
+                            })),
+                        ),
+                        Stmt::IndirectCall(
+                            FnSig {
+                                inputs: vec![Type::OpaquePointer, Type::Integer(32)],
+                                output: Some(Box::new(Type::Integer(32))),
+                                variadic: false,
+                            },
+                            Some(1189),
+                            None,
+                        ),
+                        Stmt::Other,
+                    ],
+                    sig: FnSig {
+                        inputs: vec![
+                            Type::OpaquePointer,
+                            Type::OpaquePointer,
+                            Type::OpaquePointer
+                        ],
+                        output: None,
+                        variadic: false,
                     },
+                    references: vec![],
+                    stores: vec![],
+                    calls: vec![],
+                    personality: None,
+                }
+            ))
+        );
+
+        // function pointers stored in and loaded from statics
+        assert_eq!(
+            super::parse(include_str!("define/parse7.ll")),
+            Ok((
+                "",
+                Define {
+                    name: "_RNvCs5QGOdkcCnx8_3app3bar",
+                    internal: true,
+                    stmts: vec![
+                        Stmt::Label,
+                        Stmt::Other,
+                        Stmt::Other,
+                        Stmt::Other,
+                        Stmt::DirectCall("_RNvCs5QGOdkcCnx8_3app8register"),
+                        Stmt::Other,
+                        Stmt::IndirectCall(
+                            FnSig {
+                                inputs: vec![],
+                                output: None,
+                                variadic: false,
+                            },
+                            Some(1206),
+                            Some(Origin::Static("_RNvCs5QGOdkcCnx8_3app7HANDLER")),
+                        ),
+                        Stmt::Other,
+                    ],
+                    sig: FnSig {
+                        inputs: vec![Type::OpaquePointer],
+                        output: None,
+                        variadic: false,
+                    },
+                    references: vec![
+                        "_RNvCs5QGOdkcCnx8_3app7handler",
+                        "_RNvCs5QGOdkcCnx8_3app8callback"
+                    ],
+                    stores: vec![
+                        (
+                            "_RNvCs5QGOdkcCnx8_3app7HANDLER",
+                            Some("_RNvCs5QGOdkcCnx8_3app7handler")
+                        ),
+                        ("_RNvCs5QGOdkcCnx8_3app8HANDLERS", None),
+                    ],
+                    calls: vec![(
+                        "_RNvCs5QGOdkcCnx8_3app8register",
+                        vec!["_RNvCs5QGOdkcCnx8_3app8callback"]
+                    )],
+                    personality: None,
+                }
+            ))
+        );
+
+        assert_eq!(
+            super::parse(include_str!("define/parse8.ll")),
+            Ok((
+                "",
+                Define {
+                    name: "_RNvCs5QGOdkcCnx8_3app4drop",
+                    internal: true,
+                    stmts: vec![
+                        Stmt::Label,
+                        Stmt::Other,
+                        Stmt::IndirectCall(
+                            FnSig {
+                                inputs: vec![Type::erased()],
+                                output: None,
+                                variadic: false,
+                            },
+                            // `!dbg` is in the line that follows the `invoke`
+                            Some(1302),
+                            Some(Origin::Vtable(Slot {
+                                ty: Type::Integer(8),
+                                index: 0,
+                            })),
+                        ),
+                        Stmt::Other,
+                        Stmt::Label,
+                        Stmt::Asm("jmp ${0:l}", vec![None]),
+                        Stmt::Other,
+                        Stmt::Label,
+                        Stmt::Other,
+                        Stmt::Label,
+                        Stmt::Other,
+                        Stmt::Other,
+                        Stmt::DirectCall("_RNvCs5QGOdkcCnx8_3app7dealloc"),
+                        Stmt::Other,
+                        Stmt::Label,
+                        Stmt::Resume,
+                        Stmt::Label,
+                        Stmt::Other,
+                        Stmt::Other,
+                        Stmt::DirectCall("_RNvNtCsgEmfK2I1SDS_4core9panicking16panic_in_cleanup"),
+                        Stmt::Other,
+                    ],
+                    sig: FnSig {
+                        inputs: vec![Type::OpaquePointer, Type::OpaquePointer],
+                        output: None,
+                        variadic: false,
+                    },
+                    references: vec![],
+                    stores: vec![],
+                    calls: vec![
+                        ("_RNvCs5QGOdkcCnx8_3app7dealloc", vec![]),
+                        (
+                            "_RNvNtCsgEmfK2I1SDS_4core9panicking16panic_in_cleanup",
+                            vec![]
+                        )
+                    ],
+                    personality: Some("rust_eh_personality"),
                 }
             ))
         );
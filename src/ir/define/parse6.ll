@@ -0,0 +1,8 @@
+define internal void @_RNvCs5QGOdkcCnx8_3app3foo(ptr noundef nonnull align 1 %x.0, ptr noalias noundef readonly align 8 captures(none) dereferenceable(32) %x.1, ptr noundef nonnull %f) unnamed_addr #0 !dbg !1183 {
+start:
+  %0 = getelementptr inbounds nuw i8, ptr %x.1, i64 24
+  %1 = load ptr, ptr %0, align 8, !dbg !1188, !invariant.load !38, !nonnull !38
+  %2 = tail call noundef i32 %1(ptr noundef nonnull align 1 %x.0, i32 noundef 42), !dbg !1188
+  %3 = tail call noundef i32 %f(ptr noundef nonnull align 1 %x.0, i32 noundef -1), !dbg !1189
+  ret void, !dbg !1190
+}
\ No newline at end of file
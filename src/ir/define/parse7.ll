@@ -0,0 +1,10 @@
+define internal void @_RNvCs5QGOdkcCnx8_3app3bar(ptr noundef %f) unnamed_addr #0 !dbg !1200 {
+start:
+  store ptr @_RNvCs5QGOdkcCnx8_3app7handler, ptr @_RNvCs5QGOdkcCnx8_3app7HANDLER, align 4, !dbg !1201
+  store ptr %f, ptr getelementptr inbounds nuw (i8, ptr @_RNvCs5QGOdkcCnx8_3app8HANDLERS, i32 4), align 4, !dbg !1202
+  store ptr null, ptr @_RNvCs5QGOdkcCnx8_3app8HANDLERS, align 4, !dbg !1203
+  call void @_RNvCs5QGOdkcCnx8_3app8register(ptr noundef nonnull @_RNvCs5QGOdkcCnx8_3app8callback), !dbg !1204
+  %0 = load ptr, ptr @_RNvCs5QGOdkcCnx8_3app7HANDLER, align 4, !dbg !1205
+  tail call void %0(), !dbg !1206
+  ret void, !dbg !1207
+}
\ No newline at end of file
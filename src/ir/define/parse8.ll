@@ -0,0 +1,28 @@
+define internal void @_RNvCs5QGOdkcCnx8_3app4drop(ptr noundef %x, ptr noundef %vtable) unnamed_addr #0 personality ptr @rust_eh_personality !dbg !1300 {
+start:
+  %0 = load ptr, ptr %vtable, align 8, !dbg !1301, !invariant.load !38
+  invoke void %0(ptr noundef nonnull %x)
+          to label %bb1 unwind label %cleanup, !dbg !1302
+
+bb1:                                              ; preds = %start
+  callbr void asm sideeffect "jmp ${0:l}", "!i"()
+          to label %bb2 [label %bb2], !dbg !1303
+
+bb2:                                              ; preds = %bb1, %bb1
+  ret void, !dbg !1304
+
+cleanup:                                          ; preds = %start
+  %1 = landingpad { ptr, i32 }
+          cleanup
+  invoke void @_RNvCs5QGOdkcCnx8_3app7dealloc(ptr noundef nonnull %x)
+          to label %bb3 unwind label %abort, !dbg !1305
+
+bb3:                                              ; preds = %cleanup
+  resume { ptr, i32 } %1, !dbg !1306
+
+abort:                                            ; preds = %cleanup
+  %2 = landingpad { ptr, i32 }
+          cleanup
+  call void @_RNvNtCsgEmfK2I1SDS_4core9panicking16panic_in_cleanup() #9, !dbg !1307
+  unreachable, !dbg !1307
+}
\ No newline at end of file
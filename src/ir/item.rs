@@ -1,13 +1,14 @@
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::{char, not_line_ending, space0, space1},
-    combinator::map,
+    character::complete::{char, digit1, not_line_ending, space0, space1},
+    combinator::{map, map_res, opt},
+    error::ErrorKind,
     multi::{many0, separated_list},
     IResult,
 };
 
-use crate::ir::{define::Define, FnSig};
+use crate::ir::{define::Define, FnSig, Type};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Item<'a> {
@@ -25,7 +26,7 @@ pub enum Item<'a> {
 
     // `@0 = private constant <{ [0 x i8 ]}> zeroinitializer, align 4, !dbg 0`
     // `@__sbss = external global i32`
-    Global,
+    Global(Global<'a>),
 
     // `%Struct = type { i8, i16 }` ("new type")
     Type,
@@ -40,7 +41,64 @@ pub enum Item<'a> {
     Attributes,
 
     // `!0 = !DIGlobalVariableExpression(var: !1, expr: !DIExpression())`
-    Metadata,
+    // only the nodes needed to walk from a call site to its enclosing function are kept
+    Metadata(Option<(u32, Metadata<'a>)>),
+
+    // an item the parser doesn't understand (yet); it's skipped
+    Malformed(Malformed<'a>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metadata<'a> {
+    // `!1200 = !DILocation(line: 1, column: 2, scope: !1190, inlinedAt: !1180)`
+    Location { scope: u32 },
+
+    // `!1190 = distinct !DILexicalBlock(scope: !1180, file: !10, line: 3, column: 4)`
+    // `!1191 = !DILexicalBlockFile(scope: !1180, file: !10, discriminator: 0)`
+    LexicalBlock { scope: u32 },
+
+    // `!1180 = distinct !DISubprogram(name: "fmt", linkageName: "_ZN4core3fmt..", ..)`
+    Subprogram { linkage_name: Option<&'a str> },
+
+    // `!1300 = !DIGlobalVariableExpression(var: !1310, expr: !DIExpression())`
+    GlobalVariableExpression { var: u32 },
+
+    // `!1310 = distinct !DIGlobalVariable(name: "<app::Foo as app::Trait>::{vtable}", ..)`
+    GlobalVariable { name: Option<&'a str> },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Global<'a> {
+    // `None` for unnamed globals like `@0`
+    pub name: Option<&'a str>,
+    // `internal` or `private` linkage
+    pub internal: bool,
+    // functions and statics used in the initializer
+    pub references: Vec<&'a str>,
+    pub vtable: Option<Vtable<'a>>,
+}
+
+// e.g. `@vtable.0 = private constant <{ ptr, [16 x i8], ptr }>
+// <{ ptr @drop, [16 x i8] c"..", ptr @foo }>`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Vtable<'a> {
+    // the type of each field and the function it points to, if any
+    pub fields: Vec<(Type<'a>, Option<&'a str>)>,
+    // the debug info of the global (`!dbg !1300`); it names the trait implementation
+    pub dbg: Option<u32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Malformed<'a> {
+    // the function or global the item defines, if it could be told
+    pub name: Option<&'a str>,
+    // whether the item is a function definition
+    pub define: bool,
+    // position, in the module, of the first token that couldn't be parsed; both start at 1
+    pub line: usize,
+    pub column: usize,
+    // the rest of the line that contains that token
+    pub found: &'a str,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -97,20 +155,122 @@ fn alias(i: &str) -> IResult<&str, Item> {
 }
 
 fn global(i: &str) -> IResult<&str, Item> {
-    let i = super::global(i)?.0;
+    let (i, name) = super::global(i)?;
     let i = space1(i)?.0;
     let i = char('=')(i)?.0;
     let i = space1(i)?.0;
+    let internal = i.starts_with("internal ") || i.starts_with("private ");
     let i = many0(|i| {
         let i = super::attribute(i)?.0;
         space1(i)
     })(i)?
     .0;
-    let i = alt((tag("global"), tag("constant")))(i)?.0;
+    let (i, kind) = alt((tag("global"), tag("constant")))(i)?;
     let i = space1(i)?.0;
+    let references = super::globals(not_line_ending(i)?.1);
+    let (i, vtable) = if kind == "constant" {
+        opt(vtable)(i)?
+    } else {
+        (i, None)
+    };
+    let vtable = vtable.filter(|vtable| is_vtable(name.0, internal, &vtable.fields));
     // NOTE shortcut
-    let i = not_line_ending(i)?.0;
-    Ok((i, Item::Global))
+    let (i, rest) = not_line_ending(i)?;
+    let vtable = vtable.map(|vtable| Vtable {
+        dbg: super::define::dbg(rest),
+        ..vtable
+    });
+    Ok((
+        i,
+        Item::Global(Global {
+            name: name.0,
+            internal,
+            references,
+            vtable,
+        }),
+    ))
+}
+
+// `<{ ptr, [16 x i8], ptr }> <{ ptr @drop, [16 x i8] c"..", ptr @foo }>`
+// `{ void (%T*)*, i32, i32, i1 (%T*)* } { void (%T*)* @drop, i32 0, i32 1, i1 (%T*)* @foo }`
+fn vtable(i: &str) -> IResult<&str, Vtable> {
+    let (i, ty) = super::type_(i)?;
+    let packed = match ty {
+        Type::PackedStruct(_) => true,
+        Type::Struct(_) => false,
+        _ => return Err(nom::Err::Error((i, ErrorKind::Verify))),
+    };
+    let i = space1(i)?.0;
+    let i = tag(if packed { "<{" } else { "{" })(i)?.0;
+    let i = space0(i)?.0;
+    let (i, fields) = separated_list(
+        |i| {
+            let i = char(',')(i)?.0;
+            space1(i)
+        },
+        |i| {
+            let (i, ty) = super::type_(i)?;
+            let i = space1(i)?.0;
+            let (i, function) = alt((
+                map(super::bitcast, |bitcast| bitcast.0),
+                map(super::global, |global| global.0),
+                map(super::getelementptr, |_| None),
+                map(super::literal, |_| None),
+                map(|i| super::string(char('c')(i)?.0), |_| None),
+            ))(i)?;
+            Ok((i, (ty, function)))
+        },
+    )(i)?;
+    let i = space0(i)?.0;
+    let i = tag(if packed { "}>" } else { "}" })(i)?.0;
+    Ok((i, Vtable { fields, dbg: None }))
+}
+
+// the layout of a vtable is: destructor (or `null`), size, alignment and then the methods. Plain
+// `static`s can have the same layout (e.g. a function pointer followed by a length) so the name
+// and the destructor must also look like the ones rustc emits; the methods of a global that's
+// wrongly taken for a vtable lose their indirect callers
+pub(super) fn is_vtable(
+    name: Option<&str>,
+    internal: bool,
+    fields: &[(Type, Option<&str>)],
+) -> bool {
+    // `@vtable.0`, `@"<app::Foo as app::Trait>::{vtable}"`
+    let named = name
+        .map(|name| {
+            name.strip_prefix("vtable.")
+                .map(|n| n.bytes().all(|b| b.is_ascii_digit()))
+                .unwrap_or(false)
+                || (name.starts_with('<') && name.contains(" as "))
+        })
+        .unwrap_or(false);
+
+    // `drop_in_place::<T>` or `null` when `T` has no drop glue
+    let drop = |(ty, function): &(Type, Option<&str>)| {
+        matches!(ty, Type::OpaquePointer | Type::Pointer(_))
+            && function
+                .map(|function| function.contains("drop_in_place"))
+                .unwrap_or(true)
+    };
+
+    named
+        && internal
+        && match fields {
+            // the size and the alignment are `usize`s packed in a byte array
+            [first, (Type::Array(n, elem), None), _, ..]
+                if (*n == 8 || *n == 16) && **elem == Type::Integer(8) =>
+            {
+                drop(first)
+            }
+            [first, (Type::Integer(_), None), (Type::Integer(_), None), _, ..] => drop(first),
+            // when there's no drop glue the `null` is packed in the byte array too
+            [(Type::Array(n, elem), None), (ty, Some(_)), ..]
+                if (*n == 12 || *n == 24) && **elem == Type::Integer(8) =>
+            {
+                matches!(ty, Type::OpaquePointer | Type::Pointer(_))
+            }
+            _ => false,
+        }
 }
 
 fn type_(i: &str) -> IResult<&str, Item> {
@@ -156,6 +316,15 @@ fn declare(i: &str) -> IResult<&str, Item> {
                 Ok((i, ty))
             },
         )(i)?;
+        // variadic function, e.g. `@syscall(i64 noundef, ...)`
+        let (i, variadic) = opt(|i| {
+            let i = opt(|i| {
+                let i = char(',')(i)?.0;
+                space1(i)
+            })(i)?
+            .0;
+            tag("...")(i)
+        })(i)?;
         let i = char(')')(i)?.0;
         let i = not_line_ending(i)?.0;
         Ok((
@@ -165,6 +334,7 @@ fn declare(i: &str) -> IResult<&str, Item> {
                 sig: Some(FnSig {
                     output: output.map(Box::new),
                     inputs,
+                    variadic: variadic.is_some(),
                 }),
             }),
         ))
@@ -182,9 +352,78 @@ fn attributes(i: &str) -> IResult<&str, Item> {
 
 fn metadata(i: &str) -> IResult<&str, Item> {
     let i = tag("!")(i)?.0;
+    if let Ok((i, md)) = node(i) {
+        return Ok((i, Item::Metadata(md)));
+    }
+
     // NOTE shortcut
     let i = not_line_ending(i)?.0;
-    Ok((i, Item::Metadata))
+    Ok((i, Item::Metadata(None)))
+}
+
+// `1200 = !DILocation(line: 1, column: 2, scope: !1190)`
+fn node(i: &str) -> IResult<&str, Option<(u32, Metadata)>> {
+    let (i, id) = map_res(digit1, |d: &str| d.parse())(i)?;
+    let i = space1(i)?.0;
+    let i = char('=')(i)?.0;
+    let i = space1(i)?.0;
+    let i = opt(|i| {
+        let i = tag("distinct")(i)?.0;
+        space1(i)
+    })(i)?
+    .0;
+    let i = char('!')(i)?.0;
+    let (i, kind) = super::ident(i)?;
+    // NOTE shortcut
+    let (i, fields) = not_line_ending(i)?;
+
+    let md = match kind.0 {
+        "DILocation" => field(fields, "scope").map(|scope| Metadata::Location { scope }),
+        "DILexicalBlock" | "DILexicalBlockFile" => {
+            field(fields, "scope").map(|scope| Metadata::LexicalBlock { scope })
+        }
+        "DISubprogram" => Some(Metadata::Subprogram {
+            linkage_name: string(fields, "linkageName"),
+        }),
+        "DIGlobalVariableExpression" => {
+            field(fields, "var").map(|var| Metadata::GlobalVariableExpression { var })
+        }
+        "DIGlobalVariable" => Some(Metadata::GlobalVariable {
+            name: string(fields, "name"),
+        }),
+        _ => None,
+    };
+
+    Ok((i, md.map(|md| (id, md))))
+}
+
+// the `!N` value of field `name` in `(a: !1, scope: !2)`
+fn field(fields: &str, name: &str) -> Option<u32> {
+    fields.split(", ").find_map(|field| {
+        let field = field.trim_start_matches('(');
+        let value = field.strip_prefix(name)?.strip_prefix(": !")?;
+        let end = value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len());
+        value[..end].parse().ok()
+    })
+}
+
+// the string value of field `name` in `(name: "fmt", linkageName: "_ZN..")`
+// the values can contain `, ` so the fields are not split like in `field`
+fn string<'a>(fields: &'a str, name: &str) -> Option<&'a str> {
+    let key = format!("{}: \"", name);
+    let start = fields
+        .match_indices(&key)
+        .map(|(start, _)| start)
+        .find(|start| fields[..*start].ends_with('(') || fields[..*start].ends_with(", "))?
+        + key.len();
+    let end = fields[start..].find('"')?;
+    Some(&fields[start..start + end])
+}
+
+fn define(i: &str) -> IResult<&str, Item> {
+    map(super::define::parse, Item::Define)(i)
 }
 
 pub fn item(i: &str) -> IResult<&str, Item> {
@@ -195,16 +434,47 @@ pub fn item(i: &str) -> IResult<&str, Item> {
         type_,
         global,
         alias,
-        map(super::define::parse, Item::Define),
+        define,
         declare,
         attributes,
         metadata,
     ))(i)
 }
 
+type Parser = fn(&str) -> IResult<&str, Item>;
+
+// where parsing the item `i` went wrong: the furthest point any of the parsers reached. `item`
+// only reports the error of its last alternative
+pub fn failure(i: &str) -> &str {
+    let parsers: [Parser; 10] = [
+        comment,
+        source_filename,
+        target,
+        type_,
+        global,
+        alias,
+        define,
+        declare,
+        attributes,
+        metadata,
+    ];
+
+    parsers
+        .iter()
+        .filter_map(|parser| match parser(i) {
+            Ok((rest, _)) | Err(nom::Err::Error((rest, _))) | Err(nom::Err::Failure((rest, _))) => {
+                Some(rest)
+            }
+            Err(nom::Err::Incomplete(_)) => None,
+        })
+        .min_by_key(|rest| rest.len())
+        .unwrap_or(i)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::ir::{Declare, FnSig, Item, Type};
+    use super::{Global, Vtable};
+    use crate::ir::{Declare, FnSig, Item, Metadata, Type};
 
     #[test]
     fn alias() {
@@ -224,7 +494,23 @@ mod tests {
                     name: "malloc",
                     sig: Some(FnSig {
                         inputs: vec![Type::Integer(64)],
-                        output: Some(Box::new(Type::Pointer(Box::new(Type::Integer(8)))))
+                        output: Some(Box::new(Type::Pointer(Box::new(Type::Integer(8))))),
+                        variadic: false,
+                    })
+                })
+            ))
+        );
+
+        assert_eq!(
+            super::declare(r#"declare noundef i64 @syscall(i64 noundef, ...) unnamed_addr #3"#),
+            Ok((
+                "",
+                Item::Declare(Declare {
+                    name: "syscall",
+                    sig: Some(FnSig {
+                        inputs: vec![Type::Integer(64)],
+                        output: Some(Box::new(Type::Integer(64))),
+                        variadic: true,
                     })
                 })
             ))
@@ -235,12 +521,181 @@ mod tests {
     fn global() {
         assert_eq!(
             super::global("@0 = private constant <{ [0 x i8] }> zeroinitializer, align 4, !dbg !0"),
-            Ok(("", Item::Global))
+            Ok((
+                "",
+                Item::Global(Global {
+                    name: None,
+                    internal: true,
+                    references: vec![],
+                    vtable: None,
+                })
+            ))
         );
 
         assert_eq!(
             super::global("@DEVICE_PERIPHERALS = local_unnamed_addr global <{ [1 x i8] }> zeroinitializer, align 1, !dbg !175"),
-            Ok(("", Item::Global))
+            Ok((
+                "",
+                Item::Global(Global {
+                    name: Some("DEVICE_PERIPHERALS"),
+                    internal: false,
+                    references: vec![],
+                    vtable: None,
+                })
+            ))
+        );
+
+        assert_eq!(
+            super::global("@HANDLER = internal global ptr @default_handler, align 4"),
+            Ok((
+                "",
+                Item::Global(Global {
+                    name: Some("HANDLER"),
+                    internal: true,
+                    references: vec!["default_handler"],
+                    vtable: None,
+                })
+            ))
+        );
+    }
+
+    #[test]
+    fn vtable() {
+        assert_eq!(
+            super::global(
+                r#"@vtable.0 = private unnamed_addr constant <{ ptr, [16 x i8], ptr, ptr }> <{ ptr @_ZN4core3ptr13drop_in_place17h0123456789abcdefE, [16 x i8] c"\04\00\00\00\00\00\00\00\04\00\00\00\00\00\00\00", ptr @foo, ptr @bar }>, align 8, !dbg !1300"#
+            ),
+            Ok((
+                "",
+                Item::Global(Global {
+                    name: Some("vtable.0"),
+                    internal: true,
+                    references: vec![
+                        "_ZN4core3ptr13drop_in_place17h0123456789abcdefE",
+                        "foo",
+                        "bar"
+                    ],
+                    vtable: Some(Vtable {
+                        fields: vec![
+                            (
+                                Type::OpaquePointer,
+                                Some("_ZN4core3ptr13drop_in_place17h0123456789abcdefE")
+                            ),
+                            (Type::Array(16, Box::new(Type::Integer(8))), None),
+                            (Type::OpaquePointer, Some("foo")),
+                            (Type::OpaquePointer, Some("bar")),
+                        ],
+                        dbg: Some(1300),
+                    })
+                })
+            ))
+        );
+
+        assert_eq!(
+            super::global(
+                r#"@vtable.1 = private unnamed_addr constant { void (%Baz*)*, i32, i32, i1 ({}*)* } { void (%Baz*)* null, i32 0, i32 1, i1 ({}*)* bitcast (i1 (%Baz*)* @foo to i1 ({}*)*) }, align 4"#
+            ),
+            Ok((
+                "",
+                Item::Global(Global {
+                    name: Some("vtable.1"),
+                    internal: true,
+                    references: vec!["foo"],
+                    vtable: Some(Vtable {
+                        fields: vec![
+                            (
+                                Type::Pointer(Box::new(Type::Fn(FnSig {
+                                    inputs: vec![Type::Pointer(Box::new(Type::Alias("Baz")))],
+                                    output: None,
+                                    variadic: false,
+                                }))),
+                                None
+                            ),
+                            (Type::Integer(32), None),
+                            (Type::Integer(32), None),
+                            (
+                                Type::Pointer(Box::new(Type::Fn(FnSig {
+                                    inputs: vec![Type::erased()],
+                                    output: Some(Box::new(Type::Integer(1))),
+                                    variadic: false,
+                                }))),
+                                Some("foo")
+                            ),
+                        ],
+                        dbg: None,
+                    })
+                })
+            ))
+        );
+
+        assert_eq!(
+            super::global(
+                r#"@vtable.3 = private constant <{ [24 x i8], ptr }> <{ [24 x i8] c"\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\00\01\00\00\00\00\00\00\00", ptr @foo }>, align 8, !dbg !1400"#
+            ),
+            Ok((
+                "",
+                Item::Global(Global {
+                    name: Some("vtable.3"),
+                    internal: true,
+                    references: vec!["foo"],
+                    vtable: Some(Vtable {
+                        fields: vec![
+                            (Type::Array(24, Box::new(Type::Integer(8))), None),
+                            (Type::OpaquePointer, Some("foo")),
+                        ],
+                        dbg: Some(1400),
+                    })
+                })
+            ))
+        );
+
+        // not a vtable: a `static` table of handlers, which starts with a function pointer and
+        // two integers
+        assert_eq!(
+            super::global(
+                r#"@_ZN3app8HANDLERS17h0123456789abcdefE = internal constant <{ ptr, [8 x i8], ptr }> <{ ptr @on_reset, [8 x i8] c"\01\00\00\00\02\00\00\00", ptr @on_fault }>, align 4"#
+            ),
+            Ok((
+                "",
+                Item::Global(Global {
+                    name: Some("_ZN3app8HANDLERS17h0123456789abcdefE"),
+                    internal: true,
+                    references: vec!["on_reset", "on_fault"],
+                    vtable: None,
+                })
+            ))
+        );
+
+        // not a vtable: the name is right but the first slot is not the drop glue
+        assert_eq!(
+            super::global(
+                r#"@vtable.2 = private unnamed_addr constant { void ()*, i32, i32, void ()* } { void ()* @on_reset, i32 1, i32 2, void ()* @on_fault }, align 4"#
+            ),
+            Ok((
+                "",
+                Item::Global(Global {
+                    name: Some("vtable.2"),
+                    internal: true,
+                    references: vec!["on_reset", "on_fault"],
+                    vtable: None,
+                })
+            ))
+        );
+
+        // not a vtable
+        assert_eq!(
+            super::global(
+                r#"@anon.0 = private unnamed_addr constant <{ ptr, [16 x i8] }> <{ ptr @anon.1, [16 x i8] c"'\00\00\00\00\00\00\00\9C\00\00\00\19\00\00\00" }>, align 8"#
+            ),
+            Ok((
+                "",
+                Item::Global(Global {
+                    name: Some("anon.0"),
+                    internal: true,
+                    references: vec!["anon.1"],
+                    vtable: None,
+                })
+            ))
         );
     }
 
@@ -251,4 +706,75 @@ mod tests {
             Ok(("", Item::Type))
         );
     }
+
+    #[test]
+    fn metadata() {
+        assert_eq!(
+            super::metadata(
+                "!1200 = !DILocation(line: 2, column: 9, scope: !1190, inlinedAt: !1180)"
+            ),
+            Ok((
+                "",
+                Item::Metadata(Some((1200, Metadata::Location { scope: 1190 })))
+            ))
+        );
+
+        assert_eq!(
+            super::metadata(
+                "!1190 = distinct !DILexicalBlock(scope: !1180, file: !10, line: 3, column: 4)"
+            ),
+            Ok((
+                "",
+                Item::Metadata(Some((1190, Metadata::LexicalBlock { scope: 1180 })))
+            ))
+        );
+
+        assert_eq!(
+            super::metadata(
+                r#"!1180 = distinct !DISubprogram(name: "fmt", linkageName: "_RNvMNtNtCs5QGOdkcCnx8_4core3fmt2rtNtB2_8Argument3fmt", scope: !1170, file: !10, line: 5, type: !1171, scopeLine: 5, flags: DIFlagPrototyped, spFlags: DISPFlagLocalToUnit | DISPFlagDefinition, unit: !3)"#
+            ),
+            Ok((
+                "",
+                Item::Metadata(Some((
+                    1180,
+                    Metadata::Subprogram {
+                        linkage_name: Some("_RNvMNtNtCs5QGOdkcCnx8_4core3fmt2rtNtB2_8Argument3fmt")
+                    }
+                )))
+            ))
+        );
+
+        assert_eq!(
+            super::metadata(
+                "!1300 = !DIGlobalVariableExpression(var: !1310, expr: !DIExpression())"
+            ),
+            Ok((
+                "",
+                Item::Metadata(Some((
+                    1300,
+                    Metadata::GlobalVariableExpression { var: 1310 }
+                )))
+            ))
+        );
+
+        assert_eq!(
+            super::metadata(
+                r#"!1310 = distinct !DIGlobalVariable(name: "<app::Foo as core::ops::function::Fn<(u8, u16)>>::{vtable}", scope: null, file: !10, type: !1320, isLocal: true, isDefinition: true)"#
+            ),
+            Ok((
+                "",
+                Item::Metadata(Some((
+                    1310,
+                    Metadata::GlobalVariable {
+                        name: Some("<app::Foo as core::ops::function::Fn<(u8, u16)>>::{vtable}")
+                    }
+                )))
+            ))
+        );
+
+        assert_eq!(
+            super::metadata("!llvm.module.flags = !{!0, !1, !2}"),
+            Ok(("", Item::Metadata(None)))
+        );
+    }
 }
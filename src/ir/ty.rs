@@ -41,6 +41,12 @@ pub enum Type<'a> {
 
     // `i8*`
     Pointer(Box<Type<'a>>),
+
+    // `ptr`; LLVM 15+ no longer tracks the pointee type
+    OpaquePointer,
+
+    // `<4 x i32>`
+    Vector(usize, Box<Type<'a>>),
 }
 
 impl<'a> Type<'a> {
@@ -58,6 +64,27 @@ impl<'a> Type<'a> {
             _ => false,
         }
     }
+
+    // size in bytes; only needed for the fields of vtables so padding is not considered
+    pub fn size(&self, pointer_size: u64) -> Option<u64> {
+        Some(match self {
+            Type::Integer(bits) => (*bits as u64).div_ceil(8),
+            Type::Float => 4,
+            Type::Double => 8,
+            Type::Pointer(_) | Type::OpaquePointer => pointer_size,
+            Type::Array(count, ty) | Type::Vector(count, ty) => {
+                *count as u64 * ty.size(pointer_size)?
+            }
+            Type::Struct(fields) | Type::PackedStruct(fields) => {
+                let mut size = 0;
+                for field in fields {
+                    size += field.size(pointer_size)?;
+                }
+                size
+            }
+            Type::Alias(_) | Type::Fn(_) => return None,
+        })
+    }
 }
 
 fn fmt_struct(f: &mut fmt::Formatter, fields: &[Type]) -> fmt::Result {
@@ -129,6 +156,18 @@ impl<'a> fmt::Display for Type<'a> {
                 write!(f, "{}", ty)?;
                 f.write_str("*")?;
             }
+
+            Type::OpaquePointer => {
+                f.write_str("ptr")?;
+            }
+
+            Type::Vector(count, ty) => {
+                f.write_str("<")?;
+                write!(f, "{}", count)?;
+                f.write_str(" x ")?;
+                write!(f, "{}", ty)?;
+                f.write_str(">")?;
+            }
         }
 
         Ok(())
@@ -150,6 +189,34 @@ fn array(i: &str) -> IResult<&str, Type> {
     )(i)
 }
 
+// `<4 x i32>`
+fn vector(i: &str) -> IResult<&str, Type> {
+    delimited(
+        char('<'),
+        |i| {
+            let (i, count) = map_res(digit1, usize::from_str)(i)?;
+            let i = space1(i)?.0;
+            let i = char('x')(i)?.0;
+            let i = space1(i)?.0;
+            let (i, ty) = type_(i)?;
+            Ok((i, Type::Vector(count, Box::new(ty))))
+        },
+        char('>'),
+    )(i)
+}
+
+// `ptr` or `ptr addrspace(1)`
+fn opaque_pointer(i: &str) -> IResult<&str, Type> {
+    let i = tag("ptr")(i)?.0;
+    let i = opt(|i| {
+        let i = space1(i)?.0;
+        let i = tag("addrspace")(i)?.0;
+        delimited(char('('), digit1, char(')'))(i)
+    })(i)?
+    .0;
+    Ok((i, Type::OpaquePointer))
+}
+
 fn double(i: &str) -> IResult<&str, Type> {
     Ok((tag("double")(i)?.0, Type::Double))
 }
@@ -200,10 +267,11 @@ pub fn type_(i: &str) -> IResult<&str, Type> {
     if void.is_some() {
         // this must be a function
         let i = space1(i)?.0;
-        let (mut i, inputs) = fn_inputs(i)?;
+        let (mut i, (inputs, variadic)) = fn_inputs(i)?;
         let mut ty = Type::Fn(FnSig {
             inputs,
             output: None,
+            variadic,
         });
 
         // is this a function pointer?
@@ -220,8 +288,17 @@ pub fn type_(i: &str) -> IResult<&str, Type> {
 
         Ok((i, ty))
     } else {
-        let (mut i, mut ty) =
-            alt((array, packed_struct, struct_, alias, double, float, integer))(i)?;
+        let (mut i, mut ty) = alt((
+            array,
+            packed_struct,
+            vector,
+            struct_,
+            alias,
+            double,
+            float,
+            integer,
+            opaque_pointer,
+        ))(i)?;
 
         // is this a pointer?
         loop {
@@ -242,11 +319,12 @@ pub fn type_(i: &str) -> IResult<&str, Type> {
                 fn_inputs(i)
             })(i)?;
 
-            if let Some(inputs) = inputs {
+            if let Some((inputs, variadic)) = inputs {
                 i = i_;
                 ty = Type::Fn(FnSig {
                     inputs,
                     output: Some(Box::new(ty)),
+                    variadic,
                 });
 
                 // is this a function pointer?
@@ -269,7 +347,7 @@ pub fn type_(i: &str) -> IResult<&str, Type> {
     }
 }
 
-fn fn_inputs(i: &str) -> IResult<&str, Vec<Type>> {
+fn fn_inputs(i: &str) -> IResult<&str, (Vec<Type>, bool)> {
     let i = char('(')(i)?.0;
     let i = space0(i)?.0;
     let (i, inputs) = separated_list(
@@ -279,8 +357,17 @@ fn fn_inputs(i: &str) -> IResult<&str, Vec<Type>> {
         },
         type_,
     )(i)?;
+    // variadic function, e.g. `i32 (i32, i32, ...)`
+    let (i, variadic) = opt(|i| {
+        let i = opt(|i| {
+            let i = char(',')(i)?.0;
+            space1(i)
+        })(i)?
+        .0;
+        tag("...")(i)
+    })(i)?;
     let i = char(')')(i)?.0;
-    Ok((i, inputs))
+    Ok((i, (inputs, variadic.is_some())))
 }
 
 #[cfg(test)]
@@ -289,7 +376,23 @@ mod tests {
 
     #[test]
     fn fn_inputs() {
-        assert_eq!(super::fn_inputs(r#"()"#), Ok(("", vec![])));
+        assert_eq!(super::fn_inputs(r#"()"#), Ok(("", (vec![], false))));
+        assert_eq!(super::fn_inputs(r#"(...)"#), Ok(("", (vec![], true))));
+        assert_eq!(
+            super::fn_inputs(r#"(i32, i32, ...)"#),
+            Ok(("", (vec![Type::Integer(32), Type::Integer(32)], true)))
+        );
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(Type::OpaquePointer.size(8), Some(8));
+        assert_eq!(Type::erased().size(4), Some(4));
+        assert_eq!(
+            Type::Array(16, Box::new(Type::Integer(8))).size(8),
+            Some(16)
+        );
+        assert_eq!(Type::Alias("core::fmt::Formatter").size(4), None);
     }
 
     #[test]
@@ -338,6 +441,17 @@ mod tests {
             Ok(("", Type::Pointer(Box::new(Type::Integer(8)))))
         );
 
+        assert_eq!(super::type_("ptr"), Ok(("", Type::OpaquePointer)));
+        assert_eq!(
+            super::type_("ptr addrspace(1)"),
+            Ok(("", Type::OpaquePointer))
+        );
+
+        assert_eq!(
+            super::type_("<4 x i32>"),
+            Ok(("", Type::Vector(4, Box::new(Type::Integer(32)))))
+        );
+
         assert_eq!(
             super::type_("i8**"),
             Ok((
@@ -354,6 +468,7 @@ mod tests {
                 Type::Pointer(Box::new(Type::Fn(FnSig {
                     inputs: vec![Type::Pointer(Box::new(Type::Integer(8)))],
                     output: None,
+                    variadic: false,
                 })))
             ))
         );
@@ -365,6 +480,7 @@ mod tests {
                 Type::Pointer(Box::new(Type::Fn(FnSig {
                     inputs: vec![Type::Integer(8)],
                     output: Some(Box::new(Type::Integer(8))),
+                    variadic: false,
                 })))
             ))
         );
@@ -376,6 +492,7 @@ mod tests {
                 Type::Pointer(Box::new(Type::Pointer(Box::new(Type::Fn(FnSig {
                     inputs: vec![],
                     output: None,
+                    variadic: false,
                 })))))
             ))
         );
@@ -0,0 +1,2695 @@
+//! Whole program static stack analysis
+//!
+//! This is the library behind `cargo call-stack`. [`analyze`] builds the call graph of a program
+//! from its ELF file, its LLVM-IR and the object files that carry its stack usage information, and
+//! computes the worst-case stack usage of every function.
+//!
+//! ``` ignore
+//! let call_graph = cargo_call_stack::analyze(
+//!     Input {
+//!         elf: &elf,
+//!         ir: Ir::Text(&ll),
+//!         ir_path: None,
+//!         objects: vec![&obj],
+//!         target: "thumbv7m-none-eabi",
+//!     },
+//!     &Options::default(),
+//! )?;
+//!
+//! assert_eq!(call_graph.max("main")?, Some(Max::Exact(32)));
+//! ```
+
+#![deny(warnings)]
+
+use core::{
+    cmp,
+    convert::TryFrom,
+    fmt::{self, Write as _},
+    ops, str,
+};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::Path,
+    process::Command,
+};
+
+use ar::Archive;
+use log::{error, warn};
+use petgraph::{
+    algo,
+    graph::{DiGraph, NodeIndex},
+    visit::{Dfs, Reversed, Topo},
+    Direction, Graph,
+};
+use xmas_elf::{
+    header::Class,
+    sections::{SectionData, ShType, SHF_EXECINSTR},
+    symbol_table::Entry as _,
+    ElfFile,
+};
+
+pub use crate::{
+    erasure::Pattern,
+    ir::bitcode::Strings,
+    memory::MainStack,
+    recursion::Bound,
+    tasks::{Entry, Free, Stack, Task},
+};
+use crate::{
+    ir::{FnSig, Item, Origin, Stmt, Type},
+    simplify::Cluster,
+    symbol::Symbol,
+    thumb::Tag,
+};
+
+mod aarch64;
+mod erasure;
+mod futures;
+mod ir;
+mod memory;
+mod panicking;
+pub mod query;
+mod recursion;
+mod riscv;
+pub mod simplify;
+mod symbol;
+mod tasks;
+mod thumb;
+mod x86_64;
+
+// Font used in the dot graphs
+const FONT: &str = "monospace";
+
+// The routine of the unwinder that continues unwinding after a landing pad
+const UNWIND_RESUME: &str = "_Unwind_Resume";
+
+// Version we analyzed to extract some ad-hoc information
+const VERS: &str = "1.33.0"; // compiler-builtins = "0.1.4"
+
+/// What the analysis works on; `cargo call-stack` gets all of it from `cargo rustc --release --
+/// --emit=llvm-ir,obj -C lto -Z emit-stack-sizes`
+pub struct Input<'a> {
+    /// The final executable
+    pub elf: &'a [u8],
+    /// The LLVM module of the whole program
+    pub ir: Ir<'a>,
+    /// Where the LLVM module comes from; only used in diagnostics
+    pub ir_path: Option<&'a Path>,
+    /// Object files with stack usage information (`-Z emit-stack-sizes`); when several of them
+    /// report the stack usage of the same function the last one wins
+    pub objects: Vec<&'a [u8]>,
+    /// The compilation target, e.g. `thumbv7m-none-eabi`
+    pub target: &'a str,
+}
+
+/// An LLVM module
+#[derive(Clone, Copy)]
+pub enum Ir<'a> {
+    /// Textual LLVM-IR (`--emit=llvm-ir`)
+    Text(&'a str),
+    /// LLVM bitcode (`--emit=llvm-bc`) and where to keep the strings the analysis builds from it
+    Bitcode(&'a [u8], &'a Strings),
+}
+
+/// How to analyze the program
+pub struct Options<'a> {
+    /// Turn the problems that make the analysis incomplete into errors
+    pub strict: bool,
+    /// Type-erasure patterns; see `Pattern::builtin`
+    pub patterns: Vec<Pattern>,
+    /// Bounds on the recursion depth of the cycles of the call graph
+    pub bounds: Vec<Bound>,
+    /// Only keep the part of the call graph that can be reached from this function
+    pub start: Option<&'a str>,
+    /// Entry functions of tasks that run on their own stacks
+    pub tasks: Vec<Task>,
+    /// Functions that start tasks (`*` is a wildcard); the functions passed to them are the entry
+    /// functions of the tasks and the largest `static` passed to them is the stack
+    pub spawns: Vec<String>,
+    /// The symbol that holds the initial stack pointer; `_stack_start` by default
+    pub stack_start: Option<&'a str>,
+    /// The symbol that holds the lowest address the main stack can grow into; by default, the end
+    /// of the static data and the heap
+    pub stack_end: Option<&'a str>,
+    /// The size of the exception frame the hardware pushes onto the stack each time a handler
+    /// preempts the running code; by default, 32 bytes on ARM Cortex-M, 104 bytes on the `eabihf`
+    /// targets (lazy FP stacking) and 0 bytes on the other targets
+    pub exception_frame: Option<u64>,
+}
+
+impl Default for Options<'_> {
+    fn default() -> Self {
+        Options {
+            strict: false,
+            patterns: Pattern::builtin(),
+            bounds: vec![],
+            start: None,
+            tasks: vec![],
+            spawns: vec![],
+            stack_start: None,
+            stack_end: None,
+            exception_frame: None,
+        }
+    }
+}
+
+/// The call graph of a program and the stack usage of its functions
+pub struct CallGraph<'a> {
+    /// The functions and the calls between them
+    pub graph: Graph<Node<'a>, ()>,
+    /// The cycles of the call graph
+    pub cycles: Vec<Cycle>,
+    /// Parts of the LLVM-IR could not be parsed; some calls may be missing
+    pub incomplete: bool,
+    /// The main stack, if its bounds could be found in the ELF file
+    pub main_stack: Option<MainStack>,
+}
+
+impl<'a> CallGraph<'a> {
+    /// The node of `function`; mangled, demangled and demangled without hash names are accepted
+    pub fn find(&self, function: &str) -> Result<NodeIndex, failure::Error> {
+        query::find(&self.graph, function)
+    }
+
+    /// The worst-case stack usage of `function`, including the functions it calls
+    pub fn max(&self, function: &str) -> Result<Option<Max>, failure::Error> {
+        Ok(self.graph[self.find(function)?].max)
+    }
+
+    /// The call path from `function` that uses the most stack; cycles are followed once
+    pub fn worst_path(&self, function: &str) -> Result<Vec<NodeIndex>, failure::Error> {
+        let g = &self.graph;
+
+        let mut path = vec![self.find(function)?];
+        loop {
+            let last = *path.last().expect("UNREACHABLE");
+            let next = g
+                .neighbors(last)
+                .filter(|callee| !path.contains(callee))
+                .max_by_key(|callee| match g[*callee].max {
+                    Some(Max::Exact(n)) | Some(Max::LowerBound(n)) => n,
+                    None => 0,
+                });
+
+            if let Some(next) = next {
+                path.push(next);
+            } else {
+                break;
+            }
+        }
+
+        Ok(path)
+    }
+}
+
+/// Builds the call graph of a program and computes the worst-case stack usage of each of its
+/// functions
+pub fn analyze<'a>(input: Input<'a>, options: &Options) -> Result<CallGraph<'a>, failure::Error> {
+    let Input {
+        elf,
+        ir,
+        ir_path,
+        objects,
+        target,
+    } = input;
+    let strict = options.strict;
+    let patterns = &options.patterns;
+    let bounds = &options.bounds;
+
+    // the textual IR is parsed as it's consumed; only the items the analysis keeps stay in memory
+    let items: Box<dyn Iterator<Item = Item>> = match ir {
+        Ir::Text(ll) => Box::new(crate::ir::parse(ll)),
+        Ir::Bitcode(bc, strings) => Box::new(crate::ir::bitcode::parse(bc, strings)?.into_iter()),
+    };
+    let mut defines = HashMap::new();
+    let mut declares = HashMap::new();
+    let mut vtables = vec![];
+    let mut globals = vec![];
+    let mut metadata = HashMap::new();
+    // functions whose definition we failed to parse
+    let mut malformed = vec![];
+    // whether some part of the LLVM module is missing from the analysis
+    let mut incomplete = false;
+    for item in items {
+        match item {
+            Item::Define(def) => {
+                defines.insert(def.name, def);
+            }
+
+            Item::Declare(decl) => {
+                declares.insert(decl.name, decl);
+            }
+
+            Item::Global(global) => {
+                if let Some(vtable) = global.vtable {
+                    vtables.push((global.name, vtable));
+                } else {
+                    globals.push(global);
+                }
+            }
+
+            Item::Metadata(Some((id, md))) => {
+                metadata.insert(id, md);
+            }
+
+            Item::Malformed(item) => {
+                diagnose(
+                    strict,
+                    format!(
+                        "{}:{}:{}: failed to parse {}; skipping it (near `{}`)",
+                        ir_path.unwrap_or_else(|| Path::new("<llvm-ir>")).display(),
+                        item.line,
+                        item.column,
+                        match (item.define, item.name) {
+                            (true, Some(name)) => format!("the definition of `{}`", name),
+                            (false, Some(name)) => format!("the item that defines `{}`", name),
+                            (_, None) => "an item".to_owned(),
+                        },
+                        item.found,
+                    ),
+                )?;
+
+                incomplete = true;
+                if item.define {
+                    malformed.extend(item.name);
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    // a flow-insensitive points-to analysis for function pointers: functions and statics that are
+    // used in any other way than being called or being loaded from / stored to. The functions
+    // that are not in this set (and are not visible outside the LLVM module) can't be called
+    // through a function pointer
+    let mut referenced = HashSet::new();
+    // what gets stored in each static
+    let mut statics = HashMap::<_, Static>::new();
+    for def in defines.values() {
+        referenced.extend(def.references.iter().cloned());
+
+        for (name, value) in &def.stores {
+            let static_ = statics.entry(*name).or_default();
+            if let Some(value) = value {
+                static_.stored.insert(*value);
+            } else {
+                static_.unknown = true;
+            }
+        }
+    }
+    // NOTE vtables are left out because their methods are only called via dynamic dispatch
+    for global in &globals {
+        referenced.extend(global.references.iter().cloned());
+
+        if let Some(name) = global.name {
+            let static_ = statics.entry(name).or_default();
+            static_.internal = global.internal;
+            static_.stored.extend(global.references.iter().cloned());
+        }
+    }
+
+    // LLVM 15+ emits `ptr` instead of typed pointers like `i8*`
+    let opaque_pointers = defines
+        .values()
+        .any(|def| def.sig.inputs.contains(&Type::OpaquePointer));
+    // `*mut u8`
+    let byte_pointer = || {
+        if opaque_pointers {
+            Type::OpaquePointer
+        } else {
+            Type::Pointer(Box::new(Type::Integer(8)))
+        }
+    };
+
+    // we know how to analyze the machine code in the ELF file for these targets thus we have more
+    // information and need less LLVM-IR hacks
+    let target_ = match target {
+        "thumbv6m-none-eabi" => Target::Thumbv6m,
+        "thumbv7m-none-eabi" | "thumbv7em-none-eabi" | "thumbv7em-none-eabihf" => Target::Thumbv7m,
+        "thumbv8m.base-none-eabi" => Target::Thumbv8mBase,
+        "thumbv8m.main-none-eabi" | "thumbv8m.main-none-eabihf" => Target::Thumbv8mMain,
+        _ if target.starts_with("riscv32") => Target::Riscv32,
+        _ if target.starts_with("riscv64") => Target::Riscv64,
+        _ if target.starts_with("x86_64-") => Target::X86_64,
+        _ if target.starts_with("aarch64-") => Target::Aarch64,
+        _ => Target::Other,
+    };
+    let frame = options.exception_frame.unwrap_or(if !target_.is_thumb() {
+        0
+    } else if target.ends_with("eabihf") {
+        104
+    } else {
+        32
+    });
+
+    // extract stack size information
+    // the object files don't have address information so we just keep the stack usage information
+    let mut stack_sizes = HashMap::new();
+    for object in objects {
+        stack_sizes.extend(
+            stack_sizes::analyze_object(object)?
+                .into_iter()
+                .map(|(name, stack)| (name.to_owned(), stack)),
+        );
+    }
+
+    // extract list of "live" symbols (symbols that have not been GC-ed by the linker)
+    // this time we use the ELF and not the object file
+    let mut symbols = stack_sizes::analyze_executable(elf)?;
+
+    // size of a pointer in bytes; used to locate methods in vtables
+    let pointer_size = match ElfFile::new(elf)
+        .map_err(failure::err_msg)?
+        .header
+        .pt1
+        .class()
+    {
+        Class::ThirtyTwo => 4,
+        _ => 8,
+    };
+
+    // clear the thumb bit
+    if target_.is_thumb() {
+        symbols.defined = symbols
+            .defined
+            .into_iter()
+            .map(|(k, v)| (k & !1, v))
+            .collect();
+    }
+
+    // remove version strings from undefined symbols
+    symbols.undefined = symbols
+        .undefined
+        .into_iter()
+        .map(|sym| {
+            if let Some(name) = sym.rsplit("@@").nth(1) {
+                name
+            } else {
+                sym
+            }
+        })
+        .collect();
+
+    let mut g = DiGraph::<Node, ()>::new();
+    let mut indices = BTreeMap::<Cow<str>, _>::new();
+
+    let mut indirects: HashMap<FnSig, Indirect> = HashMap::new();
+    // functions that may be called through a function pointer
+    let mut address_taken = HashSet::new();
+    let mut dynamics: HashMap<FnSig, Dynamic> = HashMap::new();
+    // functions that could be called through the function pointer of each type-erasure pattern
+    let mut erased_callees = vec![HashSet::new(); patterns.len()];
+    // the user may have removed or replaced the built-in patterns
+    let core_fmt = patterns
+        .iter()
+        .position(|pattern| pattern.name == erasure::CORE_FMT);
+    // calls recognized as type-erasure patterns, grouped by pattern and signature
+    let mut erased: HashMap<(usize, FnSig), HashSet<NodeIndex>> = HashMap::new();
+    // shape of the signature of each function; see `erasure::shape`
+    let mut shapes = HashMap::new();
+
+    // Some functions may be aliased; we map aliases to a single name. For example, if `foo`,
+    // `bar` and `baz` all have the same address then this maps contains: `foo -> foo`, `bar -> foo`
+    // and `baz -> foo`.
+    let mut aliases = HashMap::new();
+    // whether a symbol name is ambiguous after removing the hash or the crate disambiguators
+    let mut ambiguous = HashMap::<String, u32>::new();
+
+    // we do a first pass over all the definitions to collect methods in `impl Trait for Type`
+    let mut default_methods = HashSet::new();
+    for name in defines.keys() {
+        // `<crate::module::Type as crate::module::Trait>::method`
+        if let Some((trait_, method)) = Symbol::new(name).trait_method() {
+            default_methods.insert(format!("{}::{}", trait_, method));
+        }
+    }
+
+    // add all real nodes
+    let mut has_stack_usage_info = false;
+    let mut has_untyped_symbols = false;
+    let mut addr2name = BTreeMap::new();
+    for (address, sym) in &symbols.defined {
+        let names = sym.names();
+
+        let canonical_name = if names.len() > 1 {
+            // if one of the aliases appears in the `stack_sizes` dictionary, use that
+            if let Some(needle) = names.iter().find(|name| stack_sizes.contains_key(&***name)) {
+                needle
+            } else {
+                // otherwise, pick the first name that's not a tag
+                names
+                    .iter()
+                    .filter_map(|&name| {
+                        if name == "$a" || name.starts_with("$a.") {
+                            None
+                        } else {
+                            Some(name)
+                        }
+                    })
+                    .next()
+                    .expect("UNREACHABLE")
+            }
+        } else {
+            names[0]
+        };
+
+        for name in names {
+            aliases.insert(name, canonical_name);
+        }
+
+        let _out = addr2name.insert(address, canonical_name);
+        debug_assert!(_out.is_none());
+
+        let mut stack = stack_sizes.get(canonical_name).cloned();
+        if stack.is_none() {
+            // here we inject some target specific information we got from analyzing
+            // `libcompiler_builtins.rlib`
+
+            let ad_hoc = match target {
+                "thumbv6m-none-eabi" => match canonical_name {
+                    "__aeabi_memcpy" | "__aeabi_memset" | "__aeabi_memclr" | "__aeabi_memclr4"
+                    | "__aeabi_f2uiz" => {
+                        stack = Some(0);
+                        true
+                    }
+
+                    "__aeabi_memcpy4" | "__aeabi_memset4" | "__aeabi_f2iz" | "__aeabi_fadd"
+                    | "__aeabi_fdiv" | "__aeabi_fmul" | "__aeabi_fsub" => {
+                        stack = Some(8);
+                        true
+                    }
+
+                    "memcmp" | "__aeabi_fcmpgt" | "__aeabi_fcmplt" | "__aeabi_i2f"
+                    | "__aeabi_ui2f" => {
+                        stack = Some(16);
+                        true
+                    }
+
+                    "__addsf3" => {
+                        stack = Some(32);
+                        true
+                    }
+
+                    "__divsf3" => {
+                        stack = Some(40);
+                        true
+                    }
+
+                    "__mulsf3" => {
+                        stack = Some(48);
+                        true
+                    }
+
+                    _ => false,
+                },
+
+                "thumbv7m-none-eabi" | "thumbv7em-none-eabi" | "thumbv7em-none-eabihf" => {
+                    match canonical_name {
+                        "__aeabi_memclr" | "__aeabi_memclr4" => {
+                            stack = Some(0);
+                            true
+                        }
+
+                        "__aeabi_memcpy" | "__aeabi_memcpy4" | "memcmp" => {
+                            stack = Some(16);
+                            true
+                        }
+
+                        "__aeabi_memset" | "__aeabi_memset4" => {
+                            stack = Some(8);
+                            true
+                        }
+
+                        // ARMv7-M only below this point
+                        "__aeabi_f2iz" | "__aeabi_f2uiz" | "__aeabi_fadd" | "__aeabi_fcmpgt"
+                        | "__aeabi_fcmplt" | "__aeabi_fdiv" | "__aeabi_fmul" | "__aeabi_fsub"
+                        | "__aeabi_i2f" | "__aeabi_ui2f"
+                            if target == "thumbv7m-none-eabi" =>
+                        {
+                            stack = Some(0);
+                            true
+                        }
+
+                        "__addsf3" | "__mulsf3" if target == "thumbv7m-none-eabi" => {
+                            stack = Some(16);
+                            true
+                        }
+
+                        "__divsf3" if target == "thumbv7m-none-eabi" => {
+                            stack = Some(20);
+                            true
+                        }
+
+                        _ => false,
+                    }
+                }
+
+                _ => false,
+            };
+
+            if ad_hoc {
+                warn!(
+                    "ad-hoc: injecting stack usage information for `{}` (last checked: Rust {})",
+                    canonical_name, VERS
+                );
+            } else if !target_.has_machine_code_analysis() {
+                warn!("no stack usage information for `{}`", canonical_name);
+            }
+        } else {
+            has_stack_usage_info = true;
+        }
+
+        let symbol = Symbol::new(canonical_name);
+        *ambiguous.entry(symbol.path.clone()).or_insert(0) += 1;
+
+        let idx = g.add_node(Node(canonical_name, stack, false));
+        indices.insert(canonical_name.into(), idx);
+
+        // functions that are not in the LLVM module or that are visible outside of it may have
+        // their address taken elsewhere
+        // nothing can be ruled out if part of the module was skipped
+        if incomplete
+            || names.iter().any(|name| referenced.contains(name))
+            || names
+                .iter()
+                .filter_map(|name| defines.get(name))
+                .all(|def| !def.internal)
+        {
+            address_taken.insert(idx);
+        }
+
+        // trait methods look like `<crate::module::Type as crate::module::Trait>::method`
+        // default trait methods look like `crate::module::Trait::method`
+        let is_trait_method = symbol.is_trait_impl() || default_methods.contains(&symbol.path);
+
+        if let Some(def) = names.iter().filter_map(|name| defines.get(name)).next() {
+            shapes.insert(idx, erasure::shape(&def.sig));
+
+            let dehashed = format!("{:#}", rustc_demangle::demangle(canonical_name));
+            for (pattern, callees) in patterns.iter().zip(&mut erased_callees) {
+                if pattern.is_callee(&dehashed) && pattern.fits(&def.sig) {
+                    callees.insert(idx);
+                }
+            }
+
+            // if the signature is `fn(&_, &mut fmt::Formatter) -> fmt::Result`
+            if let Some(core_fmt) = core_fmt {
+                if erasure::is_formatter(&def.sig) {
+                    erased_callees[core_fmt].insert(idx);
+                }
+            }
+
+            let is_object_safe = is_trait_method && {
+                match def.sig.inputs.first().as_ref() {
+                    Some(Type::Pointer(ty)) => match **ty {
+                        // XXX can the receiver be a *specific* function? (e.g. `fn() {foo}`)
+                        Type::Fn(_) => false,
+
+                        _ => true,
+                    },
+                    Some(Type::OpaquePointer) => true,
+                    _ => false,
+                }
+            };
+
+            if is_object_safe {
+                let mut sig = def.sig.clone();
+
+                // erase the type of the reciver
+                sig.inputs[0] = Type::erased();
+
+                dynamics.entry(sig).or_default().callees.insert(idx);
+            } else {
+                indirects
+                    .entry(def.sig.clone())
+                    .or_default()
+                    .callees
+                    .insert(idx);
+            }
+        } else if let Some(sig) = names
+            .iter()
+            .filter_map(|name| declares.get(name).and_then(|decl| decl.sig.clone()))
+            .next()
+        {
+            // sanity check (?)
+            assert!(!is_trait_method, "BUG: undefined trait method");
+
+            indirects.entry(sig).or_default().callees.insert(idx);
+        } else {
+            // from `compiler-builtins`
+            match canonical_name {
+                "__aeabi_memcpy" | "__aeabi_memcpy4" | "__aeabi_memcpy8" => {
+                    // `fn(*mut u8, *const u8, usize)`
+                    let sig = FnSig {
+                        inputs: vec![
+                            byte_pointer(),
+                            byte_pointer(),
+                            Type::Integer(32), // ARM has 32-bit pointers
+                        ],
+                        output: None,
+                        variadic: false,
+                    };
+                    indirects.entry(sig).or_default().callees.insert(idx);
+                }
+
+                "__aeabi_memclr" | "__aeabi_memclr4" | "__aeabi_memclr8" => {
+                    // `fn(*mut u8, usize)`
+                    let sig = FnSig {
+                        inputs: vec![
+                            byte_pointer(),
+                            Type::Integer(32), // ARM has 32-bit pointers
+                        ],
+                        output: None,
+                        variadic: false,
+                    };
+                    indirects.entry(sig).or_default().callees.insert(idx);
+                }
+
+                "__aeabi_memset" | "__aeabi_memset4" | "__aeabi_memset8" => {
+                    // `fn(*mut u8, usize, i32)`
+                    let sig = FnSig {
+                        inputs: vec![
+                            byte_pointer(),
+                            Type::Integer(32), // ARM has 32-bit pointers
+                            Type::Integer(32),
+                        ],
+                        output: None,
+                        variadic: false,
+                    };
+                    indirects.entry(sig).or_default().callees.insert(idx);
+                }
+
+                "__aeabi_fadd" | "__addsf3" | "__aeabi_fsub" | "__subsf3" | "__aeabi_fdiv"
+                | "__divsf3" | "__aeabi_fmul" | "__mulsf3" => {
+                    // `fn(f32, f32) -> f32`
+                    let sig = FnSig {
+                        inputs: vec![Type::Float, Type::Float],
+                        output: Some(Box::new(Type::Float)),
+                        variadic: false,
+                    };
+                    indirects.entry(sig).or_default().callees.insert(idx);
+                }
+
+                "__aeabi_fcmpgt" | "__aeabi_fcmplt" => {
+                    // `fn(f32, f32) -> i32`
+                    let sig = FnSig {
+                        inputs: vec![Type::Float, Type::Float],
+                        output: Some(Box::new(Type::Integer(32))),
+                        variadic: false,
+                    };
+                    indirects.entry(sig).or_default().callees.insert(idx);
+                }
+
+                "__aeabi_f2uiz" | "__aeabi_f2iz" => {
+                    // `fn(f32) -> {i,u}32`
+                    let sig = FnSig {
+                        inputs: vec![Type::Float],
+                        output: Some(Box::new(Type::Integer(32))),
+                        variadic: false,
+                    };
+                    indirects.entry(sig).or_default().callees.insert(idx);
+                }
+
+                "__aeabi_ui2f" | "__aeabi_i2f" => {
+                    // `fn({i,u}32) -> f32`
+                    let sig = FnSig {
+                        inputs: vec![Type::Integer(32)],
+                        output: Some(Box::new(Type::Float)),
+                        variadic: false,
+                    };
+                    indirects.entry(sig).or_default().callees.insert(idx);
+                }
+
+                "__divmoddi4" | "__udivmoddi4" => {
+                    // `fn({i,u}64, {i,u}64, *{i,u}64) -> {i,u}64`
+                    let sig = FnSig {
+                        inputs: vec![
+                            Type::Integer(64),
+                            Type::Integer(64),
+                            Type::Pointer(Box::new(Type::Integer(64))),
+                        ],
+                        output: Some(Box::new(Type::Integer(64))),
+                        variadic: false,
+                    };
+                    indirects.entry(sig).or_default().callees.insert(idx);
+                }
+
+                "__aeabi_uldivmod" | "__aeabi_ldivmod" => {
+                    // these subroutines don't use a standard calling convention and are impossible
+                    // to call from Rust code (they can be called via `asm!` though). This case is
+                    // listed here to suppress the warning below
+                }
+
+                _ => {
+                    has_untyped_symbols = true;
+                    warn!("no type information for `{}`", canonical_name);
+                }
+            }
+        }
+    }
+
+    // to avoid printing several warnings about the same thing
+    let mut asm_seen = HashSet::new();
+    // stack used by the `asm!` blocks of each function; `None` if unknown
+    let mut asm_stacks = HashMap::new();
+    let mut llvm_seen = HashSet::new();
+    // add edges
+    let mut edges: HashMap<_, HashSet<_>> = HashMap::new(); // NodeIdx -> [NodeIdx]
+    let mut defined = HashSet::new(); // functions that are `define`-d in the LLVM-IR
+    let mut personalities = HashSet::new(); // e.g. `rust_eh_personality`
+    for define in defines.values() {
+        let (caller, callees_seen) = if let Some(canonical_name) = aliases.get(&define.name) {
+            defined.insert(*canonical_name);
+
+            let idx = indices[*canonical_name];
+            (idx, edges.entry(idx).or_default())
+        } else {
+            // this symbol was GC-ed by the linker, skip
+            continue;
+        };
+
+        if let Some(personality) = define.personality {
+            personalities.insert(personality);
+        }
+
+        for stmt in &define.stmts {
+            match stmt {
+                Stmt::Asm(expr, operands) => {
+                    if !target_.is_thumb() {
+                        if !asm_seen.contains(expr) {
+                            asm_seen.insert(expr);
+                            warn!("assuming that asm!(\"{}\") does *not* use the stack", expr);
+                        }
+
+                        continue;
+                    }
+
+                    let effect = if let Some(effect) = thumb::asm::analyze(expr, operands) {
+                        effect
+                    } else {
+                        if !asm_seen.contains(expr) {
+                            asm_seen.insert(expr);
+                            warn!("unknown stack usage for asm!(\"{}\")", expr);
+                        }
+
+                        asm_stacks.insert(caller, None);
+                        continue;
+                    };
+
+                    // `asm!` blocks don't nest so only the one that uses the most stack matters
+                    if let Some(stack) = asm_stacks.entry(caller).or_insert(Some(0)) {
+                        *stack = cmp::max(*stack, effect.stack);
+                    }
+
+                    for callee in effect.calls {
+                        let callee = if let Some(canon) = aliases.get(&callee.as_str()) {
+                            indices[*canon]
+                        } else {
+                            warn!(
+                                "asm!(\"{}\") calls `{}`, which is not a function",
+                                expr, callee
+                            );
+
+                            g.add_node(Node("?", None, false))
+                        };
+
+                        if !callees_seen.contains(&callee) {
+                            callees_seen.insert(callee);
+                            g.add_edge(caller, callee, ());
+                        }
+                    }
+
+                    // the supervisor call exception handler, as named by `cortex-m-rt`
+                    if effect.svc {
+                        if let Some(canon) = aliases.get(&"SVCall") {
+                            let callee = indices[*canon];
+                            if !callees_seen.contains(&callee) {
+                                callees_seen.insert(callee);
+                                g.add_edge(caller, callee, ());
+                            }
+                        }
+                    }
+
+                    if effect.indirect {
+                        warn!(
+                            "asm!(\"{}\") performs an indirect function call; the callee is unknown",
+                            expr
+                        );
+
+                        let callee = g.add_node(Node("?", None, false));
+                        g.add_edge(caller, callee, ());
+                    }
+                }
+
+                // this is basically `(mem::transmute<*const u8, fn()>(&__some_symbol))()`
+                Stmt::BitcastCall(sym) => {
+                    // XXX we have some type information for this call but it's unclear if we should
+                    // try harder -- does this ever occur in pure Rust programs?
+
+                    let sym = sym.expect("BUG? unnamed symbol is being invoked");
+                    let callee = if let Some(idx) = indices.get(sym) {
+                        *idx
+                    } else {
+                        warn!("no stack information for `{}`", sym);
+
+                        let idx = g.add_node(Node(sym, None, false));
+                        indices.insert(Cow::Borrowed(sym), idx);
+                        idx
+                    };
+
+                    g.add_edge(caller, callee, ());
+                }
+
+                Stmt::DirectCall(func) => {
+                    match *func {
+                        // no-op / debug-info
+                        "llvm.dbg.value" => continue,
+                        "llvm.dbg.declare" => continue,
+
+                        // no-op / compiler-hint
+                        "llvm.assume" => continue,
+                        "llvm.experimental.noalias.scope.decl" => continue,
+
+                        // lowers to a single instruction
+                        "llvm.trap" => continue,
+
+                        _ => {}
+                    }
+
+                    // no-op / compiler-hint
+                    if func.starts_with("llvm.lifetime.start")
+                        || func.starts_with("llvm.lifetime.end")
+                    {
+                        continue;
+                    }
+
+                    let mut call = |callee| {
+                        if !callees_seen.contains(&callee) {
+                            g.add_edge(caller, callee, ());
+                            callees_seen.insert(callee);
+                        }
+                    };
+
+                    if target_.has_machine_code_analysis() && func.starts_with("llvm.") {
+                        // we'll analyze the machine code in the ELF file to figure out what these
+                        // lower to
+                        continue;
+                    }
+
+                    // TODO? consider alignment and `value` argument to only include one edge
+                    // TODO? consider the `len` argument to elide the call to `*mem*`
+                    if func.starts_with("llvm.memcpy.") {
+                        if let Some(callee) = indices.get("memcpy") {
+                            call(*callee);
+                        }
+
+                        // ARMv7-R and the like use these
+                        if let Some(callee) = indices.get("__aeabi_memcpy") {
+                            call(*callee);
+                        }
+
+                        if let Some(callee) = indices.get("__aeabi_memcpy4") {
+                            call(*callee);
+                        }
+
+                        continue;
+                    }
+
+                    // TODO? consider alignment and `value` argument to only include one edge
+                    // TODO? consider the `len` argument to elide the call to `*mem*`
+                    if func.starts_with("llvm.memset.") || func.starts_with("llvm.memmove.") {
+                        if let Some(callee) = indices.get("memset") {
+                            call(*callee);
+                        }
+
+                        // ARMv7-R and the like use these
+                        if let Some(callee) = indices.get("__aeabi_memset") {
+                            call(*callee);
+                        }
+
+                        if let Some(callee) = indices.get("__aeabi_memset4") {
+                            call(*callee);
+                        }
+
+                        if let Some(callee) = indices.get("memclr") {
+                            call(*callee);
+                        }
+
+                        if let Some(callee) = indices.get("__aeabi_memclr") {
+                            call(*callee);
+                        }
+
+                        if let Some(callee) = indices.get("__aeabi_memclr4") {
+                            call(*callee);
+                        }
+
+                        continue;
+                    }
+
+                    // XXX unclear whether these produce library calls on some platforms or not
+                    if func.starts_with("llvm.bswap.")
+                        | func.starts_with("llvm.ctlz.")
+                        | func.starts_with("llvm.cttz.")
+                        | func.starts_with("llvm.sadd.with.overflow.")
+                        | func.starts_with("llvm.smul.with.overflow.")
+                        | func.starts_with("llvm.ssub.with.overflow.")
+                        | func.starts_with("llvm.uadd.with.overflow.")
+                        | func.starts_with("llvm.umul.with.overflow.")
+                        | func.starts_with("llvm.usub.sat.")
+                        | func.starts_with("llvm.usub.with.overflow.")
+                        | func.starts_with("llvm.x86.sse2.pmovmskb.")
+                        | func.starts_with("llvm.abs.")
+                        | func.starts_with("llvm.bitreverse.")
+                        | func.starts_with("llvm.ctpop.")
+                        | func.starts_with("llvm.expect.")
+                        | func.starts_with("llvm.fshl.")
+                        | func.starts_with("llvm.fshr.")
+                        | func.starts_with("llvm.is.constant.")
+                        | func.starts_with("llvm.scmp.")
+                        | func.starts_with("llvm.smax.")
+                        | func.starts_with("llvm.smin.")
+                        | func.starts_with("llvm.ucmp.")
+                        | func.starts_with("llvm.umax.")
+                        | func.starts_with("llvm.umin.")
+                        | func.starts_with("llvm.uadd.sat.")
+                    {
+                        if !llvm_seen.contains(func) {
+                            llvm_seen.insert(func);
+                            warn!("assuming that `{}` directly lowers to machine code", func);
+                        }
+
+                        continue;
+                    }
+
+                    assert!(
+                        !func.starts_with("llvm."),
+                        "BUG: unhandled llvm intrinsic: {}",
+                        func
+                    );
+
+                    // use canonical name
+                    let callee = if let Some(canon) = aliases.get(func) {
+                        indices[*canon]
+                    } else {
+                        assert!(
+                            symbols.undefined.contains(func),
+                            "BUG: callee `{}` is unknown",
+                            func
+                        );
+
+                        if let Some(idx) = indices.get(*func) {
+                            *idx
+                        } else {
+                            let idx = g.add_node(Node(*func, None, false));
+                            indices.insert((*func).into(), idx);
+
+                            idx
+                        }
+                    };
+
+                    if !callees_seen.contains(&callee) {
+                        callees_seen.insert(callee);
+                        g.add_edge(caller, callee, ());
+                    }
+                }
+
+                Stmt::IndirectCall(sig, dbg, origin) => {
+                    let subprogram = dbg.and_then(|dbg| ir::subprogram(&metadata, dbg));
+
+                    if sig
+                        .inputs
+                        .first()
+                        .map(|ty| ty.has_been_erased())
+                        .unwrap_or(false)
+                    {
+                        // dynamic dispatch
+                        let dynamic = dynamics.entry(sig.clone()).or_default();
+
+                        dynamic.called = true;
+                        let offset = match origin {
+                            Some(Origin::Vtable(slot)) => slot.offset(pointer_size),
+                            _ => None,
+                        };
+                        // e.g. `<&mut dyn core::fmt::Write as core::fmt::Write>::write_str` calls
+                        // a `core::fmt::Write` method
+                        let trait_ = subprogram
+                            .and_then(|function| symbol::dyn_trait(&Symbol::new(function).path));
+                        dynamic
+                            .callers
+                            .entry((trait_, offset))
+                            .or_default()
+                            .insert(caller);
+                    } else if let Some(pattern) = subprogram.and_then(|function| {
+                        let function = format!("{:#}", rustc_demangle::demangle(function));
+                        patterns
+                            .iter()
+                            .position(|p| p.is_caller(&function) && p.fits(sig))
+                    }) {
+                        // type-erased function pointer
+                        erased
+                            .entry((pattern, sig.clone()))
+                            .or_default()
+                            .insert(caller);
+                    } else if let Some(pattern) =
+                        core_fmt.filter(|_| subprogram.is_none() && erasure::is_formatter(sig))
+                    {
+                        // without debug info `ArgumentV1.formatter` (`rt::Argument`) can still be
+                        // recognized by its signature, if pointers are typed. The type of its
+                        // first argument varies so it's canonicalized
+                        let mut sig = sig.clone();
+                        sig.inputs[0] = Type::Pointer(Box::new(Type::Alias("fmt::Void")));
+
+                        erased.entry((pattern, sig)).or_default().insert(caller);
+                    } else {
+                        let indirect = indirects.entry(sig.clone()).or_default();
+
+                        let static_ = match origin {
+                            Some(Origin::Static(name)) => Some(*name),
+                            _ => None,
+                        };
+                        indirect.called = true;
+                        indirect.callers.entry(static_).or_default().insert(caller);
+                    }
+                }
+
+                // `resume` lowers to a call into the unwinder
+                Stmt::Resume => {
+                    let callee = if let Some(canon) = aliases.get(&UNWIND_RESUME) {
+                        indices[*canon]
+                    } else if symbols.undefined.contains(UNWIND_RESUME) {
+                        *indices
+                            .entry(UNWIND_RESUME.into())
+                            .or_insert_with(|| g.add_node(Node(UNWIND_RESUME, None, false)))
+                    } else {
+                        // e.g. the unwinder is not linked in
+                        continue;
+                    };
+
+                    if !callees_seen.contains(&callee) {
+                        callees_seen.insert(callee);
+                        g.add_edge(caller, callee, ());
+                    }
+                }
+
+                Stmt::Label | Stmt::Comment | Stmt::Other => {}
+            }
+        }
+    }
+
+    // we don't know what the functions we failed to parse call so inject the uncertainty in the
+    // form of a call to an unknown function with unknown stack usage. Their own stack usage comes
+    // from the object file and is still right
+    for name in &malformed {
+        if let Some(canonical_name) = aliases.get(name) {
+            let caller = indices[*canonical_name];
+            let callee = g.add_node(Node("?", None, false));
+            g.add_edge(caller, callee, ());
+        }
+    }
+
+    // while unwinding the stack the unwinder calls the personality routine of the functions that
+    // have landing pads; the landing pads themselves run in the frame of their function
+    for unwinder in &["_Unwind_RaiseException", UNWIND_RESUME] {
+        let unwinder = aliases
+            .get(unwinder)
+            .map(|canon| indices[*canon])
+            .or_else(|| indices.get(*unwinder).cloned());
+
+        if let Some(unwinder) = unwinder {
+            for personality in &personalities {
+                if let Some(canon) = aliases.get(personality) {
+                    g.add_edge(unwinder, indices[*canon], ());
+                }
+            }
+        }
+    }
+
+    // here we parse the machine code in the ELF file to find out edges that don't appear in the
+    // LLVM-IR (e.g. `fadd` operation, `call llvm.umul.with.overflow`, etc.) or are difficult to
+    // disambiguate from the LLVM-IR (e.g. does this `llvm.memcpy` lower to a call to
+    // `__aebi_memcpy`, a call to `__aebi_memcpy4` or machine instructions?)
+    if target_.has_machine_code_analysis() {
+        let elf = ElfFile::new(elf).map_err(failure::err_msg)?;
+        let sect = elf.find_section_by_name(".symtab").expect("UNREACHABLE");
+        let mut tags: Vec<_> = match sect.get_data(&elf).unwrap() {
+            // only Thumb uses mapping symbols to tell code and data apart
+            _ if !target_.is_thumb() => vec![],
+            SectionData::SymbolTable32(entries) => entries
+                .iter()
+                .filter_map(|entry| {
+                    let addr = entry.value() as u32;
+                    entry.get_name(&elf).ok().and_then(|name| {
+                        if name.starts_with("$d") {
+                            Some((addr, Tag::Data))
+                        } else if name.starts_with("$t") {
+                            Some((addr, Tag::Thumb))
+                        } else {
+                            None
+                        }
+                    })
+                })
+                .collect(),
+            _ => unreachable!(),
+        };
+
+        tags.sort_by_key(|tag| tag.0);
+
+        // TrustZone boundaries. The non-secure state runs on its own stack so these fictitious
+        // nodes use no (secure) stack. Secure gateways hang from the first node so its `max` is
+        // the worst-case secure stack usage of a call from the non-secure state; `BLXNS` calls
+        // point to the second one
+        let mut ns_callers = None;
+        let mut ns_callees = None;
+
+        // the machine code is mostly in `.text` but not only: e.g. the linker places the secure
+        // gateway veneers of CMSE programs in `.gnu.sgstubs`
+        let code = elf
+            .section_iter()
+            .filter(|sect| {
+                sect.flags() & SHF_EXECINSTR != 0 && sect.get_type() == Ok(ShType::ProgBits)
+            })
+            .map(|sect| (sect.address(), sect.raw_data(&elf)))
+            .collect::<Vec<_>>();
+
+        if code.is_empty() {
+            error!("no executable sections found")
+        } else {
+            for (address, sym) in &symbols.defined {
+                let address = *address;
+                let canonical_name = aliases[&sym.names()[0]];
+                let mut size = sym.size();
+
+                if size == 0 {
+                    // try harder at finding out the size of this symbol
+                    if let Ok(needle) = tags.binary_search_by(|tag| u64::from(tag.0).cmp(&address))
+                    {
+                        let start = tags[needle];
+                        if start.1 == Tag::Thumb {
+                            if let Some(end) = tags.get(needle + 1) {
+                                if end.1 == Tag::Thumb {
+                                    size = u64::from(end.0 - start.0);
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let (stext, text) = if let Some(sect) = code.iter().find(|(stext, text)| {
+                    address >= *stext && (address - stext + size) as usize <= text.len()
+                }) {
+                    *sect
+                } else {
+                    // e.g. a symbol that spans several sections or that lives in a section that's
+                    // not executable
+                    warn!(
+                        "`{}` is not in an executable section; its machine code was not analyzed",
+                        canonical_name
+                    );
+                    continue;
+                };
+
+                let start = (address - stext) as usize;
+                let end = start + size as usize;
+                let caller = indices[canonical_name];
+                let thumb::Analysis {
+                    bls,
+                    bs,
+                    blxs,
+                    indirect,
+                    gateway,
+                    non_secure,
+                    modifies_sp,
+                    stack: our_stack,
+                } = match machine_code(&text[start..end], address, target_, &tags) {
+                    Ok(analysis) => analysis,
+                    Err(e) => {
+                        // we can trust neither the stack usage information of this function nor
+                        // the list of callees we extracted from it so inject the uncertainty in
+                        // the form of a call to an unknown function with unknown stack usage
+                        diagnose(strict, format!("`{}`: {}", canonical_name, e))?;
+
+                        g[caller].local = Local::Unknown;
+                        let callee = g.add_node(Node("?", None, false));
+                        g.add_edge(caller, callee, ());
+                        continue;
+                    }
+                };
+
+                // sanity check
+                if let Some(stack) = our_stack {
+                    if (stack != 0) != modifies_sp {
+                        diagnose(
+                            strict,
+                            format!(
+                                "our analysis reported that `{}` both uses {} bytes of stack and \
+                                 it does{} modify SP",
+                                canonical_name,
+                                stack,
+                                if !modifies_sp { " not" } else { "" }
+                            ),
+                        )?;
+                    }
+                }
+
+                // check the correctness of `modifies_sp` and `our_stack`
+                // also override LLVM's results when they appear to be wrong
+                let mut mismatch = None;
+                // LLVM's number doesn't include the stack used by the `asm!` blocks of the function
+                // but the machine code does
+                let asm_stack = match asm_stacks.get(&caller) {
+                    Some(Some(stack)) => *stack,
+                    Some(None) => u64::MAX,
+                    None => 0,
+                };
+                if let Local::Exact(ref mut llvm_stack) = g[caller].local {
+                    if let Some(stack) = our_stack {
+                        if *llvm_stack == 0 && stack != 0 {
+                            // this could be a `#[naked]` + `asm!` function or `global_asm!`
+
+                            warn!(
+                                "LLVM reported zero stack usage for `{}` but \
+                                 our analysis reported {} bytes; overriding LLVM's result",
+                                canonical_name, stack
+                            );
+
+                            *llvm_stack = stack;
+                            asm_stacks.remove(&caller);
+                        } else if stack >= *llvm_stack && stack - *llvm_stack <= asm_stack {
+                            // our result already includes the stack used by the `asm!` blocks
+                            *llvm_stack = stack;
+                            asm_stacks.remove(&caller);
+                        } else {
+                            // in all other cases our results should match
+                            mismatch = Some(format!(
+                                "LLVM reported that `{}` uses {} bytes of stack but our \
+                                 analysis reported {} bytes",
+                                canonical_name, *llvm_stack, stack
+                            ));
+                        }
+                    }
+
+                    if mismatch.is_none() && (*llvm_stack != 0 || asm_stack != 0) != modifies_sp {
+                        mismatch = Some(format!(
+                            "LLVM reported that `{}` uses {} bytes of stack but our analysis \
+                             found that it does{} modify SP",
+                            canonical_name,
+                            *llvm_stack,
+                            if !modifies_sp { " not" } else { "" }
+                        ));
+                    }
+                } else if let Some(stack) = our_stack {
+                    g[caller].local = Local::Exact(stack);
+                    asm_stacks.remove(&caller);
+                } else if !modifies_sp {
+                    // this happens when the function contains intra-branches and our analysis gives
+                    // up (`our_stack == None`)
+                    g[caller].local = Local::Exact(0);
+                }
+
+                if let Some(mismatch) = mismatch {
+                    // we don't know which of the two results is right
+                    diagnose(strict, mismatch)?;
+                    g[caller].local = Local::Unknown;
+                }
+
+                if g[caller].local == Local::Unknown {
+                    warn!("no stack usage information for `{}`", canonical_name);
+                }
+
+                if !defined.contains(canonical_name) && indirect {
+                    // this function performs an indirect function call and we have no type
+                    // information to narrow down the list of callees so inject the uncertainty
+                    // in the form of a call to an unknown function with unknown stack usage
+
+                    warn!(
+                        "`{}` performs an indirect function call and there's \
+                         no type information about the operation",
+                        canonical_name,
+                    );
+                    let callee = g.add_node(Node("?", None, false));
+                    g.add_edge(caller, callee, ());
+                }
+
+                if gateway {
+                    let ns = *ns_callers.get_or_insert_with(|| {
+                        g.add_node(Node("<non-secure callers>", Some(0), true))
+                    });
+                    g.add_edge(ns, caller, ());
+                }
+
+                if non_secure {
+                    let ns = *ns_callees.get_or_insert_with(|| {
+                        g.add_node(Node("<non-secure callees>", Some(0), true))
+                    });
+                    g.add_edge(caller, ns, ());
+                }
+
+                let callees_seen = edges.entry(caller).or_default();
+                for offset in bls {
+                    let addr = (address as i64).wrapping_add(offset) as u64;
+                    // address may be off by one due to the thumb bit being set
+                    if let Some(name) = addr2name.get(&addr) {
+                        let callee = indices[*name];
+                        if !callees_seen.contains(&callee) {
+                            g.add_edge(caller, callee, ());
+                            callees_seen.insert(callee);
+                        }
+                    } else {
+                        diagnose(
+                            strict,
+                            format!(
+                                "`{}` calls address {:#010x} but there's no symbol at that address",
+                                canonical_name, addr
+                            ),
+                        )?;
+
+                        let callee = g.add_node(Node("?", None, false));
+                        g.add_edge(caller, callee, ());
+                    }
+                }
+
+                for offset in bs {
+                    let addr = (address as i64).wrapping_add(offset) as u64;
+
+                    if addr >= address && addr < (address + size) {
+                        // intra-function B branches are not function calls
+                    } else {
+                        // address may be off by one due to the thumb bit being set
+                        if let Some(name) = addr2name.get(&addr) {
+                            let callee = indices[*name];
+                            if !callees_seen.contains(&callee) {
+                                g.add_edge(caller, callee, ());
+                                callees_seen.insert(callee);
+                            }
+                        } else {
+                            diagnose(
+                                strict,
+                                format!(
+                                    "`{}` branches to address {:#010x} but there's no symbol at \
+                                     that address",
+                                    canonical_name, addr
+                                ),
+                            )?;
+
+                            let callee = g.add_node(Node("?", None, false));
+                            g.add_edge(caller, callee, ());
+                        }
+                    }
+                }
+
+                // indirect function calls whose target we resolved using constant propagation
+                for addr in blxs {
+                    if let Some(name) = addr2name.get(&addr) {
+                        let callee = indices[*name];
+                        if !callees_seen.contains(&callee) {
+                            g.add_edge(caller, callee, ());
+                            callees_seen.insert(callee);
+                        }
+                    } else {
+                        warn!(
+                            "`{}` performs an indirect function call to address {:#010x} but \
+                             there's no symbol at that address",
+                            canonical_name, addr
+                        );
+
+                        let callee = g.add_node(Node("?", None, false));
+                        g.add_edge(caller, callee, ());
+                    }
+                }
+            }
+        }
+    }
+
+    // `asm!` blocks use stack on top of the frame of their function; the functions whose machine
+    // code we could analyze have already had them accounted for
+    for (caller, stack) in asm_stacks {
+        g[caller].local = match (g[caller].local, stack) {
+            (Local::Exact(frame), Some(stack)) => Local::Exact(frame + stack),
+            _ => Local::Unknown,
+        };
+    }
+
+    // the trampolines through which async executors poll their tasks: functions stored in a
+    // function pointer that poll the state machine of an `async fn`; see `futures`
+    let mut trampolines = vec![];
+    for node in g.node_indices() {
+        let symbol = Symbol::new(&g[node].name);
+        if futures::state_machine(&symbol.path).is_some() {
+            continue;
+        }
+
+        let polls = g.neighbors(node).find_map(|callee| {
+            futures::state_machine(&Symbol::new(&g[callee].name).path).map(str::to_owned)
+        });
+        if referenced.contains(&*g[node].name)
+            && (polls.is_some() || futures::is_trampoline(&symbol.path))
+        {
+            let task = polls.or_else(|| futures::async_fn(&symbol.path).map(str::to_owned));
+            trampolines.push((node, task));
+        }
+    }
+
+    // the functions that run while a task is being polled
+    let mut polled = HashSet::new();
+    let trampolines = trampolines
+        .into_iter()
+        .map(|(trampoline, task)| {
+            g[trampoline].entry = Some(Entry::Future(task));
+
+            let mut dfs = Dfs::new(&g, trampoline);
+            while let Some(node) = dfs.next(&g) {
+                polled.insert(node);
+            }
+
+            trampoline
+        })
+        .collect::<HashSet<_>>();
+
+    // add fictitious nodes for indirect function calls
+    if has_untyped_symbols {
+        warn!(
+            "the program contains untyped, external symbols (e.g. linked in from binary blobs); \
+             indirect function calls can not be bounded"
+        );
+    }
+
+    for (sig, indirect) in indirects {
+        if !indirect.called {
+            continue;
+        }
+
+        let callees = &indirect.callees;
+
+        // a task that's being polled doesn't poll other tasks so these calls get their own node
+        let groups = indirect.callers.iter().flat_map(|(static_, callers)| {
+            let (polling, others) = callers
+                .iter()
+                .cloned()
+                .partition::<Vec<_>, _>(|caller| polled.contains(caller));
+            vec![(*static_, false, others), (*static_, true, polling)]
+                .into_iter()
+                .filter(|(_, _, callers)| !callers.is_empty())
+        });
+
+        for (static_, polling, callers) in groups {
+            let mut name = sig.to_string();
+            // append '*' to denote that this is a function pointer
+            name.push('*');
+
+            // if the function pointer was loaded from a static and we have seen every value
+            // that's ever stored into it then those values are the only possible callees
+            let stored = static_.and_then(|static_| {
+                statics
+                    .get(static_)
+                    .filter(|info| {
+                        info.internal
+                            && !info.unknown
+                            && !incomplete
+                            && !referenced.contains(static_)
+                    })
+                    .map(|info| {
+                        name.push_str(&format!(" [{:#}]", rustc_demangle::demangle(static_)));
+
+                        info.stored
+                            .iter()
+                            .filter_map(|name| aliases.get(name).map(|name| indices[*name]))
+                            .collect::<HashSet<_>>()
+                    })
+            });
+
+            if polling {
+                name.push_str(" [async]");
+            }
+
+            let callees = callees
+                .iter()
+                .filter(|callee| {
+                    if polling && trampolines.contains(callee) {
+                        false
+                    } else if let Some(stored) = &stored {
+                        stored.contains(callee)
+                    } else {
+                        address_taken.contains(callee)
+                    }
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+
+            let call = g.add_node(Node(name.clone(), Some(0), true));
+
+            for caller in callers {
+                g.add_edge(caller, call, ());
+            }
+
+            if has_untyped_symbols && stored.is_none() {
+                // add an edge between this and a potential extern / untyped symbol
+                let extern_sym = g.add_node(Node("?", None, false));
+                g.add_edge(call, extern_sym, ());
+            } else {
+                if callees.is_empty() {
+                    error!("BUG? no callees for `{}`", name);
+                }
+            }
+
+            for callee in callees {
+                g.add_edge(call, callee, ());
+            }
+        }
+    }
+
+    // add fictitious nodes for the type-erasure patterns
+    for ((pattern, sig), callers) in erased {
+        let name = format!("{}* [{}]", sig, patterns[pattern].name);
+
+        // the functions stored in the function pointer have the same shape as it
+        let shape = erasure::shape(&sig);
+        let callees = erased_callees[pattern]
+            .iter()
+            .filter(|callee| address_taken.contains(callee) && shapes.get(callee) == Some(&shape))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let call = g.add_node(Node(name.clone(), Some(0), true));
+
+        for caller in callers {
+            g.add_edge(caller, call, ());
+        }
+
+        if has_untyped_symbols {
+            // add an edge between this and a potential extern / untyped symbol
+            let extern_sym = g.add_node(Node("?", None, false));
+            g.add_edge(call, extern_sym, ());
+        } else if callees.is_empty() {
+            error!("BUG? no callees for `{}`", name);
+        }
+
+        for callee in callees {
+            g.add_edge(call, callee, ());
+        }
+    }
+
+    // the methods found at each offset of the vtables, of any trait (`None`) and of the trait of
+    // the vtable; the trait is in the name of the vtable or in its debug info
+    let mut slots = HashMap::<(Option<String>, u64), HashSet<NodeIndex>>::new();
+    for (name, vtable) in &vtables {
+        let trait_ = name.and_then(symbol::vtable_trait).or_else(|| {
+            vtable
+                .dbg
+                .and_then(|dbg| ir::global_variable(&metadata, dbg))
+                .and_then(symbol::vtable_trait)
+        });
+
+        let mut offset = 0;
+        for (ty, method) in &vtable.fields {
+            if let Some(callee) = method.and_then(|method| aliases.get(&method)) {
+                slots
+                    .entry((None, offset))
+                    .or_default()
+                    .insert(indices[*callee]);
+                if let Some(trait_) = &trait_ {
+                    slots
+                        .entry((Some(trait_.clone()), offset))
+                        .or_default()
+                        .insert(indices[*callee]);
+                }
+            }
+
+            if let Some(size) = ty.size(pointer_size) {
+                offset += size;
+            } else {
+                break;
+            }
+        }
+    }
+
+    // add fictitious nodes for dynamic dispatch
+    for (sig, dynamic) in dynamics {
+        if !dynamic.called {
+            continue;
+        }
+
+        let is_ambiguous = dynamic.callers.len() > 1;
+        for ((trait_, offset), callers) in &dynamic.callers {
+            let mut name = sig.to_string();
+
+            // methods of different traits (or of the same trait) may share the signature; use the
+            // trait and the vtable slot to tell them apart
+            if is_ambiguous {
+                match (trait_, offset) {
+                    (Some(trait_), Some(offset)) => {
+                        name.push_str(&format!(" [{} slot {}]", trait_, offset / pointer_size))
+                    }
+                    (Some(trait_), None) => name.push_str(&format!(" [{}]", trait_)),
+                    (None, Some(offset)) => {
+                        name.push_str(&format!(" [slot {}]", offset / pointer_size))
+                    }
+                    (None, None) => {}
+                }
+            }
+
+            let slot = |trait_: Option<String>| {
+                offset.and_then(|offset| slots.get(&(trait_, offset)).map(|s| (offset, s)))
+            };
+            let in_slot = if let Some((_, slot)) = trait_.clone().and_then(|t| slot(Some(t))) {
+                // the methods at this position in the vtables of the trait; when the call is the
+                // `drop` of a `Box<dyn Trait>` these are the drop glues
+                slot.clone()
+            } else {
+                // the trait is unknown: only the methods that have this signature and appear at
+                // the right position in some vtable can be called
+                match slot(None) {
+                    // the first slot holds the drop glue (`drop_in_place::<T>`), not a trait
+                    // method; this call is the `drop` of a `Box<dyn Trait>`
+                    Some((0, slot)) => slot.clone(),
+                    Some((_, slot)) => dynamic
+                        .callees
+                        .intersection(slot)
+                        .cloned()
+                        .collect::<HashSet<_>>(),
+                    None => HashSet::new(),
+                }
+            };
+            let callees = if in_slot.is_empty() {
+                &dynamic.callees
+            } else {
+                &in_slot
+            };
+
+            if callees.is_empty() {
+                error!("BUG? no callees for `{}`", name);
+            }
+
+            let call = g.add_node(Node(name, Some(0), true));
+            for caller in callers {
+                g.add_edge(*caller, call, ());
+            }
+
+            for callee in callees {
+                g.add_edge(call, *callee, ());
+            }
+        }
+    }
+
+    // the interrupt and exception handlers, which run on the main stack
+    for global in &globals {
+        if global.name.map(tasks::is_vector_table).unwrap_or(false) {
+            for handler in &global.references {
+                if let Some(canon) = aliases.get(handler) {
+                    g[indices[*canon]].entry = Some(Entry::Handler);
+                }
+            }
+        }
+    }
+
+    // tasks that run on their own stacks
+    if !options.tasks.is_empty() || !options.spawns.is_empty() {
+        let elf = ElfFile::new(elf).map_err(failure::err_msg)?;
+        let sizes = tasks::sizes(&elf);
+        let stack = |name: &str| {
+            let stack = tasks::find(&sizes, name).map(|(symbol, size)| Stack {
+                symbol: format!("{:#}", rustc_demangle::demangle(symbol)),
+                size,
+                frame,
+            });
+            if stack.is_none() {
+                warn!("there's no `static` named `{}` in the ELF file", name);
+            }
+            stack
+        };
+
+        let mut entries = BTreeMap::new();
+        for task in &options.tasks {
+            let mut found = false;
+            for (name, idx) in &indices {
+                if task.matches(&Symbol::new(name).path) {
+                    found = true;
+                    entries.insert(*idx, task.stack().and_then(stack));
+                }
+            }
+
+            if !found {
+                warn!("the task `{}` matches no function", task);
+            }
+        }
+
+        let spawned = tasks::spawned(
+            defines.values(),
+            &options.spawns,
+            |name| aliases.contains_key(&name),
+            &sizes,
+        );
+        for (entry, symbol) in spawned {
+            let slot = entries.entry(indices[aliases[&entry]]).or_insert(None);
+            if slot.is_none() {
+                *slot = symbol.and_then(stack);
+            }
+        }
+
+        if entries.is_empty() {
+            warn!("no tasks found");
+        }
+
+        for (idx, stack) in entries {
+            g[idx].entry = Some(Entry::Task(stack));
+        }
+    }
+
+    // filter the call graph
+    if let Some(start) = options.start {
+        let start = indices.get(start).cloned().or_else(|| {
+            let hits = indices
+                .keys()
+                .filter(|key| Symbol::new(key).is(start))
+                .collect::<Vec<_>>();
+
+            if hits.len() > 1 {
+                error!("multiple matches for `{}`: {:?}", start, hits);
+                None
+            } else {
+                hits.first().map(|key| indices[*key])
+            }
+        });
+
+        if let Some(start) = start {
+            // create a new graph that only contains nodes reachable from `start`
+            let mut g2 = DiGraph::<Node, ()>::new();
+
+            // maps `g`'s `NodeIndex`-es to `g2`'s `NodeIndex`-es
+            let mut one2two = BTreeMap::new();
+
+            let mut dfs = Dfs::new(&g, start);
+            while let Some(caller1) = dfs.next(&g) {
+                let caller2 = if let Some(i2) = one2two.get(&caller1) {
+                    *i2
+                } else {
+                    let i2 = g2.add_node(g[caller1].clone());
+                    one2two.insert(caller1, i2);
+                    i2
+                };
+
+                let mut callees = g.neighbors(caller1).detach();
+                while let Some((_, callee1)) = callees.next(&g) {
+                    let callee2 = if let Some(i2) = one2two.get(&callee1) {
+                        *i2
+                    } else {
+                        let i2 = g2.add_node(g[callee1].clone());
+                        one2two.insert(callee1, i2);
+                        i2
+                    };
+
+                    g2.add_edge(caller2, callee2, ());
+                }
+            }
+
+            // replace the old graph
+            g = g2;
+
+            // invalidate `indices` to prevent misuse
+            indices.clear();
+        } else {
+            error!("start point not found; the graph will not be filtered")
+        }
+    }
+
+    // each task runs on its own stack and the handlers run on the main stack so neither is charged
+    // to its callers
+    let is_task = |node: &Node| matches!(node.entry, Some(Entry::Task(_)));
+    if g.raw_nodes().iter().any(|node| is_task(&node.weight)) {
+        g.retain_edges(|g, edge| {
+            let callee = &g[g.edge_endpoints(edge).expect("UNREACHABLE").1];
+            !is_task(callee) && callee.entry != Some(Entry::Handler)
+        });
+    }
+
+    let mut cycles = vec![];
+    // whether each recursion bound applied to some cycle
+    let mut used = vec![false; bounds.len()];
+    if !has_stack_usage_info {
+        error!("The graph has zero stack usage information; skipping max stack usage analysis");
+    } else {
+        cycles = max_stack(&mut g, bounds, &mut used);
+
+        // the worst-case stack usage when nothing panics: the same analysis on a call graph
+        // where the panicking functions are never called
+        let panics = panicking::entries(&g);
+        if !panics.is_empty() {
+            let mut no_panic = g.clone();
+            no_panic.retain_edges(|g, edge| {
+                !panics.contains(&g.edge_endpoints(edge).expect("UNREACHABLE").1)
+            });
+            for node in no_panic.node_weights_mut() {
+                node.max = None;
+            }
+            max_stack(&mut no_panic, bounds, &mut used);
+
+            let only = panicking::only(&g, &panics);
+            for node in g.node_indices() {
+                g[node].panic = only.contains(&node);
+                g[node].max_no_panic = no_panic[node].max;
+
+                // every path of `no_panic` is also a path of `g`. This matters when a cycle goes
+                // through the panicking functions: `g` collapses it into a lower bound while
+                // `no_panic` may be able to follow the paths that remain of it
+                if let (Some(max), Some(max_no_panic)) = (g[node].max, g[node].max_no_panic) {
+                    g[node].max = Some(self::max(max, max_no_panic));
+                }
+            }
+        }
+    }
+
+    // here we try to shorten the name of the symbol if it doesn't result in ambiguity
+    for node in g.node_weights_mut() {
+        let symbol = Symbol::new(&node.name);
+
+        if symbol.path != symbol.mangled && ambiguous.get(&symbol.path) == Some(&1) {
+            node.name = Cow::Owned(symbol.path);
+        }
+    }
+
+    for node in g.raw_nodes() {
+        let node = &node.weight;
+        if let (Some(Entry::Task(Some(stack))), Some(max)) = (&node.entry, node.max) {
+            if stack.free(max).is_overflow() {
+                warn!(
+                    "the worst-case stack usage of the task `{}` (max {}) exceeds the size of its \
+                     stack `{}` ({} bytes)",
+                    node.name, max, stack.symbol, stack.size
+                );
+            }
+        }
+    }
+
+    for (bound, used) in bounds.iter().zip(used) {
+        if !used {
+            warn!("the recursion bound `{}` matches no cycle", bound);
+        }
+    }
+
+    // the space the main stack can grow into and how much of it the program uses
+    let elf = ElfFile::new(elf).map_err(failure::err_msg)?;
+    let main_stack =
+        memory::bounds(&elf, options.stack_start, options.stack_end)?.map(|(start, end)| {
+            MainStack {
+                start,
+                end,
+                max: memory::usage(&g, &memory::roots(&g), frame),
+                frame,
+            }
+        });
+    if let Some(stack) = main_stack {
+        if let Some(max) = stack.max.filter(|max| stack.free(*max).is_overflow()) {
+            warn!(
+                "the worst-case stack usage of the program (max {}) exceeds the size of the main \
+                 stack ({} bytes)",
+                max,
+                stack.size()
+            );
+        }
+    }
+
+    Ok(CallGraph {
+        graph: g,
+        cycles,
+        incomplete,
+        main_stack,
+    })
+}
+
+/// The object files of `libcompiler_builtins.rlib` for `target`, as found in the sysroot of the
+/// current toolchain; they carry the stack usage of the compiler intrinsics
+pub fn compiler_builtins(target: &str) -> Result<Vec<Vec<u8>>, failure::Error> {
+    let mut objects = vec![];
+    let sysroot_nl = String::from_utf8(
+        Command::new("rustc")
+            .args(&["--print", "sysroot"])
+            .output()?
+            .stdout,
+    )?;
+    // remove trailing newline
+    let sysroot = Path::new(sysroot_nl.trim_end());
+    let libdir = sysroot.join("lib/rustlib").join(target).join("lib");
+
+    for entry in fs::read_dir(libdir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if path.extension().map(|ext| ext == "rlib").unwrap_or(false)
+            && path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .map(|stem| stem.starts_with("libcompiler_builtins"))
+                .unwrap_or(false)
+        {
+            let mut ar = Archive::new(File::open(path)?);
+
+            while let Some(entry) = ar.next_entry() {
+                let mut entry = entry?;
+                let header = entry.header();
+
+                if str::from_utf8(header.identifier())
+                    .map(|id| id.contains("compiler_builtins") && id.ends_with(".o"))
+                    .unwrap_or(false)
+                {
+                    let mut buf = vec![];
+                    entry.read_to_end(&mut buf)?;
+                    objects.push(buf);
+                }
+            }
+        }
+    }
+
+    Ok(objects)
+}
+
+/// How `dot` prints the call graph
+#[derive(Clone, Copy)]
+pub struct Dot {
+    /// Parts of the program are missing from the call graph
+    pub incomplete: bool,
+    /// Draw the calls that are only made while panicking differently
+    pub panic_edges: bool,
+    /// Group the nodes
+    pub cluster: Option<Cluster>,
+    /// Report the headroom of the main stack
+    pub main_stack: Option<MainStack>,
+}
+
+/// Prints the call graph to stdout in the dot format
+pub fn dot(g: Graph<Node, ()>, cycles: &[Cycle], options: &Dot) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    writeln!(stdout, "digraph {{")?;
+    writeln!(stdout, "    node [fontname={} shape=box]", FONT)?;
+
+    let mut label = vec![];
+    if options.incomplete {
+        label.push("incomplete: parts of the LLVM-IR could not be parsed".to_owned());
+    }
+    if let Some(stack) = options.main_stack {
+        let mut line = format!("main stack = {}", stack.size());
+        if let Some(max) = stack.max {
+            line.push_str(&format!(" (max {}, {})", max, stack.free(max)));
+        }
+        label.push(line);
+    }
+    if !label.is_empty() {
+        writeln!(
+            stdout,
+            "    label=\"{}\" fontname={}",
+            label.join("\\n"),
+            FONT
+        )?;
+    }
+
+    // v0 symbols that only differ in their instantiating crate demangle to the same name
+    let mut labels = HashMap::<_, u32>::new();
+    for node in g.raw_nodes() {
+        *labels
+            .entry(rustc_demangle::demangle(&node.weight.name).to_string())
+            .or_default() += 1;
+    }
+
+    for (i, node) in g.raw_nodes().iter().enumerate() {
+        let node = &node.weight;
+
+        write!(stdout, "    {} [label=\"", i,)?;
+
+        let mut escaper = Escaper::new(&mut stdout);
+        let demangled = rustc_demangle::demangle(&node.name).to_string();
+        if labels[&demangled] == 1 {
+            write!(escaper, "{}", demangled).ok();
+        } else {
+            write!(escaper, "{}", node.name).ok();
+        }
+        escaper.error?;
+
+        if node.collapsed != 0 {
+            write!(stdout, " ({} functions)", node.collapsed)?;
+        }
+        if node.instances != 0 {
+            write!(stdout, " ({} instances)", node.instances)?;
+        }
+
+        if let Some(max) = node.max {
+            write!(stdout, "\\nmax {}", max)?;
+
+            if let Some(max_no_panic) = node.max_no_panic.filter(|m| *m != max && !node.panic) {
+                write!(stdout, "\\nmax w/o panic {}", max_no_panic)?;
+            }
+        }
+
+        if node.collapsed == 0 {
+            write!(stdout, "\\nlocal = {}", node.local)?;
+        }
+
+        match &node.entry {
+            Some(Entry::Task(Some(stack))) => {
+                write!(stdout, "\\ntask stack = {}", stack.size)?;
+                if let Some(max) = node.max {
+                    write!(stdout, " ({})", stack.free(max))?;
+                }
+            }
+            Some(Entry::Task(None)) => write!(stdout, "\\ntask stack = ?")?,
+            Some(Entry::Handler) => write!(stdout, "\\nhandler (main stack)")?,
+            Some(Entry::Future(Some(task))) => write!(stdout, "\\npolls {}", task)?,
+            Some(Entry::Future(None)) => write!(stdout, "\\npolls an async task")?,
+            None => {}
+        }
+        write!(stdout, "\"")?;
+
+        if node.dashed {
+            write!(stdout, " style=dashed")?;
+        }
+
+        writeln!(stdout, "]")?;
+    }
+
+    for edge in g.raw_edges() {
+        write!(
+            stdout,
+            "    {} -> {}",
+            edge.source().index(),
+            edge.target().index()
+        )?;
+
+        if options.panic_edges && (g[edge.source()].panic || g[edge.target()].panic) {
+            write!(stdout, " [style=dotted]")?;
+        }
+
+        writeln!(stdout)?;
+    }
+
+    if let Some(level) = options.cluster {
+        // a node can only be drawn in one cluster; the cycles take precedence
+        let in_cycle = cycles
+            .iter()
+            .flat_map(|(cycle, _)| cycle)
+            .collect::<HashSet<_>>();
+
+        let mut clusters = BTreeMap::<_, Vec<_>>::new();
+        for node in g.node_indices().filter(|node| !in_cycle.contains(node)) {
+            let function = format!("{:#}", rustc_demangle::demangle(&g[node].name));
+            if let Some(cluster) = simplify::cluster(&function, level) {
+                clusters.entry(cluster.to_owned()).or_default().push(node);
+            }
+        }
+
+        for (i, (cluster, nodes)) in clusters.iter().enumerate() {
+            writeln!(stdout, "\n    subgraph cluster_path_{} {{", i)?;
+            writeln!(stdout, "        style=rounded")?;
+            writeln!(stdout, "        fontname={}", FONT)?;
+            writeln!(stdout, "        label=\"{}\"", cluster)?;
+
+            for node in nodes {
+                writeln!(stdout, "        {}", node.index())?;
+            }
+
+            writeln!(stdout, "    }}")?;
+        }
+    }
+
+    for (i, (cycle, depth)) in cycles.iter().enumerate() {
+        writeln!(stdout, "\n    subgraph cluster_{} {{", i)?;
+        writeln!(stdout, "        style=dashed")?;
+        writeln!(stdout, "        fontname={}", FONT)?;
+        if let Some(depth) = depth {
+            writeln!(stdout, "        label=\"SCC{} (depth <= {})\"", i, depth)?;
+        } else {
+            writeln!(stdout, "        label=\"SCC{}\"", i)?;
+        }
+
+        for node in cycle {
+            writeln!(stdout, "        {}", node.index())?;
+        }
+
+        writeln!(stdout, "    }}")?;
+    }
+
+    writeln!(stdout, "}}")
+}
+
+struct Escaper<W>
+where
+    W: io::Write,
+{
+    writer: W,
+    error: io::Result<()>,
+}
+
+impl<W> Escaper<W>
+where
+    W: io::Write,
+{
+    fn new(writer: W) -> Self {
+        Escaper {
+            writer,
+            error: Ok(()),
+        }
+    }
+}
+
+impl<W> fmt::Write for Escaper<W>
+where
+    W: io::Write,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.write_char(c)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        match (|| -> io::Result<()> {
+            match c {
+                '"' => write!(self.writer, "\\")?,
+                _ => {}
+            }
+
+            write!(self.writer, "{}", c)
+        })() {
+            Err(e) => {
+                self.error = Err(e);
+
+                Err(fmt::Error)
+            }
+            Ok(()) => Ok(()),
+        }
+    }
+}
+
+/// A strongly connected component of the call graph and its recursion bound, if any
+pub type Cycle = (Vec<NodeIndex>, Option<u64>);
+
+/// A function of the call graph
+#[derive(Clone, Debug)]
+pub struct Node<'a> {
+    /// The symbol name; demangled and without hash when that doesn't make it ambiguous
+    pub name: Cow<'a, str>,
+    /// Stack used by the function itself
+    pub local: Local,
+    /// Worst-case stack usage, including the functions it calls
+    pub max: Option<Max>,
+    /// Worst-case stack usage on the paths that don't panic
+    pub max_no_panic: Option<Max>,
+    /// Only reachable by panicking
+    pub panic: bool,
+    /// The number of functions this node stands for; see `simplify::collapse`
+    pub collapsed: usize,
+    /// The number of instances of a generic function this node stands for; see
+    /// `simplify::generics`
+    pub instances: usize,
+    /// Drawn with a dashed outline; used for nodes that don't stand for a single function
+    pub dashed: bool,
+    /// The task or handler this function is the entry point of, if any
+    pub entry: Option<Entry>,
+}
+
+#[allow(non_snake_case)]
+fn Node<'a, S>(name: S, stack: Option<u64>, dashed: bool) -> Node<'a>
+where
+    S: Into<Cow<'a, str>>,
+{
+    Node {
+        name: name.into(),
+        local: stack.map(Local::Exact).unwrap_or(Local::Unknown),
+        max: None,
+        max_no_panic: None,
+        panic: false,
+        collapsed: 0,
+        instances: 0,
+        dashed,
+        entry: None,
+    }
+}
+
+/// Local stack usage
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Local {
+    /// Exactly this many bytes
+    Exact(u64),
+    /// No stack usage information
+    Unknown,
+}
+
+impl fmt::Display for Local {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Local::Exact(n) => write!(f, "{}", n),
+            Local::Unknown => f.write_str("?"),
+        }
+    }
+}
+
+impl Into<Max> for Local {
+    fn into(self) -> Max {
+        match self {
+            Local::Exact(n) => Max::Exact(n),
+            Local::Unknown => Max::LowerBound(0),
+        }
+    }
+}
+
+/// Worst-case stack usage
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Max {
+    /// Exactly this many bytes
+    Exact(u64),
+    /// At least this many bytes; some function in the call graph has unknown stack usage or
+    /// there's an unbounded cycle
+    LowerBound(u64),
+}
+
+impl ops::Add<Local> for Max {
+    type Output = Max;
+
+    fn add(self, rhs: Local) -> Max {
+        match (self, rhs) {
+            (Max::Exact(lhs), Local::Exact(rhs)) => Max::Exact(lhs + rhs),
+            (Max::Exact(lhs), Local::Unknown) => Max::LowerBound(lhs),
+            (Max::LowerBound(lhs), Local::Exact(rhs)) => Max::LowerBound(lhs + rhs),
+            (Max::LowerBound(lhs), Local::Unknown) => Max::LowerBound(lhs),
+        }
+    }
+}
+
+impl ops::Add<Max> for Max {
+    type Output = Max;
+
+    fn add(self, rhs: Max) -> Max {
+        match (self, rhs) {
+            (Max::Exact(lhs), Max::Exact(rhs)) => Max::Exact(lhs + rhs),
+            (Max::Exact(lhs), Max::LowerBound(rhs)) => Max::LowerBound(lhs + rhs),
+            (Max::LowerBound(lhs), Max::Exact(rhs)) => Max::LowerBound(lhs + rhs),
+            (Max::LowerBound(lhs), Max::LowerBound(rhs)) => Max::LowerBound(lhs + rhs),
+        }
+    }
+}
+
+impl ops::Mul<u64> for Max {
+    type Output = Max;
+
+    // saturates; a recursion bound can be arbitrarily large
+    fn mul(self, rhs: u64) -> Max {
+        match self {
+            Max::Exact(n) => Max::Exact(n.saturating_mul(rhs)),
+            Max::LowerBound(n) => Max::LowerBound(n.saturating_mul(rhs)),
+        }
+    }
+}
+
+fn max_of(mut iter: impl Iterator<Item = Max>) -> Option<Max> {
+    iter.next().map(|first| iter.fold(first, max))
+}
+
+fn max(lhs: Max, rhs: Max) -> Max {
+    match (lhs, rhs) {
+        (Max::Exact(lhs), Max::Exact(rhs)) => Max::Exact(cmp::max(lhs, rhs)),
+        (Max::Exact(lhs), Max::LowerBound(rhs)) => Max::LowerBound(cmp::max(lhs, rhs)),
+        (Max::LowerBound(lhs), Max::Exact(rhs)) => Max::LowerBound(cmp::max(lhs, rhs)),
+        (Max::LowerBound(lhs), Max::LowerBound(rhs)) => Max::LowerBound(cmp::max(lhs, rhs)),
+    }
+}
+
+impl fmt::Display for Max {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Max::Exact(n) => write!(f, "= {}", n),
+            Max::LowerBound(n) => write!(f, ">= {}", n),
+        }
+    }
+}
+
+// used to track indirect function calls (`fn` pointers)
+#[derive(Default)]
+struct Indirect<'a> {
+    called: bool,
+    // grouped by the static the function pointer was loaded from, if known
+    callers: BTreeMap<Option<&'a str>, HashSet<NodeIndex>>,
+    callees: HashSet<NodeIndex>,
+}
+
+// used to track the function pointers stored in statics
+#[derive(Default)]
+struct Static<'a> {
+    // `internal` or `private` linkage
+    internal: bool,
+    // globals stored into the static, including its initial value
+    stored: HashSet<&'a str>,
+    // something other than a global (e.g. a function argument) was stored into the static
+    unknown: bool,
+}
+
+// used to track dynamic dispatch (trait objects)
+#[derive(Debug, Default)]
+struct Dynamic {
+    called: bool,
+    // grouped by the trait of the called method and its offset in the vtable, if known
+    callers: BTreeMap<(Option<String>, Option<u64>), HashSet<NodeIndex>>,
+    callees: HashSet<NodeIndex>,
+}
+
+// analyzes the machine code of a subroutine. The results of the other architectures are expressed
+// in terms of the Thumb ones, which are a superset of them
+fn machine_code(
+    bytes: &[u8],
+    address: u64,
+    target: Target,
+    tags: &[(u32, Tag)],
+) -> Result<thumb::Analysis, String> {
+    if target.is_riscv() {
+        let riscv::Analysis {
+            jals,
+            js,
+            jalrs,
+            indirect,
+            modifies_sp,
+            stack,
+        } = riscv::analyze(bytes, address, target.riscv_arch()).map_err(|e| e.to_string())?;
+
+        Ok(thumb::Analysis {
+            bls: jals,
+            bs: js,
+            blxs: jalrs,
+            indirect,
+            gateway: false,
+            non_secure: false,
+            modifies_sp,
+            stack,
+        })
+    } else if target == Target::X86_64 {
+        let x86_64::Analysis {
+            calls,
+            jmps,
+            resolved,
+            indirect,
+            modifies_sp,
+            stack,
+        } = x86_64::analyze(bytes, address).map_err(|e| e.to_string())?;
+
+        Ok(thumb::Analysis {
+            bls: calls,
+            bs: jmps,
+            blxs: resolved,
+            indirect,
+            gateway: false,
+            non_secure: false,
+            modifies_sp,
+            stack,
+        })
+    } else if target == Target::Aarch64 {
+        let aarch64::Analysis {
+            bls,
+            bs,
+            blrs,
+            indirect,
+            modifies_sp,
+            stack,
+        } = aarch64::analyze(bytes, address).map_err(|e| e.to_string())?;
+
+        Ok(thumb::Analysis {
+            bls,
+            bs,
+            blxs: blrs,
+            indirect,
+            gateway: false,
+            non_secure: false,
+            modifies_sp,
+            stack,
+        })
+    } else {
+        let address = u32::try_from(address)
+            .map_err(|_| format!("address {:#x} is out of the 32-bit address space", address))?;
+        thumb::analyze(bytes, address, target.arch(), tags).map_err(|e| e.to_string())
+    }
+}
+
+// computes the worst-case stack usage of every node of the call graph; returns its cycles
+fn max_stack(g: &mut Graph<Node, ()>, bounds: &[Bound], used: &mut [bool]) -> Vec<Cycle> {
+    let mut cycles = vec![];
+    if algo::is_cyclic_directed(&*g) {
+        let sccs = algo::kosaraju_scc(&*g);
+
+        // iterate over SCCs (Strongly Connected Components) in reverse topological order
+        for scc in &sccs {
+            let first = scc[0];
+
+            let is_a_cycle = scc.len() > 1
+                || g.neighbors_directed(first, Direction::Outgoing)
+                    .any(|n| n == first);
+
+            if is_a_cycle {
+                let depth = scc.iter().find_map(|node| {
+                    let function = format!("{:#}", rustc_demangle::demangle(&g[*node].name));
+                    bounds
+                        .iter()
+                        .position(|bound| bound.matches(&function))
+                        .map(|i| {
+                            used[i] = true;
+                            bounds[i].depth
+                        })
+                });
+                cycles.push((scc.clone(), depth));
+
+                let scc_local = if let Some(depth) = depth {
+                    // each function of the SCC is on the stack at most `depth` times. This
+                    // over-approximates SCCs made of several cycles (`a -> b -> a` and `a -> c ->
+                    // a`), where a trip doesn't go through every function; charging the heaviest
+                    // simple cycle instead would be unsound when the recursion alternates
+                    // between the cycles (`a -> b -> c -> b -> a` with `b -> c -> b`)
+                    scc.iter()
+                        .fold(Max::Exact(0), |sum, node| sum + g[*node].local)
+                        * depth
+                } else {
+                    let scc_local =
+                        max_of(scc.iter().map(|node| g[*node].local.into())).expect("UNREACHABLE");
+
+                    // the cumulative stack usage is only exact when all nodes do *not* use the
+                    // stack
+                    match scc_local {
+                        Max::Exact(n) if n != 0 => Max::LowerBound(n),
+                        _ => scc_local,
+                    }
+                };
+
+                let neighbors_max = max_of(scc.iter().flat_map(|inode| {
+                    g.neighbors_directed(*inode, Direction::Outgoing)
+                        .filter_map(|neighbor| {
+                            if scc.contains(&neighbor) {
+                                // we only care about the neighbors of the SCC
+                                None
+                            } else {
+                                Some(g[neighbor].max.expect("UNREACHABLE"))
+                            }
+                        })
+                }));
+
+                for inode in scc {
+                    let node = &mut g[*inode];
+                    if let Some(max) = neighbors_max {
+                        node.max = Some(max + scc_local);
+                    } else {
+                        node.max = Some(scc_local);
+                    }
+                }
+            } else {
+                let inode = first;
+
+                let neighbors_max = max_of(
+                    g.neighbors_directed(inode, Direction::Outgoing)
+                        .map(|neighbor| g[neighbor].max.expect("UNREACHABLE")),
+                );
+
+                let node = &mut g[inode];
+                if let Some(max) = neighbors_max {
+                    node.max = Some(max + node.local);
+                } else {
+                    node.max = Some(node.local.into());
+                }
+            }
+        }
+    } else {
+        // compute max stack usage
+        let mut topo = Topo::new(Reversed(&*g));
+        while let Some(node) = topo.next(Reversed(&*g)) {
+            debug_assert!(g[node].max.is_none());
+
+            let neighbors_max = max_of(
+                g.neighbors_directed(node, Direction::Outgoing)
+                    .map(|neighbor| g[neighbor].max.expect("UNREACHABLE")),
+            );
+
+            if let Some(max) = neighbors_max {
+                g[node].max = Some(max + g[node].local);
+            } else {
+                g[node].max = Some(g[node].local.into());
+            }
+        }
+    }
+
+    cycles
+}
+
+// reports an inconsistency found during the analysis. In `--strict` mode this is a hard error;
+// otherwise the analysis continues with less precise information
+fn diagnose(strict: bool, msg: String) -> Result<(), failure::Error> {
+    if strict {
+        Err(failure::err_msg(msg))
+    } else {
+        warn!("{}", msg);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Target {
+    Aarch64,
+    Other,
+    Riscv32,
+    Riscv64,
+    Thumbv6m,
+    Thumbv7m,
+    Thumbv8mBase,
+    Thumbv8mMain,
+    X86_64,
+}
+
+impl Target {
+    fn is_thumb(&self) -> bool {
+        match *self {
+            Target::Thumbv6m | Target::Thumbv7m | Target::Thumbv8mBase | Target::Thumbv8mMain => {
+                true
+            }
+            Target::Aarch64
+            | Target::Riscv32
+            | Target::Riscv64
+            | Target::X86_64
+            | Target::Other => false,
+        }
+    }
+
+    fn is_riscv(&self) -> bool {
+        matches!(*self, Target::Riscv32 | Target::Riscv64)
+    }
+
+    // we know how to analyze the machine code in the ELF file for these targets
+    fn has_machine_code_analysis(&self) -> bool {
+        self.is_thumb() || self.is_riscv() || matches!(*self, Target::X86_64 | Target::Aarch64)
+    }
+
+    // NOTE must only be called on thumb targets
+    fn arch(&self) -> thumb::Arch {
+        match *self {
+            Target::Thumbv6m => thumb::Arch::V6M,
+            Target::Thumbv7m => thumb::Arch::V7M,
+            Target::Thumbv8mBase => thumb::Arch::V8MBase,
+            Target::Thumbv8mMain => thumb::Arch::V8MMain,
+            _ => unreachable!(),
+        }
+    }
+
+    // NOTE must only be called on RISC-V targets
+    fn riscv_arch(&self) -> riscv::Arch {
+        match *self {
+            Target::Riscv32 => riscv::Arch::Rv32,
+            Target::Riscv64 => riscv::Arch::Rv64,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::graph::DiGraph;
+
+    use super::{Bound, CallGraph, Input, Ir, Max, Node, Options};
+
+    #[test]
+    fn worst_path() {
+        // main -> a -> c, main -> b, a -> main
+        let mut g = DiGraph::new();
+        let main = g.add_node(Node("main", Some(8), false));
+        let a = g.add_node(Node("a", Some(16), false));
+        let b = g.add_node(Node("b", Some(64), false));
+        let c = g.add_node(Node("c", Some(32), false));
+        g.add_edge(main, a, ());
+        g.add_edge(main, b, ());
+        g.add_edge(a, c, ());
+        g.add_edge(a, main, ());
+
+        let cycles = super::max_stack(&mut g, &[], &mut []);
+        let call_graph = CallGraph {
+            graph: g,
+            cycles,
+            incomplete: false,
+            main_stack: None,
+        };
+
+        assert_eq!(call_graph.max("c").unwrap(), Some(Max::Exact(32)));
+        assert_eq!(call_graph.max("b").unwrap(), Some(Max::Exact(64)));
+        assert!(call_graph.max("d").is_err());
+        // the cycle has unbounded stack usage so it's worse than `b`
+        assert_eq!(call_graph.worst_path("main").unwrap(), vec![main, a, c]);
+        assert_eq!(call_graph.worst_path("a").unwrap(), vec![a, main, b]);
+    }
+
+    #[test]
+    fn bounded_cycle() {
+        // main -> a, a -> b -> a, a -> c -> a
+        let mut g = DiGraph::new();
+        let main = g.add_node(Node("main", Some(8), false));
+        let a = g.add_node(Node("a", Some(16), false));
+        let b = g.add_node(Node("b", Some(32), false));
+        let c = g.add_node(Node("c", Some(64), false));
+        g.add_edge(main, a, ());
+        g.add_edge(a, b, ());
+        g.add_edge(b, a, ());
+        g.add_edge(a, c, ());
+        g.add_edge(c, a, ());
+
+        let mut used = [false];
+        let bounds = ["a=2".parse::<Bound>().unwrap()];
+        super::max_stack(&mut g, &bounds, &mut used);
+        assert!(used[0]);
+        // every function of the SCC is charged twice, although a trip goes through `b` or `c`
+        assert_eq!(g[main].max, Some(Max::Exact(8 + 2 * (16 + 32 + 64))));
+
+        assert_eq!(Max::Exact(u64::MAX / 2 + 1) * 2, Max::Exact(u64::MAX));
+    }
+
+    #[test]
+    fn secure_gateways() {
+        // the `SG` veneer of `foo` lives in `.gnu.sgstubs`, not in `.text`; see `secure.s`
+        // NOTE copied to get the alignment `xmas-elf` needs
+        let elf = include_bytes!("thumb/cmse/secure.elf").to_vec();
+        let call_graph = super::analyze(
+            Input {
+                elf: &elf,
+                ir: Ir::Text(""),
+                ir_path: None,
+                objects: vec![],
+                target: "thumbv8m.main-none-eabi",
+            },
+            &Options::default(),
+        )
+        .unwrap();
+
+        let g = &call_graph.graph;
+        let ns_callers = call_graph.find("<non-secure callers>").unwrap();
+        let veneer = call_graph.find("foo").unwrap();
+        let foo = call_graph.find("__acle_se_foo").unwrap();
+        let bar = call_graph.find("bar").unwrap();
+
+        assert_eq!(g.neighbors(ns_callers).collect::<Vec<_>>(), vec![veneer]);
+        assert_eq!(g.neighbors(veneer).collect::<Vec<_>>(), vec![foo]);
+        assert!(g.contains_edge(foo, bar));
+        // `bxns lr` returns to the non-secure caller
+        assert!(call_graph.find("<non-secure callees>").is_err());
+    }
+}
@@ -1,43 +1,25 @@
 #![deny(warnings)]
 
-use core::{
-    cmp,
-    fmt::{self, Write as _},
-    ops, str,
-};
 use std::{
-    borrow::Cow,
-    collections::{BTreeMap, HashMap, HashSet},
     env,
     fs::{self, File},
-    io::{self, Read, Write},
-    path::{Path, PathBuf},
+    path::PathBuf,
     process::{self, Command},
+    str,
     time::SystemTime,
 };
 
-use ar::Archive;
+use cargo_call_stack::{
+    query::{self, Query},
+    simplify::{self, Cluster},
+    Bound, CallGraph, Dot, Input, Ir, Options, Pattern, Strings, Task,
+};
 use cargo_project::{Artifact, Profile, Project};
 use clap::{crate_authors, crate_version, App, Arg};
 use env_logger::{Builder, Env};
 use filetime::FileTime;
-use log::{error, warn};
-use petgraph::{
-    algo,
-    graph::{DiGraph, NodeIndex},
-    visit::{Dfs, Reversed, Topo},
-    Direction, Graph,
-};
+use memmap::Mmap;
 use walkdir::WalkDir;
-use xmas_elf::{sections::SectionData, symbol_table::Entry, ElfFile};
-
-use crate::{
-    ir::{FnSig, Item, Stmt, Type},
-    thumb::Tag,
-};
-
-mod ir;
-mod thumb;
 
 fn main() -> Result<(), failure::Error> {
     match run() {
@@ -49,12 +31,6 @@ fn main() -> Result<(), failure::Error> {
     }
 }
 
-// Font used in the dot graphs
-const FONT: &str = "monospace";
-
-// Version we analyzed to extract some ad-hoc information
-const VERS: &str = "1.33.0"; // compiler-builtins = "0.1.4"
-
 #[allow(deprecated)]
 fn run() -> Result<i32, failure::Error> {
     Builder::from_env(Env::default().default_filter_or("warn")).init();
@@ -106,14 +82,180 @@ fn run() -> Result<i32, failure::Error> {
                 .takes_value(false)
                 .help("Activate all available features"),
         )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .takes_value(false)
+                .help("Abort on analysis inconsistencies (e.g. undecodable instructions)"),
+        )
+        .arg(
+            Arg::with_name("bitcode")
+                .long("bitcode")
+                .takes_value(false)
+                .help("Read the LLVM bitcode (`--emit=llvm-bc`) instead of the textual LLVM-IR"),
+        )
+        .arg(
+            Arg::with_name("type-erasure")
+                .long("type-erasure")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("CALLER => CALLEE")
+                .help("Restrict the function pointer calls made by CALLER to the functions that match CALLEE (`*` is a wildcard)"),
+        )
+        .arg(
+            Arg::with_name("recursion-bound")
+                .long("recursion-bound")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("FUNCTION=DEPTH")
+                .help("Bound the recursion of the cycle that contains FUNCTION: each of its functions is on the stack at most DEPTH times at once (`*` is a wildcard)"),
+        )
+        .arg(
+            Arg::with_name("task")
+                .long("task")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("ENTRY[=STACK]")
+                .help("ENTRY is the entry function of a task that runs on its own stack, the `static` array STACK (`*` is a wildcard)"),
+        )
+        .arg(
+            Arg::with_name("spawn")
+                .long("spawn")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("FUNCTION")
+                .help("FUNCTION starts tasks: the functions passed to it are task entry functions and the largest `static` passed to it is the task stack (`*` is a wildcard)"),
+        )
+        .arg(
+            Arg::with_name("stack-start")
+                .long("stack-start")
+                .takes_value(true)
+                .value_name("SYMBOL")
+                .help("The symbol that holds the initial stack pointer (default: `_stack_start`)"),
+        )
+        .arg(
+            Arg::with_name("stack-end")
+                .long("stack-end")
+                .takes_value(true)
+                .value_name("SYMBOL")
+                .help("The symbol that holds the lowest address the stack can grow into (default: the end of the static data and the heap)"),
+        )
+        .arg(
+            Arg::with_name("exception-frame")
+                .long("exception-frame")
+                .takes_value(true)
+                .value_name("BYTES")
+                .help("The size of the exception frame pushed each time a handler preempts the running code (default: 32 on ARM Cortex-M, 104 on `eabihf` targets, 0 elsewhere)"),
+        )
+        .arg(
+            Arg::with_name("panic-edges")
+                .long("panic-edges")
+                .takes_value(false)
+                .help("Draw the calls that are only made while panicking with dotted lines"),
+        )
+        .arg(
+            Arg::with_name("cluster")
+                .long("cluster")
+                .takes_value(true)
+                .possible_values(&["crate", "module"])
+                .help("Group the nodes of the call graph by crate or by module"),
+        )
+        .arg(
+            Arg::with_name("collapse")
+                .long("collapse")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("PATH")
+                .help("Draw the functions of the crate or module PATH (e.g. `core::fmt`) as a single node"),
+        )
+        .arg(
+            Arg::with_name("group-generics")
+                .long("group-generics")
+                .takes_value(false)
+                .help("Draw the instances of each generic function as a single node"),
+        )
+        .arg(
+            Arg::with_name("around")
+                .long("around")
+                .takes_value(true)
+                .value_name("FUNCTION")
+                .help("Only draw the functions that are at most `--hops` calls away from FUNCTION"),
+        )
+        .arg(
+            Arg::with_name("hops")
+                .long("hops")
+                .takes_value(true)
+                .value_name("N")
+                .default_value("2")
+                .help("Number of calls used by `--around`"),
+        )
         .arg(
             Arg::with_name("START").help("consider only the call graph that starts from this node"),
         )
+        .subcommand(query::app())
         .get_matches();
     let is_example = matches.is_present("example");
     let is_binary = matches.is_present("bin");
     let verbose = matches.is_present("verbose");
+    let strict = matches.is_present("strict");
+    let hops = matches
+        .value_of("hops")
+        .map(|hops| {
+            hops.parse::<usize>()
+                .map_err(|_| failure::format_err!("invalid number of hops `{}`", hops))
+        })
+        .transpose()?
+        .unwrap_or(2);
+    let exception_frame = matches
+        .value_of("exception-frame")
+        .map(|frame| {
+            frame
+                .parse::<u64>()
+                .map_err(|_| failure::format_err!("invalid exception frame size `{}`", frame))
+        })
+        .transpose()?;
+    let options = Dot {
+        incomplete: false,
+        panic_edges: matches.is_present("panic-edges"),
+        cluster: match matches.value_of("cluster") {
+            Some("crate") => Some(Cluster::Crate),
+            Some("module") => Some(Cluster::Module),
+            _ => None,
+        },
+        main_stack: None,
+    };
     let target_flag = matches.value_of("target");
+    let mut patterns = Pattern::builtin();
+    for pattern in matches.values_of("type-erasure").into_iter().flatten() {
+        patterns.push(pattern.parse()?);
+    }
+    let bounds = matches
+        .values_of("recursion-bound")
+        .into_iter()
+        .flatten()
+        .map(str::parse)
+        .collect::<Result<Vec<Bound>, _>>()?;
+    let tasks = matches
+        .values_of("task")
+        .into_iter()
+        .flatten()
+        .map(str::parse)
+        .collect::<Result<Vec<Task>, _>>()?;
+    let spawns = matches
+        .values_of("spawn")
+        .into_iter()
+        .flatten()
+        .map(str::to_owned)
+        .collect();
+    let query = matches
+        .subcommand_matches("query")
+        .map(Query::from_matches)
+        .transpose()?;
     let profile = Profile::Release;
 
     let file;
@@ -154,10 +296,16 @@ fn run() -> Result<i32, failure::Error> {
         cargo.arg("--release");
     }
 
+    let bitcode = matches.is_present("bitcode");
+    let (emit, extension) = if bitcode {
+        ("--emit=llvm-bc,obj", "bc")
+    } else {
+        ("--emit=llvm-ir,obj", "ll")
+    };
     cargo.args(&[
         "--",
-        // .ll file
-        "--emit=llvm-ir,obj",
+        // .ll (or .bc) file
+        emit,
         // needed to produce a single .ll file
         "-C",
         "lto",
@@ -227,7 +375,7 @@ fn run() -> Result<i32, failure::Error> {
         let e = e?;
         let p = e.path();
 
-        if p.extension().map(|e| e == "ll").unwrap_or(false) {
+        if p.extension().map(|e| e == extension).unwrap_or(false) {
             if p.file_stem()
                 .expect("unreachable")
                 .to_str()
@@ -248,1390 +396,88 @@ fn run() -> Result<i32, failure::Error> {
         }
     }
 
-    let ll = ll.expect("unreachable");
-    let obj = ll.with_extension("o");
-    let ll = fs::read_to_string(ll)?;
+    let ir = ll.expect("unreachable");
+    let obj = ir.with_extension("o");
     let obj = fs::read(obj)?;
 
-    let items = crate::ir::parse(&ll)?;
-    let mut defines = HashMap::new();
-    let mut declares = HashMap::new();
-    for item in items {
-        match item {
-            Item::Define(def) => {
-                defines.insert(def.name, def);
-            }
-
-            Item::Declare(decl) => {
-                declares.insert(decl.name, decl);
-            }
-
-            _ => {}
-        }
-    }
-
-    let target = project.target().or(target_flag).unwrap_or(&host);
-
-    // we know how to analyze the machine code in the ELF file for these targets thus we have more
-    // information and need less LLVM-IR hacks
-    let target_ = match target {
-        "thumbv6m-none-eabi" => Target::Thumbv6m,
-        "thumbv7m-none-eabi" | "thumbv7em-none-eabi" | "thumbv7em-none-eabihf" => Target::Thumbv7m,
-        _ => Target::Other,
+    // the module can be hundreds of MB when compiled with debug info; map it instead of reading it
+    // into memory. `cargo` is done writing it at this point
+    let map = unsafe { Mmap::map(&File::open(&ir)?)? };
+    let strings = Strings::default();
+    let contents = if bitcode {
+        Ir::Bitcode(&map, &strings)
+    } else {
+        Ir::Text(str::from_utf8(&map)?)
     };
 
-    // extract stack size information
-    // the `.o` file doesn't have address information so we just keep the stack usage information
-    let mut stack_sizes: HashMap<_, _> = stack_sizes::analyze_object(&obj)?
-        .into_iter()
-        .map(|(name, stack)| (name.to_owned(), stack))
-        .collect();
+    let target = project.target().or(target_flag).unwrap_or(&host);
 
-    // extract stack usage info from `libcompiler-builtins.rlib`
-    let sysroot_nl = String::from_utf8(
-        Command::new("rustc")
-            .args(&["--print", "sysroot"])
-            .output()?
-            .stdout,
+    // the stack usage of the compiler intrinsics comes from `libcompiler_builtins.rlib`
+    let builtins = cargo_call_stack::compiler_builtins(target)?;
+    let mut objects = vec![&obj[..]];
+    objects.extend(builtins.iter().map(|object| &object[..]));
+
+    let CallGraph {
+        graph: mut g,
+        mut cycles,
+        incomplete,
+        main_stack,
+    } = cargo_call_stack::analyze(
+        Input {
+            elf: &elf,
+            ir: contents,
+            ir_path: Some(&ir),
+            objects,
+            target,
+        },
+        &Options {
+            strict,
+            patterns,
+            bounds,
+            start: matches.value_of("START"),
+            tasks,
+            spawns,
+            stack_start: matches.value_of("stack-start"),
+            stack_end: matches.value_of("stack-end"),
+            exception_frame,
+        },
     )?;
-    // remove trailing newline
-    let sysroot = Path::new(sysroot_nl.trim_end());
-    let libdir = sysroot.join("lib/rustlib").join(target).join("lib");
-
-    for entry in fs::read_dir(libdir)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.extension().map(|ext| ext == "rlib").unwrap_or(false)
-            && path
-                .file_stem()
-                .and_then(|stem| stem.to_str())
-                .map(|stem| stem.starts_with("libcompiler_builtins"))
-                .unwrap_or(false)
-        {
-            let mut ar = Archive::new(File::open(path)?);
-
-            let mut buf = vec![];
-            while let Some(entry) = ar.next_entry() {
-                let mut entry = entry?;
-                let header = entry.header();
-
-                if str::from_utf8(header.identifier())
-                    .map(|id| id.contains("compiler_builtins") && id.ends_with(".o"))
-                    .unwrap_or(false)
-                {
-                    buf.clear();
-                    entry.read_to_end(&mut buf)?;
-                    stack_sizes.extend(
-                        stack_sizes::analyze_object(&buf)?
-                            .into_iter()
-                            .map(|(name, stack)| (name.to_owned(), stack)),
-                    );
-                }
-            }
-        }
-    }
-
-    // extract list of "live" symbols (symbols that have not been GC-ed by the linker)
-    // this time we use the ELF and not the object file
-    let mut symbols = stack_sizes::analyze_executable(&elf)?;
-
-    // clear the thumb bit
-    if target_.is_thumb() {
-        symbols.defined = symbols
-            .defined
-            .into_iter()
-            .map(|(k, v)| (k & !1, v))
-            .collect();
-    }
-
-    // remove version strings from undefined symbols
-    symbols.undefined = symbols
-        .undefined
-        .into_iter()
-        .map(|sym| {
-            if let Some(name) = sym.rsplit("@@").nth(1) {
-                name
-            } else {
-                sym
-            }
-        })
-        .collect();
-
-    let mut g = DiGraph::<Node, ()>::new();
-    let mut indices = BTreeMap::<Cow<str>, _>::new();
-
-    let mut indirects: HashMap<FnSig, Indirect> = HashMap::new();
-    let mut dynamics: HashMap<FnSig, Dynamic> = HashMap::new();
-    // functions that could be called by `ArgumentV1.formatter`
-    let mut fmts = HashSet::new();
-
-    // Some functions may be aliased; we map aliases to a single name. For example, if `foo`,
-    // `bar` and `baz` all have the same address then this maps contains: `foo -> foo`, `bar -> foo`
-    // and `baz -> foo`.
-    let mut aliases = HashMap::new();
-    // whether a symbol name is ambiguous after removing the hash
-    let mut ambiguous = HashMap::<String, u32>::new();
-
-    // we do a first pass over all the definitions to collect methods in `impl Trait for Type`
-    let mut default_methods = HashSet::new();
-    for name in defines.keys() {
-        let demangled = rustc_demangle::demangle(name).to_string();
-
-        // `<crate::module::Type as crate::module::Trait>::method::hdeadbeef`
-        if demangled.starts_with("<") {
-            if let Some(rhs) = demangled.splitn(2, " as ").nth(1) {
-                // rhs = `crate::module::Trait>::method::hdeadbeef`
-                let mut parts = rhs.splitn(2, ">::");
-
-                if let (Some(trait_), Some(rhs)) = (parts.next(), parts.next()) {
-                    // trait_ = `crate::module::Trait`, rhs = `method::hdeadbeef`
-
-                    if let Some(method) = dehash(rhs) {
-                        default_methods.insert(format!("{}::{}", trait_, method));
-                    }
-                }
-            }
-        }
-    }
-
-    // add all real nodes
-    let mut has_stack_usage_info = false;
-    let mut has_untyped_symbols = false;
-    let mut addr2name = BTreeMap::new();
-    for (address, sym) in &symbols.defined {
-        let names = sym.names();
-
-        let canonical_name = if names.len() > 1 {
-            // if one of the aliases appears in the `stack_sizes` dictionary, use that
-            if let Some(needle) = names.iter().find(|name| stack_sizes.contains_key(&***name)) {
-                needle
-            } else {
-                // otherwise, pick the first name that's not a tag
-                names
-                    .iter()
-                    .filter_map(|&name| {
-                        if name == "$a" || name.starts_with("$a.") {
-                            None
-                        } else {
-                            Some(name)
-                        }
-                    })
-                    .next()
-                    .expect("UNREACHABLE")
-            }
-        } else {
-            names[0]
-        };
-
-        for name in names {
-            aliases.insert(name, canonical_name);
-        }
-
-        let _out = addr2name.insert(address, canonical_name);
-        debug_assert!(_out.is_none());
-
-        let mut stack = stack_sizes.get(canonical_name).cloned();
-        if stack.is_none() {
-            // here we inject some target specific information we got from analyzing
-            // `libcompiler_builtins.rlib`
-
-            let ad_hoc = match target {
-                "thumbv6m-none-eabi" => match canonical_name {
-                    "__aeabi_memcpy" | "__aeabi_memset" | "__aeabi_memclr" | "__aeabi_memclr4"
-                    | "__aeabi_f2uiz" => {
-                        stack = Some(0);
-                        true
-                    }
-
-                    "__aeabi_memcpy4" | "__aeabi_memset4" | "__aeabi_f2iz" | "__aeabi_fadd"
-                    | "__aeabi_fdiv" | "__aeabi_fmul" | "__aeabi_fsub" => {
-                        stack = Some(8);
-                        true
-                    }
-
-                    "memcmp" | "__aeabi_fcmpgt" | "__aeabi_fcmplt" | "__aeabi_i2f"
-                    | "__aeabi_ui2f" => {
-                        stack = Some(16);
-                        true
-                    }
-
-                    "__addsf3" => {
-                        stack = Some(32);
-                        true
-                    }
-
-                    "__divsf3" => {
-                        stack = Some(40);
-                        true
-                    }
-
-                    "__mulsf3" => {
-                        stack = Some(48);
-                        true
-                    }
-
-                    _ => false,
-                },
-
-                "thumbv7m-none-eabi" | "thumbv7em-none-eabi" | "thumbv7em-none-eabihf" => {
-                    match canonical_name {
-                        "__aeabi_memclr" | "__aeabi_memclr4" => {
-                            stack = Some(0);
-                            true
-                        }
-
-                        "__aeabi_memcpy" | "__aeabi_memcpy4" | "memcmp" => {
-                            stack = Some(16);
-                            true
-                        }
-
-                        "__aeabi_memset" | "__aeabi_memset4" => {
-                            stack = Some(8);
-                            true
-                        }
-
-                        // ARMv7-M only below this point
-                        "__aeabi_f2iz" | "__aeabi_f2uiz" | "__aeabi_fadd" | "__aeabi_fcmpgt"
-                        | "__aeabi_fcmplt" | "__aeabi_fdiv" | "__aeabi_fmul" | "__aeabi_fsub"
-                        | "__aeabi_i2f" | "__aeabi_ui2f"
-                            if target == "thumbv7m-none-eabi" =>
-                        {
-                            stack = Some(0);
-                            true
-                        }
-
-                        "__addsf3" | "__mulsf3" if target == "thumbv7m-none-eabi" => {
-                            stack = Some(16);
-                            true
-                        }
-
-                        "__divsf3" if target == "thumbv7m-none-eabi" => {
-                            stack = Some(20);
-                            true
-                        }
-
-                        _ => false,
-                    }
-                }
-
-                _ => false,
-            };
-
-            if ad_hoc {
-                warn!(
-                    "ad-hoc: injecting stack usage information for `{}` (last checked: Rust {})",
-                    canonical_name, VERS
-                );
-            } else if !target_.is_thumb() {
-                warn!("no stack usage information for `{}`", canonical_name);
-            }
-        } else {
-            has_stack_usage_info = true;
-        }
-
-        let demangled = rustc_demangle::demangle(canonical_name).to_string();
-        if let Some(dehashed) = dehash(&demangled) {
-            *ambiguous.entry(dehashed.to_string()).or_insert(0) += 1;
-        }
-
-        let idx = g.add_node(Node(canonical_name, stack, false));
-        indices.insert(canonical_name.into(), idx);
-
-        // trait methods look like `<crate::module::Type as crate::module::Trait>::method::h$hash`
-        // default trait methods look like `crate::module::Trait::method::h$hash`
-        let is_trait_method = demangled.starts_with("<") && demangled.contains(" as ") || {
-            dehash(&demangled)
-                .map(|path| default_methods.contains(path))
-                .unwrap_or(false)
-        };
-
-        if let Some(def) = names.iter().filter_map(|name| defines.get(name)).next() {
-            // if the signature is `fn(&_, &mut fmt::Formatter) -> fmt::Result`
-            match (&def.sig.inputs[..], def.sig.output.as_ref()) {
-                ([Type::Pointer(..), Type::Pointer(fmt)], Some(output))
-                    if **fmt == Type::Alias("core::fmt::Formatter")
-                        && **output == Type::Integer(1) =>
-                {
-                    fmts.insert(idx);
-                }
-
-                _ => {}
-            }
-
-            let is_object_safe = is_trait_method && {
-                match def.sig.inputs.first().as_ref() {
-                    Some(Type::Pointer(ty)) => match **ty {
-                        // XXX can the receiver be a *specific* function? (e.g. `fn() {foo}`)
-                        Type::Fn(_) => false,
-
-                        _ => true,
-                    },
-                    _ => false,
-                }
-            };
-
-            if is_object_safe {
-                let mut sig = def.sig.clone();
-
-                // erase the type of the reciver
-                sig.inputs[0] = Type::erased();
-
-                dynamics.entry(sig).or_default().callees.insert(idx);
-            } else {
-                indirects
-                    .entry(def.sig.clone())
-                    .or_default()
-                    .callees
-                    .insert(idx);
-            }
-        } else if let Some(sig) = names
-            .iter()
-            .filter_map(|name| declares.get(name).and_then(|decl| decl.sig.clone()))
-            .next()
-        {
-            // sanity check (?)
-            assert!(!is_trait_method, "BUG: undefined trait method");
-
-            indirects.entry(sig).or_default().callees.insert(idx);
-        } else {
-            // from `compiler-builtins`
-            match canonical_name {
-                "__aeabi_memcpy" | "__aeabi_memcpy4" | "__aeabi_memcpy8" => {
-                    // `fn(*mut u8, *const u8, usize)`
-                    let sig = FnSig {
-                        inputs: vec![
-                            Type::Pointer(Box::new(Type::Integer(8))),
-                            Type::Pointer(Box::new(Type::Integer(8))),
-                            Type::Integer(32), // ARM has 32-bit pointers
-                        ],
-                        output: None,
-                    };
-                    indirects.entry(sig).or_default().callees.insert(idx);
-                }
-
-                "__aeabi_memclr" | "__aeabi_memclr4" | "__aeabi_memclr8" => {
-                    // `fn(*mut u8, usize)`
-                    let sig = FnSig {
-                        inputs: vec![
-                            Type::Pointer(Box::new(Type::Integer(8))),
-                            Type::Integer(32), // ARM has 32-bit pointers
-                        ],
-                        output: None,
-                    };
-                    indirects.entry(sig).or_default().callees.insert(idx);
-                }
-
-                "__aeabi_memset" | "__aeabi_memset4" | "__aeabi_memset8" => {
-                    // `fn(*mut u8, usize, i32)`
-                    let sig = FnSig {
-                        inputs: vec![
-                            Type::Pointer(Box::new(Type::Integer(8))),
-                            Type::Integer(32), // ARM has 32-bit pointers
-                            Type::Integer(32),
-                        ],
-                        output: None,
-                    };
-                    indirects.entry(sig).or_default().callees.insert(idx);
-                }
-
-                "__aeabi_fadd" | "__addsf3" | "__aeabi_fsub" | "__subsf3" | "__aeabi_fdiv"
-                | "__divsf3" | "__aeabi_fmul" | "__mulsf3" => {
-                    // `fn(f32, f32) -> f32`
-                    let sig = FnSig {
-                        inputs: vec![Type::Float, Type::Float],
-                        output: Some(Box::new(Type::Float)),
-                    };
-                    indirects.entry(sig).or_default().callees.insert(idx);
-                }
-
-                "__aeabi_fcmpgt" | "__aeabi_fcmplt" => {
-                    // `fn(f32, f32) -> i32`
-                    let sig = FnSig {
-                        inputs: vec![Type::Float, Type::Float],
-                        output: Some(Box::new(Type::Integer(32))),
-                    };
-                    indirects.entry(sig).or_default().callees.insert(idx);
-                }
-
-                "__aeabi_f2uiz" | "__aeabi_f2iz" => {
-                    // `fn(f32) -> {i,u}32`
-                    let sig = FnSig {
-                        inputs: vec![Type::Float],
-                        output: Some(Box::new(Type::Integer(32))),
-                    };
-                    indirects.entry(sig).or_default().callees.insert(idx);
-                }
-
-                "__aeabi_ui2f" | "__aeabi_i2f" => {
-                    // `fn({i,u}32) -> f32`
-                    let sig = FnSig {
-                        inputs: vec![Type::Integer(32)],
-                        output: Some(Box::new(Type::Float)),
-                    };
-                    indirects.entry(sig).or_default().callees.insert(idx);
-                }
-
-                "__divmoddi4" | "__udivmoddi4" => {
-                    // `fn({i,u}64, {i,u}64, *{i,u}64) -> {i,u}64`
-                    let sig = FnSig {
-                        inputs: vec![
-                            Type::Integer(64),
-                            Type::Integer(64),
-                            Type::Pointer(Box::new(Type::Integer(64))),
-                        ],
-                        output: Some(Box::new(Type::Integer(64))),
-                    };
-                    indirects.entry(sig).or_default().callees.insert(idx);
-                }
-
-                "__aeabi_uldivmod" | "__aeabi_ldivmod" => {
-                    // these subroutines don't use a standard calling convention and are impossible
-                    // to call from Rust code (they can be called via `asm!` though). This case is
-                    // listed here to suppress the warning below
-                }
-
-                _ => {
-                    has_untyped_symbols = true;
-                    warn!("no type information for `{}`", canonical_name);
-                }
-            }
-        }
-    }
-
-    // to avoid printing several warnings about the same thing
-    let mut asm_seen = HashSet::new();
-    let mut llvm_seen = HashSet::new();
-    // add edges
-    let mut edges: HashMap<_, HashSet<_>> = HashMap::new(); // NodeIdx -> [NodeIdx]
-    let mut defined = HashSet::new(); // functions that are `define`-d in the LLVM-IR
-    for define in defines.values() {
-        let (caller, callees_seen) = if let Some(canonical_name) = aliases.get(&define.name) {
-            defined.insert(*canonical_name);
-
-            let idx = indices[*canonical_name];
-            (idx, edges.entry(idx).or_default())
-        } else {
-            // this symbol was GC-ed by the linker, skip
-            continue;
-        };
-
-        for stmt in &define.stmts {
-            match stmt {
-                Stmt::Asm(expr) => {
-                    if !asm_seen.contains(expr) {
-                        asm_seen.insert(expr);
-                        warn!("assuming that asm!(\"{}\") does *not* use the stack", expr);
-                    }
-                }
-
-                // this is basically `(mem::transmute<*const u8, fn()>(&__some_symbol))()`
-                Stmt::BitcastCall(sym) => {
-                    // XXX we have some type information for this call but it's unclear if we should
-                    // try harder -- does this ever occur in pure Rust programs?
-
-                    let sym = sym.expect("BUG? unnamed symbol is being invoked");
-                    let callee = if let Some(idx) = indices.get(sym) {
-                        *idx
-                    } else {
-                        warn!("no stack information for `{}`", sym);
-
-                        let idx = g.add_node(Node(sym, None, false));
-                        indices.insert(Cow::Borrowed(sym), idx);
-                        idx
-                    };
-
-                    g.add_edge(caller, callee, ());
-                }
-
-                Stmt::DirectCall(func) => {
-                    match *func {
-                        // no-op / debug-info
-                        "llvm.dbg.value" => continue,
-                        "llvm.dbg.declare" => continue,
-
-                        // no-op / compiler-hint
-                        "llvm.assume" => continue,
-
-                        // lowers to a single instruction
-                        "llvm.trap" => continue,
-
-                        _ => {}
-                    }
-
-                    // no-op / compiler-hint
-                    if func.starts_with("llvm.lifetime.start")
-                        || func.starts_with("llvm.lifetime.end")
-                    {
-                        continue;
-                    }
-
-                    let mut call = |callee| {
-                        if !callees_seen.contains(&callee) {
-                            g.add_edge(caller, callee, ());
-                            callees_seen.insert(callee);
-                        }
-                    };
-
-                    if target_.is_thumb() && func.starts_with("llvm.") {
-                        // we'll analyze the machine code in the ELF file to figure out what these
-                        // lower to
-                        continue;
-                    }
-
-                    // TODO? consider alignment and `value` argument to only include one edge
-                    // TODO? consider the `len` argument to elide the call to `*mem*`
-                    if func.starts_with("llvm.memcpy.") {
-                        if let Some(callee) = indices.get("memcpy") {
-                            call(*callee);
-                        }
-
-                        // ARMv7-R and the like use these
-                        if let Some(callee) = indices.get("__aeabi_memcpy") {
-                            call(*callee);
-                        }
-
-                        if let Some(callee) = indices.get("__aeabi_memcpy4") {
-                            call(*callee);
-                        }
-
-                        continue;
-                    }
-
-                    // TODO? consider alignment and `value` argument to only include one edge
-                    // TODO? consider the `len` argument to elide the call to `*mem*`
-                    if func.starts_with("llvm.memset.") || func.starts_with("llvm.memmove.") {
-                        if let Some(callee) = indices.get("memset") {
-                            call(*callee);
-                        }
-
-                        // ARMv7-R and the like use these
-                        if let Some(callee) = indices.get("__aeabi_memset") {
-                            call(*callee);
-                        }
-
-                        if let Some(callee) = indices.get("__aeabi_memset4") {
-                            call(*callee);
-                        }
-
-                        if let Some(callee) = indices.get("memclr") {
-                            call(*callee);
-                        }
-
-                        if let Some(callee) = indices.get("__aeabi_memclr") {
-                            call(*callee);
-                        }
-
-                        if let Some(callee) = indices.get("__aeabi_memclr4") {
-                            call(*callee);
-                        }
-
-                        continue;
-                    }
-
-                    // XXX unclear whether these produce library calls on some platforms or not
-                    if func.starts_with("llvm.bswap.")
-                        | func.starts_with("llvm.ctlz.")
-                        | func.starts_with("llvm.cttz.")
-                        | func.starts_with("llvm.sadd.with.overflow.")
-                        | func.starts_with("llvm.smul.with.overflow.")
-                        | func.starts_with("llvm.ssub.with.overflow.")
-                        | func.starts_with("llvm.uadd.with.overflow.")
-                        | func.starts_with("llvm.umul.with.overflow.")
-                        | func.starts_with("llvm.usub.sat.")
-                        | func.starts_with("llvm.usub.with.overflow.")
-                        | func.starts_with("llvm.x86.sse2.pmovmskb.")
-                    {
-                        if !llvm_seen.contains(func) {
-                            llvm_seen.insert(func);
-                            warn!("assuming that `{}` directly lowers to machine code", func);
-                        }
-
-                        continue;
-                    }
-
-                    assert!(
-                        !func.starts_with("llvm."),
-                        "BUG: unhandled llvm intrinsic: {}",
-                        func
-                    );
-
-                    // use canonical name
-                    let callee = if let Some(canon) = aliases.get(func) {
-                        indices[*canon]
-                    } else {
-                        assert!(
-                            symbols.undefined.contains(func),
-                            "BUG: callee `{}` is unknown",
-                            func
-                        );
-
-                        if let Some(idx) = indices.get(*func) {
-                            *idx
-                        } else {
-                            let idx = g.add_node(Node(*func, None, false));
-                            indices.insert((*func).into(), idx);
-
-                            idx
-                        }
-                    };
-
-                    if !callees_seen.contains(&callee) {
-                        callees_seen.insert(callee);
-                        g.add_edge(caller, callee, ());
-                    }
-                }
-
-                Stmt::IndirectCall(sig) => {
-                    if sig
-                        .inputs
-                        .first()
-                        .map(|ty| ty.has_been_erased())
-                        .unwrap_or(false)
-                    {
-                        // dynamic dispatch
-                        let dynamic = dynamics.entry(sig.clone()).or_default();
 
-                        dynamic.called = true;
-                        dynamic.callers.insert(caller);
-                    } else {
-                        let indirect = indirects.entry(sig.clone()).or_default();
-
-                        indirect.called = true;
-                        indirect.callers.insert(caller);
-                    }
-                }
-
-                Stmt::Label | Stmt::Comment | Stmt::Other => {}
-            }
-        }
-    }
-
-    // here we parse the machine code in the ELF file to find out edges that don't appear in the
-    // LLVM-IR (e.g. `fadd` operation, `call llvm.umul.with.overflow`, etc.) or are difficult to
-    // disambiguate from the LLVM-IR (e.g. does this `llvm.memcpy` lower to a call to
-    // `__aebi_memcpy`, a call to `__aebi_memcpy4` or machine instructions?)
-    if target_.is_thumb() {
-        let elf = ElfFile::new(&elf).map_err(failure::err_msg)?;
-        let sect = elf.find_section_by_name(".symtab").expect("UNREACHABLE");
-        let mut tags: Vec<_> = match sect.get_data(&elf).unwrap() {
-            SectionData::SymbolTable32(entries) => entries
-                .iter()
-                .filter_map(|entry| {
-                    let addr = entry.value() as u32;
-                    entry.get_name(&elf).ok().and_then(|name| {
-                        if name.starts_with("$d") {
-                            Some((addr, Tag::Data))
-                        } else if name.starts_with("$t") {
-                            Some((addr, Tag::Thumb))
-                        } else {
-                            None
-                        }
-                    })
-                })
-                .collect(),
-            _ => unreachable!(),
-        };
-
-        tags.sort_by(|a, b| a.0.cmp(&b.0));
-
-        if let Some(sect) = elf.find_section_by_name(".text") {
-            let stext = sect.address() as u32;
-            let text = sect.raw_data(&elf);
-
-            for (address, sym) in &symbols.defined {
-                let address = *address as u32;
-                let canonical_name = aliases[&sym.names()[0]];
-                let mut size = sym.size() as u32;
-
-                if size == 0 {
-                    // try harder at finding out the size of this symbol
-                    if let Ok(needle) = tags.binary_search_by(|tag| tag.0.cmp(&address)) {
-                        let start = tags[needle];
-                        if start.1 == Tag::Thumb {
-                            if let Some(end) = tags.get(needle + 1) {
-                                if end.1 == Tag::Thumb {
-                                    size = end.0 - start.0;
-                                }
-                            }
-                        }
-                    }
-                }
-
-                let start = (address - stext) as usize;
-                let end = start + size as usize;
-                let (bls, bs, indirect, modifies_sp, our_stack) = thumb::analyze(
-                    &text[start..end],
-                    address,
-                    target_ == Target::Thumbv7m,
-                    &tags,
-                );
-                let caller = indices[canonical_name];
-
-                // sanity check
-                if let Some(stack) = our_stack {
-                    assert_eq!(
-                        stack != 0,
-                        modifies_sp,
-                        "BUG: our analysis reported that `{}` both uses {} bytes of stack and \
-                         it does{} modify SP",
-                        canonical_name,
-                        stack,
-                        if !modifies_sp { " not" } else { "" }
-                    );
-                }
-
-                // check the correctness of `modifies_sp` and `our_stack`
-                // also override LLVM's results when they appear to be wrong
-                if let Local::Exact(ref mut llvm_stack) = g[caller].local {
-                    if let Some(stack) = our_stack {
-                        if *llvm_stack == 0 && stack != 0 {
-                            // this could be a `#[naked]` + `asm!` function or `global_asm!`
-
-                            warn!(
-                                "LLVM reported zero stack usage for `{}` but \
-                                 our analysis reported {} bytes; overriding LLVM's result",
-                                canonical_name, stack
-                            );
-
-                            *llvm_stack = stack;
-                        } else {
-                            // in all other cases our results should match
-
-                            assert_eq!(
-                                *llvm_stack, stack,
-                                "BUG: LLVM reported that `{}` uses {} bytes of stack but \
-                                 this doesn't match our analysis",
-                                canonical_name, stack
-                            );
-                        }
-                    }
-
-                    assert_eq!(
-                        *llvm_stack != 0,
-                        modifies_sp,
-                        "BUG: LLVM reported that `{}` uses {} bytes of stack but this doesn't \
-                         match our analysis",
-                        canonical_name,
-                        *llvm_stack
-                    );
-                } else if let Some(stack) = our_stack {
-                    g[caller].local = Local::Exact(stack);
-                } else if !modifies_sp {
-                    // this happens when the function contains intra-branches and our analysis gives
-                    // up (`our_stack == None`)
-                    g[caller].local = Local::Exact(0);
-                }
-
-                if g[caller].local == Local::Unknown {
-                    warn!("no stack usage information for `{}`", canonical_name);
-                }
-
-                if !defined.contains(canonical_name) && indirect {
-                    // this function performs an indirect function call and we have no type
-                    // information to narrow down the list of callees so inject the uncertainty
-                    // in the form of a call to an unknown function with unknown stack usage
-
-                    warn!(
-                        "`{}` performs an indirect function call and there's \
-                         no type information about the operation",
-                        canonical_name,
-                    );
-                    let callee = g.add_node(Node("?", None, false));
-                    g.add_edge(caller, callee, ());
-                }
-
-                let callees_seen = edges.entry(caller).or_default();
-                for offset in bls {
-                    let addr = (address as i64 + i64::from(offset)) as u64;
-                    // address may be off by one due to the thumb bit being set
-                    let name = addr2name
-                        .get(&addr)
-                        .unwrap_or_else(|| panic!("BUG? no symbol at address {}", addr));
-
-                    let callee = indices[*name];
-                    if !callees_seen.contains(&callee) {
-                        g.add_edge(caller, callee, ());
-                        callees_seen.insert(callee);
-                    }
-                }
-
-                for offset in bs {
-                    let addr = (address as i32 + offset) as u32;
-
-                    if addr >= address && addr < (address + size) {
-                        // intra-function B branches are not function calls
-                    } else {
-                        // address may be off by one due to the thumb bit being set
-                        let name = addr2name
-                            .get(&(addr as u64))
-                            .unwrap_or_else(|| panic!("BUG? no symbol at address {}", addr));
-
-                        let callee = indices[*name];
-                        if !callees_seen.contains(&callee) {
-                            g.add_edge(caller, callee, ());
-                            callees_seen.insert(callee);
-                        }
-                    }
-                }
-            }
-        } else {
-            error!(".text section not found")
-        }
-    }
-
-    // add fictitious nodes for indirect function calls
-    if has_untyped_symbols {
-        warn!(
-            "the program contains untyped, external symbols (e.g. linked in from binary blobs); \
-             indirect function calls can not be bounded"
-        );
-    }
-
-    // this is a bit weird but for some reason `ArgumentV1.formatter` sometimes lowers to different
-    // LLVM types. In theory it should always be: `i1 (*%fmt::Void, *&core::fmt::Formatter)*` but
-    // sometimes the type of the first argument is `%fmt::Void`, sometimes it's `%core::fmt::Void`,
-    // sometimes is `%core::fmt::Void.12` and on occasion it's even `%SomeRandomType`
-    //
-    // To cope with this weird fact the following piece of code will try to find the right LLVM
-    // type.
-    let all_maybe_void = indirects
-        .keys()
-        .filter_map(|sig| match (&sig.inputs[..], sig.output.as_ref()) {
-            ([Type::Pointer(receiver), Type::Pointer(formatter)], Some(output))
-                if **formatter == Type::Alias("core::fmt::Formatter")
-                    && **output == Type::Integer(1) =>
-            {
-                if let Type::Alias(receiver) = **receiver {
-                    Some(receiver)
-                } else {
-                    None
-                }
-            }
-            _ => None,
-        })
-        .collect::<Vec<_>>();
-
-    let one_true_void = if all_maybe_void.contains(&"fmt::Void") {
-        Some("fmt::Void")
-    } else {
-        all_maybe_void
-            .iter()
-            .filter_map(|maybe_void| {
-                // this could be `core::fmt::Void` or `core::fmt::Void.12`
-                if maybe_void.starts_with("core::fmt::Void") {
-                    Some(*maybe_void)
-                } else {
-                    None
-                }
-            })
-            .next()
-            .or_else(|| {
-                if all_maybe_void.len() == 1 {
-                    // we got a random type!
-                    Some(all_maybe_void[0])
-                } else {
-                    None
-                }
-            })
+    let options = Dot {
+        incomplete,
+        main_stack,
+        ..options
     };
-
-    for (mut sig, indirect) in indirects {
-        if !indirect.called {
-            continue;
-        }
-
-        let callees = if let Some(one_true_void) = one_true_void {
-            match (&sig.inputs[..], sig.output.as_ref()) {
-                // special case: this is `ArgumentV1.formatter` a pseudo trait object
-                ([Type::Pointer(void), Type::Pointer(fmt)], Some(output))
-                    if **void == Type::Alias(one_true_void)
-                        && **fmt == Type::Alias("core::fmt::Formatter")
-                        && **output == Type::Integer(1) =>
-                {
-                    if fmts.is_empty() {
-                        error!("BUG? no callees for `{}`", sig.to_string());
-                    }
-
-                    // canonicalize the signature
-                    if one_true_void != "fmt::Void" {
-                        sig.inputs[0] = Type::Alias("fmt::Void");
-                    }
-
-                    &fmts
-                }
-
-                _ => &indirect.callees,
-            }
-        } else {
-            &indirect.callees
-        };
-
-        let mut name = sig.to_string();
-        // append '*' to denote that this is a function pointer
-        name.push('*');
-
-        let call = g.add_node(Node(name.clone(), Some(0), true));
-
-        for caller in &indirect.callers {
-            g.add_edge(*caller, call, ());
-        }
-
-        if has_untyped_symbols {
-            // add an edge between this and a potential extern / untyped symbol
-            let extern_sym = g.add_node(Node("?", None, false));
-            g.add_edge(call, extern_sym, ());
-        } else {
-            if callees.is_empty() {
-                error!("BUG? no callees for `{}`", name);
-            }
-        }
-
-        for callee in callees {
-            g.add_edge(call, *callee, ());
-        }
-    }
-
-    // add fictitious nodes for dynamic dispatch
-    for (sig, dynamic) in dynamics {
-        if !dynamic.called {
-            continue;
-        }
-
-        let name = sig.to_string();
-
-        if dynamic.callees.is_empty() {
-            error!("BUG? no callees for `{}`", name);
-        }
-
-        let call = g.add_node(Node(name, Some(0), true));
-        for caller in &dynamic.callers {
-            g.add_edge(*caller, call, ());
-        }
-
-        for callee in &dynamic.callees {
-            g.add_edge(call, *callee, ());
-        }
-    }
-
-    // filter the call graph
-    if let Some(start) = matches.value_of("START") {
-        let start = indices.get(start).cloned().or_else(|| {
-            let start_ = start.to_owned() + "::h";
-            let hits = indices
-                .keys()
-                .filter_map(|key| {
-                    if rustc_demangle::demangle(key)
-                        .to_string()
-                        .starts_with(&start_)
-                    {
-                        Some(key)
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>();
-
-            if hits.len() > 1 {
-                error!("multiple matches for `{}`: {:?}", start, hits);
-                None
-            } else {
-                hits.first().map(|key| indices[*key])
-            }
-        });
-
-        if let Some(start) = start {
-            // create a new graph that only contains nodes reachable from `start`
-            let mut g2 = DiGraph::<Node, ()>::new();
-
-            // maps `g`'s `NodeIndex`-es to `g2`'s `NodeIndex`-es
-            let mut one2two = BTreeMap::new();
-
-            let mut dfs = Dfs::new(&g, start);
-            while let Some(caller1) = dfs.next(&g) {
-                let caller2 = if let Some(i2) = one2two.get(&caller1) {
-                    *i2
-                } else {
-                    let i2 = g2.add_node(g[caller1].clone());
-                    one2two.insert(caller1, i2);
-                    i2
-                };
-
-                let mut callees = g.neighbors(caller1).detach();
-                while let Some((_, callee1)) = callees.next(&g) {
-                    let callee2 = if let Some(i2) = one2two.get(&callee1) {
-                        *i2
-                    } else {
-                        let i2 = g2.add_node(g[callee1].clone());
-                        one2two.insert(callee1, i2);
-                        i2
-                    };
-
-                    g2.add_edge(caller2, callee2, ());
-                }
-            }
-
-            // replace the old graph
+    if let Some((query, format)) = query {
+        query::run(&g, &cycles, &options, &query, format)?;
+    } else {
+        // these only change what gets printed; the stack usage was computed on the full graph
+        if let Some(around) = matches.value_of("around") {
+            let center = query::find(&g, around)?;
+            let (g2, cycles2) = simplify::around(&g, &cycles, center, hops);
             g = g2;
-
-            // invalidate `indices` to prevent misuse
-            indices.clear();
-        } else {
-            error!("start point not found; the graph will not be filtered")
+            cycles = cycles2;
         }
-    }
-
-    let mut cycles = vec![];
-    if !has_stack_usage_info {
-        error!("The graph has zero stack usage information; skipping max stack usage analysis");
-    } else if algo::is_cyclic_directed(&g) {
-        let sccs = algo::kosaraju_scc(&g);
-
-        // iterate over SCCs (Strongly Connected Components) in reverse topological order
-        for scc in &sccs {
-            let first = scc[0];
-
-            let is_a_cycle = scc.len() > 1
-                || g.neighbors_directed(first, Direction::Outgoing)
-                    .any(|n| n == first);
-
-            if is_a_cycle {
-                cycles.push(scc.clone());
-
-                let mut scc_local =
-                    max_of(scc.iter().map(|node| g[*node].local.into())).expect("UNREACHABLE");
-
-                // the cumulative stack usage is only exact when all nodes do *not* use the stack
-                if let Max::Exact(n) = scc_local {
-                    if n != 0 {
-                        scc_local = Max::LowerBound(n)
-                    }
-                }
 
-                let neighbors_max = max_of(scc.iter().flat_map(|inode| {
-                    g.neighbors_directed(*inode, Direction::Outgoing)
-                        .filter_map(|neighbor| {
-                            if scc.contains(&neighbor) {
-                                // we only care about the neighbors of the SCC
-                                None
-                            } else {
-                                Some(g[neighbor].max.expect("UNREACHABLE"))
-                            }
-                        })
-                }));
-
-                for inode in scc {
-                    let node = &mut g[*inode];
-                    if let Some(max) = neighbors_max {
-                        node.max = Some(max + scc_local);
-                    } else {
-                        node.max = Some(scc_local);
-                    }
-                }
-            } else {
-                let inode = first;
-
-                let neighbors_max = max_of(
-                    g.neighbors_directed(inode, Direction::Outgoing)
-                        .map(|neighbor| g[neighbor].max.expect("UNREACHABLE")),
-                );
-
-                let node = &mut g[inode];
-                if let Some(max) = neighbors_max {
-                    node.max = Some(max + node.local);
-                } else {
-                    node.max = Some(node.local.into());
-                }
-            }
+        if matches.is_present("group-generics") {
+            let (g2, cycles2) = simplify::generics(&g, &cycles);
+            g = g2;
+            cycles = cycles2;
         }
-    } else {
-        // compute max stack usage
-        let mut topo = Topo::new(Reversed(&g));
-        while let Some(node) = topo.next(Reversed(&g)) {
-            debug_assert!(g[node].max.is_none());
-
-            let neighbors_max = max_of(
-                g.neighbors_directed(node, Direction::Outgoing)
-                    .map(|neighbor| g[neighbor].max.expect("UNREACHABLE")),
-            );
 
-            if let Some(max) = neighbors_max {
-                g[node].max = Some(max + g[node].local);
-            } else {
-                g[node].max = Some(g[node].local.into());
-            }
+        let paths = matches
+            .values_of("collapse")
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        if !paths.is_empty() {
+            let (g2, cycles2) = simplify::collapse(&g, &cycles, &paths);
+            g = g2;
+            cycles = cycles2;
         }
-    }
-
-    // here we try to shorten the name of the symbol if it doesn't result in ambiguity
-    for node in g.node_weights_mut() {
-        let demangled = rustc_demangle::demangle(&node.name).to_string();
 
-        if let Some(dehashed) = dehash(&demangled) {
-            if ambiguous[dehashed] == 1 {
-                node.name = Cow::Owned(dehashed.to_owned());
-            }
-        }
+        cargo_call_stack::dot(g, &cycles, &options)?;
     }
 
-    dot(g, &cycles)?;
-
     Ok(0)
 }
-
-fn dot(g: Graph<Node, ()>, cycles: &[Vec<NodeIndex>]) -> io::Result<()> {
-    let stdout = io::stdout();
-    let mut stdout = stdout.lock();
-
-    writeln!(stdout, "digraph {{")?;
-    writeln!(stdout, "    node [fontname={} shape=box]", FONT)?;
-
-    for (i, node) in g.raw_nodes().iter().enumerate() {
-        let node = &node.weight;
-
-        write!(stdout, "    {} [label=\"", i,)?;
-
-        let mut escaper = Escaper::new(&mut stdout);
-        write!(escaper, "{}", rustc_demangle::demangle(&node.name)).ok();
-        escaper.error?;
-
-        if let Some(max) = node.max {
-            write!(stdout, "\\nmax {}", max)?;
-        }
-
-        write!(stdout, "\\nlocal = {}\"", node.local,)?;
-
-        if node.dashed {
-            write!(stdout, " style=dashed")?;
-        }
-
-        writeln!(stdout, "]")?;
-    }
-
-    for edge in g.raw_edges() {
-        writeln!(
-            stdout,
-            "    {} -> {}",
-            edge.source().index(),
-            edge.target().index()
-        )?;
-    }
-
-    for (i, cycle) in cycles.iter().enumerate() {
-        writeln!(stdout, "\n    subgraph cluster_{} {{", i)?;
-        writeln!(stdout, "        style=dashed")?;
-        writeln!(stdout, "        fontname={}", FONT)?;
-        writeln!(stdout, "        label=\"SCC{}\"", i)?;
-
-        for node in cycle {
-            writeln!(stdout, "        {}", node.index())?;
-        }
-
-        writeln!(stdout, "    }}")?;
-    }
-
-    writeln!(stdout, "}}")
-}
-
-struct Escaper<W>
-where
-    W: io::Write,
-{
-    writer: W,
-    error: io::Result<()>,
-}
-
-impl<W> Escaper<W>
-where
-    W: io::Write,
-{
-    fn new(writer: W) -> Self {
-        Escaper {
-            writer,
-            error: Ok(()),
-        }
-    }
-}
-
-impl<W> fmt::Write for Escaper<W>
-where
-    W: io::Write,
-{
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        for c in s.chars() {
-            self.write_char(c)?;
-        }
-
-        Ok(())
-    }
-
-    fn write_char(&mut self, c: char) -> fmt::Result {
-        match (|| -> io::Result<()> {
-            match c {
-                '"' => write!(self.writer, "\\")?,
-                _ => {}
-            }
-
-            write!(self.writer, "{}", c)
-        })() {
-            Err(e) => {
-                self.error = Err(e);
-
-                Err(fmt::Error)
-            }
-            Ok(()) => Ok(()),
-        }
-    }
-}
-
-#[derive(Clone)]
-struct Node<'a> {
-    name: Cow<'a, str>,
-    local: Local,
-    max: Option<Max>,
-    dashed: bool,
-}
-
-#[allow(non_snake_case)]
-fn Node<'a, S>(name: S, stack: Option<u64>, dashed: bool) -> Node<'a>
-where
-    S: Into<Cow<'a, str>>,
-{
-    Node {
-        name: name.into(),
-        local: stack.map(Local::Exact).unwrap_or(Local::Unknown),
-        max: None,
-        dashed,
-    }
-}
-
-/// Local stack usage
-#[derive(Clone, Copy, PartialEq)]
-enum Local {
-    Exact(u64),
-    Unknown,
-}
-
-impl fmt::Display for Local {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Local::Exact(n) => write!(f, "{}", n),
-            Local::Unknown => f.write_str("?"),
-        }
-    }
-}
-
-impl Into<Max> for Local {
-    fn into(self) -> Max {
-        match self {
-            Local::Exact(n) => Max::Exact(n),
-            Local::Unknown => Max::LowerBound(0),
-        }
-    }
-}
-
-#[derive(Clone, Copy, Eq, PartialEq)]
-enum Max {
-    Exact(u64),
-    LowerBound(u64),
-}
-
-impl ops::Add<Local> for Max {
-    type Output = Max;
-
-    fn add(self, rhs: Local) -> Max {
-        match (self, rhs) {
-            (Max::Exact(lhs), Local::Exact(rhs)) => Max::Exact(lhs + rhs),
-            (Max::Exact(lhs), Local::Unknown) => Max::LowerBound(lhs),
-            (Max::LowerBound(lhs), Local::Exact(rhs)) => Max::LowerBound(lhs + rhs),
-            (Max::LowerBound(lhs), Local::Unknown) => Max::LowerBound(lhs),
-        }
-    }
-}
-
-impl ops::Add<Max> for Max {
-    type Output = Max;
-
-    fn add(self, rhs: Max) -> Max {
-        match (self, rhs) {
-            (Max::Exact(lhs), Max::Exact(rhs)) => Max::Exact(lhs + rhs),
-            (Max::Exact(lhs), Max::LowerBound(rhs)) => Max::LowerBound(lhs + rhs),
-            (Max::LowerBound(lhs), Max::Exact(rhs)) => Max::LowerBound(lhs + rhs),
-            (Max::LowerBound(lhs), Max::LowerBound(rhs)) => Max::LowerBound(lhs + rhs),
-        }
-    }
-}
-
-fn max_of(mut iter: impl Iterator<Item = Max>) -> Option<Max> {
-    iter.next().map(|first| iter.fold(first, max))
-}
-
-fn max(lhs: Max, rhs: Max) -> Max {
-    match (lhs, rhs) {
-        (Max::Exact(lhs), Max::Exact(rhs)) => Max::Exact(cmp::max(lhs, rhs)),
-        (Max::Exact(lhs), Max::LowerBound(rhs)) => Max::LowerBound(cmp::max(lhs, rhs)),
-        (Max::LowerBound(lhs), Max::Exact(rhs)) => Max::LowerBound(cmp::max(lhs, rhs)),
-        (Max::LowerBound(lhs), Max::LowerBound(rhs)) => Max::LowerBound(cmp::max(lhs, rhs)),
-    }
-}
-
-impl fmt::Display for Max {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Max::Exact(n) => write!(f, "= {}", n),
-            Max::LowerBound(n) => write!(f, ">= {}", n),
-        }
-    }
-}
-
-// used to track indirect function calls (`fn` pointers)
-#[derive(Default)]
-struct Indirect {
-    called: bool,
-    callers: HashSet<NodeIndex>,
-    callees: HashSet<NodeIndex>,
-}
-
-// used to track dynamic dispatch (trait objects)
-#[derive(Debug, Default)]
-struct Dynamic {
-    called: bool,
-    callers: HashSet<NodeIndex>,
-    callees: HashSet<NodeIndex>,
-}
-
-// removes hashes like `::hfc5adc5d79855638`, if present
-fn dehash(demangled: &str) -> Option<&str> {
-    const HASH_LENGTH: usize = 19;
-
-    let len = demangled.as_bytes().len();
-    if len > HASH_LENGTH {
-        if demangled
-            .get(len - HASH_LENGTH..)
-            .map(|hash| hash.starts_with("::h"))
-            .unwrap_or(false)
-        {
-            Some(&demangled[..len - HASH_LENGTH])
-        } else {
-            None
-        }
-    } else {
-        None
-    }
-}
-
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum Target {
-    Other,
-    Thumbv6m,
-    Thumbv7m,
-}
-
-impl Target {
-    fn is_thumb(&self) -> bool {
-        match *self {
-            Target::Thumbv6m | Target::Thumbv7m => true,
-            Target::Other => false,
-        }
-    }
-}
@@ -0,0 +1,244 @@
+//! Memory layout
+//!
+//! The main stack starts at the initial stack pointer and grows down towards the static data
+//! (`.bss`, `.uninit`) and the heap, if any. The linker scripts of the `*-rt` crates name these
+//! boundaries with symbols (`_stack_start`, `__ebss`, `__sheap`, ..) so the space the stack can
+//! use is known from the ELF file alone.
+
+use std::collections::HashMap;
+
+use petgraph::{graph::NodeIndex, Direction, Graph};
+use xmas_elf::{
+    sections::SectionData,
+    symbol_table::{Entry as _, Type},
+    ElfFile,
+};
+
+use crate::{tasks, Entry, Free, Local, Max, Node};
+
+// the initial stack pointer
+const START: &str = "_stack_start";
+// the lowest address the stack can grow into, when the linker script reserves the stack
+const END: &str = "_stack_end";
+// the size of the reserved stack
+const SIZE: &str = "_stack_size";
+// the end of the static data and of the heap
+const STATIC_ENDS: &[&str] = &[
+    "__ebss",
+    "_ebss",
+    "__euninit",
+    "__sheap",
+    "_sheap",
+    "_eheap",
+];
+// sections that hold static data
+const STATIC_SECTIONS: &[&str] = &[".data", ".bss", ".uninit"];
+
+/// The main stack
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MainStack {
+    /// The initial stack pointer
+    pub start: u64,
+    /// The lowest address the stack can grow into
+    pub end: u64,
+    /// Worst-case stack usage of the whole program; see `usage`
+    pub max: Option<Max>,
+    /// The exception frame the hardware pushes onto the stack each time a handler preempts the
+    /// code that runs on it; see `Options::exception_frame`
+    pub frame: u64,
+}
+
+impl MainStack {
+    /// Size in bytes
+    pub fn size(&self) -> u64 {
+        self.start - self.end
+    }
+
+    /// How much of the main stack is left when `max` bytes of it are used
+    pub fn free(&self, max: Max) -> Free {
+        Free::new(max, self.size())
+    }
+}
+
+/// Finds the bounds of the main stack in the ELF file
+///
+/// `start` and `end` name the symbols that hold the bounds (mangled or demangled); otherwise the
+/// symbols of the linker scripts of the `*-rt` crates are used. The initial stack pointer can also
+/// come from the vector table
+pub fn bounds(
+    elf: &ElfFile,
+    start: Option<&str>,
+    end: Option<&str>,
+) -> Result<Option<(u64, u64)>, failure::Error> {
+    let symbols = symbols(elf);
+    let symbol = |name: &str| {
+        tasks::find(&symbols, name)
+            .map(|(_, value)| value)
+            .ok_or_else(|| {
+                failure::format_err!("there's no symbol named `{}` in the ELF file", name)
+            })
+    };
+
+    let start = match start {
+        Some(name) => symbol(name)?,
+        None => match symbols.get(START).cloned().or_else(|| initial_sp(elf)) {
+            Some(start) => start,
+            None => return Ok(None),
+        },
+    };
+
+    let end = match end {
+        Some(name) => symbol(name)?,
+        None => {
+            let static_end = STATIC_ENDS
+                .iter()
+                .filter_map(|name| symbols.get(name).cloned())
+                .chain(elf.section_iter().filter_map(|sect| {
+                    let name = sect.get_name(elf).ok()?;
+                    if STATIC_SECTIONS.contains(&name) {
+                        Some(sect.address() + sect.size())
+                    } else {
+                        None
+                    }
+                }))
+                .filter(|end| *end <= start)
+                .max();
+
+            let reserved = symbols
+                .get(END)
+                .cloned()
+                .or_else(|| symbols.get(SIZE).map(|size| start.saturating_sub(*size)));
+
+            match (static_end, reserved) {
+                (Some(lhs), Some(rhs)) => lhs.max(rhs),
+                (Some(end), None) | (None, Some(end)) => end,
+                (None, None) => return Ok(None),
+            }
+        }
+    };
+
+    if end > start {
+        failure::bail!(
+            "the main stack ends ({:#010x}) above its start ({:#010x})",
+            end,
+            start
+        );
+    }
+
+    Ok(Some((start, end)))
+}
+
+/// The functions that run on the main stack and that no function calls: the entry point of the
+/// program and the handlers
+pub fn roots(g: &Graph<Node, ()>) -> Vec<NodeIndex> {
+    g.node_indices()
+        .filter(|node| {
+            let node_ = &g[*node];
+            g.neighbors_directed(*node, Direction::Incoming)
+                .next()
+                .is_none()
+                && !node_.dashed
+                && node_.name != "?"
+                && !matches!(node_.entry, Some(Entry::Task(_)))
+        })
+        .collect()
+}
+
+/// Worst-case usage of the main stack: the worst of the `roots` that aren't handlers plus every
+/// handler, as any of them may preempt the others, and the `frame` the hardware pushes each time a
+/// handler preempts the code that runs on the main stack
+pub fn usage(g: &Graph<Node, ()>, roots: &[NodeIndex], frame: u64) -> Option<Max> {
+    let (handlers, others) = roots
+        .iter()
+        .cloned()
+        .partition::<Vec<_>, _>(|root| g[*root].entry == Some(Entry::Handler));
+
+    let thread = crate::max_of(others.iter().filter_map(|root| g[*root].max));
+    handlers
+        .iter()
+        .filter_map(|root| g[*root].max)
+        .map(|max| max + Local::Exact(frame))
+        .fold(thread, |sum, max| {
+            Some(sum.map(|sum| sum + max).unwrap_or(max))
+        })
+}
+
+// the value of every symbol, including the absolute ones, except functions
+fn symbols<'a>(elf: &ElfFile<'a>) -> HashMap<&'a str, u64> {
+    let mut symbols = HashMap::new();
+    if let Some(Ok(data)) = elf
+        .find_section_by_name(".symtab")
+        .map(|sect| sect.get_data(elf))
+    {
+        let mut insert = |name: Result<&'a str, _>, ty: Result<Type, _>, value| {
+            if let (Ok(name), Ok(ty)) = (name, ty) {
+                if ty != Type::Func {
+                    symbols.insert(name, value);
+                }
+            }
+        };
+
+        match data {
+            SectionData::SymbolTable32(entries) => {
+                for entry in entries {
+                    insert(entry.get_name(elf), entry.get_type(), entry.value());
+                }
+            }
+            SectionData::SymbolTable64(entries) => {
+                for entry in entries {
+                    insert(entry.get_name(elf), entry.get_type(), entry.value());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    symbols
+}
+
+// the first entry of the ARM Cortex-M vector table
+fn initial_sp(elf: &ElfFile) -> Option<u64> {
+    let data = elf.find_section_by_name(".vector_table")?.raw_data(elf);
+    match data {
+        [a, b, c, d, ..] => Some(u64::from(u32::from_le_bytes([*a, *b, *c, *d]))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::graph::DiGraph;
+
+    use super::MainStack;
+    use crate::{Entry, Free, Max, Node};
+
+    #[test]
+    fn usage() {
+        // Reset -> main; SysTick and UART0 are handlers
+        let mut g = DiGraph::new();
+        let reset = g.add_node(Node("Reset", Some(8), false));
+        let main = g.add_node(Node("main", Some(64), false));
+        let systick = g.add_node(Node("SysTick", Some(16), false));
+        let uart0 = g.add_node(Node("UART0", Some(32), false));
+        g.add_edge(reset, main, ());
+        g[systick].entry = Some(Entry::Handler);
+        g[uart0].entry = Some(Entry::Handler);
+        crate::max_stack(&mut g, &[], &mut []);
+
+        let roots = super::roots(&g);
+        assert_eq!(roots, vec![reset, systick, uart0]);
+
+        // each handler also pushes a 32-byte exception frame
+        let max = super::usage(&g, &roots, 32).unwrap();
+        assert_eq!(max, Max::Exact(184));
+
+        let stack = MainStack {
+            start: 0x2000_1000,
+            end: 0x2000_0f40,
+            max: Some(max),
+            frame: 32,
+        };
+        assert_eq!(stack.size(), 192);
+        assert_eq!(stack.free(max), Free::Exact(8));
+    }
+}
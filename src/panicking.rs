@@ -0,0 +1,99 @@
+//! Panicking paths
+//!
+//! The panic handler and the formatting machinery it pulls in often dominate the worst-case stack
+//! usage of a program, yet panicking is a terminal path that's usually budgeted separately. These
+//! paths are identified by the functions they go through: `core::panicking::*`
+//! (`std::panicking::*`) and the `#[panic_handler]`, which is exported as `rust_begin_unwind`.
+
+use std::collections::HashSet;
+
+use petgraph::{
+    graph::NodeIndex,
+    visit::{Dfs, EdgeRef},
+    Direction, Graph,
+};
+
+use crate::Node;
+
+/// Whether calling `function` means that the program is panicking
+///
+/// `function` is a demangled name without hash (`{:#}`)
+pub fn is_entry(function: &str) -> bool {
+    function.starts_with("core::panicking::")
+        || function.starts_with("std::panicking::")
+        || function == "rust_begin_unwind"
+        || function == "__rustc::rust_begin_unwind"
+}
+
+/// The nodes of the call graph through which the panicking paths start
+pub fn entries(g: &Graph<Node, ()>) -> HashSet<NodeIndex> {
+    g.node_indices()
+        .filter(|node| is_entry(&format!("{:#}", rustc_demangle::demangle(&g[*node].name))))
+        .collect()
+}
+
+/// The nodes that are only reached by going through one of the `entries`
+pub fn only(g: &Graph<Node, ()>, entries: &HashSet<NodeIndex>) -> HashSet<NodeIndex> {
+    // what the roots of the call graph reach without panicking
+    let mut reached = HashSet::new();
+    for root in g.node_indices().filter(|node| {
+        !entries.contains(node)
+            && g.neighbors_directed(*node, Direction::Incoming)
+                .next()
+                .is_none()
+    }) {
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            if reached.insert(node) {
+                stack.extend(
+                    g.edges(node)
+                        .map(|edge| edge.target())
+                        .filter(|callee| !entries.contains(callee)),
+                );
+            }
+        }
+    }
+
+    let mut only = HashSet::new();
+    for entry in entries {
+        let mut dfs = Dfs::new(g, *entry);
+        while let Some(node) = dfs.next(g) {
+            if !reached.contains(&node) {
+                only.insert(node);
+            }
+        }
+    }
+    only
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::graph::DiGraph;
+
+    use crate::Node;
+
+    #[test]
+    fn only() {
+        // main -> foo -> core::panicking::panic -> rust_begin_unwind -> fmt
+        //         foo -> fmt
+        let mut g = DiGraph::new();
+        let main = g.add_node(Node("main", Some(8), false));
+        let foo = g.add_node(Node("foo", Some(8), false));
+        let panic = g.add_node(Node("core::panicking::panic", Some(8), false));
+        let handler = g.add_node(Node("rust_begin_unwind", Some(8), false));
+        let fmt = g.add_node(Node("fmt", Some(8), false));
+        g.add_edge(main, foo, ());
+        g.add_edge(foo, panic, ());
+        g.add_edge(panic, handler, ());
+        g.add_edge(handler, fmt, ());
+        g.add_edge(foo, fmt, ());
+
+        let entries = super::entries(&g);
+        assert_eq!(entries.len(), 2);
+        assert!(entries.contains(&panic) && entries.contains(&handler));
+
+        let only = super::only(&g, &entries);
+        assert_eq!(only.len(), 2);
+        assert!(!only.contains(&fmt));
+    }
+}
@@ -0,0 +1,746 @@
+//! Questions about the call graph
+//!
+//! `cargo call-stack query` answers a question about the call graph, e.g. who calls a function or
+//! how a function gets called from `main`, instead of printing the whole graph. The answer is
+//! either plain text or the subgraph that contains only the relevant nodes.
+
+use std::{
+    cmp,
+    collections::{hash_map::Entry, BTreeMap, HashMap, HashSet, VecDeque},
+    io::{self, Write},
+};
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use petgraph::{
+    graph::{DiGraph, NodeIndex},
+    visit::{Dfs, Reversed},
+    Direction, Graph,
+};
+
+use crate::{
+    memory, simplify,
+    symbol::{self, Symbol},
+    Cycle, Local, Max, Node,
+};
+
+/// A question about the call graph
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query<'a> {
+    /// Functions from which `function` can be reached
+    CallersOf(&'a str),
+
+    /// Functions that can be reached from `function`
+    CalleesOf(&'a str),
+
+    /// The `k` shortest call paths that go from `from` to `to`
+    Paths {
+        from: &'a str,
+        to: &'a str,
+        k: usize,
+    },
+
+    /// The shortest call path from `root`, or from any function that has no callers, to `function`
+    WhyReachable {
+        function: &'a str,
+        root: Option<&'a str>,
+    },
+
+    /// The instances of each generic function, or only of `function`, worst first
+    Generics(Option<&'a str>),
+
+    /// The worst-case stack usage of each task against the size of its stack, that of each poll
+    /// of the async tasks and that of the handlers
+    Tasks,
+
+    /// The headroom of the main stack for each function that runs on it and that no function
+    /// calls, and for the whole program
+    Headroom,
+}
+
+/// The number of paths `paths` lists when `-k` is not given
+pub const PATHS: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Dot,
+}
+
+/// The `query` subcommand
+pub fn app() -> App<'static, 'static> {
+    let function = || {
+        Arg::with_name("FUNCTION")
+            .required(true)
+            .help("Name of the function, with or without hash")
+    };
+
+    SubCommand::with_name("query")
+        .about("Answer a question about the call graph instead of printing all of it")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["text", "dot"])
+                .default_value("text")
+                .help(
+                    "Print the answer as text or as the subgraph that contains the relevant nodes",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("callers-of")
+                .about("Functions from which FUNCTION can be reached")
+                .arg(function()),
+        )
+        .subcommand(
+            SubCommand::with_name("callees-of")
+                .about("Functions that can be reached from FUNCTION")
+                .arg(function()),
+        )
+        .subcommand(
+            SubCommand::with_name("paths")
+                .about(
+                    "The shortest call paths from FROM to TO and the sum of the local stack usage \
+                     of the functions along each of them",
+                )
+                .arg(Arg::with_name("FROM").required(true))
+                .arg(Arg::with_name("TO").required(true))
+                .arg(
+                    Arg::with_name("k")
+                        .short("k")
+                        .takes_value(true)
+                        .value_name("N")
+                        .help("Only the N shortest paths [default: 10]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("why-reachable")
+                .about("The shortest call path that reaches FUNCTION")
+                .arg(function())
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .takes_value(true)
+                        .value_name("ROOT")
+                        .help("Start from ROOT instead of the functions that have no callers"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("generics")
+                .about("Instances of each generic function and their stack usage, worst first")
+                .arg(Arg::with_name("FUNCTION").help(
+                    "Only the instances of this generic function, e.g. `<heapless::vec::Vec>::push`",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("tasks")
+                .about("Stack usage of each task against the size of its stack, of each poll of the async tasks and of the handlers"),
+        )
+        .subcommand(
+            SubCommand::with_name("headroom")
+                .about("Space left on the main stack by each root of the call graph and by the whole program"),
+        )
+}
+
+impl<'a> Query<'a> {
+    /// Parses the arguments of the `query` subcommand
+    pub fn from_matches(matches: &'a ArgMatches<'a>) -> Result<(Self, Format), failure::Error> {
+        let format = match matches.value_of("format") {
+            Some("dot") => Format::Dot,
+            _ => Format::Text,
+        };
+
+        let query = match matches.subcommand() {
+            ("callers-of", Some(m)) => Query::CallersOf(m.value_of("FUNCTION").unwrap()),
+            ("callees-of", Some(m)) => Query::CalleesOf(m.value_of("FUNCTION").unwrap()),
+            ("paths", Some(m)) => Query::Paths {
+                from: m.value_of("FROM").unwrap(),
+                to: m.value_of("TO").unwrap(),
+                k: m.value_of("k")
+                    .map(|k| {
+                        k.parse()
+                            .map_err(|_| failure::format_err!("invalid number of paths `{}`", k))
+                    })
+                    .transpose()?
+                    .unwrap_or(PATHS),
+            },
+            ("why-reachable", Some(m)) => Query::WhyReachable {
+                function: m.value_of("FUNCTION").unwrap(),
+                root: m.value_of("from"),
+            },
+            ("generics", Some(m)) => Query::Generics(m.value_of("FUNCTION")),
+            ("tasks", Some(_)) => Query::Tasks,
+            ("headroom", Some(_)) => Query::Headroom,
+            _ => unreachable!(),
+        };
+
+        Ok((query, format))
+    }
+}
+
+/// Answers `query` and prints the answer to stdout
+pub fn run(
+    g: &Graph<Node, ()>,
+    cycles: &[Cycle],
+    options: &crate::Dot,
+    query: &Query,
+    format: Format,
+) -> Result<(), failure::Error> {
+    match *query {
+        Query::Generics(function) => return generics(g, cycles, options, function, format),
+        Query::Tasks => return tasks(g, cycles, options, format),
+        Query::Headroom => return headroom(g, cycles, options, format),
+        _ => {}
+    }
+
+    let (nodes, paths) = match *query {
+        Query::CallersOf(function) => {
+            let function = find(g, function)?;
+            (reachable(Reversed(g), function), None)
+        }
+
+        Query::CalleesOf(function) => {
+            let function = find(g, function)?;
+            (reachable(g, function), None)
+        }
+
+        Query::Paths { from, to, k } => {
+            let paths = paths(g, find(g, from)?, find(g, to)?, k);
+            (paths.iter().flatten().cloned().collect(), Some(paths))
+        }
+
+        Query::WhyReachable { function, root } => {
+            let function = find(g, function)?;
+            let roots = if let Some(root) = root {
+                vec![find(g, root)?]
+            } else {
+                g.node_indices()
+                    .filter(|node| {
+                        g.neighbors_directed(*node, Direction::Incoming)
+                            .next()
+                            .is_none()
+                    })
+                    .collect()
+            };
+
+            let paths = shortest(g, &roots, function)
+                .into_iter()
+                .collect::<Vec<_>>();
+            (paths.iter().flatten().cloned().collect(), Some(paths))
+        }
+
+        Query::Generics(_) | Query::Tasks | Query::Headroom => unreachable!(),
+    };
+
+    if format == Format::Dot {
+        let (g, cycles) = subgraph(g, cycles, &nodes);
+        crate::dot(g, &cycles, options)?;
+        return Ok(());
+    }
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+
+    if let Some(paths) = paths {
+        if paths.is_empty() {
+            writeln!(stdout, "no paths found")?;
+        }
+
+        // NOTE this is not the stack usage of the path: the functions of the path also call other
+        // functions, including the ones in the same cycle as them
+        for (i, path) in paths.iter().enumerate() {
+            let sum = path
+                .iter()
+                .fold(Max::Exact(0), |sum, node| sum + g[*node].local);
+            writeln!(stdout, "path {} (sum of locals {}):", i + 1, sum)?;
+
+            let mut sum = Max::Exact(0);
+            for node in path {
+                sum = sum + g[*node].local;
+                writeln!(
+                    stdout,
+                    "    {} (local = {}, sum of locals {})",
+                    name(&g[*node]),
+                    g[*node].local,
+                    sum
+                )?;
+            }
+        }
+    } else {
+        // sorted by name to get a stable output
+        let nodes = nodes
+            .iter()
+            .map(|node| (name(&g[*node]), *node))
+            .collect::<BTreeMap<_, _>>();
+
+        for (name, node) in nodes {
+            let node = &g[node];
+            if let Some(max) = node.max {
+                write!(stdout, "{} (max {}", name, max)?;
+                if let Some(max_no_panic) = node.max_no_panic.filter(|m| *m != max && !node.panic) {
+                    write!(stdout, ", max w/o panic {}", max_no_panic)?;
+                }
+                writeln!(stdout, ", local = {})", node.local)?;
+            } else {
+                writeln!(stdout, "{} (local = {})", name, node.local)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// lists the instances of each generic function; the worst instance, marked with `*`, goes first
+fn generics(
+    g: &Graph<Node, ()>,
+    cycles: &[Cycle],
+    options: &crate::Dot,
+    function: Option<&str>,
+    format: Format,
+) -> Result<(), failure::Error> {
+    let function = function.map(|function| symbol::generic(&Symbol::new(function).path).0);
+    let mut groups = simplify::instances(g)
+        .into_iter()
+        .filter(|(generic, _)| function.as_ref().map(|f| f == generic).unwrap_or(true))
+        .collect::<Vec<_>>();
+
+    if let Some(function) = function {
+        if groups.is_empty() {
+            failure::bail!(
+                "there are no instances of the generic function `{}`",
+                function
+            );
+        }
+    }
+
+    if format == Format::Dot {
+        let nodes = groups
+            .iter()
+            .flat_map(|(_, instances)| instances.iter().cloned())
+            .collect();
+        let (g, cycles) = subgraph(g, cycles, &nodes);
+        crate::dot(g, &cycles, options)?;
+        return Ok(());
+    }
+
+    for (_, instances) in &mut groups {
+        instances.sort_by_key(|node| cmp::Reverse(bytes(g[*node].max)));
+    }
+    groups.sort_by_key(|(_, instances)| cmp::Reverse(bytes(g[instances[0]].max)));
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for (generic, instances) in groups {
+        writeln!(stdout, "{} ({} instances)", generic, instances.len())?;
+
+        for (i, node) in instances.iter().enumerate() {
+            let node = &g[*node];
+            let marker = if i == 0 { '*' } else { ' ' };
+
+            let path = Symbol::new(&node.name).path;
+            let arguments = symbol::generic(&path).1.join(" ");
+            write!(stdout, "  {} {}", marker, arguments)?;
+
+            if let Some(max) = node.max {
+                writeln!(stdout, " (max {}, local = {})", max, node.local)?;
+            } else {
+                writeln!(stdout, " (local = {})", node.local)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// lists the tasks, the async tasks and the handlers with their worst-case stack usage and the stack
+// they run on
+fn tasks(
+    g: &Graph<Node, ()>,
+    cycles: &[Cycle],
+    options: &crate::Dot,
+    format: Format,
+) -> Result<(), failure::Error> {
+    // sorted by kind and name to get a stable output
+    let entries = g
+        .node_indices()
+        .filter_map(|node| {
+            let (kind, name) = match &g[node].entry {
+                Some(crate::Entry::Task(_)) => (0, name(&g[node])),
+                // named after the `async fn`, not after the executor's trampoline
+                Some(crate::Entry::Future(task)) => {
+                    (1, task.clone().unwrap_or_else(|| name(&g[node])))
+                }
+                Some(crate::Entry::Handler) => (2, name(&g[node])),
+                None => return None,
+            };
+            Some(((kind, name), node))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    if entries.is_empty() {
+        failure::bail!(
+            "there are no tasks; declare them with `--task` or `--spawn`, or use an async executor"
+        );
+    }
+
+    if format == Format::Dot {
+        let nodes = entries
+            .values()
+            .flat_map(|entry| reachable(g, *entry))
+            .collect();
+        let (g, cycles) = subgraph(g, cycles, &nodes);
+        crate::dot(g, &cycles, options)?;
+        return Ok(());
+    }
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for ((_, name), node) in entries {
+        let node = &g[node];
+        let max = node
+            .max
+            .map(|max| max.to_string())
+            .unwrap_or_else(|| "?".to_owned());
+
+        match &node.entry {
+            Some(crate::Entry::Task(Some(stack))) => {
+                write!(
+                    stdout,
+                    "task {} (max {}, stack {} = {}",
+                    name, max, stack.symbol, stack.size
+                )?;
+                if let Some(max) = node.max {
+                    write!(stdout, ", {}", stack.free(max))?;
+                }
+                writeln!(stdout, ")")?;
+            }
+
+            Some(crate::Entry::Task(None)) => {
+                writeln!(stdout, "task {} (max {}, stack = ?)", name, max)?
+            }
+
+            Some(crate::Entry::Future(_)) => {
+                writeln!(stdout, "async task {} (max {} per poll)", name, max)?
+            }
+
+            _ => writeln!(stdout, "handler {} (max {}, main stack)", name, max)?,
+        }
+    }
+
+    Ok(())
+}
+
+// lists the functions that run on the main stack and that no function calls, then the handlers,
+// with the space they leave on the main stack; the last line is the whole program
+fn headroom(
+    g: &Graph<Node, ()>,
+    cycles: &[Cycle],
+    options: &crate::Dot,
+    format: Format,
+) -> Result<(), failure::Error> {
+    let stack = options.main_stack.ok_or_else(|| {
+        failure::err_msg(
+            "the bounds of the main stack are unknown; name them with `--stack-start` and \
+             `--stack-end`",
+        )
+    })?;
+    let roots = memory::roots(g);
+
+    if format == Format::Dot {
+        let nodes = roots.iter().flat_map(|root| reachable(g, *root)).collect();
+        let (g, cycles) = subgraph(g, cycles, &nodes);
+        crate::dot(g, &cycles, options)?;
+        return Ok(());
+    }
+
+    // sorted by kind and name to get a stable output
+    let roots = roots
+        .iter()
+        .map(|root| {
+            let is_handler = g[*root].entry == Some(crate::Entry::Handler);
+            ((is_handler, name(&g[*root])), *root)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    writeln!(
+        stdout,
+        "main stack = {} ({:#010x}..{:#010x})",
+        stack.size(),
+        stack.end,
+        stack.start
+    )?;
+
+    for ((is_handler, name), root) in roots {
+        let kind = if is_handler { "handler " } else { "" };
+        if let Some(max) = g[root].max {
+            // a handler also pushes an exception frame onto the stack it preempts
+            let used = if is_handler {
+                max + Local::Exact(stack.frame)
+            } else {
+                max
+            };
+            writeln!(
+                stdout,
+                "{}{} (max {}, {})",
+                kind,
+                name,
+                max,
+                stack.free(used)
+            )?;
+        } else {
+            writeln!(stdout, "{}{} (max ?)", kind, name)?;
+        }
+    }
+
+    if let Some(max) = stack.max {
+        writeln!(stdout, "total (max {}, {})", max, stack.free(max))?;
+    }
+
+    Ok(())
+}
+
+// the number of bytes in `max`, for sorting
+fn bytes(max: Option<Max>) -> u64 {
+    match max {
+        Some(Max::Exact(n)) | Some(Max::LowerBound(n)) => n,
+        None => 0,
+    }
+}
+
+/// The node named `function`; mangled, demangled and demangled without hash names are accepted
+pub fn find(g: &Graph<Node, ()>, function: &str) -> Result<NodeIndex, failure::Error> {
+    let hits = g
+        .node_indices()
+        .filter(|node| Symbol::new(&g[*node].name).is(function))
+        .collect::<Vec<_>>();
+
+    match hits[..] {
+        [node] => Ok(node),
+        [] => Err(failure::format_err!(
+            "there's no function named `{}` in the call graph",
+            function
+        )),
+        _ => Err(failure::format_err!(
+            "multiple functions match `{}`: {}; use the name with hash or crate disambiguators",
+            function,
+            hits.iter()
+                .map(|node| format!("`{}`", rustc_demangle::demangle(&g[*node].name)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+fn name(node: &Node) -> String {
+    rustc_demangle::demangle(&node.name).to_string()
+}
+
+// the nodes that can be reached from `start`, including `start`
+fn reachable<G>(g: G, start: NodeIndex) -> HashSet<NodeIndex>
+where
+    G: petgraph::visit::IntoNeighbors<NodeId = NodeIndex> + petgraph::visit::Visitable,
+{
+    let mut nodes = HashSet::new();
+    let mut dfs = Dfs::new(g, start);
+    while let Some(node) = dfs.next(g) {
+        nodes.insert(node);
+    }
+    nodes
+}
+
+// the `k` shortest paths from `from` to `to` that don't visit a node twice, shortest first. This is
+// Yen's algorithm: each path is found by deviating from one of the paths found before it
+fn paths(g: &Graph<Node, ()>, from: NodeIndex, to: NodeIndex, k: usize) -> Vec<Vec<NodeIndex>> {
+    let mut paths = match search(g, &[from], to, |_, _| true) {
+        Some(path) if k != 0 => vec![path],
+        _ => return vec![],
+    };
+
+    // candidates for the next path
+    let mut candidates = Vec::<Vec<NodeIndex>>::new();
+    while paths.len() < k {
+        let last = paths.last().expect("UNREACHABLE");
+        for i in 0..last.len() - 1 {
+            // the next path follows `last` up to `spur` and then deviates from it
+            let spur = last[i];
+            let root = &last[..=i];
+
+            // the edges that leave `spur` on the paths that have the same root
+            let taken = paths
+                .iter()
+                .filter(|path| path.len() > i + 1 && path[..=i] == *root)
+                .map(|path| path[i + 1])
+                .collect::<HashSet<_>>();
+
+            let deviation = search(g, &[spur], to, |node, next| {
+                !(root.contains(&next) || (node == spur && taken.contains(&next)))
+            });
+
+            if let Some(deviation) = deviation {
+                let mut path = root.to_vec();
+                path.extend(&deviation[1..]);
+                if !candidates.contains(&path) && !paths.contains(&path) {
+                    candidates.push(path);
+                }
+            }
+        }
+
+        // the shortest candidate; the first one found wins ties
+        let next = candidates
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, path)| path.len())
+            .map(|(i, _)| i);
+        if let Some(next) = next {
+            paths.push(candidates.remove(next));
+        } else {
+            break;
+        }
+    }
+
+    paths
+}
+
+// the shortest path from any of the `roots` to `to`
+fn shortest(g: &Graph<Node, ()>, roots: &[NodeIndex], to: NodeIndex) -> Option<Vec<NodeIndex>> {
+    search(g, roots, to, |_, _| true)
+}
+
+// breadth-first search of the shortest path from any of the `roots` to `to` that only follows the
+// edges `follow` accepts
+fn search(
+    g: &Graph<Node, ()>,
+    roots: &[NodeIndex],
+    to: NodeIndex,
+    follow: impl Fn(NodeIndex, NodeIndex) -> bool,
+) -> Option<Vec<NodeIndex>> {
+    // maps each visited node to the node it was reached from
+    let mut parents = HashMap::new();
+    let mut queue = VecDeque::new();
+    for root in roots {
+        parents.insert(*root, None);
+        queue.push_back(*root);
+    }
+
+    while let Some(node) = queue.pop_front() {
+        if node == to {
+            let mut path = vec![node];
+            while let Some(Some(parent)) = parents.get(path.last().expect("UNREACHABLE")) {
+                path.push(*parent);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for next in g.neighbors(node) {
+            if !follow(node, next) {
+                continue;
+            }
+
+            if let Entry::Vacant(entry) = parents.entry(next) {
+                entry.insert(Some(node));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// The subgraph of `g` that contains `nodes` and the edges between them
+pub fn subgraph<'a>(
+    g: &Graph<Node<'a>, ()>,
+    cycles: &[Cycle],
+    nodes: &HashSet<NodeIndex>,
+) -> (Graph<Node<'a>, ()>, Vec<Cycle>) {
+    let mut g2 = DiGraph::new();
+
+    // maps `g`'s `NodeIndex`-es to `g2`'s `NodeIndex`-es
+    let mut one2two = BTreeMap::new();
+    for node in g.node_indices().filter(|node| nodes.contains(node)) {
+        one2two.insert(node, g2.add_node(g[node].clone()));
+    }
+
+    for edge in g.raw_edges() {
+        if let (Some(source), Some(target)) =
+            (one2two.get(&edge.source()), one2two.get(&edge.target()))
+        {
+            g2.add_edge(*source, *target, ());
+        }
+    }
+
+    let cycles = cycles
+        .iter()
+        .filter_map(|(cycle, depth)| {
+            let cycle = cycle
+                .iter()
+                .filter_map(|node| one2two.get(node).cloned())
+                .collect::<Vec<_>>();
+
+            if cycle.is_empty() {
+                None
+            } else {
+                Some((cycle, *depth))
+            }
+        })
+        .collect();
+
+    (g2, cycles)
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::graph::DiGraph;
+
+    use crate::{Max, Node};
+
+    #[test]
+    fn paths() {
+        // main -> a -> c, main -> b -> c, main -> c, c -> a
+        let mut g = DiGraph::new();
+        let main = g.add_node(Node("main", Some(8), false));
+        let a = g.add_node(Node("a", Some(16), false));
+        let b = g.add_node(Node("b", None, false));
+        let c = g.add_node(Node("c", Some(32), false));
+        let d = g.add_node(Node("d", Some(0), false));
+        g.add_edge(main, a, ());
+        g.add_edge(main, b, ());
+        g.add_edge(main, c, ());
+        g.add_edge(a, c, ());
+        g.add_edge(b, c, ());
+        g.add_edge(c, a, ());
+        g.add_edge(main, d, ());
+
+        let paths = super::paths(&g, main, c, super::PATHS);
+        assert_eq!(
+            paths,
+            vec![vec![main, c], vec![main, b, c], vec![main, a, c]]
+        );
+        assert_eq!(
+            super::paths(&g, main, c, 2),
+            vec![vec![main, c], vec![main, b, c]]
+        );
+        assert!(super::paths(&g, d, c, super::PATHS).is_empty());
+        // `c -> a -> c` visits `c` twice
+        assert_eq!(super::paths(&g, c, a, super::PATHS), vec![vec![c, a]]);
+
+        assert_eq!(super::shortest(&g, &[main], a), Some(vec![main, a]));
+        assert_eq!(super::shortest(&g, &[b], a), Some(vec![b, c, a]));
+        assert_eq!(super::shortest(&g, &[d], a), None);
+
+        let callers = super::reachable(petgraph::visit::Reversed(&g), a);
+        assert_eq!(callers.len(), 4);
+        assert!(!callers.contains(&d));
+
+        assert_eq!(super::find(&g, "c").unwrap(), c);
+        assert!(super::find(&g, "e").is_err());
+
+        // `b` has unknown stack usage
+        let stack = paths[1]
+            .iter()
+            .fold(Max::Exact(0), |stack, node| stack + g[*node].local);
+        assert!(stack == Max::LowerBound(40));
+    }
+}
@@ -0,0 +1,81 @@
+//! User-supplied recursion bounds
+//!
+//! The stack usage of a cycle in the call graph is unbounded unless something limits how deep the
+//! recursion goes, e.g. a parser that gives up after nesting 8 expressions. Only the user knows
+//! these limits; a bound states how many times each function of a cycle can be on the stack at
+//! once.
+
+use core::{fmt, str::FromStr};
+
+use crate::erasure::glob;
+
+/// A recursion bound: `FUNCTION=DEPTH`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bound {
+    // a function of the cycle
+    function: String,
+    /// How many times each function of the cycle can be on the stack at once; `1` means that the
+    /// cycle is never taken
+    pub depth: u64,
+}
+
+impl Bound {
+    /// Whether this bound applies to a cycle that contains `function`
+    ///
+    /// `function` is a demangled name without hash (`{:#}`)
+    pub fn matches(&self, function: &str) -> bool {
+        glob(&self.function, function)
+    }
+}
+
+impl fmt::Display for Bound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.function, self.depth)
+    }
+}
+
+/// Parses a user-defined bound: `FUNCTION=DEPTH`
+///
+/// `FUNCTION` is a demangled function name (without hash) where `*` matches any sequence of
+/// characters, e.g. `app::parser::parse_expr=8`
+impl FromStr for Bound {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, failure::Error> {
+        let mut parts = s.rsplitn(2, '=').map(str::trim);
+        match (parts.next().map(str::parse), parts.next()) {
+            (Some(Ok(depth)), Some(function)) if depth != 0 && !function.is_empty() => Ok(Bound {
+                function: function.to_owned(),
+                depth,
+            }),
+
+            _ => Err(failure::format_err!(
+                "malformed recursion bound `{}`; expected `FUNCTION=DEPTH` with a non-zero DEPTH",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bound;
+
+    #[test]
+    fn from_str() {
+        let bound = "app::parser::parse_expr = 8".parse::<Bound>().unwrap();
+
+        assert_eq!(bound.depth, 8);
+        assert_eq!(bound.to_string(), "app::parser::parse_expr=8");
+        assert!(bound.matches("app::parser::parse_expr"));
+        assert!(!bound.matches("app::parser::parse_term"));
+
+        let bound = "app::parser::parse_*=3".parse::<Bound>().unwrap();
+        assert!(bound.matches("app::parser::parse_term"));
+
+        assert!("app::parser::parse_expr".parse::<Bound>().is_err());
+        assert!("app::parser::parse_expr=".parse::<Bound>().is_err());
+        assert!("app::parser::parse_expr=0".parse::<Bound>().is_err());
+        assert!("=8".parse::<Bound>().is_err());
+    }
+}
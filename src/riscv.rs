@@ -0,0 +1,950 @@
+use core::{cmp, fmt};
+use std::collections::HashSet;
+
+// Reference: The RISC-V Instruction Set Manual, Volume I: Unprivileged ISA (20191213)
+// - Chapter 2 (RV32I), Chapter 5 (RV64I) and Chapter 16 ("C" Standard Extension)
+
+/// Base integer instruction sets
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Arch {
+    /// RV32I plus the standard extensions
+    Rv32,
+    /// RV64I plus the standard extensions
+    Rv64,
+}
+
+const ZERO: u32 = 0;
+const RA: u32 = 1;
+const SP: u32 = 2;
+// alternate link register; used by the `-msave-restore` millicode routines
+const T0: u32 = 5;
+
+// `ra`, `t0`-`t6` and `a0`-`a7`; the callee may clobber them
+const CALLER_SAVED: [u32; 16] = [1, 5, 6, 7, 10, 11, 12, 13, 14, 15, 16, 17, 28, 29, 30, 31];
+
+/// Analyzes a subroutine and returns all the `JAL` and branch instructions in it, plus whether this
+/// function performs an indirect function call or not
+///
+/// Encodings that can't be decoded are reported as an `Error`; the caller decides whether that's
+/// fatal or not
+pub fn analyze(bytes: &[u8], address: u64, arch: Arch) -> Result<Analysis, Error> {
+    // we want to know if any of the instructions modifies the SP (stack pointer). We use this
+    // information to determine if the subroutine uses stack space or not. We want to detect the
+    // following instructions:
+    // - 1141            addi    sp, sp, -16
+    // - 7139            addi    sp, sp, -64
+    // - 40b10133        sub     sp, sp, a1
+    let mut modifies_sp = false;
+
+    // same as in the Thumb analysis: we only compute the stack usage of subroutines that contain
+    // no intra-branches. `sp` is the running offset of the SP from its value on entry; `stack` is
+    // the lowest offset seen so far, i.e. minus the peak stack usage
+    let mut sp = 0i64;
+    let mut stack = Some(0i64);
+
+    let mut jals = vec![];
+    let mut js = vec![];
+    let mut indirect = false;
+
+    // lightweight constant propagation used to resolve `auipc` + `jalr` pairs (`call` and `tail`
+    // when the linker didn't relax them) and `lui` + `addi` + `jalr` sequences. A register keeps
+    // its constant until an instruction writes to it or a call clobbers it. Values known on one
+    // path may not hold on the others so the constants are also forgotten after unconditional jumps
+    // and returns, and at the targets of intra-function branches
+    let mut unknown: [Option<u64>; 32] = [None; 32];
+    unknown[ZERO as usize] = Some(0);
+    let mut consts = unknown;
+    let mut jalrs = vec![];
+
+    let mask = match arch {
+        Arch::Rv32 => u64::from(u32::MAX),
+        Arch::Rv64 => u64::MAX,
+    };
+
+    let mut insns = vec![];
+    let mut offset = 0;
+    while offset + 2 <= bytes.len() {
+        let pc = address.wrapping_add(offset as u64) & mask;
+
+        let (op, rd, size) = match decode(&bytes[offset..], arch) {
+            Some(insn) => insn,
+            None => return Err(Error::unknown(&bytes[offset..], pc)),
+        };
+
+        insns.push((offset, op, rd));
+        offset += size;
+    }
+
+    let targets = insns
+        .iter()
+        .filter_map(|(offset, op, _)| match op {
+            Op::Jal(ZERO, imm) | Op::Branch(imm) => Some(*offset as i64 + i64::from(*imm)),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    for (offset, op, rd) in insns {
+        let pc = address.wrapping_add(offset as u64) & mask;
+
+        if targets.contains(&(offset as i64)) {
+            consts = unknown;
+        }
+
+        let known = consts;
+        match rd {
+            // `x0` is hardwired to zero
+            Some(ZERO) | None => {}
+            Some(rd) => consts[rd as usize] = None,
+        }
+
+        // the value of the register written by a constant-producing instruction, if known
+        let constant = match op {
+            Op::Jal(rd, imm) => {
+                let target = offset as i64 + i64::from(imm);
+
+                if rd == ZERO {
+                    if target >= 0 && (target as usize) < bytes.len() {
+                        // this is an `if` or `loop`; give up the stack usage analysis
+                        stack = None;
+                    }
+
+                    js.push(target);
+                } else {
+                    jals.push(target);
+                }
+                None
+            }
+
+            Op::Branch(imm) => {
+                let target = offset as i64 + i64::from(imm);
+
+                if target >= 0 && (target as usize) < bytes.len() {
+                    stack = None;
+                }
+
+                js.push(target);
+                None
+            }
+
+            Op::Jalr(rd, rs1, imm) => {
+                // `ret` (or the return of a millicode routine)
+                let ret = rd == ZERO && imm == 0 && (rs1 == RA || rs1 == T0);
+
+                if !ret {
+                    let target =
+                        known[rs1 as usize].map(|base| base.wrapping_add(imm as u64) & mask & !1);
+
+                    if let Some(target) = target {
+                        // `rd == ZERO` is a tail call
+                        jalrs.push(target);
+                    } else {
+                        indirect = true;
+                    }
+                }
+                None
+            }
+
+            Op::AddImm(rd, rs1, imm) => {
+                if rd == SP {
+                    modifies_sp = true;
+
+                    if rs1 == SP {
+                        sp += imm;
+                        if let Some(stack) = stack.as_mut() {
+                            *stack = cmp::min(*stack, sp);
+                        }
+                    }
+                }
+
+                known[rs1 as usize].map(|base| (rd, base.wrapping_add(imm as u64) & mask))
+            }
+
+            Op::Add(rd, rs1, rs2) | Op::Sub(rd, rs1, rs2) => {
+                let rhs = known[rs2 as usize];
+
+                if rd == SP {
+                    modifies_sp = true;
+
+                    // e.g. `lui t0, 1; sub sp, sp, t0` for frames larger than 2 KiB
+                    if rs1 == SP {
+                        let imm = rhs.map(|rhs| {
+                            if let Op::Sub(..) = op {
+                                rhs.wrapping_neg() & mask
+                            } else {
+                                rhs
+                            }
+                        });
+
+                        match imm.map(|imm| sign_extend(imm, mask)) {
+                            Some(imm) => {
+                                sp += imm;
+                                if let Some(stack) = stack.as_mut() {
+                                    *stack = cmp::min(*stack, sp);
+                                }
+                            }
+
+                            None => stack = None,
+                        }
+                    }
+                }
+
+                match (known[rs1 as usize], rhs) {
+                    (Some(lhs), Some(rhs)) => {
+                        let value = if let Op::Sub(..) = op {
+                            lhs.wrapping_sub(rhs)
+                        } else {
+                            lhs.wrapping_add(rhs)
+                        };
+
+                        Some((rd, value & mask))
+                    }
+                    _ => None,
+                }
+            }
+
+            Op::Lui(rd, imm) => Some((rd, imm & mask)),
+
+            Op::Auipc(rd, imm) => Some((rd, pc.wrapping_add(imm) & mask)),
+
+            Op::Other => {
+                if rd == Some(SP) {
+                    modifies_sp = true;
+                    stack = None;
+                }
+                None
+            }
+        };
+
+        if let Some((reg, value)) = constant {
+            if reg != ZERO {
+                consts[reg as usize] = Some(value);
+            }
+        }
+
+        match op {
+            Op::Jal(rd, _) | Op::Jalr(rd, ..) if rd != ZERO => {
+                for reg in &CALLER_SAVED {
+                    consts[*reg as usize] = None;
+                }
+            }
+
+            // the next instruction can only be reached through a branch
+            Op::Jal(..) | Op::Jalr(..) => consts = unknown,
+
+            _ => {}
+        }
+    }
+
+    Ok(Analysis {
+        jals,
+        js,
+        jalrs,
+        indirect,
+        modifies_sp,
+        stack: stack.map(|min| (-min) as u64),
+    })
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Analysis {
+    /// Offsets (relative to the start of the subroutine) of the targets of `JAL` instructions that
+    /// write a link register
+    pub jals: Vec<i64>,
+
+    /// Offsets (relative to the start of the subroutine) of the targets of `J` (`JAL x0`) and
+    /// conditional branch instructions
+    pub js: Vec<i64>,
+
+    /// Absolute addresses of the targets of `JALR` instructions that were resolved using constant
+    /// propagation (`auipc` + `jalr` or `lui` + `addi` + `jalr`)
+    pub jalrs: Vec<u64>,
+
+    /// Whether the subroutine performs an indirect function call we couldn't resolve
+    pub indirect: bool,
+
+    /// Whether the subroutine modifies the SP register
+    pub modifies_sp: bool,
+
+    /// Stack usage of the subroutine; `None` if the subroutine contains intra-branches
+    pub stack: Option<u64>,
+}
+
+// What an instruction does, as far as the analysis is concerned. Register operands are register
+// numbers; immediates are already sign extended
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    // `JAL rd, offset`
+    Jal(u32, i32),
+
+    // `BEQ`, `BNE`, etc.
+    Branch(i32),
+
+    // `JALR rd, offset(rs1)`
+    Jalr(u32, u32, i32),
+
+    // `ADDI rd, rs1, imm` and its compressed forms
+    AddImm(u32, u32, i64),
+
+    // `ADD rd, rs1, rs2`
+    Add(u32, u32, u32),
+
+    // `SUB rd, rs1, rs2`
+    Sub(u32, u32, u32),
+
+    // `LUI rd, imm`; `imm` is already shifted and sign extended
+    Lui(u32, u64),
+
+    // `AUIPC rd, imm`; `imm` is already shifted and sign extended
+    Auipc(u32, u64),
+
+    // everything else; the register written by the instruction, if any, is reported separately
+    Other,
+}
+
+// Decodes the instruction at the start of `bytes`. Returns the operation, the integer register it
+// writes to (if any) and its size in bytes
+fn decode(bytes: &[u8], arch: Arch) -> Option<(Op, Option<u32>, usize)> {
+    let lo = u32::from(halfword(bytes, 0)?);
+
+    if lo & 0b11 != 0b11 {
+        return decode16(lo, arch).map(|(op, rd)| (op, rd, 2));
+    }
+
+    // 48-bit and longer encodings are not used by any of the standard extensions
+    if lo & 0b11100 == 0b11100 {
+        return None;
+    }
+
+    let raw = lo | (u32::from(halfword(bytes, 2)?) << 16);
+    decode32(raw, arch).map(|(op, rd)| (op, rd, 4))
+}
+
+fn halfword(bytes: &[u8], offset: usize) -> Option<u16> {
+    let hw = bytes.get(offset..offset + 2)?;
+    Some(u16::from(hw[0]) | (u16::from(hw[1]) << 8))
+}
+
+// Table 24.1 RISC-V base opcode map
+fn decode32(raw: u32, arch: Arch) -> Option<(Op, Option<u32>)> {
+    let rd = bits(raw, 11, 7);
+    let rs1 = bits(raw, 19, 15);
+    let rs2 = bits(raw, 24, 20);
+    let funct3 = bits(raw, 14, 12);
+    let funct7 = bits(raw, 31, 25);
+    let rv64 = arch == Arch::Rv64;
+
+    // instructions that are not interesting to the analysis; `Some(rd)` if they write to an
+    // integer register
+    let other = |rd| Some((Op::Other, rd));
+
+    let op = match bits(raw, 6, 0) {
+        // LUI
+        0b0110111 => Op::Lui(rd, u_imm(raw)),
+
+        // AUIPC
+        0b0010111 => Op::Auipc(rd, u_imm(raw)),
+
+        // JAL
+        0b1101111 => Op::Jal(rd, j_imm(raw)),
+
+        // JALR
+        0b1100111 if funct3 == 0 => Op::Jalr(rd, rs1, i_imm(raw)),
+
+        // BRANCH
+        0b1100011 if funct3 != 0b010 && funct3 != 0b011 => Op::Branch(b_imm(raw)),
+
+        // LOAD: LB, LH, LW, LBU, LHU and LD, LWU (RV64)
+        0b0000011 => match funct3 {
+            0b000 | 0b001 | 0b010 | 0b100 | 0b101 => return other(Some(rd)),
+            0b011 | 0b110 if rv64 => return other(Some(rd)),
+            _ => return None,
+        },
+
+        // STORE: SB, SH, SW and SD (RV64)
+        0b0100011 => match funct3 {
+            0b000..=0b010 => return other(None),
+            0b011 if rv64 => return other(None),
+            _ => return None,
+        },
+
+        // OP-IMM
+        0b0010011 => match funct3 {
+            0b000 => Op::AddImm(rd, rs1, i64::from(i_imm(raw))),
+
+            // SLLI, SRLI and SRAI; the shift amount is 6 bits wide in RV64. Like LLVM, we accept
+            // the (reserved) 6-bit shift amounts in RV32 as well
+            0b001 | 0b101 => match (funct3, bits(raw, 31, 26)) {
+                (_, 0) | (0b101, 0b01_0000) => return other(Some(rd)),
+                _ => return None,
+            },
+
+            _ => return other(Some(rd)),
+        },
+
+        // OP-IMM-32 (RV64): ADDIW, SLLIW, SRLIW and SRAIW
+        0b0011011 if rv64 => match (funct3, funct7) {
+            (0b000, _) | (0b001, 0) | (0b101, 0) | (0b101, 0b010_0000) => return other(Some(rd)),
+            _ => return None,
+        },
+
+        // OP
+        0b0110011 => match (funct7, funct3) {
+            (0b000_0000, 0b000) => Op::Add(rd, rs1, rs2),
+            (0b010_0000, 0b000) => Op::Sub(rd, rs1, rs2),
+            // the rest of the base instructions and the "M" extension
+            (0b000_0000, _) | (0b010_0000, 0b101) | (0b000_0001, _) => return other(Some(rd)),
+            _ => return None,
+        },
+
+        // OP-32 (RV64): ADDW, SUBW, SLLW, SRLW, SRAW and the "M" extension
+        0b0111011 if rv64 => match (funct7, funct3) {
+            (0b000_0000, 0b000) | (0b010_0000, 0b000) => return other(Some(rd)),
+            (0b000_0000, 0b001) | (0b000_0000, 0b101) | (0b010_0000, 0b101) => {
+                return other(Some(rd));
+            }
+            (0b000_0001, 0b000) | (0b000_0001, 0b100..=0b111) => return other(Some(rd)),
+            _ => return None,
+        },
+
+        // MISC-MEM
+        0b0001111 => match funct3 {
+            // FENCE and FENCE.TSO
+            0b000 if rd == 0 && rs1 == 0 && (bits(raw, 31, 28) == 0 || raw == 0x8330_000f) => {
+                return other(None);
+            }
+            // FENCE.I
+            0b001 if raw == 0x0000_100f => return other(None),
+            _ => return None,
+        },
+
+        // SYSTEM
+        0b1110011 => match funct3 {
+            // ECALL, EBREAK, SRET, MRET, WFI and SFENCE.VMA
+            0b000 => match raw {
+                0x0000_0073 | 0x0010_0073 | 0x1020_0073 | 0x3020_0073 | 0x1050_0073 => {
+                    return other(None);
+                }
+                _ if funct7 == 0b000_1001 && rd == 0 => return other(None),
+                _ => return None,
+            },
+            // the "Zicsr" extension
+            0b100 => return None,
+            _ => return other(Some(rd)),
+        },
+
+        // AMO ("A" extension)
+        0b0101111 if funct3 == 0b010 || rv64 && funct3 == 0b011 => match bits(raw, 31, 27) {
+            // LR
+            0b00010 if rs2 == 0 => return other(Some(rd)),
+            // SC, AMOSWAP, AMOADD, AMOXOR, AMOAND, AMOOR, AMOMIN, AMOMAX, AMOMINU and AMOMAXU
+            0b00011 | 0b00001 | 0b00000 | 0b00100 | 0b01100 | 0b01000 | 0b10000 | 0b10100
+            | 0b11000 | 0b11100 => return other(Some(rd)),
+            _ => return None,
+        },
+
+        // LOAD-FP and STORE-FP: FLW, FLD, FSW and FSD
+        0b0000111 | 0b0100111 if funct3 == 0b010 || funct3 == 0b011 => return other(None),
+
+        // MADD, MSUB, NMSUB and NMADD
+        0b1000011 | 0b1000111 | 0b1001011 | 0b1001111
+            if bits(raw, 26, 25) < 0b10 && rounding_mode(funct3) =>
+        {
+            return other(None);
+        }
+
+        // OP-FP
+        0b1010011 => return op_fp(raw, rv64),
+
+        _ => return None,
+    };
+
+    let rd = match op {
+        Op::Branch(..) => None,
+        _ => Some(rd),
+    };
+
+    Some((op, rd))
+}
+
+// the single- and double-precision instructions of the OP-FP major opcode ("F" and "D"
+// extensions). Only the comparisons, the conversions to integer and the moves to integer
+// registers write to an integer register
+fn op_fp(raw: u32, rv64: bool) -> Option<(Op, Option<u32>)> {
+    let rd = bits(raw, 11, 7);
+    let rs2 = bits(raw, 24, 20);
+    let rm = bits(raw, 14, 12);
+
+    // the format (single / double precision) is encoded in the lowest bit of `funct7`
+    if bits(raw, 26, 25) >= 0b10 {
+        return None;
+    }
+
+    // integer conversions: W and WU; plus L and LU in RV64
+    let int_format = rs2 < 0b10 || rv64 && rs2 < 0b100;
+    let double = bits(raw, 25, 25) == 1;
+
+    let writes = match bits(raw, 31, 27) {
+        // FADD, FSUB, FMUL and FDIV
+        0b00000..=0b00011 if rounding_mode(rm) => None,
+        // FSQRT
+        0b01011 if rs2 == 0 && rounding_mode(rm) => None,
+        // FSGNJ, FSGNJN and FSGNJX
+        0b00100 if rm < 0b011 => None,
+        // FMIN and FMAX
+        0b00101 if rm < 0b010 => None,
+        // FCVT.S.D and FCVT.D.S
+        0b01000 if rs2 == !bits(raw, 25, 25) & 1 && rounding_mode(rm) => None,
+        // FEQ, FLT and FLE
+        0b10100 if rm < 0b011 => Some(rd),
+        // FCVT.{W,WU,L,LU}.{S,D}
+        0b11000 if int_format && rounding_mode(rm) => Some(rd),
+        // FCVT.{S,D}.{W,WU,L,LU}
+        0b11010 if int_format && rounding_mode(rm) => None,
+        // FMV.X.W, FMV.X.D (RV64) and FCLASS
+        0b11100 if rs2 == 0 && (rm == 0b001 || rm == 0b000 && (!double || rv64)) => Some(rd),
+        // FMV.W.X and FMV.D.X (RV64)
+        0b11110 if rs2 == 0 && rm == 0b000 && (!double || rv64) => None,
+        _ => return None,
+    };
+
+    Some((Op::Other, writes))
+}
+
+// whether `rm` is a valid rounding mode; 0b101 and 0b110 are reserved
+fn rounding_mode(rm: u32) -> bool {
+    rm != 0b101 && rm != 0b110
+}
+
+// 16.8 RVC instruction set listings
+fn decode16(raw: u32, arch: Arch) -> Option<(Op, Option<u32>)> {
+    let rv64 = arch == Arch::Rv64;
+    let funct3 = bits(raw, 15, 13);
+
+    // full register number of the `rd'` / `rs1'` field (bits 9:7) or the `rd'` / `rs2'` field
+    // (bits 4:2)
+    let rd_hi = 8 + bits(raw, 9, 7);
+    let rd_lo = 8 + bits(raw, 4, 2);
+    // full register number of the `rd` / `rs1` and `rs2` fields
+    let rd = bits(raw, 11, 7);
+    let rs2 = bits(raw, 6, 2);
+
+    // 6-bit signed immediate of the CI format
+    let ci_imm = sign_extend32((bits(raw, 12, 12) << 5) | bits(raw, 6, 2), 6);
+
+    let op = match (raw & 0b11, funct3) {
+        /* Quadrant 0 */
+        // C.UNIMP (defined illegal instruction)
+        (0b00, 0b000) if raw == 0 => Op::Other,
+
+        // C.ADDI4SPN
+        (0b00, 0b000) => {
+            let nzuimm = (bits(raw, 10, 7) << 6)
+                | (bits(raw, 12, 11) << 4)
+                | (bits(raw, 5, 5) << 3)
+                | (bits(raw, 6, 6) << 2);
+
+            if nzuimm == 0 {
+                return None;
+            }
+
+            Op::AddImm(rd_lo, SP, i64::from(nzuimm))
+        }
+
+        // C.FLD
+        (0b00, 0b001) => return Some((Op::Other, None)),
+
+        // C.LW and C.LD (RV64) / C.FLW (RV32)
+        (0b00, 0b010) => return Some((Op::Other, Some(rd_lo))),
+        (0b00, 0b011) => return Some((Op::Other, if rv64 { Some(rd_lo) } else { None })),
+
+        // C.FSD, C.SW and C.SD (RV64) / C.FSW (RV32)
+        (0b00, 0b101) | (0b00, 0b110) | (0b00, 0b111) => return Some((Op::Other, None)),
+
+        /* Quadrant 1 */
+        // C.NOP and C.ADDI
+        (0b01, 0b000) => Op::AddImm(rd, rd, i64::from(ci_imm)),
+
+        // C.JAL (RV32)
+        (0b01, 0b001) if !rv64 => Op::Jal(RA, cj_imm(raw)),
+
+        // C.ADDIW (RV64)
+        (0b01, 0b001) if rd != ZERO => return Some((Op::Other, Some(rd))),
+
+        // C.LI
+        (0b01, 0b010) => Op::AddImm(rd, ZERO, i64::from(ci_imm)),
+
+        // C.ADDI16SP
+        (0b01, 0b011) if rd == SP => {
+            let nzimm = sign_extend32(
+                (bits(raw, 12, 12) << 9)
+                    | (bits(raw, 4, 3) << 7)
+                    | (bits(raw, 5, 5) << 6)
+                    | (bits(raw, 2, 2) << 5)
+                    | (bits(raw, 6, 6) << 4),
+                10,
+            );
+
+            if nzimm == 0 {
+                return None;
+            }
+
+            Op::AddImm(SP, SP, i64::from(nzimm))
+        }
+
+        // C.LUI
+        (0b01, 0b011) => Op::Lui(rd, (i64::from(ci_imm) << 12) as u64),
+
+        // C.SRLI, C.SRAI, C.ANDI, C.SUB, C.XOR, C.OR, C.AND, C.SUBW and C.ADDW
+        (0b01, 0b100) => {
+            if bits(raw, 11, 10) == 0b11 {
+                // C.SUB, C.XOR, C.OR and C.AND; C.SUBW and C.ADDW are RV64 only
+                if bits(raw, 12, 12) == 1 && (!rv64 || bits(raw, 6, 6) == 1) {
+                    return None;
+                }
+
+                if bits(raw, 12, 12) == 0 && bits(raw, 6, 5) == 0 {
+                    Op::Sub(rd_hi, rd_hi, rd_lo)
+                } else {
+                    return Some((Op::Other, Some(rd_hi)));
+                }
+            } else {
+                return Some((Op::Other, Some(rd_hi)));
+            }
+        }
+
+        // C.J
+        (0b01, 0b101) => Op::Jal(ZERO, cj_imm(raw)),
+
+        // C.BEQZ and C.BNEZ
+        (0b01, 0b110) | (0b01, 0b111) => {
+            let offset = (bits(raw, 12, 12) << 8)
+                | (bits(raw, 6, 5) << 6)
+                | (bits(raw, 2, 2) << 5)
+                | (bits(raw, 11, 10) << 3)
+                | (bits(raw, 4, 3) << 1);
+
+            Op::Branch(sign_extend32(offset, 9))
+        }
+
+        /* Quadrant 2 */
+        // C.SLLI
+        (0b10, 0b000) => return Some((Op::Other, Some(rd))),
+
+        // C.FLDSP
+        (0b10, 0b001) => return Some((Op::Other, None)),
+
+        // C.LWSP and C.LDSP (RV64) / C.FLWSP (RV32)
+        (0b10, 0b010) if rd != ZERO => return Some((Op::Other, Some(rd))),
+        (0b10, 0b011) if !rv64 => return Some((Op::Other, None)),
+        (0b10, 0b011) if rd != ZERO => return Some((Op::Other, Some(rd))),
+
+        (0b10, 0b100) => match (bits(raw, 12, 12), rd, rs2) {
+            // C.JR
+            (0, ZERO, 0) => return None,
+            (0, _, 0) => Op::Jalr(ZERO, rd, 0),
+
+            // C.MV
+            (0, _, _) => Op::Add(rd, ZERO, rs2),
+
+            // C.EBREAK
+            (1, ZERO, 0) => return Some((Op::Other, None)),
+
+            // C.JALR
+            (1, _, 0) => Op::Jalr(RA, rd, 0),
+
+            // C.ADD
+            (_, _, _) => Op::Add(rd, rd, rs2),
+        },
+
+        // C.FSDSP, C.SWSP and C.SDSP (RV64) / C.FSWSP (RV32)
+        (0b10, 0b101) | (0b10, 0b110) | (0b10, 0b111) => return Some((Op::Other, None)),
+
+        _ => return None,
+    };
+
+    let rd = match op {
+        Op::Jal(rd, _) | Op::Jalr(rd, _, _) => Some(rd),
+        Op::AddImm(rd, ..) | Op::Add(rd, ..) | Op::Sub(rd, ..) | Op::Lui(rd, _) => Some(rd),
+        _ => None,
+    };
+
+    Some((op, rd))
+}
+
+fn bits(raw: u32, hi: u32, lo: u32) -> u32 {
+    (raw >> lo) & ((1 << (hi - lo + 1)) - 1)
+}
+
+// sign extends the lowest `width` bits of `x`
+fn sign_extend32(x: u32, width: u32) -> i32 {
+    ((x << (32 - width)) as i32) >> (32 - width)
+}
+
+// interprets `x` as a signed `XLEN`-bit value
+fn sign_extend(x: u64, mask: u64) -> i64 {
+    if mask == u64::from(u32::MAX) {
+        i64::from(x as u32 as i32)
+    } else {
+        x as i64
+    }
+}
+
+// I-type immediate
+fn i_imm(raw: u32) -> i32 {
+    (raw as i32) >> 20
+}
+
+// U-type immediate, shifted into place and sign extended to 64 bits
+fn u_imm(raw: u32) -> u64 {
+    i64::from((raw & 0xffff_f000) as i32) as u64
+}
+
+// B-type immediate
+fn b_imm(raw: u32) -> i32 {
+    let imm = (bits(raw, 31, 31) << 12)
+        | (bits(raw, 7, 7) << 11)
+        | (bits(raw, 30, 25) << 5)
+        | (bits(raw, 11, 8) << 1);
+
+    sign_extend32(imm, 13)
+}
+
+// J-type immediate
+fn j_imm(raw: u32) -> i32 {
+    let imm = (bits(raw, 31, 31) << 20)
+        | (bits(raw, 19, 12) << 12)
+        | (bits(raw, 20, 20) << 11)
+        | (bits(raw, 30, 21) << 1);
+
+    sign_extend32(imm, 21)
+}
+
+// CJ-format jump target of `C.J` and `C.JAL`
+fn cj_imm(raw: u32) -> i32 {
+    let imm = (bits(raw, 12, 12) << 11)
+        | (bits(raw, 8, 8) << 10)
+        | (bits(raw, 10, 9) << 8)
+        | (bits(raw, 6, 6) << 7)
+        | (bits(raw, 7, 7) << 6)
+        | (bits(raw, 2, 2) << 5)
+        | (bits(raw, 11, 11) << 4)
+        | (bits(raw, 5, 3) << 1);
+
+    sign_extend32(imm, 12)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    // the instruction at `address` could not be decoded
+    UnknownInstruction { address: u64, encoding: String },
+}
+
+impl Error {
+    fn unknown(bytes: &[u8], address: u64) -> Self {
+        let lo = u32::from(bytes[0]) | (u32::from(bytes[1]) << 8);
+        let encoding = match bytes.get(2..4) {
+            Some(hi) if lo & 0b11 == 0b11 => format!(
+                "{:08x}",
+                lo | (u32::from(hi[0]) << 16) | (u32::from(hi[1]) << 24)
+            ),
+            _ => format!("{:04x}", lo),
+        };
+
+        Error::UnknownInstruction { address, encoding }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownInstruction { address, encoding } => write!(
+                f,
+                "unknown instruction {} at address {:#010x}",
+                encoding, address
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Arch;
+
+    #[test]
+    fn sanity() {
+        // 2801            jal     16
+        // 2021            jal     8
+        // bfe5            j       -8
+        // 0463 04b5       beq     a0, a1, 64
+        let branches = super::analyze(
+            &[0x01, 0x28, 0x21, 0x20, 0xe5, 0xbf, 0x63, 0x00, 0xb5, 0x04],
+            0,
+            Arch::Rv32,
+        )
+        .unwrap();
+        assert_eq!(branches.jals, vec![16, 2 + 8]);
+        assert_eq!(branches.js, vec![4 - 8, 6 + 64]);
+
+        // 0001            nop
+        // 0001            nop
+        // 0001            nop
+        // 0001            nop
+        // bfe5            j       -8
+        let looping = super::analyze(
+            &[0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0xe5, 0xbf],
+            0,
+            Arch::Rv32,
+        )
+        .unwrap();
+        assert_eq!(looping.js, vec![0]);
+        assert_eq!(looping.stack, None);
+
+        // 8082            ret
+        let ret = super::analyze(&[0x82, 0x80], 0, Arch::Rv32).unwrap();
+        assert_eq!(
+            ret,
+            super::Analysis {
+                jals: vec![],
+                js: vec![],
+                jalrs: vec![],
+                indirect: false,
+                modifies_sp: false,
+                stack: Some(0),
+            }
+        );
+
+        // 48-bit encodings are not supported
+        assert_eq!(
+            super::analyze(&[0x01, 0x00, 0x1f, 0x00, 0x00, 0x00], 0x100, Arch::Rv32),
+            Err(super::Error::UnknownInstruction {
+                address: 0x102,
+                encoding: "0000001f".to_owned(),
+            })
+        );
+
+        // `ld` is RV64 only
+        let ld = [0x03, 0x35, 0x05, 0x00];
+        assert!(super::analyze(&ld, 0, Arch::Rv32).is_err());
+        assert!(super::analyze(&ld, 0, Arch::Rv64).is_ok());
+    }
+
+    #[test]
+    fn constants() {
+        // 00000097        auipc   ra, 0
+        // 010080e7        jalr    16(ra)
+        let call = super::analyze(
+            &[0x97, 0x00, 0x00, 0x00, 0xe7, 0x80, 0x00, 0x01],
+            0x100,
+            Arch::Rv32,
+        )
+        .unwrap();
+        assert!(!call.indirect);
+        assert_eq!(call.jalrs, vec![0x110]);
+
+        // 00001317        auipc   t1, 1
+        // ffc30067        jr      -4(t1)
+        let tail = super::analyze(
+            &[0x17, 0x13, 0x00, 0x00, 0x67, 0x00, 0xc3, 0xff],
+            0x100,
+            Arch::Rv64,
+        )
+        .unwrap();
+        assert!(!tail.indirect);
+        assert_eq!(tail.jalrs, vec![0x10fc]);
+
+        // 20000537        lui     a0, 131072
+        // 0505            addi    a0, a0, 1
+        // 9502            jalr    a0
+        let lui = super::analyze(
+            &[0x37, 0x05, 0x00, 0x20, 0x05, 0x05, 0x02, 0x95],
+            0,
+            Arch::Rv32,
+        )
+        .unwrap();
+        assert!(!lui.indirect);
+        assert_eq!(lui.jalrs, vec![0x2000_0000]);
+
+        // 4108            lw      a0, 0(a0)
+        // 9502            jalr    a0
+        let clobbered = super::analyze(&[0x08, 0x41, 0x02, 0x95], 0, Arch::Rv32).unwrap();
+        assert!(clobbered.indirect);
+        assert!(clobbered.jalrs.is_empty());
+
+        // 00000517        auipc   a0, 0
+        // 0541            addi    a0, a0, 16
+        // 2081            jal     64
+        // 9502            jalr    a0
+        let after_call = super::analyze(
+            &[0x17, 0x05, 0x00, 0x00, 0x41, 0x05, 0x81, 0x20, 0x02, 0x95],
+            0,
+            Arch::Rv32,
+        )
+        .unwrap();
+        assert!(after_call.indirect);
+        assert!(after_call.jalrs.is_empty());
+
+        // 20000537        lui     a0, 131072
+        // c199            beqz    a1, 6
+        // 20001537        lui     a0, 131073
+        // 9502            jalr    a0
+        let join = super::analyze(
+            &[
+                0x37, 0x05, 0x00, 0x20, 0x99, 0xc1, 0x37, 0x15, 0x00, 0x20, 0x02, 0x95,
+            ],
+            0,
+            Arch::Rv32,
+        )
+        .unwrap();
+        assert!(join.indirect);
+        assert!(join.jalrs.is_empty());
+    }
+
+    #[test]
+    fn modifies_sp() {
+        // 0001            nop
+        let nop = super::analyze(&[0x01, 0x00], 0, Arch::Rv32).unwrap();
+        assert!(!nop.modifies_sp);
+        assert_eq!(nop.stack, Some(0));
+
+        // 1141            addi    sp, sp, -16
+        // c606            sw      ra, 12(sp)
+        // 40b2            lw      ra, 12(sp)
+        // 0141            addi    sp, sp, 16
+        // 8082            ret
+        let frame = super::analyze(
+            &[0x41, 0x11, 0x06, 0xc6, 0xb2, 0x40, 0x41, 0x01, 0x82, 0x80],
+            0,
+            Arch::Rv32,
+        )
+        .unwrap();
+        assert!(frame.modifies_sp);
+        assert_eq!(frame.stack, Some(16));
+
+        // 1141            addi    sp, sp, -16
+        // 0141            addi    sp, sp, 16
+        // 1161            addi    sp, sp, -8
+        let peak = super::analyze(&[0x41, 0x11, 0x41, 0x01, 0x61, 0x11], 0, Arch::Rv32).unwrap();
+        assert_eq!(peak.stack, Some(16));
+
+        // 7139            addi    sp, sp, -64
+        let addi16sp = super::analyze(&[0x39, 0x71], 0, Arch::Rv64).unwrap();
+        assert_eq!(addi16sp.stack, Some(64));
+
+        // 81010113        addi    sp, sp, -2032
+        // 6285            lui     t0, 1
+        // 40510133        sub     sp, sp, t0
+        let large = super::analyze(
+            &[0x13, 0x01, 0x01, 0x81, 0x85, 0x62, 0x33, 0x01, 0x51, 0x40],
+            0,
+            Arch::Rv32,
+        )
+        .unwrap();
+        assert!(large.modifies_sp);
+        assert_eq!(large.stack, Some(2032 + 4096));
+
+        // 40a10133        sub     sp, sp, a0
+        let dynamic = super::analyze(&[0x33, 0x01, 0xa1, 0x40], 0, Arch::Rv32).unwrap();
+        assert!(dynamic.modifies_sp);
+        assert_eq!(dynamic.stack, None);
+    }
+}
@@ -0,0 +1,409 @@
+//! Simplifications of the call graph for display
+//!
+//! The call graphs of real programs have thousands of nodes. These transformations only change
+//! what gets printed: the stack usage of every node has already been computed on the full call
+//! graph.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use petgraph::{
+    graph::{DiGraph, NodeIndex},
+    Graph,
+};
+
+use crate::{
+    query,
+    symbol::{self, Symbol},
+    Cycle, Local, Max, Node,
+};
+
+/// How to group the nodes of the call graph
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cluster {
+    /// By crate, e.g. `core`
+    Crate,
+    /// By module, e.g. `core::fmt`
+    Module,
+}
+
+/// The path of the item `function` belongs to, e.g. `core::fmt` for `core::fmt::write` and
+/// `alloc::vec::Vec` for `<alloc::vec::Vec<u8> as core::fmt::Debug>::fmt`
+///
+/// `function` is a demangled name without hash (`{:#}`). Returns `None` for names that have no
+/// path, like `memcpy`
+pub fn path(function: &str) -> Option<&str> {
+    let path = if let Some(rest) = function.strip_prefix('<') {
+        let self_ty = rest
+            .trim_start_matches(['&', '*', '['])
+            .trim_start_matches("mut ")
+            .trim_start_matches("const ")
+            .trim_start_matches("dyn ");
+
+        let self_path = prefix(self_ty);
+        if self_path.contains("::") {
+            self_path
+        } else {
+            // a primitive type, e.g. `<u8 as core::fmt::Debug>::fmt`; go by the trait
+            prefix(&rest[rest.find(" as ")? + " as ".len()..])
+        }
+    } else {
+        let path = prefix(function);
+        // drop the function itself
+        &path[..path.rfind("::")?]
+    };
+
+    if path.is_empty() {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+// the leading `a::b::c` of `s`, without generic arguments or closures
+fn prefix(s: &str) -> &str {
+    let end = s
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .unwrap_or(s.len());
+    s[..end].trim_end_matches(':')
+}
+
+/// The cluster `function` belongs to
+pub fn cluster(function: &str, level: Cluster) -> Option<&str> {
+    let path = path(function)?;
+    Some(match level {
+        Cluster::Crate => path.split("::").next().unwrap_or(path),
+        Cluster::Module => path,
+    })
+}
+
+/// Whether `function` belongs to the item or module `path`
+fn belongs(function: &str, path: &str) -> bool {
+    self::path(function)
+        .map(|p| p == path || p.starts_with(path) && p[path.len()..].starts_with("::"))
+        .unwrap_or(false)
+}
+
+/// The subgraph of the nodes that are at most `hops` calls away from `center`, in either
+/// direction
+pub fn around<'a>(
+    g: &Graph<Node<'a>, ()>,
+    cycles: &[Cycle],
+    center: NodeIndex,
+    hops: usize,
+) -> (Graph<Node<'a>, ()>, Vec<Cycle>) {
+    let mut nodes = HashSet::new();
+    nodes.insert(center);
+
+    let mut frontier = vec![center];
+    for _ in 0..hops {
+        let mut next = vec![];
+        for node in frontier {
+            for neighbor in g.neighbors_undirected(node) {
+                if nodes.insert(neighbor) {
+                    next.push(neighbor);
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    query::subgraph(g, cycles, &nodes)
+}
+
+/// Replaces the functions that belong to each of the `paths` with a single node that carries
+/// their worst-case stack usage
+pub fn collapse<'a>(
+    g: &Graph<Node<'a>, ()>,
+    cycles: &[Cycle],
+    paths: &[&str],
+) -> (Graph<Node<'a>, ()>, Vec<Cycle>) {
+    let (mut g2, cycles, members) = merge(g, cycles, |node| {
+        let function = format!("{:#}", rustc_demangle::demangle(&g[node].name));
+        paths
+            .iter()
+            .find(|path| belongs(&function, path))
+            .map(|path| path.to_string())
+    });
+
+    for (summary, members) in members {
+        g2[summary].collapsed = members.len();
+        g2[summary].dashed = true;
+    }
+
+    (g2, cycles)
+}
+
+/// Replaces the instances of each generic function with a single node that carries their
+/// worst-case local and overall stack usage
+pub fn generics<'a>(
+    g: &Graph<Node<'a>, ()>,
+    cycles: &[Cycle],
+) -> (Graph<Node<'a>, ()>, Vec<Cycle>) {
+    let generic = instances(g)
+        .into_iter()
+        .flat_map(|(generic, instances)| {
+            instances
+                .into_iter()
+                .map(move |instance| (instance, generic.clone()))
+        })
+        .collect::<HashMap<_, _>>();
+    let (mut g2, cycles, members) = merge(g, cycles, |node| generic.get(&node).cloned());
+
+    for (summary, members) in members {
+        g2[summary].instances = members.len();
+        g2[summary].local =
+            members
+                .iter()
+                .map(|member| g[*member].local)
+                .fold(Local::Exact(0), |lhs, rhs| match (lhs, rhs) {
+                    (Local::Exact(lhs), Local::Exact(rhs)) => Local::Exact(lhs.max(rhs)),
+                    _ => Local::Unknown,
+                });
+    }
+
+    (g2, cycles)
+}
+
+/// The functions of `g` that are instances of the same generic function, keyed by the path of the
+/// generic function; see `symbol::generic`
+///
+/// Only functions whose names differ in their generic arguments are instances. Legacy symbol names
+/// don't encode the generic arguments of functions so functions that share a path, like the
+/// `{{closure}}`s of a function or the copies of a function linked in from two versions of a
+/// crate, are left alone
+pub fn instances(g: &Graph<Node, ()>) -> BTreeMap<String, Vec<NodeIndex>> {
+    // the nodes of each generic function, by generic arguments
+    let mut groups = BTreeMap::<_, BTreeMap<_, Vec<_>>>::new();
+    for node in g.node_indices() {
+        let path = Symbol::new(&g[node].name).path;
+        let (generic, arguments) = symbol::generic(&path);
+        if arguments.is_empty() {
+            continue;
+        }
+
+        groups
+            .entry(generic)
+            .or_default()
+            .entry(arguments.concat())
+            .or_default()
+            .push(node);
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|(generic, arguments)| {
+            let mut instances = arguments
+                .into_values()
+                .filter(|nodes| nodes.len() == 1)
+                .map(|nodes| nodes[0])
+                .collect::<Vec<_>>();
+            instances.sort();
+
+            if instances.len() > 1 {
+                Some((generic, instances))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// the merged graph, its cycles and the nodes of the original graph each summary stands for
+type Merged<'a> = (
+    Graph<Node<'a>, ()>,
+    Vec<Cycle>,
+    HashMap<NodeIndex, Vec<NodeIndex>>,
+);
+
+// replaces the nodes that map to the same key with a single node named after the key; returns the
+// new graph, its cycles and the nodes of `g` each new node stands for
+fn merge<'a>(
+    g: &Graph<Node<'a>, ()>,
+    cycles: &[Cycle],
+    key: impl Fn(NodeIndex) -> Option<String>,
+) -> Merged<'a> {
+    let mut g2 = DiGraph::new();
+
+    // maps `g`'s `NodeIndex`-es to `g2`'s `NodeIndex`-es
+    let mut one2two = HashMap::new();
+    // the node that stands for each key
+    let mut summaries = HashMap::new();
+    let mut members = HashMap::<_, Vec<_>>::new();
+    for node in g.node_indices() {
+        let two = if let Some(key) = key(node) {
+            let two = *summaries
+                .entry(key.clone())
+                .or_insert_with(|| g2.add_node(Node(key, None, false)));
+
+            let node_ = &g[node];
+            let first = !members.contains_key(&two);
+            let summary = &mut g2[two];
+            summary.max = combine(summary.max, node_.max);
+            summary.max_no_panic = combine(summary.max_no_panic, node_.max_no_panic);
+            summary.panic = node_.panic && (first || summary.panic);
+            members.entry(two).or_default().push(node);
+            two
+        } else {
+            g2.add_node(g[node].clone())
+        };
+
+        one2two.insert(node, two);
+    }
+
+    for edge in g.raw_edges() {
+        let (source, target) = (one2two[&edge.source()], one2two[&edge.target()]);
+
+        // the calls between the functions of a summary are not shown
+        let internal = source == target && members.contains_key(&source);
+        if !internal && g2.find_edge(source, target).is_none() {
+            g2.add_edge(source, target, ());
+        }
+    }
+
+    let cycles = cycles
+        .iter()
+        .filter_map(|(cycle, depth)| {
+            let mut cycle = cycle.iter().map(|node| one2two[node]).collect::<Vec<_>>();
+            cycle.sort();
+            cycle.dedup();
+
+            // a cycle that's now inside a summary
+            if cycle.len() == 1 && members.contains_key(&cycle[0]) {
+                None
+            } else {
+                Some((cycle, *depth))
+            }
+        })
+        .collect();
+
+    (g2, cycles, members)
+}
+
+fn combine(lhs: Option<Max>, rhs: Option<Max>) -> Option<Max> {
+    match (lhs, rhs) {
+        (Some(lhs), Some(rhs)) => Some(crate::max(lhs, rhs)),
+        _ => lhs.or(rhs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use petgraph::graph::DiGraph;
+
+    use super::Cluster;
+    use crate::{Local, Max, Node};
+
+    #[test]
+    fn path() {
+        assert_eq!(super::path("core::fmt::write"), Some("core::fmt"));
+        assert_eq!(
+            super::path("<alloc::vec::Vec<u8> as core::fmt::Debug>::fmt"),
+            Some("alloc::vec::Vec")
+        );
+        assert_eq!(
+            super::path("<&str as core::fmt::Display>::fmt"),
+            Some("core::fmt::Display")
+        );
+        assert_eq!(
+            super::path("core::ptr::drop_in_place::<app::Foo>"),
+            Some("core::ptr")
+        );
+        assert_eq!(super::path("app::main::{closure#0}"), Some("app"));
+        assert_eq!(super::path("memcpy"), None);
+
+        assert_eq!(
+            super::cluster("core::fmt::num::imp::fmt_u64", Cluster::Crate),
+            Some("core")
+        );
+        assert_eq!(
+            super::cluster("core::fmt::num::imp::fmt_u64", Cluster::Module),
+            Some("core::fmt::num::imp")
+        );
+
+        assert!(super::belongs("core::fmt::write", "core::fmt"));
+        assert!(super::belongs("core::fmt::write", "core"));
+        assert!(!super::belongs("core::fmtx::write", "core::fmt"));
+    }
+
+    #[test]
+    fn collapse() {
+        // main -> core::fmt::write -> core::fmt::Formatter::pad -> core::fmt::write
+        let mut g = DiGraph::new();
+        let main = g.add_node(Node("main", Some(8), false));
+        let write = g.add_node(Node("core::fmt::write", Some(64), false));
+        let pad = g.add_node(Node("core::fmt::Formatter::pad", Some(32), false));
+        g.add_edge(main, write, ());
+        g.add_edge(write, pad, ());
+        g.add_edge(pad, write, ());
+        g[main].max = Some(Max::LowerBound(72));
+        g[write].max = Some(Max::LowerBound(64));
+        g[pad].max = Some(Max::LowerBound(64));
+        let cycles = vec![(vec![write, pad], None)];
+
+        let (g2, cycles2) = super::collapse(&g, &cycles, &["core::fmt"]);
+        assert_eq!(g2.node_count(), 2);
+        assert_eq!(g2.edge_count(), 1);
+        assert!(cycles2.is_empty());
+
+        let summary = g2
+            .node_indices()
+            .find(|node| g2[*node].collapsed != 0)
+            .unwrap();
+        assert_eq!(g2[summary].collapsed, 2);
+        assert!(g2[summary].max == Some(Max::LowerBound(64)));
+
+        let (g3, cycles3) = super::around(&g, &cycles, main, 1);
+        assert_eq!(g3.node_count(), 2);
+        assert_eq!(cycles3.len(), 1);
+    }
+
+    #[test]
+    fn generics() {
+        // main -> <Vec<u8, 4>>::push -> memcpy, main -> <Vec<u32, 4>>::push -> memcpy
+        let mut g = DiGraph::new();
+        let main = g.add_node(Node("main", Some(8), false));
+        let push_u8 = g.add_node(Node("<heapless::vec::Vec<u8, 4>>::push", Some(16), false));
+        let push_u32 = g.add_node(Node("<heapless::vec::Vec<u32, 4>>::push", Some(32), false));
+        let memcpy = g.add_node(Node("memcpy", None, false));
+        g.add_edge(main, push_u8, ());
+        g.add_edge(main, push_u32, ());
+        g.add_edge(push_u8, memcpy, ());
+        g.add_edge(push_u32, memcpy, ());
+        g[push_u8].max = Some(Max::LowerBound(16));
+        g[push_u32].max = Some(Max::LowerBound(32));
+
+        // legacy closures and a function linked in from two versions of a crate are not instances
+        g.add_node(Node(
+            "_ZN3app4main28_$u7b$$u7b$closure$u7d$$u7d$17h0123456789abcdefE",
+            Some(0),
+            false,
+        ));
+        g.add_node(Node(
+            "_ZN3app4main28_$u7b$$u7b$closure$u7d$$u7d$17hfedcba9876543210E",
+            Some(0),
+            false,
+        ));
+        g.add_node(Node("<heapless::vec::Vec<u8, 4>>::pop", Some(8), false));
+        g.add_node(Node("<heapless::vec::Vec<u8, 4>>::pop", Some(8), false));
+
+        let instances = super::instances(&g);
+        assert_eq!(instances.len(), 1);
+        assert_eq!(
+            instances["<heapless::vec::Vec>::push"],
+            vec![push_u8, push_u32]
+        );
+
+        let (g2, _) = super::generics(&g, &[]);
+        assert_eq!(g2.node_count(), 7);
+        assert_eq!(g2.edge_count(), 2);
+
+        let push = g2
+            .node_indices()
+            .find(|node| g2[*node].instances != 0)
+            .unwrap();
+        assert_eq!(g2[push].name, "<heapless::vec::Vec>::push");
+        assert_eq!(g2[push].instances, 2);
+        assert!(g2[push].local == Local::Exact(32));
+        assert!(g2[push].max == Some(Max::LowerBound(32)));
+    }
+}
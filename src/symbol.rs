@@ -0,0 +1,316 @@
+//! Symbol names
+//!
+//! Rust symbols are mangled with either the legacy scheme (`_ZN4core3fmt5write17h..E`) or the v0
+//! one (`_RNvNtCs.._4core3fmt5write`, `-C symbol-mangling-version=v0`). Their demangled forms
+//! differ: the legacy one ends with a hash (`core::fmt::write::h0123456789abcdef`) while the v0
+//! one carries crate disambiguators (`core[0123456789abcdef]::fmt::write`). Without the hash and
+//! the disambiguators both become the same path (`core::fmt::write`), which is what the analysis
+//! works with.
+
+/// A symbol name in its mangled and demangled forms
+#[derive(Clone, Debug, PartialEq)]
+pub struct Symbol<'a> {
+    /// The name as it appears in the object files
+    pub mangled: &'a str,
+    /// Demangled name, with hash or crate disambiguators
+    pub full: String,
+    /// Demangled name, without hash or crate disambiguators (`{:#}`)
+    pub path: String,
+}
+
+impl<'a> Symbol<'a> {
+    pub fn new(mangled: &'a str) -> Self {
+        let demangled = rustc_demangle::demangle(mangled);
+        Symbol {
+            mangled,
+            full: demangled.to_string(),
+            path: format!("{:#}", demangled),
+        }
+    }
+
+    /// Whether `name` refers to this symbol; mangled, demangled and demangled without hash names
+    /// are accepted
+    pub fn is(&self, name: &str) -> bool {
+        self.mangled == name || self.full == name || self.path == name
+    }
+
+    /// Whether this is an item of a trait implementation, e.g. `<app::Foo as
+    /// core::fmt::Debug>::fmt` or one of its closures
+    pub fn is_trait_impl(&self) -> bool {
+        split_impl(&self.path).is_some()
+    }
+
+    /// The trait and the method of a method of a trait implementation, e.g. `core::fmt::Debug`
+    /// and `fmt` for `<app::Foo as core::fmt::Debug>::fmt`
+    pub fn trait_method(&self) -> Option<(&str, &str)> {
+        trait_method(&self.path)
+    }
+}
+
+/// The trait and the method of `path` if it's a method of a trait implementation; see
+/// `Symbol::trait_method`
+pub fn trait_method(path: &str) -> Option<(&str, &str)> {
+    let (_, trait_, method) = split_impl(path)?;
+
+    // `<app::Foo as core::fmt::Debug>::fmt::{closure#0}` is a closure, not a method, but
+    // `<app::Foo as core::fmt::Debug>::fmt::{shim:vtable#0}` stands in for the method
+    let method = method.split("::{shim:").next().unwrap_or(method);
+    let name = method.split("::<").next().unwrap_or(method);
+    if name.contains("::") || name.contains('{') {
+        None
+    } else {
+        Some((trait_, name))
+    }
+}
+
+/// The trait of a vtable named after the implementation it belongs to, without generic arguments,
+/// e.g. `core::fmt::Write` for `<alloc::string::String as core::fmt::Write>::{vtable}`
+pub fn vtable_trait(name: &str) -> Option<String> {
+    match split_impl(name)? {
+        (_, trait_, "{vtable}") => Some(generic(trait_).0),
+        _ => None,
+    }
+}
+
+/// The trait of `path` if it's a method that implements a trait for a trait object of that same
+/// trait, without generic arguments, e.g. `core::fmt::Write` for `<&mut dyn core::fmt::Write as
+/// core::fmt::Write>::write_str`; the dynamic calls in such a method call methods of that trait
+///
+/// Legacy symbol names keep the generic parameters (`<&mut W as core::fmt::Write>`) so this only
+/// works with v0 names
+pub fn dyn_trait(path: &str) -> Option<String> {
+    let (ty, trait_, _) = split_impl(path)?;
+    let trait_ = generic(trait_).0;
+    let is_object = ty.match_indices("dyn ").any(|(start, _)| {
+        let object = generic(&ty[start + "dyn ".len()..]).0;
+        object
+            .strip_prefix(trait_.as_str())
+            .map(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == ':'))
+            .unwrap_or(false)
+    });
+
+    if is_object {
+        Some(trait_)
+    } else {
+        None
+    }
+}
+
+/// Splits `<Type as Trait>::rest` into its three parts; generic arguments may nest
+pub fn split_impl(path: &str) -> Option<(&str, &str, &str)> {
+    let inner = path.strip_prefix('<')?;
+
+    let mut depth = 0;
+    let mut as_ = None;
+    let mut prev = '\0';
+    for (i, c) in inner.char_indices() {
+        match c {
+            '<' => depth += 1,
+            // `->` of a function type
+            '>' if prev == '-' => {}
+            '>' if depth == 0 => {
+                let start = as_?;
+                let rest = inner[i + 1..].strip_prefix("::")?;
+                return Some((&inner[..start], &inner[start + " as ".len()..i], rest));
+            }
+            '>' => depth -= 1,
+            ' ' if depth == 0 && as_.is_none() && inner[i..].starts_with(" as ") => {
+                as_ = Some(i);
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+
+    None
+}
+
+/// Removes the generic arguments from `path`; returns the path of the generic function and the
+/// generic argument lists that were removed, e.g. `<heapless::vec::Vec>::push` and `["<u8, 4>"]`
+/// for `<heapless::vec::Vec<u8, 4>>::push`
+///
+/// Legacy symbol names don't encode the generic arguments of functions so their instances have the
+/// same path
+pub fn generic(path: &str) -> (String, Vec<&str>) {
+    let mut generic = String::with_capacity(path.len());
+    let mut arguments = vec![];
+
+    let mut rest = path;
+    while let Some(c) = rest.chars().next() {
+        // `Vec<u8>` or `drop_in_place::<u8>`; a `<` anywhere else starts a qualified path
+        let turbofish = generic.ends_with("::");
+        let is_arguments = c == '<'
+            && (turbofish
+                || generic
+                    .chars()
+                    .last()
+                    .map(|c| c.is_alphanumeric() || c == '_')
+                    .unwrap_or(false));
+
+        if is_arguments {
+            let end = closing(rest).unwrap_or(rest.len());
+            arguments.push(&rest[..end]);
+            rest = &rest[end..];
+
+            if turbofish {
+                generic.truncate(generic.len() - "::".len());
+            }
+        } else {
+            generic.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    (generic, arguments)
+}
+
+// the length of the `<..>` list that starts `s`, including both angle brackets
+fn closing(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut prev = '\0';
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' => depth += 1,
+            // `->` of a function type
+            '>' if prev == '-' => {}
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Symbol;
+
+    #[test]
+    fn legacy() {
+        let write = Symbol::new("_ZN4core3fmt5write17h0123456789abcdefE");
+        assert_eq!(write.full, "core::fmt::write::h0123456789abcdef");
+        assert_eq!(write.path, "core::fmt::write");
+        assert!(write.is("core::fmt::write"));
+        assert!(write.is("_ZN4core3fmt5write17h0123456789abcdefE"));
+        assert!(!write.is("core::fmt"));
+        assert_eq!(write.trait_method(), None);
+
+        let fmt = Symbol::new(
+            "_ZN60_$LT$alloc..string..String$u20$as$u20$core..fmt..Display$GT$3fmt17h0123456789abcdefE",
+        );
+        assert_eq!(
+            fmt.path,
+            "<alloc::string::String as core::fmt::Display>::fmt"
+        );
+        assert_eq!(fmt.trait_method(), Some(("core::fmt::Display", "fmt")));
+    }
+
+    #[test]
+    fn v0() {
+        let path_push = Symbol::new("_RNvNtCs4X4t9plMPHF_9addr2line4line9path_push");
+        assert_eq!(path_push.path, "addr2line::line::path_push");
+        assert!(path_push.full.starts_with("addr2line["));
+        assert_eq!(path_push.trait_method(), None);
+
+        let fmt = Symbol::new("_RNvXs_NvXsf_NtNtCslNYArtu3iFV_5alloc5boxed7convertINtBc_3BoxDNtNtCsgEmfK2I1SDS_4core5error5ErrorNtNtB11_6marker4SyncNtB1y_4SendEL_EINtNtB11_7convert4FromNtNtBe_6string6StringE4fromNtB4_11StringErrorNtNtB11_3fmt7Display3fmt");
+        assert_eq!(fmt.trait_method(), Some(("core::fmt::Display", "fmt")));
+    }
+
+    #[test]
+    fn split_impl() {
+        assert_eq!(
+            super::split_impl("<alloc::vec::Vec<u8> as core::fmt::Debug>::fmt"),
+            Some(("alloc::vec::Vec<u8>", "core::fmt::Debug", "fmt"))
+        );
+        assert_eq!(
+            super::split_impl("<fn() -> u8 as app::Handler<u8>>::call::{closure#0}"),
+            Some(("fn() -> u8", "app::Handler<u8>", "call::{closure#0}"))
+        );
+        assert_eq!(super::split_impl("<alloc::vec::Vec<u8>>::push"), None);
+        assert_eq!(
+            super::Symbol {
+                mangled: "",
+                full: String::new(),
+                path: "<app::main::{closure#0} as core::ops::function::FnOnce<()>>::call_once::{shim:vtable#0}".to_string(),
+            }
+            .trait_method(),
+            Some(("core::ops::function::FnOnce<()>", "call_once"))
+        );
+        assert_eq!(super::split_impl("core::fmt::write"), None);
+    }
+
+    #[test]
+    fn traits() {
+        assert_eq!(
+            super::vtable_trait("<alloc::string::String as core::fmt::Write>::{vtable}"),
+            Some("core::fmt::Write".to_string())
+        );
+        assert_eq!(
+            super::vtable_trait(
+                "<app::main::{closure#0} as core::ops::function::Fn<(u8,)>>::{vtable}"
+            ),
+            Some("core::ops::function::Fn".to_string())
+        );
+        assert_eq!(
+            super::vtable_trait("<alloc::string::String as core::fmt::Write>::write_str"),
+            None
+        );
+
+        assert_eq!(
+            super::dyn_trait("<&mut dyn core::fmt::Write as core::fmt::Write>::write_str"),
+            Some("core::fmt::Write".to_string())
+        );
+        assert_eq!(
+            super::dyn_trait(
+                "<alloc::boxed::Box<dyn core::ops::function::Fn<(u8,), Output = ()>> as core::ops::function::Fn<(u8,)>>::call"
+            ),
+            Some("core::ops::function::Fn".to_string())
+        );
+        // a method of another trait, or of a trait whose name is a prefix of the object's
+        assert_eq!(
+            super::dyn_trait("<&dyn core::fmt::Debug as core::fmt::Display>::fmt"),
+            None
+        );
+        assert_eq!(super::dyn_trait("<dyn app::FooBar as app::Foo>::foo"), None);
+        assert_eq!(
+            super::dyn_trait("<&mut W as core::fmt::Write>::write_str"),
+            None
+        );
+    }
+
+    #[test]
+    fn generic() {
+        assert_eq!(
+            super::generic("<heapless::vec::Vec<u8, 4>>::push"),
+            ("<heapless::vec::Vec>::push".to_string(), vec!["<u8, 4>"])
+        );
+        assert_eq!(
+            super::generic("core::ptr::drop_in_place::<alloc::vec::Vec<u8>>"),
+            (
+                "core::ptr::drop_in_place".to_string(),
+                vec!["<alloc::vec::Vec<u8>>"]
+            )
+        );
+        assert_eq!(
+            super::generic("<&[u8] as core::fmt::Debug>::fmt"),
+            ("<&[u8] as core::fmt::Debug>::fmt".to_string(), vec![])
+        );
+        assert_eq!(
+            super::generic("<fn() -> u8 as app::Handler<u8>>::call::<i32>"),
+            (
+                "<fn() -> u8 as app::Handler>::call".to_string(),
+                vec!["<u8>", "<i32>"]
+            )
+        );
+        assert_eq!(
+            super::generic("core::fmt::write"),
+            ("core::fmt::write".to_string(), vec![])
+        );
+    }
+}
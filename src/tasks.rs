@@ -0,0 +1,319 @@
+//! Tasks of a real-time operating system
+//!
+//! An RTOS (or any scheduler that switches stacks with `msr psp`) runs each task entry function on
+//! its own stack, a `static` array whose size is fixed at compile time. The worst-case stack usage
+//! of a task must fit in that array; it's not charged to the function that started the task.
+//! Interrupt and exception handlers preempt the tasks but run on the main stack (MSP) so they are
+//! not charged to the tasks either.
+
+use core::{fmt, str::FromStr};
+use std::collections::HashMap;
+
+use xmas_elf::{
+    sections::SectionData,
+    symbol_table::{Entry as _, Type},
+    ElfFile,
+};
+
+use crate::{erasure::glob, ir::Define, symbol::Symbol, Local, Max};
+
+// the vector tables, as named by `cortex-m-rt` and the device crates
+const VECTOR_TABLES: &[&str] = &["__EXCEPTIONS", "__INTERRUPTS"];
+
+/// A task entry function and its stack: `ENTRY[=STACK]`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Task {
+    // the entry function
+    entry: String,
+    // the `static` the task uses as its stack
+    stack: Option<String>,
+}
+
+impl Task {
+    /// Whether `function` is the entry function of this task
+    ///
+    /// `function` is a demangled name without hash (`{:#}`)
+    pub fn matches(&self, function: &str) -> bool {
+        glob(&self.entry, function)
+    }
+
+    /// The name of the `static` the task uses as its stack, if given
+    pub fn stack(&self) -> Option<&str> {
+        self.stack.as_deref()
+    }
+}
+
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.entry)?;
+        if let Some(stack) = &self.stack {
+            write!(f, "={}", stack)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a user-declared task: `ENTRY[=STACK]`
+///
+/// `ENTRY` is a demangled function name (without hash) where `*` matches any sequence of
+/// characters; `STACK` is the name of the `static` array the task runs on, e.g.
+/// `app::blink=app::BLINK_STACK`
+impl FromStr for Task {
+    type Err = failure::Error;
+
+    fn from_str(s: &str) -> Result<Self, failure::Error> {
+        let mut parts = s.splitn(2, '=').map(str::trim);
+        match (parts.next(), parts.next()) {
+            (Some(entry), stack) if !entry.is_empty() && stack != Some("") => Ok(Task {
+                entry: entry.to_owned(),
+                stack: stack.map(str::to_owned),
+            }),
+
+            _ => Err(failure::format_err!(
+                "malformed task `{}`; expected `ENTRY` or `ENTRY=STACK`",
+                s
+            )),
+        }
+    }
+}
+
+/// The stack of a task
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stack {
+    /// The `static` array; demangled and without hash
+    pub symbol: String,
+    /// Its size in bytes
+    pub size: u64,
+    /// The exception frame the hardware pushes onto this stack when a handler preempts the task;
+    /// see `Options::exception_frame`
+    pub frame: u64,
+}
+
+impl Stack {
+    /// How much of this stack is left when the task uses `max` bytes of it and gets preempted
+    pub fn free(&self, max: Max) -> Free {
+        Free::new(max + Local::Exact(self.frame), self.size)
+    }
+}
+
+/// Unused stack in the worst case
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Free {
+    /// Exactly this many bytes
+    Exact(u64),
+    /// At most this many bytes; the worst-case stack usage is a lower bound
+    AtMost(u64),
+    /// The task overflows its stack by this many bytes
+    Overflow(Max),
+}
+
+impl Free {
+    /// How much of a stack of `size` bytes is left when `max` bytes of it are used
+    pub fn new(max: Max, size: u64) -> Self {
+        match max {
+            Max::Exact(n) if n <= size => Free::Exact(size - n),
+            Max::LowerBound(n) if n <= size => Free::AtMost(size - n),
+            Max::Exact(n) => Free::Overflow(Max::Exact(n - size)),
+            Max::LowerBound(n) => Free::Overflow(Max::LowerBound(n - size)),
+        }
+    }
+
+    /// Whether the stack overflows
+    pub fn is_overflow(&self) -> bool {
+        matches!(self, Free::Overflow(_))
+    }
+}
+
+impl fmt::Display for Free {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Free::Exact(n) => write!(f, "{} free", n),
+            Free::AtMost(n) => write!(f, "at most {} free", n),
+            Free::Overflow(Max::Exact(n)) => write!(f, "overflows by {}", n),
+            Free::Overflow(Max::LowerBound(n)) => write!(f, "overflows by at least {}", n),
+        }
+    }
+}
+
+/// What a function is the entry point of, other than `main`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Entry {
+    /// A task; it runs on its own stack, whose size is known if the array could be found
+    Task(Option<Stack>),
+    /// An interrupt or exception handler; it runs on the main stack
+    Handler,
+    /// The function an async executor calls to poll a task, and the `async fn` of the task if
+    /// known; see `futures`
+    Future(Option<String>),
+}
+
+/// Finds the tasks started by calls to the `spawns` functions: the functions passed to them are
+/// the entry functions and the largest of the other `static`s passed to them is the stack
+///
+/// `is_function` tells functions and other globals apart; `sizes` are the sizes of the `static`s
+pub fn spawned<'a>(
+    defines: impl Iterator<Item = &'a Define<'a>>,
+    spawns: &[String],
+    is_function: impl Fn(&str) -> bool,
+    sizes: &HashMap<&str, u64>,
+) -> Vec<(&'a str, Option<&'a str>)> {
+    let mut tasks = vec![];
+    for define in defines {
+        for (callee, globals) in &define.calls {
+            let callee = Symbol::new(callee).path;
+            if !spawns.iter().any(|spawn| glob(spawn, &callee)) {
+                continue;
+            }
+
+            let stack = globals
+                .iter()
+                .filter(|global| !is_function(global))
+                .filter_map(|global| sizes.get(global).map(|size| (*global, *size)))
+                .max_by_key(|(_, size)| *size)
+                .map(|(global, _)| global);
+
+            for entry in globals.iter().filter(|global| is_function(global)) {
+                tasks.push((*entry, stack));
+            }
+        }
+    }
+
+    tasks
+}
+
+/// Whether the global `name` is a vector table; the functions stored in it are handlers
+pub fn is_vector_table(name: &str) -> bool {
+    VECTOR_TABLES.contains(&name)
+}
+
+/// The sizes of the data objects (e.g. `static`s) of the ELF file
+pub fn sizes<'a>(elf: &ElfFile<'a>) -> HashMap<&'a str, u64> {
+    let mut sizes = HashMap::new();
+    if let Some(Ok(data)) = elf
+        .find_section_by_name(".symtab")
+        .map(|sect| sect.get_data(elf))
+    {
+        let mut insert = |name: Result<&'a str, _>, ty: Result<Type, _>, size| {
+            if let (Ok(name), Ok(Type::Object)) = (name, ty) {
+                sizes.insert(name, size);
+            }
+        };
+
+        match data {
+            SectionData::SymbolTable32(entries) => {
+                for entry in entries {
+                    insert(entry.get_name(elf), entry.get_type(), entry.size());
+                }
+            }
+            SectionData::SymbolTable64(entries) => {
+                for entry in entries {
+                    insert(entry.get_name(elf), entry.get_type(), entry.size());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    sizes
+}
+
+/// Looks up the `static` named `name`; mangled, demangled and demangled without hash names are
+/// accepted
+pub fn find<'a>(sizes: &HashMap<&'a str, u64>, name: &str) -> Option<(&'a str, u64)> {
+    sizes
+        .get_key_value(name)
+        .or_else(|| {
+            sizes
+                .iter()
+                .find(|(symbol, _)| Symbol::new(symbol).is(name))
+        })
+        .map(|(symbol, size)| (*symbol, *size))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{Free, Stack, Task};
+    use crate::{
+        ir::{Define, FnSig},
+        Max,
+    };
+
+    #[test]
+    fn from_str() {
+        let task = "app::blink = app::BLINK_STACK".parse::<Task>().unwrap();
+        assert_eq!(task.stack(), Some("app::BLINK_STACK"));
+        assert_eq!(task.to_string(), "app::blink=app::BLINK_STACK");
+        assert!(task.matches("app::blink"));
+        assert!(!task.matches("app::idle"));
+
+        let task = "app::tasks::*".parse::<Task>().unwrap();
+        assert_eq!(task.stack(), None);
+        assert!(task.matches("app::tasks::idle"));
+
+        assert!("".parse::<Task>().is_err());
+        assert!("app::blink=".parse::<Task>().is_err());
+        assert!("=app::BLINK_STACK".parse::<Task>().is_err());
+    }
+
+    #[test]
+    fn free() {
+        let stack = Stack {
+            symbol: "app::BLINK_STACK".to_owned(),
+            size: 1024,
+            frame: 0,
+        };
+
+        assert_eq!(stack.free(Max::Exact(1000)), Free::Exact(24));
+        assert_eq!(stack.free(Max::LowerBound(1000)), Free::AtMost(24));
+        assert_eq!(stack.free(Max::Exact(1040)), Free::Overflow(Max::Exact(16)));
+        assert_eq!(
+            stack.free(Max::LowerBound(1040)).to_string(),
+            "overflows by at least 16"
+        );
+
+        // the exception frame of a handler that preempts the task doesn't fit
+        let stack = Stack { frame: 32, ..stack };
+        assert_eq!(stack.free(Max::Exact(1000)), Free::Overflow(Max::Exact(8)));
+    }
+
+    #[test]
+    fn spawned() {
+        // `os::spawn(blink, "blink", &mut BLINK_STACK)`
+        let main = Define {
+            name: "main",
+            internal: false,
+            sig: FnSig {
+                inputs: vec![],
+                output: None,
+                variadic: false,
+            },
+            stmts: vec![],
+            references: vec![],
+            stores: vec![],
+            calls: vec![
+                (
+                    "os::spawn",
+                    vec!["app::blink", "alloc_0", "app::BLINK_STACK"],
+                ),
+                ("os::start", vec![]),
+            ],
+            personality: None,
+        };
+
+        let mut sizes = HashMap::new();
+        sizes.insert("alloc_0", 5);
+        sizes.insert("app::BLINK_STACK", 1024);
+
+        assert_eq!(
+            super::spawned(
+                [main].iter(),
+                &["os::spawn*".to_owned()],
+                |name| name == "app::blink",
+                &sizes
+            ),
+            vec![("app::blink", Some("app::BLINK_STACK"))]
+        );
+    }
+}
@@ -1,30 +1,26 @@
+use core::{cmp, fmt};
+use std::collections::HashSet;
+
+pub mod asm;
+mod decode;
+
+pub use self::decode::Arch;
+use self::decode::{Instruction, Op};
+
+const LR: u8 = 14;
+const PC: u8 = 15;
+
 /// Analyzes a subroutine and returns all the `BL` and `B` instructions in it, plus whether this
 /// function performs an indirect function call or not
-// NOTE we assume that `bytes` is always valid input so all errors are bugs
-// Reference: ARMv7-M Architecture Reference Manual (ARM DDI 0403E.b)
-// Reference: ARMv6-M Architecture Reference Manual (ARM DDI 0419D)
+///
+/// Encodings that can't be decoded are reported as an `Error`; the caller decides whether that's
+/// fatal or not
 pub fn analyze(
     bytes: &[u8],
     address: u32,
-    v7: bool,
+    arch: Arch,
     tags: &[(u32, Tag)],
-) -> (Vec<i32>, Vec<i32>, bool, bool, Option<u64>) {
-    macro_rules! bug {
-        ($first:expr) => {
-            panic!(
-                "BUG: unknown instruction {:02x}{:02x}",
-                $first[1], $first[0]
-            );
-        };
-
-        ($first:expr, $second:expr) => {
-            panic!(
-                "BUG: unknown instruction {:02x}{:02x} {:02x}{:02x}",
-                $first[1], $first[0], $second[1], $second[0]
-            );
-        };
-    }
-
+) -> Result<Analysis, Error> {
     // we want to know if any of the instructions modifies the SP (stack pointer). We use this
     // information to determine if the subroutine uses stack space or not. We want to detect the
     // following instructions:
@@ -40,566 +36,360 @@ pub fn analyze(
     // that reason we'll give up the analysis if we encounter conditionals or loops, i.e.
     // intra-branching, within the function. Analyzing those functions would be more work and won't
     // help with our main goal of analyzing trampolines.
-    let mut stack = Some(0);
+    //
+    // `sp` is the running offset of the SP from its value on entry; `stack` is the lowest offset
+    // seen so far, i.e. minus the peak stack usage
+    let mut sp = 0i64;
+    let mut stack = Some(0i64);
 
-    // we want to avoid writing a full blown decoder since we are only interested in a single type
-    // of instruction. We know that instructions can be 16-bit or 32-bit so we'll only decode 16-bit
-    // instructions and assume that the rest are 32-bit instructions.
-    // NOTE this implementation has been optimized to be easy to write, not to be high-performance
     let mut bls = vec![];
     let mut bs = vec![];
     let mut indirect = false;
-    let mut halfwords = bytes.chunks_exact(2).zip(0i32..);
-    while let Some((first, i)) = halfwords.next() {
-        let start = address + 2 * i as u32;
-        if let Ok(needle) = tags.binary_search_by(|(addr, _)| addr.cmp(&start)) {
-            if tags[needle].1 == Tag::Data {
-                // start of a data section
 
-                if let Some(tag) = tags.get(needle + 1) {
-                    assert_eq!(
-                        tag.1,
-                        Tag::Thumb,
-                        "BUG: expected a thumb tag at {:#10x} but found another data tag",
-                        tag.0
-                    );
+    // TrustZone: secure gateways (`SG`) are entered from the non-secure state and `BLXNS` /
+    // `BXNS` leave the secure state. Code on the other side runs on its own stack
+    let mut gateway = false;
+    let mut non_secure = false;
+
+    // lightweight constant propagation used to resolve indirect calls like `ldr r0, =sym; blx r0`
+    // and `movw r12, #lo; movt r12, #hi; bx r12`. A register keeps its constant until an
+    // instruction writes to it or control flow joins: values known on one path may not hold on the
+    // others so the constants are forgotten after unconditional branches and returns, and at the
+    // targets of intra-function branches
+    let mut consts = [None; 16];
+    let mut blxs = vec![];
+
+    // number of instructions left in the current IT block. Conditional instructions may or may not
+    // execute so they can only invalidate constants
+    let mut it = 0;
+
+    let insns = decode_all(bytes, address, arch, tags)?;
+    let targets = insns
+        .iter()
+        .filter_map(|(offset, insn)| match insn.op {
+            Op::Branch(imm32) | Op::CondBranch(imm32) => Some(*offset as i32 + 4 + imm32),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    for (offset, insn) in insns {
+        let Instruction { op, writes, .. } = insn;
+        let start = address + offset as u32;
+
+        if targets.contains(&(offset as i32)) {
+            consts = [None; 16];
+        }
 
-                    // skip the data section
-                    let end = tag.0;
-                    // NOTE the range starts at 1 because we'll skip `first` using the `continue`
-                    // that comes after this `for` loop
-                    for _ in 1..(end - start) / 2 {
-                        halfwords.next();
-                    }
+        let conditional = it != 0;
+        if conditional {
+            it -= 1;
+        }
 
-                    continue;
-                } else {
-                    // continues until the end of the binary; we won't find more instructions so
-                    // let's stop decoding
-                    break;
-                }
+        let known = consts;
+        for (reg, value) in consts.iter_mut().enumerate() {
+            if writes & (1 << reg) != 0 {
+                *value = None;
             }
         }
 
-        if matches(first, "0b010000_0101_xxx_xxx") {
-            // A7.7.2 ADC (register) - T1
-            continue;
-        } else if matches(first, "0b000_11_1_0_xxx_xxx_xxx") {
-            // A7.7.3 ADD (immediate) - T1
-            continue;
-        } else if matches(first, "0b001_10_xxx_xxxxxxxx") {
-            // A7.7.3 ADD (immediate) - T2
-            continue;
-        } else if matches(first, "0b000_11_0_0_xxx_xxx_xxx") {
-            // A7.7.4 ADD (register) - T1
-            continue;
-        } else if matches(first, "0b010001_00_x_xxxx_xxx") {
-            // A7.7.4 ADD (register) - T2
-            continue;
-        } else if matches(first, "0b1010_1_xxx_xxxxxxxx") {
-            // A7.7.5  ADD (SP plus immediate) - T1
-            continue;
-        } else if matches(first, "0b1011_0000_0_xxxxxxx") {
-            // A7.7.5  ADD (SP plus immediate) - T2
-            continue;
-        } else if matches(first, "0b01000100_x_1101_xxx") {
-            // A7.7.6  ADD (SP plus register) - T1
-            continue;
-        } else if matches(first, "0b01000100_1_xxxx_101") {
-            // A7.7.6  ADD (SP plus register) - T2
-            continue;
-        } else if matches(first, "0b1010_0_xxx_xxxxxxxx") {
-            // A7.7.7  ADR - T1
-            continue;
-        } else if matches(first, "0b010000_0000_xxx_xxx") {
-            // A7.7.9  AND (register) - T1
-            continue;
-        } else if matches(first, "0b000_10_xxxxx_xxx_xxx") {
-            // A7.7.10  ASR (immediate) - T1
-            continue;
-        } else if matches(first, "0b010000_0100_xxx_xxx") {
-            // A7.7.11  ASR (register) - T1
-            continue;
-        } else if v7 && matches(first, "0b1101_1110_xxxxxxxx") {
-            // NOTE we break the alphabetical order because the rule for `B` overlaps with the rule
-            // for `UDF` but `UDF` takes precedence
-            // A7.7.191      UDF - T1
-            continue;
-        } else if matches(first, "0b1101_1111_xxxxxxxx") {
-            // NOTE we break the alphabetical order because the rule for `B` overlaps with the rule
-            // for `SVC` but `SVC` takes precedence
-            // A7.7.175      SVC - T1
-            continue;
-        } else if matches(first, "0b1101_xxxx_xxxxxxxx") {
-            // A7.7.12  B - T1
-            let cond = first[1] & 0b1111;
-            assert_ne!(cond, 0b1110); // UDF
-            assert_ne!(cond, 0b1111); // SVC
-            let imm8 = first[0] as i32;
-            let mut imm32 = sign_extend(imm8 << 1, 9);
-
-            // offset is computed from the address of the *next* instruction; adjust
-            // accordingly
-            // (it's unclear to me why this needs to be `4` instead of `2` but that's what works)
-            imm32 += 2 * i + 4;
-
-            if imm32 >= 0 && (imm32 as usize) < bytes.len() {
-                // this is an `if` or `loop`; give up the stack usage analysis
-                stack = None;
-            }
+        // offset of the PC, relative to the start of the subroutine. Branch offsets are computed
+        // from it
+        let pc = offset as i32 + 4;
+        // `Align(PC, 4)`; base address of `LDR (literal)` and `ADR`
+        let base = align4(start + 4);
 
-            bs.push(imm32);
-        } else if matches(first, "0b11100_xxxxxxxxxxx") {
-            // A7.7.12  B - T2
-            let imm11 = (i32::from(first[1] & 0b111) << 8) | first[0] as i32;
-            let mut imm32 = sign_extend(imm11 << 1, 12);
+        // the value of the register written by a constant-producing instruction, if known
+        let constant = match op {
+            Op::Branch(imm32) | Op::CondBranch(imm32) => {
+                let target = pc + imm32;
 
-            // offset is computed from the address of the *next* instruction; adjust
-            // accordingly
-            // (it's unclear to me why this needs to be `4` instead of `2` but that's what works)
-            imm32 += 2 * i + 4;
+                if target >= 0 && (target as usize) < bytes.len() {
+                    // this is an `if` or `loop`; give up the stack usage analysis
+                    stack = None;
+                }
 
-            if imm32 >= 0 && (imm32 as usize) < bytes.len() {
-                // this is an `if` or `loop`; give up the stack usage analysis
-                stack = None;
+                bs.push(i64::from(target));
+                None
             }
 
-            bs.push(imm32);
-        } else if matches(first, "0b010000_1110_xxx_xxx") {
-            // A7.7.16  BIC (register) - T1
-            continue;
-        } else if matches(first, "0b1010_1110_xxxxxxxx") {
-            // A7.7.17  BKPT - T1
-            continue;
-        } else if matches(first, "0b010001_11_1_xxxx_000") {
-            // A7.7.19  BLX (register) - T1
-            indirect = true;
-        } else if matches(first, "0b010001_11_0_xxxx_000") {
-            // A7.7.20  BX - T1
-            let rm = (first[0] >> 3) & 0b1111;
-
-            // `bx lr` is just a `return`
-            if rm != 0b1110 {
-                indirect = true;
-            }
-        } else if v7 && matches(first, "0b1011_x_0_x_1_xxxxx_xxx") {
-            // A7.7.21  CBNZ, CBZ - T1
-            continue;
-        } else if matches(first, "0b010000_1011_xxx_xxx") {
-            // A7.7.26  CMN (register) - T1
-            continue;
-        } else if matches(first, "0b001_01_xxx_xxxxxxxx") {
-            // A7.7.27  CMP (immediate) - T1
-            continue;
-        } else if matches(first, "0b010000_1010_xxx_xxx") {
-            // A7.7.28  CMP (register) - T1
-            continue;
-        } else if matches(first, "0b010001_01_x_xxxx_xxx") {
-            // A7.7.28  CMP (register) - T2
-            continue;
-        } else if matches(first, "0b1011_0110_011_x_00_xx") {
-            // A7.7.29  CPS - T1
-            continue;
-        } else if matches(first, "0b010000_0001_xxx_xxx") {
-            // A7.7.35  EOR (register) - T1
-            continue;
-        } else if v7 && matches(first, "0b1011_1111_xxxx_xxxx") {
-            // A7.7.37  IT - T1
-            continue;
-        } else if matches(first, "0b1100_1_xxx_xxxxxxxx") {
-            // A7.7.40  LDM, LDMIA, LDMFD - T1
-            continue;
-        } else if matches(first, "0b011_0_1_xxxxx_xxx_xxx") {
-            // A7.7.42  LDR (immediate) - T1
-            continue;
-        } else if matches(first, "0b1001_1_xxx_xxxxxxxx") {
-            // A7.7.42  LDR (immediate) - T2
-            continue;
-        } else if matches(first, "0b01001_xxx_xxxxxxxx") {
-            // A7.7.43  LDR (literal) - T1
-            continue;
-        } else if matches(first, "0b0101_100_xxx_xxx_xxx") {
-            // A7.7.44  LDR (register) - T1
-            continue;
-        } else if matches(first, "0b011_1_1_xxxxx_xxx_xxx") {
-            // A7.7.45  LDRB (immediate) - T1
-            continue;
-        } else if matches(first, "0b0101_110_xxx_xxx_xxx") {
-            // A7.7.47  LDRB (register) - T1
-            continue;
-        } else if matches(first, "0b1000_1_xxxxx_xxx_xxx") {
-            // A7.7.54  LDRH (immediate) - T1
-            continue;
-        } else if matches(first, "0b0101_101_xxx_xxx_xxx") {
-            // A7.7.56  LDRH (register) - T1
-            continue;
-        } else if matches(first, "0b0101_011_xxx_xxx_xxx") {
-            // A7.7.60  LDRSB (register) - T1
-            continue;
-        } else if matches(first, "0b0101_111_xxx_xxx_xxx") {
-            // A7.7.64  LDRSH (register) - T1
-            continue;
-        } else if matches(first, "0b000_00_xxxxx_xxx_xxx") {
-            // A7.7.67  LSL (immediate) - T1
-            continue;
-        } else if matches(first, "0b010000_0010_xxx_xxx") {
-            // A7.7.68  LSL (register) - T1
-            continue;
-        } else if matches(first, "0b000_01_xxxxx_xxx_xxx") {
-            // A7.7.69  LSR (immediate) - T1
-            continue;
-        } else if matches(first, "0b010000_0011_xxx_xxx") {
-            // A7.7.70  LSR (register) - T1
-            continue;
-        } else if matches(first, "0b001_00_xxx_xxxxxxxx") {
-            // A7.7.75  MOV (immediate) - T1
-            continue;
-        } else if matches(first, "0b010001_10_x_xxxx_xxx") {
-            // A7.7.76  MOV (register) - T1
-            continue;
-        } else if matches(first, "0b000_00_00000_xxx_xxx") {
-            // A7.7.76  MOV (register) - T2
-            continue;
-        } else if matches(first, "0b010000_1101_xxx_xxx") {
-            // A7.7.83  MUL - T1
-            continue;
-        } else if matches(first, "0b010000_1111_xxx_xxx") {
-            // A7.7.85  MVN (register) - T1
-            continue;
-        } else if matches(first, "0b1011_1111_0000_0000") {
-            // A7.7.87  NOP - T1 (in ARMv7-M-ARM)
-            // A6.7.47  NOP - T1 (in ARMv6-M-ARM)
-            continue;
-        } else if matches(first, "0b010000_1100_xxx_xxx") {
-            // A7.7.91  ORR (register) - T1
-            continue;
-        } else if matches(first, "0b1011_1_10_x_xxxxxxxx") {
-            // A7.7.98  POP - T1
-            continue;
-        } else if matches(first, "0b1011_0_10_x_xxxxxxxx") {
-            // A7.7.99  PUSH - T1
-            // e.g. 'b580            push    {r7, lr}'
-            modifies_sp = true;
-
-            let m = first[1] & 1;
-            let register_list = first[0];
-            let register = (u16::from(m) << 14) | u16::from(register_list);
-            if let Some(stack) = stack.as_mut() {
-                *stack += 4 * u64::from(register.count_ones());
+            Op::Call(imm32) => {
+                bls.push(i64::from(pc + imm32));
+                None
             }
 
-            continue;
-        } else if matches(first, "0b1011_1010_00_xxx_xxx") {
-            // A7.7.111  REV - T1
-            continue;
-        } else if matches(first, "0b1011_1010_01_xxx_xxx") {
-            // A7.7.112      REV16 - T1
-            continue;
-        } else if matches(first, "0b1011_1010_11_xxx_xxx") {
-            // A7.7.113      REVSH - T1
-            continue;
-        } else if matches(first, "0b010000_0111_xxx_xxx") {
-            // A7.7.115      ROR (register) - T1
-            continue;
-        } else if matches(first, "0b010000_1001_xxx_xxx") {
-            // A7.7.117  RSB (immediate) - T1
-            continue;
-        } else if matches(first, "0b010000_0110_xxx_xxx") {
-            // A7.7.123      SBC (register) - T1
-            continue;
-        } else if matches(first, "0b1011_1111_0100_0000") {
-            // A7.7.127      SEV - T1
-            continue;
-        } else if matches(first, "0b1100_0_xxx_xxxxxxxx") {
-            // A7.7.156      STM, STMIA, STMEA - T1
-            continue;
-        } else if matches(first, "0b011_0_0_xxxxx_xxx_xxx") {
-            // A7.7.158      STR (immediate) - T1
-            continue;
-        } else if matches(first, "0b1001_0_xxx_xxxxxxxx") {
-            // A7.7.158      STR (immediate) - T2
-            continue;
-        } else if matches(first, "0b0101_000_xxx_xxx_xxx") {
-            // A7.7.159      STR (register) - T1
-            continue;
-        } else if matches(first, "0b011_1_0_xxxxx_xxx_xxx") {
-            // A7.7.160      STRB (immediate) - T1
-            continue;
-        } else if matches(first, "0b0101_010_xxx_xxx_xxx") {
-            // A7.7.161      STRB (register) - T1
-            continue;
-        } else if matches(first, "0b1000_0_xxxxx_xxx_xxx") {
-            // A7.7.167      STRH (immediate) - T1
-            continue;
-        } else if matches(first, "0b0101_001_xxx_xxx_xxx") {
-            // A7.7.168      STRH (register) - T1
-            continue;
-        } else if matches(first, "0b000_11_1_1_xxx_xxx_xxx") {
-            // A7.7.171      SUB (immediate) - T1
-            continue;
-        } else if matches(first, "0b001_11_xxx_xxxxxxxx") {
-            // A7.7.171      SUB (immediate) - T2
-            continue;
-        } else if matches(first, "0b000_11_0_1_xxx_xxx_xxx") {
-            // A7.7.172      SUB (register) - T1
-            continue;
-        } else if matches(first, "0b1011_0000_1_xxxxxxx") {
-            // A7.7.173      SUB (SP minus immediate) - T1
-            // e.g. 'b081            sub     sp, #4'
-            modifies_sp = true;
-
-            let imm7 = first[0] & 0b0111_1111;
-            let imm32 = u32::from(imm7) << 2;
-
-            if let Some(stack) = stack.as_mut() {
-                *stack += u64::from(imm32);
+            Op::CallRegister(rm) => {
+                if let Some(target) = known[usize::from(rm)] {
+                    blxs.push(u64::from(target & !1));
+                } else {
+                    indirect = true;
+                }
+                None
             }
 
-            continue;
-        } else if matches(first, "0b1011_0010_01_xxx_xxx") {
-            // A7.7.179      SXTB - T1
-            continue;
-        } else if matches(first, "0b1011_0010_00_xxx_xxx") {
-            // A7.7.181      SXTH - T1
-            continue;
-        } else if matches(first, "0b010000_1000_xxx_xxx") {
-            // A7.7.186      TST (register) - T1
-            continue;
-        } else if matches(first, "0b1011_0010_11_xxx_xxx") {
-            // A7.7.218      UXTB - T1
-            continue;
-        } else if matches(first, "0b1011_0010_10_xxx_xxx") {
-            // A7.7.220      UXTH - T1
-            continue;
-        } else if matches(first, "0b1011_1111_0010_0000") {
-            // A7.7.258      WFE - T1
-            continue;
-        } else if matches(first, "0b1011_1111_0011_0000") {
-            // A7.7.259      WFI - T1
-            continue;
-        } else if matches(first, "0b1011_1111_0001_0000") {
-            // A7.7.260      YIELD - T1
-            continue;
-        } else {
-            let second = halfwords.next().unwrap_or_else(|| bug!(first)).0;
-
-            const SP: u8 = 0b1101;
-
-            if v7
-                && matches(first, "0b11101_00_100_x_0_xxxx")
-                && matches(second, "0b0_x_0_xxxxxxxxxxxxx")
-            {
-                // A7.7.157      STMDB, STMFD
-                // e.g. 'e92d 41f0       stmdb   sp!, {r4, r5, r6, r7, r8, lr}'
-                let rn = first[0] & 0b1111;
-                if rn == SP {
-                    modifies_sp = true;
-
-                    let register_list =
-                        ((u16::from(second[1]) & 0b0001_1111) << 8) | u16::from(second[0]);
-                    let m = (second[1] >> 6) & 1;
-                    let registers = (u16::from(m) << 14) | register_list;
-
-                    if let Some(stack) = stack.as_mut() {
-                        *stack += 4 * u64::from(registers.count_ones());
+            Op::BranchRegister(rm) => {
+                // `bx lr` is just a `return`
+                if rm != LR {
+                    if let Some(target) = known[usize::from(rm)] {
+                        // tail call
+                        blxs.push(u64::from(target & !1));
+                    } else {
+                        indirect = true;
                     }
                 }
-            } else if v7
-                && matches(first, "0b11110_x_0_1101_x_1101")
-                && matches(second, "0b0_xxx_xxxx_xxxxxxxx")
-            {
-                // A7.7.173      SUB (SP minus immediate) - T2
-                let rd = second[1] & 0b1111;
-                if rd == SP {
-                    modifies_sp = true;
-
-                    let imm8 = second[0];
-                    let imm3 = (second[1] >> 4) & 0b0111;
-                    let i = (first[1] >> 2) & 1;
-                    let imm32 = thumb_expand_imm(
-                        (u16::from(i) << 11) | (u16::from(imm3) << 8) | u16::from(imm8),
-                    );
-
-                    if let Some(stack) = stack.as_mut() {
-                        *stack += u64::from(imm32);
-                    }
+                None
+            }
+
+            Op::SecureGateway => {
+                gateway = true;
+                None
+            }
+
+            Op::NonSecureCall(_) => {
+                non_secure = true;
+                None
+            }
+
+            Op::NonSecureBranch(rm) => {
+                // `bxns lr` returns to the non-secure caller
+                if rm != LR {
+                    non_secure = true;
                 }
-            } else if v7
-                && matches(first, "0b1110_110_1_0_x_1_0_1101")
-                && matches(second, "0bxxxx_1011_xxxxxxxx")
-            {
-                // A7.7.249      VPUSH - T1
-                modifies_sp = true;
+                None
+            }
+
+            Op::ComputedBranch => {
+                // e.g. `ldr pc, [r0]`: either a jump table or an indirect tail call
+                indirect = true;
+                stack = None;
+                None
+            }
 
-                let imm8 = second[0] & 0b1111_1111;
-                let imm32 = u32::from(imm8) << 2;
+            Op::TableBranch => {
+                // a `match` lowered to a jump table
+                stack = None;
+                None
+            }
 
+            Op::Return | Op::Other => None,
+
+            Op::Alloc(imm32) => {
+                modifies_sp = true;
+
+                sp -= i64::from(imm32);
                 if let Some(stack) = stack.as_mut() {
-                    *stack += u64::from(imm32);
+                    *stack = cmp::min(*stack, sp);
                 }
-            } else if v7
-                && matches(first, "0b1110_110_1_0_x_1_0_1101")
-                && matches(second, "0bxxxx_1010_xxxxxxxx")
-            {
-                // A7.7.249      VPUSH - T2
+                None
+            }
+
+            Op::Free(imm32) => {
+                sp += i64::from(imm32);
+                None
+            }
+
+            Op::DynamicAlloc => {
                 modifies_sp = true;
+                stack = None;
+                None
+            }
 
-                let imm8 = second[0] & 0b1111_1111;
-                let imm32 = u32::from(imm8) << 2;
+            Op::IfThen(n) => {
+                it = n;
+                None
+            }
 
-                if let Some(stack) = stack.as_mut() {
-                    *stack += u64::from(imm32);
-                }
-            } else if v7
-                && matches(first, "0b11110_x_xxxxxxxxxx")
-                && matches(second, "0b10_x_0_x_xxxxxxxxxxx")
-            {
-                // A7.7.12  B - T3
-
-                let cond = ((first[1] & 0b11) << 2) | (first[0] >> 6);
-                if cond >> 1 == 0b111 {
-                    // MSR instruction
-                    continue;
+            // e.g. '4801            ldr     r0, [pc, #4]'
+            Op::LoadLiteral(rt, imm32) => {
+                let value = base
+                    .checked_add(imm32 as u32)
+                    .and_then(|addr| literal(bytes, address, addr));
+
+                if rt == PC {
+                    // tail call through the literal pool
+                    if let Some(target) = value {
+                        blxs.push(u64::from(target & !1));
+                    } else {
+                        indirect = true;
+                    }
                 }
 
-                let s = (first[1] >> 2) & 1 == 1;
-                let imm6 = (first[0] & 0b111111) as i32;
-                let j1 = second[1] & (1 << 5) != 0;
-                let j2 = second[1] & (1 << 3) != 0;
-                let imm11 = (i32::from(second[1] & 0b111) << 8) | second[0] as i32;
-                let imm21 = (if s { 1 } else { 0 } << 20)
-                    | (if j1 { 1 } else { 0 } << 19)
-                    | (if j2 { 1 } else { 0 } << 18)
-                    | (imm6 << 12)
-                    | (imm11 << 1);
+                value.map(|value| (rt, value))
+            }
 
-                let mut imm32 = sign_extend(imm21, 21);
+            Op::MovImm(rd, imm32) => Some((rd, imm32)),
 
-                // offset is computed from the address of the *next* instruction; adjust
-                // accordingly
-                imm32 += 2 * i + 4;
+            // e.g. 'f2c0 0c00       movt    ip, #0'
+            Op::MovTop(rd, imm16) => {
+                known[usize::from(rd)].map(|lo| (rd, (u32::from(imm16) << 16) | (lo & 0xffff)))
+            }
 
-                if imm32 >= 0 && (imm32 as usize) < bytes.len() {
-                    // this is an `if` or `loop`; give up the stack usage analysis
-                    stack = None;
-                }
+            Op::MovReg(rd, rm) => known[usize::from(rm)].map(|value| (rd, value)),
 
-                bs.push(imm32);
-            } else if v7
-                && matches(first, "0b11110_x_xxxxxxxxxx")
-                && matches(second, "0b10_x_1_x_xxxxxxxxxxx")
-            {
-                // A7.7.12  B - T4
+            Op::Adr(rd, imm32) => Some((rd, base.wrapping_add(imm32 as u32))),
+        };
 
-                let s = (first[1] >> 2) & 1 == 1;
-                let imm10 = (i32::from(first[1] & 0b11) << 8) + first[0] as i32;
-                let j1 = second[1] & (1 << 5) != 0;
-                let j2 = second[1] & (1 << 3) != 0;
-                let imm11 = (i32::from(second[1] & 0b111) << 8) + second[0] as i32;
+        if let Some((reg, value)) = constant {
+            if !conditional {
+                consts[usize::from(reg)] = Some(value);
+            }
+        }
 
-                let i1 = if !(j1 ^ s) { 1 } else { 0 };
-                let i2 = if !(j2 ^ s) { 1 } else { 0 };
+        // the next instruction can only be reached through a branch
+        let ends_block = match op {
+            Op::Branch(_)
+            | Op::BranchRegister(_)
+            | Op::NonSecureBranch(_)
+            | Op::ComputedBranch
+            | Op::TableBranch
+            | Op::Return => true,
+            Op::LoadLiteral(rt, _) => rt == PC,
+            _ => false,
+        };
+        if ends_block && !conditional {
+            consts = [None; 16];
+        }
+    }
 
-                let imm25 = (if s { 1 } else { 0 } << 24)
-                    | (i1 << 23)
-                    | (i2 << 22)
-                    | (imm10 << 12)
-                    | (imm11 << 1);
+    Ok(Analysis {
+        bls,
+        bs,
+        blxs,
+        indirect,
+        gateway,
+        non_secure,
+        modifies_sp,
+        stack: stack.map(|min| (-min) as u64),
+    })
+}
 
-                let mut imm32 = sign_extend(imm25, 25);
+// decodes the subroutine, skipping the data embedded in it; returns the offset of each instruction
+fn decode_all(
+    bytes: &[u8],
+    address: u32,
+    arch: Arch,
+    tags: &[(u32, Tag)],
+) -> Result<Vec<(usize, Instruction)>, Error> {
+    let mut insns = vec![];
+    let mut offset = 0;
+    while offset + 2 <= bytes.len() {
+        let start = address + offset as u32;
 
-                // offset is computed from the address of the *next* instruction; adjust
-                // accordingly
-                imm32 += 2 * i + 4;
+        if let Ok(needle) = tags.binary_search_by(|(addr, _)| addr.cmp(&start)) {
+            if tags[needle].1 == Tag::Data {
+                // start of a data section
 
-                if imm32 >= 0 && (imm32 as usize) < bytes.len() {
-                    // this is an `if` or `loop`; give up the stack usage analysis
-                    stack = None;
-                }
+                if let Some(tag) = tags.get(needle + 1) {
+                    if tag.1 != Tag::Thumb {
+                        return Err(Error::UnexpectedTag { address: tag.0 });
+                    }
 
-                bs.push(imm32);
-            } else if matches(first, "0b11110_x_xxxxxxxxxx")
-                && matches(second, "0b11_x_1_x_xxxxxxxxxxx")
-            {
-                // A7.7.18  BL - T1
-
-                let s = (first[1] >> 2) & 1 == 1;
-                let imm10 = (i32::from(first[1] & 0b11) << 8) | i32::from(first[0]);
-                let j1 = (second[1] & (1 << 5)) == 1 << 5;
-                let j2 = (second[1] & (1 << 3)) == 1 << 3;
-                let imm11 = (i32::from(second[1] & 0b111) << 8) | i32::from(second[0]);
-
-                let i1 = if !(j1 ^ s) { 1 } else { 0 };
-                let i2 = if !(j2 ^ s) { 1 } else { 0 };
-                let imm25 = (if s { 1 } else { 0 } << 24)
-                    | (i1 << 23)
-                    | (i2 << 22)
-                    | (imm10 << 12)
-                    | (imm11 << 1);
-
-                let mut imm32 = sign_extend(imm25, 25);
-
-                // offset is computed from the address of the *next* instruction; adjust
-                // accordingly
-                imm32 += 2 * i + 4;
-
-                bls.push(imm32);
-            } else {
-                // some other 32-bit instruction
-                continue;
+                    // skip the data section
+                    offset += (tag.0 - start).max(2) as usize;
+                    continue;
+                } else {
+                    // continues until the end of the binary; we won't find more instructions so
+                    // let's stop decoding
+                    break;
+                }
             }
         }
+
+        let insn = match decode::decode(&bytes[offset..], arch) {
+            Some(insn) => insn,
+            None => return Err(Error::unknown(&bytes[offset..], start)),
+        };
+
+        insns.push((offset, insn));
+        offset += insn.size as usize;
     }
 
-    (bls, bs, indirect, modifies_sp, stack)
+    Ok(insns)
 }
 
-fn matches(bytes: &[u8], pattern: &str) -> bool {
-    assert!(pattern.starts_with("0b"));
+fn align4(addr: u32) -> u32 {
+    addr & !0b11
+}
 
-    let pattern = pattern[2..].replace("_", "");
-    assert_eq!(pattern.len(), 16);
+// reads a word from the literal pool of the subroutine; returns `None` if `addr` is outside the
+// subroutine
+fn literal(bytes: &[u8], address: u32, addr: u32) -> Option<u32> {
+    let offset = addr.checked_sub(address)? as usize;
+    let word = bytes.get(offset..offset + 4)?;
+
+    Some(
+        u32::from(word[0])
+            | u32::from(word[1]) << 8
+            | u32::from(word[2]) << 16
+            | u32::from(word[3]) << 24,
+    )
+}
 
-    let mask1 =
-        u8::from_str_radix(&pattern[..8].replace("0", "1").replace("x", "0"), 2).expect("BUG");
-    let value1 = u8::from_str_radix(&pattern[..8].replace("x", "0"), 2).expect("BUG");
+#[derive(Debug, PartialEq)]
+pub struct Analysis {
+    /// Offsets (relative to the start of the subroutine) of the targets of `BL` instructions
+    pub bls: Vec<i64>,
 
-    let mask2 =
-        u8::from_str_radix(&pattern[8..].replace("0", "1").replace("x", "0"), 2).expect("BUG");
-    let value2 = u8::from_str_radix(&pattern[8..].replace("x", "0"), 2).expect("BUG");
+    /// Offsets (relative to the start of the subroutine) of the targets of `B` instructions
+    pub bs: Vec<i64>,
 
-    let first = bytes[1];
-    let second = bytes[0];
-    (first & mask1 == value1) && (second & mask2 == value2)
-}
+    /// Absolute addresses (thumb bit cleared) of the targets of `BLX` / `BX` instructions that were
+    /// resolved using constant propagation (`ldr rX, =sym` or a `movw` / `movt` pair)
+    pub blxs: Vec<u64>,
+
+    /// Whether the subroutine performs an indirect function call we couldn't resolve
+    pub indirect: bool,
+
+    /// Whether the subroutine is a secure gateway (contains an `SG` instruction)
+    pub gateway: bool,
+
+    /// Whether the subroutine calls, or tail calls, into the non-secure state
+    pub non_secure: bool,
+
+    /// Whether the subroutine modifies the SP register
+    pub modifies_sp: bool,
 
-fn sign_extend(x: i32, nbits: u32) -> i32 {
-    let shift = 32 - nbits;
-    x.wrapping_shl(shift).wrapping_shr(shift)
+    /// Stack usage of the subroutine; `None` if the subroutine contains intra-branches
+    pub stack: Option<u64>,
 }
 
-fn thumb_expand_imm(imm12: u16) -> u32 {
-    if imm12 >> 10 == 0b00 {
-        match (imm12 >> 8) & 0b0011 {
-            0b00 => u32::from(imm12 & 0b0000_1111_1111),
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    // the instruction at `address` could not be decoded
+    UnknownInstruction { address: u32, encoding: String },
 
-            0b01 => u32::from(imm12 & 0b0000_1111_1111) << 8,
+    // expected a `$t` tag at `address` but found another `$d` tag
+    UnexpectedTag { address: u32 },
+}
 
-            0b10 => {
-                let imm8 = u32::from(imm12 & 0b0000_1111_1111);
-                (imm8 << 24) | (imm8 << 8)
+impl Error {
+    fn unknown(bytes: &[u8], address: u32) -> Self {
+        let first = u32::from(bytes[0]) | (u32::from(bytes[1]) << 8);
+        let encoding = match bytes.get(2..4) {
+            Some(second) if decode::is_32bit(first) => {
+                format!("{:04x} {:02x}{:02x}", first, second[1], second[0])
             }
+            _ => format!("{:04x}", first),
+        };
 
-            0b11 => {
-                let imm8 = u32::from(imm12 & 0b0000_1111_1111);
-                (imm8 << 24) | (imm8 << 16) | (imm8 << 8) | imm8
-            }
+        Error::UnknownInstruction { address, encoding }
+    }
+}
 
-            _ => unreachable!(),
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownInstruction { address, encoding } => write!(
+                f,
+                "unknown instruction {} at address {:#010x}",
+                encoding, address
+            ),
+
+            Error::UnexpectedTag { address } => write!(
+                f,
+                "expected a thumb tag at address {:#010x} but found another data tag",
+                address
+            ),
         }
-    } else {
-        let imm8 = (1 << 7) | u32::from(imm12) & 0b0111_1111;
-        imm8.rotate_right(u32::from(imm12) >> 7)
     }
 }
 
@@ -617,57 +407,234 @@ mod tests {
     #[test]
     fn sanity() {
         assert_eq!(
-            super::analyze(&[0xff, 0xf7, 0xe4, 0xfe], 0, false, &[]).0,
+            super::analyze(&[0xff, 0xf7, 0xe4, 0xfe], 0, super::Arch::V6M, &[])
+                .unwrap()
+                .bls,
             vec![-568 + 4]
         );
 
         assert_eq!(
-            super::analyze(&[0x00, 0xf0, 0x2a, 0xfa], 0, false, &[]).0,
+            super::analyze(&[0x00, 0xf0, 0x2a, 0xfa], 0, super::Arch::V6M, &[])
+                .unwrap()
+                .bls,
             vec![1108 + 4]
         );
 
         assert_eq!(
-            super::analyze(&[0x03, 0xe2], 0, false, &[]).1,
+            super::analyze(&[0x03, 0xe2], 0, super::Arch::V6M, &[])
+                .unwrap()
+                .bs,
             vec![1030 + 4]
         );
 
         // UDF
         assert_eq!(
-            super::analyze(&[0xfe, 0xde], 0, true, &[]),
-            (vec![], vec![], false, false, Some(0))
+            super::analyze(&[0xfe, 0xde], 0, super::Arch::V7M, &[]).unwrap(),
+            super::Analysis {
+                bls: vec![],
+                bs: vec![],
+                blxs: vec![],
+                indirect: false,
+                gateway: false,
+                non_secure: false,
+                modifies_sp: false,
+                stack: Some(0),
+            }
         );
+
+        // the first half of `stmdb` with nothing after it
+        assert_eq!(
+            super::analyze(&[0x00, 0xbf, 0x2d, 0xe9], 0x100, super::Arch::V7M, &[]),
+            Err(super::Error::UnknownInstruction {
+                address: 0x102,
+                encoding: "e92d".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn constants() {
+        // 4801            ldr     r0, [pc, #4]
+        // 4780            blx     r0
+        // bf00            nop
+        // bf00            nop
+        //                 .word   0x00000401
+        let ldr = super::analyze(
+            &[
+                0x01, 0x48, 0x80, 0x47, 0x00, 0xbf, 0x00, 0xbf, 0x01, 0x04, 0x00, 0x00,
+            ],
+            0,
+            super::Arch::V6M,
+            &[],
+        )
+        .unwrap();
+        assert!(!ldr.indirect);
+        assert_eq!(ldr.blxs, vec![0x400]);
+
+        // f240 4c01       movw    ip, #1025
+        // f2c0 0c01       movt    ip, #1
+        // 4760            bx      ip
+        let movw = super::analyze(
+            &[0x40, 0xf2, 0x01, 0x4c, 0xc0, 0xf2, 0x01, 0x0c, 0x60, 0x47],
+            0,
+            super::Arch::V7M,
+            &[],
+        )
+        .unwrap();
+        assert!(!movw.indirect);
+        assert_eq!(movw.blxs, vec![0x1_0400]);
+
+        // 4801            ldr     r0, [pc, #4]
+        // 6800            ldr     r0, [r0, #0]
+        // 4780            blx     r0
+        let clobbered = super::analyze(
+            &[
+                0x01, 0x48, 0x00, 0x68, 0x80, 0x47, 0x00, 0xbf, 0x01, 0x04, 0x00, 0x00,
+            ],
+            0,
+            super::Arch::V6M,
+            &[],
+        )
+        .unwrap();
+        assert!(clobbered.indirect);
+        assert!(clobbered.blxs.is_empty());
+
+        // d002            beq     #8
+        // 4903            ldr     r1, [pc, #12]
+        // e001            b       #10
+        // bf00            nop
+        // 4902            ldr     r1, [pc, #8]
+        // 4788            blx     r1
+        // bf00            nop
+        // bf00            nop
+        //                 .word   0x00000401
+        //                 .word   0x00000801
+        let join = super::analyze(
+            &[
+                0x02, 0xd0, 0x03, 0x49, 0x01, 0xe0, 0x00, 0xbf, 0x02, 0x49, 0x88, 0x47, 0x00, 0xbf,
+                0x00, 0xbf, 0x01, 0x04, 0x00, 0x00, 0x01, 0x08, 0x00, 0x00,
+            ],
+            0,
+            super::Arch::V6M,
+            &[],
+        )
+        .unwrap();
+        assert!(join.indirect);
+        assert!(join.blxs.is_empty());
+
+        // 4801            ldr     r0, [pc, #4]
+        // 4770            bx      lr
+        // 4780            blx     r0
+        let ret = super::analyze(
+            &[
+                0x01, 0x48, 0x70, 0x47, 0x80, 0x47, 0x00, 0xbf, 0x01, 0x04, 0x00, 0x00,
+            ],
+            0,
+            super::Arch::V6M,
+            &[],
+        )
+        .unwrap();
+        assert!(ret.indirect);
+        assert!(ret.blxs.is_empty());
+    }
+
+    #[test]
+    fn control_flow() {
+        // e8bd 8ff0       pop.w   {r4, r5, r6, r7, r8, r9, r10, r11, pc}
+        let pop = super::analyze(&[0xbd, 0xe8, 0xf0, 0x8f], 0, super::Arch::V7M, &[]).unwrap();
+        assert!(!pop.indirect);
+
+        // f85d fb04       ldr     pc, [sp], #4
+        let ldr = super::analyze(&[0x5d, 0xf8, 0x04, 0xfb], 0, super::Arch::V7M, &[]).unwrap();
+        assert!(!ldr.indirect);
+
+        // e8d0 f001       tbb     [r0, r1]
+        let tbb = super::analyze(&[0xd0, 0xe8, 0x01, 0xf0], 0, super::Arch::V7M, &[]).unwrap();
+        assert!(!tbb.indirect);
+        assert_eq!(tbb.stack, None);
+
+        // e890 8003       ldm.w   r0, {r0, r1, pc}
+        let ldm = super::analyze(&[0x90, 0xe8, 0x03, 0x80], 0, super::Arch::V7M, &[]).unwrap();
+        assert!(ldm.indirect);
+
+        // f8d0 f000       ldr.w   pc, [r0]
+        let ldr = super::analyze(&[0xd0, 0xf8, 0x00, 0xf0], 0, super::Arch::V7M, &[]).unwrap();
+        assert!(ldr.indirect);
+    }
+
+    #[test]
+    fn trustzone() {
+        // e97f e97f       sg
+        // f000 b800       b.w     <__acle_se_foo>
+        let veneer = super::analyze(
+            &[0x7f, 0xe9, 0x7f, 0xe9, 0x00, 0xf0, 0x00, 0xb8],
+            0,
+            super::Arch::V8MMain,
+            &[],
+        )
+        .unwrap();
+        assert!(veneer.gateway);
+        assert!(!veneer.non_secure);
+        assert_eq!(veneer.bs, vec![8]);
+
+        // 4784            blxns   r0
+        // 4774            bxns    lr
+        let call = super::analyze(&[0x84, 0x47, 0x74, 0x47], 0, super::Arch::V8MBase, &[]).unwrap();
+        assert!(call.non_secure);
+        assert!(!call.indirect);
+
+        // 4774            bxns    lr
+        let ret = super::analyze(&[0x74, 0x47], 0, super::Arch::V8MBase, &[]).unwrap();
+        assert!(!ret.non_secure);
+
+        // not available on ARMv7-M
+        assert!(super::analyze(&[0x84, 0x47], 0, super::Arch::V7M, &[]).is_err());
     }
 
     #[test]
     fn modifies_sp() {
         // bf00            nop
-        let nop = super::analyze(&[0x00, 0xbf], 0, false, &[]);
-        assert!(!nop.3);
-        assert_eq!(nop.4, Some(0));
+        let nop = super::analyze(&[0x00, 0xbf], 0, super::Arch::V6M, &[]).unwrap();
+        assert!(!nop.modifies_sp);
+        assert_eq!(nop.stack, Some(0));
 
         // b081            sub     sp, #4
-        let sub = super::analyze(&[0x81, 0xb0], 0, false, &[]);
-        assert!(sub.3);
-        assert_eq!(sub.4, Some(4));
+        let sub = super::analyze(&[0x81, 0xb0], 0, super::Arch::V6M, &[]).unwrap();
+        assert!(sub.modifies_sp);
+        assert_eq!(sub.stack, Some(4));
 
         // b580            push    {r7, lr}
-        let push = super::analyze(&[0x80, 0xb5], 0, false, &[]);
-        assert!(push.3);
-        assert_eq!(push.4, Some(8));
+        let push = super::analyze(&[0x80, 0xb5], 0, super::Arch::V6M, &[]).unwrap();
+        assert!(push.modifies_sp);
+        assert_eq!(push.stack, Some(8));
 
         // e92d 41f0       stmdb   sp!, {r4, r5, r6, r7, r8, lr}
-        let stmdb = super::analyze(&[0x2d, 0xe9, 0xf0, 0x41], 0, true, &[]);
-        assert!(stmdb.3);
-        assert_eq!(stmdb.4, Some(24));
+        let stmdb = super::analyze(&[0x2d, 0xe9, 0xf0, 0x41], 0, super::Arch::V7M, &[]).unwrap();
+        assert!(stmdb.modifies_sp);
+        assert_eq!(stmdb.stack, Some(24));
 
         // ed2d 8b02       vpush   {d8}
-        let vpush = super::analyze(&[0x2d, 0xed, 0x02, 0x8b], 0, true, &[]);
-        assert!(vpush.3);
-        assert_eq!(vpush.4, Some(8));
+        let vpush = super::analyze(&[0x2d, 0xed, 0x02, 0x8b], 0, super::Arch::V7M, &[]).unwrap();
+        assert!(vpush.modifies_sp);
+        assert_eq!(vpush.stack, Some(8));
 
         // f5ad 7d02       sub.w   sp, sp, #520    ; 0x208
-        let subw = super::analyze(&[0xad, 0xf5, 0x02, 0x7d], 0, true, &[]);
-        assert!(subw.3);
-        assert_eq!(subw.4, Some(520));
+        let subw = super::analyze(&[0xad, 0xf5, 0x02, 0x7d], 0, super::Arch::V7M, &[]).unwrap();
+        assert!(subw.modifies_sp);
+        assert_eq!(subw.stack, Some(520));
+
+        // b082            sub     sp, #8
+        // b002            add     sp, #8
+        // b410            push    {r4}
+        // bc10            pop     {r4}
+        let peak = super::analyze(
+            &[0x82, 0xb0, 0x02, 0xb0, 0x10, 0xb4, 0x10, 0xbc],
+            0,
+            super::Arch::V6M,
+            &[],
+        )
+        .unwrap();
+        assert!(peak.modifies_sp);
+        assert_eq!(peak.stack, Some(8));
     }
 }
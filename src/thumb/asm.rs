@@ -0,0 +1,359 @@
+//! Analysis of `asm!` templates
+//!
+//! LLVM knows nothing about the contents of inline assembly so neither its stack usage information
+//! nor the call graph in the LLVM-IR account for them. This module "assembles" simple templates
+//! -- a line at a time, one instruction per line -- and reports what matters to the call graph:
+//! the functions they call and how much stack they use. Templates that contain instructions this
+//! module doesn't understand are reported as unknown.
+
+/// What an `asm!` block does to the call graph
+#[derive(Debug, Default, PartialEq)]
+pub struct Effect {
+    /// Functions called (`bl`) or tail called (`b`) by name
+    pub calls: Vec<String>,
+
+    /// Whether the block performs a supervisor call (`svc`)
+    pub svc: bool,
+
+    /// Whether the block calls, or branches to, an address held in a register
+    pub indirect: bool,
+
+    /// Peak stack usage of the block, in bytes
+    pub stack: u64,
+}
+
+/// Analyzes the template of an `asm!` block, as it appears in the LLVM-IR
+///
+/// `globals` are the globals the operands of the template (`${0:c}`) refer to, if any; see
+/// `Stmt::Asm`. Returns `None` if the template contains instructions that are not understood
+pub fn analyze(template: &str, globals: &[Option<&str>]) -> Option<Effect> {
+    let template = unescape(template);
+
+    // labels defined in the template; branches to them are not calls
+    let labels = template
+        .lines()
+        .flat_map(|line| line.split(';'))
+        .filter_map(|stmt| label(stmt.trim()).map(|label| label.to_owned()))
+        .collect::<Vec<_>>();
+    let is_local = |target: &str| {
+        // `1b` and `1f` refer to the numeric label `1:`
+        let numeric = target.len() > 1
+            && target[..target.len() - 1]
+                .bytes()
+                .all(|b| b.is_ascii_digit())
+            && (target.ends_with('b') || target.ends_with('f'));
+        numeric || labels.iter().any(|label| label == target)
+    };
+
+    let mut effect = Effect::default();
+    let mut sp = 0i64;
+    for stmt in template.lines().flat_map(|line| line.split(';')) {
+        // comments
+        let stmt = stmt
+            .split("//")
+            .next()
+            .unwrap_or("")
+            .split('@')
+            .next()
+            .unwrap_or("");
+        let stmt = stmt.trim();
+        let stmt = match label(stmt) {
+            Some(label) => stmt[label.len() + 1..].trim(),
+            None => stmt,
+        };
+        if stmt.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, operands) = match stmt.find(char::is_whitespace) {
+            Some(end) => (&stmt[..end], stmt[end..].trim()),
+            None => (stmt, ""),
+        };
+        let mnemonic = mnemonic.to_ascii_lowercase();
+        let mnemonic = mnemonic.trim_end_matches(".w").trim_end_matches(".n");
+        // symbol names are case sensitive; everything else is not
+        let symbols = operands.split(',').map(str::trim).collect::<Vec<_>>();
+        let operands = operands.to_ascii_lowercase();
+        let operands = operands.split(',').map(str::trim).collect::<Vec<_>>();
+
+        match mnemonic {
+            "push" | "vpush" => sp += registers(&operands)?,
+            "pop" | "vpop" => {
+                sp -= registers(&operands)?;
+                if operands.iter().any(|op| op.contains("pc")) {
+                    // return
+                    break;
+                }
+            }
+            "stmdb" | "stmfd" if operands[0] == "sp!" => sp += registers(&operands[1..])?,
+            "ldmia" | "ldmfd" | "ldm" if operands[0] == "sp!" => sp -= registers(&operands[1..])?,
+
+            // `sub sp, #8` and `sub sp, sp, #8`
+            "sub" | "subs" | "add" | "adds" if operands[0] == "sp" => {
+                let imm = immediate(operands.last()?)?;
+                let sign = if mnemonic.starts_with("sub") { 1 } else { -1 };
+                match operands.len() {
+                    2 => sp += sign * imm,
+                    3 if operands[1] == "sp" => sp += sign * imm,
+                    _ => return None,
+                }
+            }
+
+            "bl" | "blx" | "b" | "bx" => {
+                let target = *operands.first()?;
+                if is_register(target) {
+                    if target == "lr" {
+                        // return
+                        break;
+                    }
+
+                    effect.indirect = true;
+                } else if target.starts_with('$') {
+                    match operand(target).and_then(|n| globals.get(n).cloned().flatten()) {
+                        // `sym foo`
+                        Some(callee) => effect.calls.push(callee.to_owned()),
+                        // an operand (e.g. `in(reg) f`) we don't know the value of
+                        None => effect.indirect = true,
+                    }
+                } else if !is_local(target) {
+                    effect.calls.push(symbols[0].to_owned());
+                }
+            }
+
+            "svc" => effect.svc = true,
+
+            // conditional branches are only allowed to local labels
+            _ if is_conditional_branch(mnemonic) => {
+                if !operands.first().map(|op| is_local(op)).unwrap_or(false) {
+                    return None;
+                }
+            }
+
+            // anything else must leave the stack pointer alone
+            _ if NO_STACK.contains(&mnemonic) || mnemonic.starts_with("it") => {
+                let writes_sp = operands.first() == Some(&"sp")
+                    || operands.contains(&"sp!")
+                    // `str r0, [sp, #-4]!`
+                    || (operands.iter().any(|op| op.starts_with("[sp")) && stmt.contains('!'))
+                    // `ldr r0, [sp], #4`
+                    || (operands.contains(&"[sp]") && operands.last() != Some(&"[sp]"));
+                if writes_sp {
+                    return None;
+                }
+            }
+
+            _ => return None,
+        }
+
+        effect.stack = effect.stack.max(sp.max(0) as u64);
+    }
+
+    Some(effect)
+}
+
+// instructions that don't modify the stack pointer unless it's the destination register
+const NO_STACK: &[&str] = &[
+    "adc", "adcs", "add", "adds", "adr", "and", "ands", "asr", "asrs", "bfc", "bfi", "bic", "bics",
+    "bkpt", "cbnz", "cbz", "clrex", "clz", "cmn", "cmp", "cpsid", "cpsie", "dmb", "dsb", "eor",
+    "eors", "isb", "ldm", "ldmia", "ldr", "ldrb", "ldrd", "ldrex", "ldrh", "ldrsb", "ldrsh", "lsl",
+    "lsls", "lsr", "lsrs", "mla", "mls", "mov", "movs", "movt", "movw", "mrs", "msr", "mul",
+    "muls", "mvn", "mvns", "nop", "orn", "orr", "orrs", "rbit", "rev", "rev16", "revsh", "ror",
+    "rors", "rsb", "rsbs", "sbc", "sbcs", "sdiv", "sev", "stm", "stmia", "str", "strb", "strd",
+    "strex", "strh", "sub", "subs", "sxtb", "sxth", "teq", "tst", "udf", "udiv", "umull", "uxtb",
+    "uxth", "vldr", "vmov", "vmrs", "vmsr", "vstr", "wfe", "wfi", "yield",
+];
+
+// `1:` or `loop:` at the start of a statement
+fn label(stmt: &str) -> Option<&str> {
+    let end = stmt.find(':')?;
+    let label = &stmt[..end];
+
+    // `${0:c}` is an operand, not a label
+    if label.is_empty() || label.contains(|c: char| c.is_whitespace() || c == '$' || c == '{') {
+        None
+    } else {
+        Some(label)
+    }
+}
+
+fn is_conditional_branch(mnemonic: &str) -> bool {
+    const CONDITIONS: &[&str] = &[
+        "eq", "ne", "cs", "hs", "cc", "lo", "mi", "pl", "vs", "vc", "hi", "ls", "ge", "lt", "gt",
+        "le",
+    ];
+
+    mnemonic.starts_with('b') && CONDITIONS.contains(&&mnemonic[1..])
+}
+
+fn is_register(operand: &str) -> bool {
+    match operand {
+        "sp" | "lr" | "pc" | "ip" | "fp" | "sb" | "sl" => true,
+        _ => {
+            operand.starts_with('r')
+                && operand.len() > 1
+                && operand[1..].bytes().all(|b| b.is_ascii_digit())
+        }
+    }
+}
+
+// the number of an operand like `$0`, `${0}` or `${0:c}`
+fn operand(operand: &str) -> Option<usize> {
+    let operand = operand.strip_prefix('$')?;
+    let operand = match operand.strip_prefix('{') {
+        Some(operand) => operand.strip_suffix('}')?.split(':').next()?,
+        None => operand,
+    };
+    operand.parse().ok()
+}
+
+// `#8`, `#0x8` or `8`
+fn immediate(operand: &str) -> Option<i64> {
+    let operand = operand.trim_start_matches('#');
+    if let Some(hex) = operand.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()
+    } else {
+        operand.parse().ok()
+    }
+}
+
+// size, in bytes, of a register list like `{r4-r7, lr}` or `{d8-d9}`; the operands have been
+// split at the commas
+fn registers(operands: &[&str]) -> Option<i64> {
+    let mut size = 0;
+    for operand in operands {
+        let operand = operand.trim_matches(['{', '}']).trim();
+        let (bank, count) = if let Some(dash) = operand.find('-') {
+            let (first, last) = (&operand[..dash], &operand[dash + 1..]);
+            let bank = first.chars().next()?;
+            let first = first[1..].parse::<i64>().ok()?;
+            let last = last.trim_start_matches(bank).parse::<i64>().ok()?;
+            (bank, last - first + 1)
+        } else if is_register(operand) {
+            ('r', 1)
+        } else {
+            (operand.chars().next()?, 1)
+        };
+
+        size += count
+            * match bank {
+                'r' | 's' => 4,
+                'd' => 8,
+                _ => return None,
+            };
+    }
+
+    Some(size)
+}
+
+// undoes the escaping of LLVM strings (`\0A` is a newline) and of `asm!` templates (`$$` is `$`)
+fn unescape(template: &str) -> String {
+    let mut s = String::with_capacity(template.len());
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let hex = chars.by_ref().take(2).collect::<String>();
+                if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                    s.push(char::from(byte));
+                } else {
+                    s.push('\\');
+                    s.push_str(&hex);
+                }
+            }
+
+            '$' if chars.as_str().starts_with('$') => {
+                chars.next();
+                s.push('$');
+            }
+
+            _ => s.push(c),
+        }
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Effect;
+
+    #[test]
+    fn known() {
+        assert_eq!(super::analyze("", &[]), Some(Effect::default()));
+        assert_eq!(super::analyze("cpsid i", &[]), Some(Effect::default()));
+        assert_eq!(super::analyze("nop\\0Anop", &[]), Some(Effect::default()));
+        assert_eq!(
+            super::analyze("ldr r0, [sp, #4]", &[]),
+            Some(Effect::default())
+        );
+
+        // trampoline
+        assert_eq!(
+            super::analyze("push {r7, lr}\\0Abl foo\\0Apop {r7, pc}", &[]),
+            Some(Effect {
+                calls: vec!["foo".to_owned()],
+                stack: 8,
+                ..Effect::default()
+            })
+        );
+
+        assert_eq!(
+            super::analyze(
+                "sub sp, #16\\0Avpush {d8-d9}\\0Avpop {d8-d9}\\0Aadd sp, sp, #16",
+                &[]
+            ),
+            Some(Effect {
+                stack: 32,
+                ..Effect::default()
+            })
+        );
+
+        assert_eq!(
+            super::analyze("svc #0", &[]),
+            Some(Effect {
+                svc: true,
+                ..Effect::default()
+            })
+        );
+
+        assert_eq!(
+            super::analyze("blx r0", &[]),
+            Some(Effect {
+                indirect: true,
+                ..Effect::default()
+            })
+        );
+
+        // symbol operand
+        assert_eq!(
+            super::analyze("bl ${0:c}", &[]),
+            Some(Effect {
+                indirect: true,
+                ..Effect::default()
+            })
+        );
+
+        // `asm!("bl {}", "b {}", out("r0") _, sym foo, sym bar)`
+        assert_eq!(
+            super::analyze("bl ${1:c}\\0Ab ${2:c}", &[None, Some("foo"), Some("bar")]),
+            Some(Effect {
+                calls: vec!["foo".to_owned(), "bar".to_owned()],
+                ..Effect::default()
+            })
+        );
+
+        // loop
+        assert_eq!(
+            super::analyze("1:\\0Asubs r0, #1\\0Abne 1b", &[]),
+            Some(Effect::default())
+        );
+    }
+
+    #[test]
+    fn unknown() {
+        assert_eq!(super::analyze("mov sp, r0", &[]), None);
+        assert_eq!(super::analyze("str r0, [sp, #-4]!", &[]), None);
+        assert_eq!(super::analyze("ldr r0, [sp], #4", &[]), None);
+        assert_eq!(super::analyze(".inst 0xdeadbeef", &[]), None);
+        assert_eq!(super::analyze("bne foo", &[]), None);
+    }
+}
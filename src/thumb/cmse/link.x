@@ -0,0 +1,5 @@
+SECTIONS
+{
+  .text 0x10000000 : { *(.text .text.*) }
+  .gnu.sgstubs 0x10000020 : { *(.gnu.sgstubs*) }
+}
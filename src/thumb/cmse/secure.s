@@ -0,0 +1,32 @@
+@ A secure entry function, as emitted for `#[cmse_nonsecure_entry]`; the linker places its
+@ `SG` veneer in `.gnu.sgstubs`. `secure.elf` was built with:
+@
+@ llvm-mc --triple=thumbv8m.main-none-eabi -mattr=+8msecext -filetype=obj secure.s -o secure.o
+@ rust-lld -flavor gnu --cmse-implib -N --strip-debug -e foo -T link.x secure.o -o secure.elf
+
+    .arch armv8-m.main
+    .syntax unified
+    .thumb
+
+    .section .text.foo,"ax",%progbits
+    .globl __acle_se_foo
+    .type __acle_se_foo,%function
+    .globl foo
+    .type foo,%function
+    .thumb_func
+__acle_se_foo:
+foo:
+    push {r7, lr}
+    bl bar
+    pop.w {r7, lr}
+    bxns lr
+    .size foo, . - foo
+    .size __acle_se_foo, . - __acle_se_foo
+
+    .section .text.bar,"ax",%progbits
+    .globl bar
+    .type bar,%function
+    .thumb_func
+bar:
+    bx lr
+    .size bar, . - bar
@@ -0,0 +1,1497 @@
+//! Table-driven Thumb / Thumb-2 instruction decoder
+//!
+//! Each entry of the `T16` and `T32` tables pairs a bit pattern with the mnemonic of the
+//! instruction, the architectures that support it and two functions that extract the information
+//! the analysis needs: what the instruction does to the control flow and to the stack pointer
+//! (`Op`) and which registers it writes to. The bit patterns are parsed at compile time.
+//!
+//! Entries are tried in order and the first one that matches wins so more specific patterns must
+//! come before more general ones. `UNDEFINED` entries carve holes into the encoding space.
+
+// Reference: ARMv7-M Architecture Reference Manual (ARM DDI 0403E.b) - A5 and A6
+// Reference: ARMv6-M Architecture Reference Manual (ARM DDI 0419D) - A5
+// Reference: ARMv8-M Architecture Reference Manual (ARM DDI 0553B.k) - C2
+
+use std::sync::OnceLock;
+
+/// Architecture profiles; these select which subset of the encoding tables is valid
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Arch {
+    /// ARMv6-M
+    V6M,
+    /// ARMv7-M and ARMv7E-M (DSP and FP extensions included)
+    V7M,
+    /// ARMv8-M Baseline
+    V8MBase,
+    /// ARMv8-M Mainline (DSP and FP extensions included)
+    V8MMain,
+}
+
+impl Arch {
+    fn bit(self) -> u8 {
+        match self {
+            Arch::V6M => V6M,
+            Arch::V7M => V7M,
+            Arch::V8MBase => V8M_BASE,
+            Arch::V8MMain => V8M_MAIN,
+        }
+    }
+}
+
+const V6M: u8 = 1 << 0;
+const V7M: u8 = 1 << 1;
+const V8M_BASE: u8 = 1 << 2;
+const V8M_MAIN: u8 = 1 << 3;
+
+// all M-profile architectures
+const ALL: u8 = V6M | V7M | V8M_BASE | V8M_MAIN;
+// everything but ARMv6-M (e.g. `CBZ`, `MOVW`, `SDIV`)
+const NOT_V6M: u8 = V7M | V8M_BASE | V8M_MAIN;
+// the full Thumb-2 instruction set
+const T2: u8 = V7M | V8M_MAIN;
+// ARMv8-M additions (e.g. `SG`, `TT`, `LDA`)
+const V8M: u8 = V8M_BASE | V8M_MAIN;
+
+const SP: u32 = 13;
+const LR: u32 = 14;
+const PC: u32 = 15;
+
+/// What a decoded instruction does to the control flow and the stack pointer
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Op {
+    /// No effect on the control flow or the stack pointer, other than the register writes
+    Other,
+
+    /// Unconditional `B`; the offset is relative to the PC (address of the instruction + 4)
+    Branch(i32),
+
+    /// `B<c>`, `CBZ` or `CBNZ`; the offset is relative to the PC (address of the instruction + 4)
+    CondBranch(i32),
+
+    /// `BL`; the offset is relative to the PC (address of the instruction + 4)
+    Call(i32),
+
+    /// `BLX Rm`
+    CallRegister(u8),
+
+    /// `BX Rm` or `MOV pc, Rm`
+    BranchRegister(u8),
+
+    /// `SG`; marks a secure gateway, an entry point from the non-secure state
+    SecureGateway,
+
+    /// `BLXNS Rm`; calls a function in the non-secure state
+    NonSecureCall(u8),
+
+    /// `BXNS Rm`; returns to, or tail calls a function in, the non-secure state
+    NonSecureBranch(u8),
+
+    /// The instruction writes a value to PC that can't be known statically (e.g.
+    /// `LDR pc, [r0]`, `ADD pc, r1`)
+    ComputedBranch,
+
+    /// `TBB` or `TBH`; a jump table within the subroutine
+    TableBranch,
+
+    /// `IT`; the number of instructions in the IT block
+    IfThen(u32),
+
+    /// `POP {.., pc}`, `LDR pc, [sp], #4`, etc.
+    Return,
+
+    /// SP is decremented by a constant amount (e.g. `PUSH`, `SUB sp, #imm`, `VPUSH`)
+    Alloc(u32),
+
+    /// SP is decremented by an amount held in a register (`SUB sp, sp, Rm`)
+    DynamicAlloc,
+
+    /// SP is incremented by a constant amount (e.g. `POP`, `ADD sp, #imm`, `VPOP`)
+    Free(u32),
+
+    /// `LDR Rt, [pc, #imm]`; the offset is relative to `Align(PC, 4)`
+    LoadLiteral(u8, i32),
+
+    /// `MOV Rd, #imm` or `MOVW Rd, #imm`
+    MovImm(u8, u32),
+
+    /// `MOVT Rd, #imm`
+    MovTop(u8, u16),
+
+    /// `MOV Rd, Rm`
+    MovReg(u8, u8),
+
+    /// `ADR Rd, label`; the offset is relative to `Align(PC, 4)`
+    Adr(u8, i32),
+}
+
+/// A decoded instruction
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Instruction {
+    /// Mnemonic, without condition code or width qualifier
+    pub name: &'static str,
+
+    pub op: Op,
+
+    /// Registers written to by the instruction; bit `n` stands for register `Rn`
+    pub writes: u16,
+
+    /// Size of the instruction in bytes: 2 or 4
+    pub size: u32,
+}
+
+/// Decodes the instruction at the start of `bytes`
+///
+/// Returns `None` if the encoding is unknown, not available in `arch` or if `bytes` ends in the
+/// middle of a 32-bit instruction
+pub fn decode(bytes: &[u8], arch: Arch) -> Option<Instruction> {
+    static T16_INDEX: OnceLock<Index> = OnceLock::new();
+    static T32_INDEX: OnceLock<Index> = OnceLock::new();
+
+    let first = u32::from(halfword(bytes, 0)?);
+
+    let (table, index, raw, size) = if is_32bit(first) {
+        let second = u32::from(halfword(bytes, 2)?);
+        let index = T32_INDEX.get_or_init(|| Index::new(T32, T32_KEY));
+        (T32, index, (first << 16) | second, 4)
+    } else {
+        let index = T16_INDEX.get_or_init(|| Index::new(T16, T16_KEY));
+        (T16, index, first, 2)
+    };
+
+    let encoding = index
+        .candidates(raw)
+        .iter()
+        .map(|i| &table[usize::from(*i)])
+        .find(|encoding| raw & encoding.mask == encoding.value)?;
+
+    if encoding.isa & arch.bit() == 0 {
+        return None;
+    }
+
+    let name = match encoding.name {
+        Name::Fixed(name) => name,
+        Name::Dynamic(f) => f(raw),
+        Name::Undefined => return None,
+    };
+
+    Some(Instruction {
+        name,
+        op: (encoding.op)(raw),
+        writes: (encoding.writes)(raw),
+        size,
+    })
+}
+
+fn halfword(bytes: &[u8], offset: usize) -> Option<u16> {
+    let hw = bytes.get(offset..offset + 2)?;
+    Some(u16::from(hw[0]) | (u16::from(hw[1]) << 8))
+}
+
+// A5.1 Thumb instruction set encoding
+pub fn is_32bit(first: u32) -> bool {
+    first >> 11 >= 0b11101
+}
+
+// Bits of the encoding used to pick a bucket of the `Index`
+// T16: bits 15:10 (A5.2)
+const T16_KEY: &[(u32, u32)] = &[(15, 10)];
+// T32: hw1 bits 12:4 and hw2 bits 15:14 (A5.3)
+const T32_KEY: &[(u32, u32)] = &[(28, 20), (15, 14)];
+
+// First-level dispatch: the entries of a table bucketed by some of the bits of the encoding.
+// Each bucket holds, in table order, the indices of the entries that may match an encoding that
+// falls into the bucket. This turns a linear search over the whole table into a search over a
+// handful of entries
+struct Index {
+    key: &'static [(u32, u32)],
+    buckets: Vec<Vec<u16>>,
+}
+
+impl Index {
+    fn new(table: &[Encoding], key: &'static [(u32, u32)]) -> Self {
+        let nbits: u32 = key.iter().map(|(hi, lo)| hi - lo + 1).sum();
+        let key_mask = key.iter().fold(0, |mask, &(hi, lo)| {
+            mask | (((1 << (hi - lo + 1)) - 1) << lo)
+        });
+
+        let buckets = (0..1u32 << nbits)
+            .map(|bucket| {
+                // spread the bucket number over the key bits
+                let mut raw = 0;
+                let mut rest = bucket;
+                for &(hi, lo) in key.iter().rev() {
+                    let width = hi - lo + 1;
+                    raw |= (rest & ((1 << width) - 1)) << lo;
+                    rest >>= width;
+                }
+
+                table
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, e)| (raw ^ e.value) & e.mask & key_mask == 0)
+                    .map(|(i, _)| i as u16)
+                    .collect()
+            })
+            .collect();
+
+        Index { key, buckets }
+    }
+
+    fn candidates(&self, raw: u32) -> &[u16] {
+        let bucket = self.key.iter().fold(0, |bucket, &(hi, lo)| {
+            (bucket << (hi - lo + 1)) | bits(raw, hi, lo)
+        });
+
+        &self.buckets[bucket as usize]
+    }
+}
+
+struct Encoding {
+    mask: u32,
+    value: u32,
+    name: Name,
+    isa: u8,
+    op: fn(u32) -> Op,
+    writes: fn(u32) -> u16,
+}
+
+enum Name {
+    Fixed(&'static str),
+    // the mnemonic depends on some of the bits of the encoding
+    Dynamic(fn(u32) -> &'static str),
+    // not an instruction
+    Undefined,
+}
+
+// NOTE `pattern` has the form `0b0100_00xx_xxxx_xxxx` for 16-bit instructions and
+// `0b1110_1xxx_xxxx_xxxx 0b0xxx_xxxx_xxxx_xxxx` for 32-bit ones; underscores are ignored
+const fn parse(pattern: &str) -> (u32, u32) {
+    let bytes = pattern.as_bytes();
+    let mut mask = 0;
+    let mut value = 0;
+    let mut nbits = 0;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'0' if i + 1 < bytes.len() && bytes[i + 1] == b'b' => {
+                // `0b` prefix
+                i += 1;
+            }
+            b'0' => {
+                mask = (mask << 1) | 1;
+                value <<= 1;
+                nbits += 1;
+            }
+            b'1' => {
+                mask = (mask << 1) | 1;
+                value = (value << 1) | 1;
+                nbits += 1;
+            }
+            b'x' => {
+                mask <<= 1;
+                value <<= 1;
+                nbits += 1;
+            }
+            b'_' | b' ' => {}
+            _ => panic!("invalid character in bit pattern"),
+        }
+
+        i += 1;
+    }
+
+    if nbits != 16 && nbits != 32 {
+        panic!("bit patterns must be 16 or 32 bits long");
+    }
+
+    (mask, value)
+}
+
+const fn enc(
+    pattern: &str,
+    name: &'static str,
+    isa: u8,
+    op: fn(u32) -> Op,
+    writes: fn(u32) -> u16,
+) -> Encoding {
+    let (mask, value) = parse(pattern);
+    Encoding {
+        mask,
+        value,
+        name: Name::Fixed(name),
+        isa,
+        op,
+        writes,
+    }
+}
+
+const fn dyn_enc(
+    pattern: &str,
+    name: fn(u32) -> &'static str,
+    isa: u8,
+    op: fn(u32) -> Op,
+    writes: fn(u32) -> u16,
+) -> Encoding {
+    let (mask, value) = parse(pattern);
+    Encoding {
+        mask,
+        value,
+        name: Name::Dynamic(name),
+        isa,
+        op,
+        writes,
+    }
+}
+
+const fn undefined(pattern: &str) -> Encoding {
+    let (mask, value) = parse(pattern);
+    Encoding {
+        mask,
+        value,
+        name: Name::Undefined,
+        isa: ALL,
+        op: other,
+        writes: none,
+    }
+}
+
+/* Field extraction */
+fn bits(raw: u32, hi: u32, lo: u32) -> u32 {
+    (raw >> lo) & ((1 << (hi - lo + 1)) - 1)
+}
+
+fn bit(raw: u32, n: u32) -> bool {
+    raw & (1 << n) != 0
+}
+
+fn reg(n: u32) -> u16 {
+    1 << n
+}
+
+fn sign_extend(x: i32, nbits: u32) -> i32 {
+    let shift = 32 - nbits;
+    x.wrapping_shl(shift).wrapping_shr(shift)
+}
+
+fn thumb_expand_imm(imm12: u32) -> u32 {
+    if imm12 >> 10 == 0b00 {
+        let imm8 = imm12 & 0b1111_1111;
+
+        match (imm12 >> 8) & 0b11 {
+            0b00 => imm8,
+            0b01 => (imm8 << 16) | imm8,
+            0b10 => (imm8 << 24) | (imm8 << 8),
+            _ => (imm8 << 24) | (imm8 << 16) | (imm8 << 8) | imm8,
+        }
+    } else {
+        let unrotated = (1 << 7) | (imm12 & 0b0111_1111);
+        unrotated.rotate_right(imm12 >> 7)
+    }
+}
+
+// `i:imm3:imm8` of 32-bit data processing instructions
+fn imm12(raw: u32) -> u32 {
+    (bits(raw, 26, 26) << 11) | (bits(raw, 14, 12) << 8) | bits(raw, 7, 0)
+}
+
+// `imm4:i:imm3:imm8` of `MOVW` and `MOVT`
+fn imm16(raw: u32) -> u32 {
+    (bits(raw, 19, 16) << 12) | imm12(raw)
+}
+
+/* `op` functions */
+fn other(_: u32) -> Op {
+    Op::Other
+}
+
+fn table_branch(_: u32) -> Op {
+    Op::TableBranch
+}
+
+// A7.7.38 IT - T1
+fn it(raw: u32) -> Op {
+    Op::IfThen(4 - bits(raw, 3, 0).trailing_zeros())
+}
+
+// A7.7.12 B - T1
+fn b_t1(raw: u32) -> Op {
+    Op::CondBranch(sign_extend((bits(raw, 7, 0) << 1) as i32, 9))
+}
+
+// A7.7.12 B - T2
+fn b_t2(raw: u32) -> Op {
+    Op::Branch(sign_extend((bits(raw, 10, 0) << 1) as i32, 12))
+}
+
+// A7.7.12 B - T3
+fn b_t3(raw: u32) -> Op {
+    let s = bits(raw, 26, 26);
+    let j1 = bits(raw, 13, 13);
+    let j2 = bits(raw, 11, 11);
+    let imm6 = bits(raw, 21, 16);
+    let imm11 = bits(raw, 10, 0);
+    let imm21 = (s << 20) | (j2 << 19) | (j1 << 18) | (imm6 << 12) | (imm11 << 1);
+
+    Op::CondBranch(sign_extend(imm21 as i32, 21))
+}
+
+// `S:I1:I2:imm10:imm11:'0'` of `B` - T4 and `BL` - T1
+fn imm25(raw: u32) -> i32 {
+    let s = bits(raw, 26, 26);
+    let j1 = bits(raw, 13, 13);
+    let j2 = bits(raw, 11, 11);
+    let i1 = !(j1 ^ s) & 1;
+    let i2 = !(j2 ^ s) & 1;
+    let imm10 = bits(raw, 25, 16);
+    let imm11 = bits(raw, 10, 0);
+    let imm25 = (s << 24) | (i1 << 23) | (i2 << 22) | (imm10 << 12) | (imm11 << 1);
+
+    sign_extend(imm25 as i32, 25)
+}
+
+// A7.7.12 B - T4
+fn b_t4(raw: u32) -> Op {
+    Op::Branch(imm25(raw))
+}
+
+// A7.7.18 BL - T1
+fn bl(raw: u32) -> Op {
+    Op::Call(imm25(raw))
+}
+
+// A7.7.19 BLX (register) - T1
+fn blx(raw: u32) -> Op {
+    Op::CallRegister(bits(raw, 6, 3) as u8)
+}
+
+// A7.7.20 BX - T1
+fn bx(raw: u32) -> Op {
+    Op::BranchRegister(bits(raw, 6, 3) as u8)
+}
+
+// C2.4 BLXNS - T1
+fn blxns(raw: u32) -> Op {
+    Op::NonSecureCall(bits(raw, 6, 3) as u8)
+}
+
+// C2.4 BXNS - T1
+fn bxns(raw: u32) -> Op {
+    Op::NonSecureBranch(bits(raw, 6, 3) as u8)
+}
+
+// C2.4 SG - T1
+fn sg(_: u32) -> Op {
+    Op::SecureGateway
+}
+
+// A7.7.21 CBNZ, CBZ - T1
+fn cbz(raw: u32) -> Op {
+    Op::CondBranch(((bits(raw, 9, 9) << 6) | (bits(raw, 7, 3) << 1)) as i32)
+}
+
+// A7.7.4 ADD (register) - T2
+fn add_reg_t2(raw: u32) -> Op {
+    if rd_dn(raw) == PC {
+        Op::ComputedBranch
+    } else {
+        Op::Other
+    }
+}
+
+// A7.7.76 MOV (register) - T1
+fn mov_reg_t1(raw: u32) -> Op {
+    let rd = rd_dn(raw);
+    let rm = bits(raw, 6, 3) as u8;
+
+    if rd == PC {
+        Op::BranchRegister(rm)
+    } else {
+        Op::MovReg(rd as u8, rm)
+    }
+}
+
+// A7.7.75 MOV (immediate) - T1
+fn mov_imm_t1(raw: u32) -> Op {
+    Op::MovImm(bits(raw, 10, 8) as u8, bits(raw, 7, 0))
+}
+
+// A7.7.75 MOV (immediate) - T2
+fn mov_imm_t2(raw: u32) -> Op {
+    Op::MovImm(bits(raw, 11, 8) as u8, thumb_expand_imm(imm12(raw)))
+}
+
+// A7.7.75 MOV (immediate) - T3
+fn movw(raw: u32) -> Op {
+    Op::MovImm(bits(raw, 11, 8) as u8, imm16(raw))
+}
+
+// A7.7.78 MOVT - T1
+fn movt(raw: u32) -> Op {
+    Op::MovTop(bits(raw, 11, 8) as u8, imm16(raw) as u16)
+}
+
+// A7.7.76 MOV (register) - T3
+fn mov_reg_t3(raw: u32) -> Op {
+    // `imm3:imm2 == 0` and `type == 0b00` means no shift
+    if bits(raw, 14, 12) == 0 && bits(raw, 7, 4) == 0 {
+        Op::MovReg(bits(raw, 11, 8) as u8, bits(raw, 3, 0) as u8)
+    } else {
+        Op::Other
+    }
+}
+
+// A7.7.43 LDR (literal) - T1
+fn ldr_lit_t1(raw: u32) -> Op {
+    Op::LoadLiteral(bits(raw, 10, 8) as u8, (bits(raw, 7, 0) << 2) as i32)
+}
+
+// A7.7.43 LDR (literal) - T2
+fn ldr_lit_t2(raw: u32) -> Op {
+    let imm12 = bits(raw, 11, 0) as i32;
+    let offset = if bit(raw, 23) { imm12 } else { -imm12 };
+
+    Op::LoadLiteral(bits(raw, 15, 12) as u8, offset)
+}
+
+// A7.7.7 ADR - T1
+fn adr_t1(raw: u32) -> Op {
+    Op::Adr(bits(raw, 10, 8) as u8, (bits(raw, 7, 0) << 2) as i32)
+}
+
+// A7.7.7 ADR - T2
+fn adr_t2(raw: u32) -> Op {
+    Op::Adr(bits(raw, 11, 8) as u8, -(imm12(raw) as i32))
+}
+
+// A7.7.7 ADR - T3
+fn adr_t3(raw: u32) -> Op {
+    Op::Adr(bits(raw, 11, 8) as u8, imm12(raw) as i32)
+}
+
+// A7.7.99 PUSH - T1
+fn push_t1(raw: u32) -> Op {
+    let registers = bits(raw, 7, 0) | (bits(raw, 8, 8) << 14);
+    Op::Alloc(4 * registers.count_ones())
+}
+
+// A7.7.98 POP - T1
+fn pop_t1(raw: u32) -> Op {
+    if bit(raw, 8) {
+        Op::Return
+    } else {
+        Op::Free(4 * bits(raw, 7, 0).count_ones())
+    }
+}
+
+// A7.7.5 ADD (SP plus immediate) - T2
+fn add_sp_t2(raw: u32) -> Op {
+    Op::Free(bits(raw, 6, 0) << 2)
+}
+
+// A7.7.5 ADD (SP plus immediate) - T3
+fn add_sp_t3(raw: u32) -> Op {
+    if bits(raw, 19, 16) == SP && bits(raw, 11, 8) == SP {
+        Op::Free(thumb_expand_imm(imm12(raw)))
+    } else {
+        Op::Other
+    }
+}
+
+// A7.7.5 ADD (SP plus immediate) - T4
+fn add_sp_t4(raw: u32) -> Op {
+    if bits(raw, 19, 16) == SP && bits(raw, 11, 8) == SP {
+        Op::Free(imm12(raw))
+    } else {
+        Op::Other
+    }
+}
+
+// A7.7.173 SUB (SP minus immediate) - T1
+fn sub_sp_t1(raw: u32) -> Op {
+    Op::Alloc(bits(raw, 6, 0) << 2)
+}
+
+// A7.7.173 SUB (SP minus immediate) - T2
+fn sub_sp_t2(raw: u32) -> Op {
+    if bits(raw, 11, 8) == SP {
+        Op::Alloc(thumb_expand_imm(imm12(raw)))
+    } else {
+        Op::Other
+    }
+}
+
+// A7.7.173 SUB (SP minus immediate) - T3
+fn sub_sp_t3(raw: u32) -> Op {
+    if bits(raw, 11, 8) == SP {
+        Op::Alloc(imm12(raw))
+    } else {
+        Op::Other
+    }
+}
+
+// A7.7.174 SUB (SP minus register) - T1
+fn sub_sp_reg(raw: u32) -> Op {
+    if bits(raw, 11, 8) == SP {
+        Op::DynamicAlloc
+    } else {
+        Op::Other
+    }
+}
+
+// A7.7.157 STMDB, STMFD - T1 (`PUSH` - T2 when the base register is SP)
+fn stmdb(raw: u32) -> Op {
+    if bits(raw, 19, 16) == SP && bit(raw, 21) {
+        Op::Alloc(4 * bits(raw, 15, 0).count_ones())
+    } else {
+        Op::Other
+    }
+}
+
+// A7.7.41 LDM, LDMIA, LDMFD - T2 and A7.7.42 LDMDB, LDMEA - T1
+fn ldm_t2(raw: u32) -> Op {
+    let pop = bits(raw, 19, 16) == SP && bit(raw, 21) && bit(raw, 23);
+    if !bit(raw, 15) {
+        if pop {
+            // `POP {..}`
+            Op::Free(4 * bits(raw, 15, 0).count_ones())
+        } else {
+            Op::Other
+        }
+    } else if pop {
+        // `POP {.., pc}`
+        Op::Return
+    } else {
+        Op::ComputedBranch
+    }
+}
+
+// A7.7.166 STRD (immediate) - T1
+fn strd(raw: u32) -> Op {
+    // pre-indexed, negative offset, SP as the base register
+    if bits(raw, 19, 16) == SP && bit(raw, 24) && !bit(raw, 23) && bit(raw, 21) {
+        Op::Alloc(bits(raw, 7, 0) << 2)
+    } else {
+        Op::Other
+    }
+}
+
+// A7.7.158 STR (immediate) - T4 (`PUSH` - T3 when the base register is SP); also used for
+// STRB (immediate) - T3 and STRH (immediate) - T3
+fn str_imm8(raw: u32) -> Op {
+    // pre-indexed, negative offset, SP as the base register
+    if bits(raw, 19, 16) == SP && bits(raw, 10, 8) == 0b101 {
+        Op::Alloc(bits(raw, 7, 0))
+    } else {
+        Op::Other
+    }
+}
+
+// A7.7.42 LDR (immediate) - T3
+fn ldr_imm12(raw: u32) -> Op {
+    if bits(raw, 15, 12) == PC {
+        Op::ComputedBranch
+    } else {
+        Op::Other
+    }
+}
+
+// A7.7.42 LDR (immediate) - T4
+fn ldr_imm8(raw: u32) -> Op {
+    // post-indexed, positive offset, SP as the base register
+    let pop = bits(raw, 19, 16) == SP && bits(raw, 10, 8) == 0b011;
+    if bits(raw, 15, 12) != PC {
+        if pop {
+            // `LDR Rt, [sp], #4` (`POP {Rt}`)
+            Op::Free(bits(raw, 7, 0))
+        } else {
+            Op::Other
+        }
+    } else if pop {
+        // `LDR pc, [sp], #4` (`POP {pc}`)
+        Op::Return
+    } else {
+        Op::ComputedBranch
+    }
+}
+
+// A7.7.250 VPUSH - T1 and T2
+fn vpush(raw: u32) -> Op {
+    Op::Alloc(bits(raw, 7, 0) << 2)
+}
+
+// A7.7.249 VPOP - T1 and T2
+fn vpop(raw: u32) -> Op {
+    Op::Free(bits(raw, 7, 0) << 2)
+}
+
+/* `writes` functions */
+fn none(_: u32) -> u16 {
+    0
+}
+
+// `BL`, `BLX`, `SVC`: the callee may clobber the caller-saved registers
+fn call(_: u32) -> u16 {
+    reg(0) | reg(1) | reg(2) | reg(3) | reg(12) | reg(LR)
+}
+
+fn sp(_: u32) -> u16 {
+    reg(SP)
+}
+
+fn pc(_: u32) -> u16 {
+    reg(PC)
+}
+
+// `Rd` / `Rt` in bits 2:0
+fn rd_2_0(raw: u32) -> u16 {
+    reg(bits(raw, 2, 0))
+}
+
+// `Rd` / `Rt` in bits 10:8
+fn rd_10_8(raw: u32) -> u16 {
+    reg(bits(raw, 10, 8))
+}
+
+// `DN:Rdn` of 16-bit instructions that can access the high registers
+fn rd_dn(raw: u32) -> u32 {
+    (bits(raw, 7, 7) << 3) | bits(raw, 2, 0)
+}
+
+fn rd_hi(raw: u32) -> u16 {
+    reg(rd_dn(raw))
+}
+
+// `Rd` in bits 11:8 of 32-bit instructions
+fn rd_11_8(raw: u32) -> u16 {
+    reg(bits(raw, 11, 8))
+}
+
+// `Rt` in bits 15:12 of 32-bit instructions
+fn rt_15_12(raw: u32) -> u16 {
+    reg(bits(raw, 15, 12))
+}
+
+// `Rd` in bits 3:0 of the exclusive stores
+fn rd_3_0(raw: u32) -> u16 {
+    reg(bits(raw, 3, 0))
+}
+
+// `RdLo` and `RdHi` of the long multiplies
+fn rdlo_rdhi(raw: u32) -> u16 {
+    rt_15_12(raw) | rd_11_8(raw)
+}
+
+// `MRC`, `VMRS`: `Rt == 15` writes to the APSR flags rather than to PC
+fn rt_not_pc(raw: u32) -> u16 {
+    if bits(raw, 15, 12) == PC {
+        0
+    } else {
+        rt_15_12(raw)
+    }
+}
+
+// base register `Rn`, if written back
+fn rn_wb(raw: u32) -> u16 {
+    if bit(raw, 21) {
+        reg(bits(raw, 19, 16))
+    } else {
+        0
+    }
+}
+
+// loads with an 8-bit immediate: `Rt` plus `Rn` when the addressing mode writes back
+fn rt_imm8(raw: u32) -> u16 {
+    rt_15_12(raw) | str_imm8_wb(raw)
+}
+
+// stores with an 8-bit immediate: `Rn` when the addressing mode writes back
+fn str_imm8_wb(raw: u32) -> u16 {
+    if bit(raw, 8) {
+        reg(bits(raw, 19, 16))
+    } else {
+        0
+    }
+}
+
+fn ldrd(raw: u32) -> u16 {
+    rt_15_12(raw) | rd_11_8(raw) | rn_wb(raw)
+}
+
+// A7.7.41 LDM - T1: the base register is written back unless it's in the register list
+fn ldm_t1(raw: u32) -> u16 {
+    let list = bits(raw, 7, 0) as u16;
+    let rn = reg(bits(raw, 10, 8));
+
+    if list & rn == 0 {
+        list | rn
+    } else {
+        list
+    }
+}
+
+fn stm_t1(raw: u32) -> u16 {
+    reg(bits(raw, 10, 8))
+}
+
+fn pop16(raw: u32) -> u16 {
+    bits(raw, 7, 0) as u16 | (bits(raw, 8, 8) << PC) as u16 | reg(SP)
+}
+
+fn ldm32(raw: u32) -> u16 {
+    bits(raw, 15, 0) as u16 | rn_wb(raw)
+}
+
+fn vmov_rr(raw: u32) -> u16 {
+    if bit(raw, 20) {
+        // to core registers
+        rt_15_12(raw) | rd_11_8(raw)
+    } else {
+        0
+    }
+}
+
+fn vldm(raw: u32) -> u16 {
+    rn_wb(raw)
+}
+
+/* `name` functions */
+fn shift_name(raw: u32) -> &'static str {
+    match bits(raw, 22, 21) {
+        0b00 => "lsl",
+        0b01 => "lsr",
+        0b10 => "asr",
+        _ => "ror",
+    }
+}
+
+fn mov_shift_name(raw: u32) -> &'static str {
+    let imm5 = (bits(raw, 14, 12) << 2) | bits(raw, 7, 6);
+
+    match (bits(raw, 5, 4), imm5) {
+        (0b00, 0) => "mov",
+        (0b00, _) => "lsl",
+        (0b01, _) => "lsr",
+        (0b10, _) => "asr",
+        (_, 0) => "rrx",
+        _ => "ror",
+    }
+}
+
+fn parallel_name(raw: u32) -> &'static str {
+    let unsigned = bit(raw, 6);
+    let op = match bits(raw, 22, 20) {
+        0b000 => "add8",
+        0b001 => "add16",
+        0b010 => "asx",
+        0b100 => "sub8",
+        0b101 => "sub16",
+        _ => "sax",
+    };
+
+    // NOTE the combinations not listed below are `UNDEFINED` and filtered out by the table
+    match (unsigned, bits(raw, 5, 4), op) {
+        (false, 0b00, "add8") => "sadd8",
+        (false, 0b00, "add16") => "sadd16",
+        (false, 0b00, "asx") => "sasx",
+        (false, 0b00, "sub8") => "ssub8",
+        (false, 0b00, "sub16") => "ssub16",
+        (false, 0b00, _) => "ssax",
+        (false, 0b01, "add8") => "qadd8",
+        (false, 0b01, "add16") => "qadd16",
+        (false, 0b01, "asx") => "qasx",
+        (false, 0b01, "sub8") => "qsub8",
+        (false, 0b01, "sub16") => "qsub16",
+        (false, 0b01, _) => "qsax",
+        (false, _, "add8") => "shadd8",
+        (false, _, "add16") => "shadd16",
+        (false, _, "asx") => "shasx",
+        (false, _, "sub8") => "shsub8",
+        (false, _, "sub16") => "shsub16",
+        (false, _, _) => "shsax",
+        (true, 0b00, "add8") => "uadd8",
+        (true, 0b00, "add16") => "uadd16",
+        (true, 0b00, "asx") => "uasx",
+        (true, 0b00, "sub8") => "usub8",
+        (true, 0b00, "sub16") => "usub16",
+        (true, 0b00, _) => "usax",
+        (true, 0b01, "add8") => "uqadd8",
+        (true, 0b01, "add16") => "uqadd16",
+        (true, 0b01, "asx") => "uqasx",
+        (true, 0b01, "sub8") => "uqsub8",
+        (true, 0b01, "sub16") => "uqsub16",
+        (true, 0b01, _) => "uqsax",
+        (true, _, "add8") => "uhadd8",
+        (true, _, "add16") => "uhadd16",
+        (true, _, "asx") => "uhasx",
+        (true, _, "sub8") => "uhsub8",
+        (true, _, "sub16") => "uhsub16",
+        (true, _, _) => "uhsax",
+    }
+}
+
+fn extend_name(raw: u32) -> &'static str {
+    let accumulate = bits(raw, 19, 16) != PC;
+
+    match (bits(raw, 22, 20), accumulate) {
+        (0b000, false) => "sxth",
+        (0b000, true) => "sxtah",
+        (0b001, false) => "uxth",
+        (0b001, true) => "uxtah",
+        (0b010, false) => "sxtb16",
+        (0b010, true) => "sxtab16",
+        (0b011, false) => "uxtb16",
+        (0b011, true) => "uxtab16",
+        (0b100, false) => "sxtb",
+        (0b100, true) => "sxtab",
+        (_, false) => "uxtb",
+        (_, true) => "uxtab",
+    }
+}
+
+fn misc_name(raw: u32) -> &'static str {
+    match (bits(raw, 21, 20), bits(raw, 5, 4)) {
+        (0b00, 0b00) => "qadd",
+        (0b00, 0b01) => "qdadd",
+        (0b00, 0b10) => "qsub",
+        (0b00, _) => "qdsub",
+        (0b01, 0b00) => "rev",
+        (0b01, 0b01) => "rev16",
+        (0b01, 0b10) => "rbit",
+        (0b01, _) => "revsh",
+        (0b10, _) => "sel",
+        _ => "clz",
+    }
+}
+
+fn multiply_name(raw: u32) -> &'static str {
+    let accumulate = bits(raw, 15, 12) != PC;
+
+    match (bits(raw, 22, 20), accumulate) {
+        (0b000, _) if bits(raw, 5, 4) == 0b01 => "mls",
+        (0b000, false) => "mul",
+        (0b000, true) => "mla",
+        (0b001, false) => match bits(raw, 5, 4) {
+            0b00 => "smulbb",
+            0b01 => "smulbt",
+            0b10 => "smultb",
+            _ => "smultt",
+        },
+        (0b001, true) => match bits(raw, 5, 4) {
+            0b00 => "smlabb",
+            0b01 => "smlabt",
+            0b10 => "smlatb",
+            _ => "smlatt",
+        },
+        (0b010, false) => "smuad",
+        (0b010, true) => "smlad",
+        (0b011, false) => "smulw",
+        (0b011, true) => "smlaw",
+        (0b100, false) => "smusd",
+        (0b100, true) => "smlsd",
+        (0b101, false) => "smmul",
+        (0b101, true) => "smmla",
+        (0b110, _) => "smmls",
+        (_, false) => "usad8",
+        (_, true) => "usada8",
+    }
+}
+
+fn long_multiply_name(raw: u32) -> &'static str {
+    match (bits(raw, 22, 20), bits(raw, 7, 4)) {
+        (0b100, 0b0000) => "smlal",
+        (0b100, 0b1000) => "smlalbb",
+        (0b100, 0b1001) => "smlalbt",
+        (0b100, 0b1010) => "smlaltb",
+        (0b100, 0b1011) => "smlaltt",
+        (0b100, _) => "smlald",
+        (0b101, _) => "smlsld",
+        (0b110, 0b0000) => "umlal",
+        _ => "umaal",
+    }
+}
+
+fn fp_name(raw: u32) -> &'static str {
+    let opc1 = (bits(raw, 23, 23) << 2) | bits(raw, 21, 20);
+    let opc2 = bits(raw, 19, 16);
+    let op = bit(raw, 6);
+
+    match (opc1, op) {
+        (0b000, false) => "vmla",
+        (0b000, true) => "vmls",
+        (0b001, false) => "vnmls",
+        (0b001, true) => "vnmla",
+        (0b010, false) => "vmul",
+        (0b010, true) => "vnmul",
+        (0b011, false) => "vadd",
+        (0b011, true) => "vsub",
+        (0b100, _) => "vdiv",
+        (0b101, false) => "vfnms",
+        (0b101, true) => "vfnma",
+        (0b110, false) => "vfma",
+        (0b110, true) => "vfms",
+        // "other floating-point data-processing instructions"
+        (_, false) => "vmov",
+        (_, true) => match (opc2, bit(raw, 7)) {
+            (0b0000, false) => "vmov",
+            (0b0000, true) => "vabs",
+            (0b0001, false) => "vneg",
+            (0b0001, true) => "vsqrt",
+            (0b0100, _) | (0b0101, _) => "vcmp",
+            (0b0110, false) => "vrintr",
+            (0b0110, true) => "vrintz",
+            (0b0111, false) => "vrintx",
+            _ => "vcvt",
+        },
+    }
+}
+
+fn ldst_excl_name(raw: u32) -> &'static str {
+    let load = bit(raw, 20);
+
+    match (load, bits(raw, 7, 4)) {
+        (false, 0b0100) => "strexb",
+        (false, 0b0101) => "strexh",
+        (false, 0b1000) => "stlb",
+        (false, 0b1001) => "stlh",
+        (false, 0b1010) => "stl",
+        (false, 0b1100) => "stlexb",
+        (false, 0b1101) => "stlexh",
+        (false, _) => "stlex",
+        (true, 0b0100) => "ldrexb",
+        (true, 0b0101) => "ldrexh",
+        (true, 0b1000) => "ldab",
+        (true, 0b1001) => "ldah",
+        (true, 0b1010) => "lda",
+        (true, 0b1100) => "ldaexb",
+        (true, 0b1101) => "ldaexh",
+        (true, _) => "ldaex",
+    }
+}
+
+fn tt_name(raw: u32) -> &'static str {
+    match bits(raw, 7, 6) {
+        0b00 => "tt",
+        0b01 => "ttt",
+        0b10 => "tta",
+        _ => "ttat",
+    }
+}
+
+fn coproc_load_name(raw: u32) -> &'static str {
+    if bit(raw, 20) {
+        "ldc"
+    } else {
+        "stc"
+    }
+}
+
+fn coproc_transfer_name(raw: u32) -> &'static str {
+    if bit(raw, 20) {
+        "mrc"
+    } else {
+        "mcr"
+    }
+}
+
+fn pkh_name(raw: u32) -> &'static str {
+    if bit(raw, 5) {
+        "pkhtb"
+    } else {
+        "pkhbt"
+    }
+}
+
+fn vmaxnm_name(raw: u32) -> &'static str {
+    if bit(raw, 6) {
+        "vminnm"
+    } else {
+        "vmaxnm"
+    }
+}
+
+fn store_name(raw: u32) -> &'static str {
+    match bits(raw, 22, 21) {
+        0b00 => "strb",
+        0b01 => "strh",
+        _ => "str",
+    }
+}
+
+fn unprivileged_store_name(raw: u32) -> &'static str {
+    match bits(raw, 22, 21) {
+        0b00 => "strbt",
+        0b01 => "strht",
+        _ => "strt",
+    }
+}
+
+fn load_name(raw: u32) -> &'static str {
+    match (bit(raw, 24), bits(raw, 22, 21)) {
+        (false, 0b00) => "ldrb",
+        (false, 0b01) => "ldrh",
+        (true, 0b00) => "ldrsb",
+        (true, 0b01) => "ldrsh",
+        _ => "ldr",
+    }
+}
+
+fn unprivileged_load_name(raw: u32) -> &'static str {
+    match (bit(raw, 24), bits(raw, 22, 21)) {
+        (false, 0b00) => "ldrbt",
+        (false, 0b01) => "ldrht",
+        (true, 0b00) => "ldrsbt",
+        (true, 0b01) => "ldrsht",
+        _ => "ldrt",
+    }
+}
+
+// A5.2 16-bit Thumb instruction encoding
+#[rustfmt::skip]
+const T16: &[Encoding] = &[
+    /* A5.2.1 Shift (immediate), add, subtract, move, and compare */
+    enc("0b000_00_00000_xxx_xxx", "mov", ALL, other, rd_2_0),
+    enc("0b000_00_xxxxx_xxx_xxx", "lsl", ALL, other, rd_2_0),
+    enc("0b000_01_xxxxx_xxx_xxx", "lsr", ALL, other, rd_2_0),
+    enc("0b000_10_xxxxx_xxx_xxx", "asr", ALL, other, rd_2_0),
+    enc("0b000_11_0_0_xxx_xxx_xxx", "add", ALL, other, rd_2_0),
+    enc("0b000_11_0_1_xxx_xxx_xxx", "sub", ALL, other, rd_2_0),
+    enc("0b000_11_1_0_xxx_xxx_xxx", "add", ALL, other, rd_2_0),
+    enc("0b000_11_1_1_xxx_xxx_xxx", "sub", ALL, other, rd_2_0),
+    enc("0b001_00_xxx_xxxxxxxx", "mov", ALL, mov_imm_t1, rd_10_8),
+    enc("0b001_01_xxx_xxxxxxxx", "cmp", ALL, other, none),
+    enc("0b001_10_xxx_xxxxxxxx", "add", ALL, other, rd_10_8),
+    enc("0b001_11_xxx_xxxxxxxx", "sub", ALL, other, rd_10_8),
+    /* A5.2.2 Data processing */
+    enc("0b010000_0000_xxx_xxx", "and", ALL, other, rd_2_0),
+    enc("0b010000_0001_xxx_xxx", "eor", ALL, other, rd_2_0),
+    enc("0b010000_0010_xxx_xxx", "lsl", ALL, other, rd_2_0),
+    enc("0b010000_0011_xxx_xxx", "lsr", ALL, other, rd_2_0),
+    enc("0b010000_0100_xxx_xxx", "asr", ALL, other, rd_2_0),
+    enc("0b010000_0101_xxx_xxx", "adc", ALL, other, rd_2_0),
+    enc("0b010000_0110_xxx_xxx", "sbc", ALL, other, rd_2_0),
+    enc("0b010000_0111_xxx_xxx", "ror", ALL, other, rd_2_0),
+    enc("0b010000_1000_xxx_xxx", "tst", ALL, other, none),
+    enc("0b010000_1001_xxx_xxx", "rsb", ALL, other, rd_2_0),
+    enc("0b010000_1010_xxx_xxx", "cmp", ALL, other, none),
+    enc("0b010000_1011_xxx_xxx", "cmn", ALL, other, none),
+    enc("0b010000_1100_xxx_xxx", "orr", ALL, other, rd_2_0),
+    enc("0b010000_1101_xxx_xxx", "mul", ALL, other, rd_2_0),
+    enc("0b010000_1110_xxx_xxx", "bic", ALL, other, rd_2_0),
+    enc("0b010000_1111_xxx_xxx", "mvn", ALL, other, rd_2_0),
+    /* A5.2.3 Special data instructions and branch and exchange */
+    enc("0b010001_00_x_xxxx_xxx", "add", ALL, add_reg_t2, rd_hi),
+    enc("0b010001_01_x_xxxx_xxx", "cmp", ALL, other, none),
+    enc("0b010001_10_x_xxxx_xxx", "mov", ALL, mov_reg_t1, rd_hi),
+    enc("0b010001_11_0_xxxx_000", "bx", ALL, bx, none),
+    enc("0b010001_11_0_xxxx_100", "bxns", V8M, bxns, none),
+    enc("0b010001_11_1_xxxx_000", "blx", ALL, blx, call),
+    enc("0b010001_11_1_xxxx_100", "blxns", V8M, blxns, call),
+    /* A7.7.43 LDR (literal) */
+    enc("0b01001_xxx_xxxxxxxx", "ldr", ALL, ldr_lit_t1, rd_10_8),
+    /* A5.2.4 Load/store single data item */
+    enc("0b0101_000_xxx_xxx_xxx", "str", ALL, other, none),
+    enc("0b0101_001_xxx_xxx_xxx", "strh", ALL, other, none),
+    enc("0b0101_010_xxx_xxx_xxx", "strb", ALL, other, none),
+    enc("0b0101_011_xxx_xxx_xxx", "ldrsb", ALL, other, rd_2_0),
+    enc("0b0101_100_xxx_xxx_xxx", "ldr", ALL, other, rd_2_0),
+    enc("0b0101_101_xxx_xxx_xxx", "ldrh", ALL, other, rd_2_0),
+    enc("0b0101_110_xxx_xxx_xxx", "ldrb", ALL, other, rd_2_0),
+    enc("0b0101_111_xxx_xxx_xxx", "ldrsh", ALL, other, rd_2_0),
+    enc("0b011_0_0_xxxxx_xxx_xxx", "str", ALL, other, none),
+    enc("0b011_0_1_xxxxx_xxx_xxx", "ldr", ALL, other, rd_2_0),
+    enc("0b011_1_0_xxxxx_xxx_xxx", "strb", ALL, other, none),
+    enc("0b011_1_1_xxxxx_xxx_xxx", "ldrb", ALL, other, rd_2_0),
+    enc("0b1000_0_xxxxx_xxx_xxx", "strh", ALL, other, none),
+    enc("0b1000_1_xxxxx_xxx_xxx", "ldrh", ALL, other, rd_2_0),
+    enc("0b1001_0_xxx_xxxxxxxx", "str", ALL, other, none),
+    enc("0b1001_1_xxx_xxxxxxxx", "ldr", ALL, other, rd_10_8),
+    /* A7.7.7 ADR and A7.7.5 ADD (SP plus immediate) */
+    enc("0b1010_0_xxx_xxxxxxxx", "adr", ALL, adr_t1, rd_10_8),
+    enc("0b1010_1_xxx_xxxxxxxx", "add", ALL, other, rd_10_8),
+    /* A5.2.5 Miscellaneous 16-bit instructions */
+    enc("0b1011_0000_0_xxxxxxx", "add", ALL, add_sp_t2, sp),
+    enc("0b1011_0000_1_xxxxxxx", "sub", ALL, sub_sp_t1, sp),
+    enc("0b1011_0_0_x_1_xxxxx_xxx", "cbz", NOT_V6M, cbz, none),
+    enc("0b1011_1_0_x_1_xxxxx_xxx", "cbnz", NOT_V6M, cbz, none),
+    enc("0b1011_0010_00_xxx_xxx", "sxth", ALL, other, rd_2_0),
+    enc("0b1011_0010_01_xxx_xxx", "sxtb", ALL, other, rd_2_0),
+    enc("0b1011_0010_10_xxx_xxx", "uxth", ALL, other, rd_2_0),
+    enc("0b1011_0010_11_xxx_xxx", "uxtb", ALL, other, rd_2_0),
+    enc("0b1011_0_10_x_xxxxxxxx", "push", ALL, push_t1, sp),
+    enc("0b1011_0110_011_x_xxxx", "cps", ALL, other, none),
+    enc("0b1011_1010_00_xxx_xxx", "rev", ALL, other, rd_2_0),
+    enc("0b1011_1010_01_xxx_xxx", "rev16", ALL, other, rd_2_0),
+    enc("0b1011_1010_11_xxx_xxx", "revsh", ALL, other, rd_2_0),
+    enc("0b1011_1_10_x_xxxxxxxx", "pop", ALL, pop_t1, pop16),
+    enc("0b1011_1110_xxxxxxxx", "bkpt", ALL, other, none),
+    enc("0b1011_1111_0000_0000", "nop", ALL, other, none),
+    enc("0b1011_1111_0001_0000", "yield", ALL, other, none),
+    enc("0b1011_1111_0010_0000", "wfe", ALL, other, none),
+    enc("0b1011_1111_0011_0000", "wfi", ALL, other, none),
+    enc("0b1011_1111_0100_0000", "sev", ALL, other, none),
+    // unallocated hints execute as NOPs
+    enc("0b1011_1111_xxxx_0000", "hint", ALL, other, none),
+    enc("0b1011_1111_xxxx_xxxx", "it", T2, it, none),
+    /* A7.7.156 STM and A7.7.40 LDM */
+    enc("0b1100_0_xxx_xxxxxxxx", "stm", ALL, other, stm_t1),
+    enc("0b1100_1_xxx_xxxxxxxx", "ldm", ALL, other, ldm_t1),
+    /* A5.2.6 Conditional branch, and Supervisor Call */
+    enc("0b1101_1110_xxxxxxxx", "udf", ALL, other, none),
+    enc("0b1101_1111_xxxxxxxx", "svc", ALL, other, call),
+    enc("0b1101_xxxx_xxxxxxxx", "b", ALL, b_t1, none),
+    /* A7.7.12 B - T2 */
+    enc("0b11100_xxxxxxxxxxx", "b", ALL, b_t2, none),
+];
+
+// A5.3 32-bit Thumb instruction encoding
+#[rustfmt::skip]
+const T32: &[Encoding] = &[
+    /* A5.3.5 Load Multiple and Store Multiple */
+    enc("0b11101_00_010_x_0_xxxx 0b0_x_0_xxxxxxxxxxxxx", "stm", T2, other, rn_wb),
+    enc("0b11101_00_010_1_1_1101 0bxxxxxxxxxxxxxxxx", "pop", T2, ldm_t2, ldm32),
+    enc("0b11101_00_010_x_1_xxxx 0bxxxxxxxxxxxxxxxx", "ldm", T2, ldm_t2, ldm32),
+    enc("0b11101_00_100_1_0_1101 0b0_x_0_xxxxxxxxxxxxx", "push", T2, stmdb, rn_wb),
+    enc("0b11101_00_100_x_0_xxxx 0b0_x_0_xxxxxxxxxxxxx", "stmdb", T2, stmdb, rn_wb),
+    enc("0b11101_00_100_x_1_xxxx 0bxxxxxxxxxxxxxxxx", "ldmdb", T2, ldm_t2, ldm32),
+    /* A5.3.6 Load/store dual or exclusive, table branch */
+    enc("0b11101_00_1_0_1_1_1_1111 0b11101_00_1_0_1_1_1_1111", "sg", V8M, sg, none),
+    dyn_enc("0b11101_00_0_0_1_0_0_xxxx 0b1111_xxxx_xx_000000", tt_name, V8M, other, rd_11_8),
+    enc("0b11101_00_0_0_1_0_0_xxxx 0bxxxx_xxxx_xxxxxxxx", "strex", NOT_V6M, other, rd_11_8),
+    enc("0b11101_00_0_0_1_0_1_xxxx 0bxxxx_1111_xxxxxxxx", "ldrex", NOT_V6M, other, rt_15_12),
+    enc("0b11101_00_1_x_1_x_0_xxxx 0bxxxx_xxxx_xxxxxxxx", "strd", T2, strd, rn_wb),
+    enc("0b11101_00_0_x_1_1_0_xxxx 0bxxxx_xxxx_xxxxxxxx", "strd", T2, strd, rn_wb),
+    enc("0b11101_00_1_x_1_x_1_xxxx 0bxxxx_xxxx_xxxxxxxx", "ldrd", T2, other, ldrd),
+    enc("0b11101_00_0_x_1_1_1_xxxx 0bxxxx_xxxx_xxxxxxxx", "ldrd", T2, other, ldrd),
+    dyn_enc("0b11101_00_0_1_1_0_0_xxxx 0bxxxx_1111_010x_xxxx", ldst_excl_name, NOT_V6M, other, rd_3_0),
+    undefined("0b11101_00_0_1_1_0_x_xxxx 0bxxxx_xxxx_1x11_xxxx"),
+    dyn_enc("0b11101_00_0_1_1_0_0_xxxx 0bxxxx_1111_10xx_1111", ldst_excl_name, V8M, other, none),
+    dyn_enc("0b11101_00_0_1_1_0_0_xxxx 0bxxxx_1111_11xx_xxxx", ldst_excl_name, V8M, other, rd_3_0),
+    enc("0b11101_00_0_1_1_0_1_xxxx 0b1111_0000_0000_xxxx", "tbb", T2, table_branch, pc),
+    enc("0b11101_00_0_1_1_0_1_xxxx 0b1111_0000_0001_xxxx", "tbh", T2, table_branch, pc),
+    dyn_enc("0b11101_00_0_1_1_0_1_xxxx 0bxxxx_1111_010x_1111", ldst_excl_name, NOT_V6M, other, rt_15_12),
+    dyn_enc("0b11101_00_0_1_1_0_1_xxxx 0bxxxx_1111_1x_xx_1111", ldst_excl_name, V8M, other, rt_15_12),
+    /* A5.3.11 Data processing (shifted register) */
+    enc("0b11101_01_0000_1_xxxx 0bx_xxx_1111_xxxxxxxx", "tst", T2, other, none),
+    enc("0b11101_01_0000_x_xxxx 0bx_xxx_xxxx_xxxxxxxx", "and", T2, other, rd_11_8),
+    enc("0b11101_01_0001_x_xxxx 0bx_xxx_xxxx_xxxxxxxx", "bic", T2, other, rd_11_8),
+    dyn_enc("0b11101_01_0010_x_1111 0b0_xxx_xxxx_xxxxxxxx", mov_shift_name, T2, mov_reg_t3, rd_11_8),
+    enc("0b11101_01_0010_x_xxxx 0b0_xxx_xxxx_xxxxxxxx", "orr", T2, other, rd_11_8),
+    enc("0b11101_01_0011_x_1111 0bx_xxx_xxxx_xxxxxxxx", "mvn", T2, other, rd_11_8),
+    enc("0b11101_01_0011_x_xxxx 0bx_xxx_xxxx_xxxxxxxx", "orn", T2, other, rd_11_8),
+    enc("0b11101_01_0100_1_xxxx 0bx_xxx_1111_xxxxxxxx", "teq", T2, other, none),
+    enc("0b11101_01_0100_x_xxxx 0bx_xxx_xxxx_xxxxxxxx", "eor", T2, other, rd_11_8),
+    dyn_enc("0b11101_01_0110_0_xxxx 0bx_xxx_xxxx_xx_x_0_xxxx", pkh_name, T2, other, rd_11_8),
+    enc("0b11101_01_1000_1_xxxx 0bx_xxx_1111_xxxxxxxx", "cmn", T2, other, none),
+    enc("0b11101_01_1000_x_xxxx 0bx_xxx_xxxx_xxxxxxxx", "add", T2, other, rd_11_8),
+    enc("0b11101_01_1010_x_xxxx 0bx_xxx_xxxx_xxxxxxxx", "adc", T2, other, rd_11_8),
+    enc("0b11101_01_1011_x_xxxx 0bx_xxx_xxxx_xxxxxxxx", "sbc", T2, other, rd_11_8),
+    enc("0b11101_01_1101_1_xxxx 0bx_xxx_1111_xxxxxxxx", "cmp", T2, other, none),
+    enc("0b11101_01_1101_x_xxxx 0bx_xxx_xxxx_xxxxxxxx", "sub", T2, sub_sp_reg, rd_11_8),
+    enc("0b11101_01_1110_x_xxxx 0bx_xxx_xxxx_xxxxxxxx", "rsb", T2, other, rd_11_8),
+    /* A5.3.18 Coprocessor instructions, including the floating-point extension (A6.4) */
+    enc("0b1110_1101_0_x_10_1101 0bxxxx_101x_xxxxxxxx", "vpush", T2, vpush, sp),
+    enc("0b1110_1100_1_x_11_1101 0bxxxx_101x_xxxxxxxx", "vpop", T2, vpop, sp),
+    enc("0b1110_1100_010_x_xxxx 0bxxxx_101x_00_x1_xxxx", "vmov", T2, other, vmov_rr),
+    enc("0b1110_1101_xx_0_0_xxxx 0bxxxx_101x_xxxxxxxx", "vstr", T2, other, none),
+    enc("0b1110_1101_xx_0_1_xxxx 0bxxxx_101x_xxxxxxxx", "vldr", T2, other, none),
+    enc("0b1110_110_0_1_x_x_0_xxxx 0bxxxx_101x_xxxxxxxx", "vstm", T2, other, rn_wb),
+    enc("0b1110_110_1_0_x_1_0_xxxx 0bxxxx_101x_xxxxxxxx", "vstmdb", T2, other, rn_wb),
+    enc("0b1110_110_0_1_x_x_1_xxxx 0bxxxx_101x_xxxxxxxx", "vldm", T2, other, vldm),
+    enc("0b1110_110_1_0_x_1_1_xxxx 0bxxxx_101x_xxxxxxxx", "vldmdb", T2, other, vldm),
+    enc("0b1110_1110_000_0_xxxx 0bxxxx_1010_x_00_1_0000", "vmov", T2, other, none),
+    enc("0b1110_1110_000_1_xxxx 0bxxxx_1010_x_00_1_0000", "vmov", T2, other, rt_15_12),
+    enc("0b1110_1110_111_0_0001 0bxxxx_1010_0001_0000", "vmsr", T2, other, none),
+    enc("0b1110_1110_111_1_0001 0bxxxx_1010_0001_0000", "vmrs", T2, other, rt_not_pc),
+    enc("0b1110_1110_00_x_0_xxxx 0bxxxx_1011_x_00_1_0000", "vmov", T2, other, none),
+    enc("0b1110_1110_00_x_1_xxxx 0bxxxx_1011_x_00_1_0000", "vmov", T2, other, rt_15_12),
+    undefined("0b1110_1110_1x_00_xxxx 0bxxxx_101x_x1x0_xxxx"),
+    dyn_enc("0b1110_1110_xxxx_xxxx 0bxxxx_101x_xxx0_xxxx", fp_name, T2, other, none),
+    // floating-point extensions introduced by FPv5 / ARMv8-M
+    enc("0b1111_1110_0x_xx_xxxx 0bxxxx_101x_x0x0_xxxx", "vsel", T2, other, none),
+    dyn_enc("0b1111_1110_1x_00_xxxx 0bxxxx_101x_xxx0_xxxx", vmaxnm_name, T2, other, none),
+    enc("0b1111_1110_1x_11_10xx 0bxxxx_101x_01x0_xxxx", "vrint", T2, other, none),
+    enc("0b1111_1110_1x_11_11xx 0bxxxx_101x_x1x0_xxxx", "vcvt", T2, other, none),
+    // the rest of the coprocessor 10 and 11 space is reserved for the floating-point extension
+    undefined("0b1110_11xx_xxxx_xxxx 0bxxxx_101x_xxxxxxxx"),
+    dyn_enc("0b1111_1100_11_0x_xxxx 0bxxxx_101x_xxxxxxxx", coproc_load_name, T2, other, none),
+    undefined("0b1111_11xx_xxxx_xxxx 0bxxxx_101x_xxxxxxxx"),
+    enc("0b111x_1100_010_0_xxxx 0bxxxx_xxxx_xxxxxxxx", "mcrr", T2, other, none),
+    enc("0b111x_1100_010_1_xxxx 0bxxxx_xxxx_xxxxxxxx", "mrrc", T2, other, rdlo_rdhi),
+    undefined("0b111x_1100_000x_xxxx 0bxxxx_xxxx_xxxxxxxx"),
+    dyn_enc("0b111x_110x_xxxx_xxxx 0bxxxx_xxxx_xxxxxxxx", coproc_load_name, T2, other, rn_wb),
+    dyn_enc("0b111x_1110_xxx_x_xxxx 0bxxxx_xxxx_xxx1_xxxx", coproc_transfer_name, T2, other, rt_not_pc),
+    enc("0b111x_1110_xxxx_xxxx 0bxxxx_xxxx_xxx0_xxxx", "cdp", T2, other, none),
+    /* A5.3.1 Data processing (modified immediate) */
+    enc("0b11110_x_0_0000_1_xxxx 0b0_xxx_1111_xxxxxxxx", "tst", T2, other, none),
+    enc("0b11110_x_0_0000_x_xxxx 0b0_xxx_xxxx_xxxxxxxx", "and", T2, other, rd_11_8),
+    enc("0b11110_x_0_0001_x_xxxx 0b0_xxx_xxxx_xxxxxxxx", "bic", T2, other, rd_11_8),
+    enc("0b11110_x_0_0010_x_1111 0b0_xxx_xxxx_xxxxxxxx", "mov", T2, mov_imm_t2, rd_11_8),
+    enc("0b11110_x_0_0010_x_xxxx 0b0_xxx_xxxx_xxxxxxxx", "orr", T2, other, rd_11_8),
+    enc("0b11110_x_0_0011_x_1111 0b0_xxx_xxxx_xxxxxxxx", "mvn", T2, other, rd_11_8),
+    enc("0b11110_x_0_0011_x_xxxx 0b0_xxx_xxxx_xxxxxxxx", "orn", T2, other, rd_11_8),
+    enc("0b11110_x_0_0100_1_xxxx 0b0_xxx_1111_xxxxxxxx", "teq", T2, other, none),
+    enc("0b11110_x_0_0100_x_xxxx 0b0_xxx_xxxx_xxxxxxxx", "eor", T2, other, rd_11_8),
+    enc("0b11110_x_0_1000_1_xxxx 0b0_xxx_1111_xxxxxxxx", "cmn", T2, other, none),
+    enc("0b11110_x_0_1000_x_xxxx 0b0_xxx_xxxx_xxxxxxxx", "add", T2, add_sp_t3, rd_11_8),
+    enc("0b11110_x_0_1010_x_xxxx 0b0_xxx_xxxx_xxxxxxxx", "adc", T2, other, rd_11_8),
+    enc("0b11110_x_0_1011_x_xxxx 0b0_xxx_xxxx_xxxxxxxx", "sbc", T2, other, rd_11_8),
+    enc("0b11110_x_0_1101_1_xxxx 0b0_xxx_1111_xxxxxxxx", "cmp", T2, other, none),
+    enc("0b11110_x_0_1101_x_1101 0b0_xxx_xxxx_xxxxxxxx", "sub", T2, sub_sp_t2, rd_11_8),
+    enc("0b11110_x_0_1101_x_xxxx 0b0_xxx_xxxx_xxxxxxxx", "sub", T2, other, rd_11_8),
+    enc("0b11110_x_0_1110_x_xxxx 0b0_xxx_xxxx_xxxxxxxx", "rsb", T2, other, rd_11_8),
+    /* A5.3.3 Data processing (plain binary immediate) */
+    enc("0b11110_x_1_0000_0_1111 0b0_xxx_xxxx_xxxxxxxx", "adr", T2, adr_t3, rd_11_8),
+    enc("0b11110_x_1_0000_0_xxxx 0b0_xxx_xxxx_xxxxxxxx", "addw", T2, add_sp_t4, rd_11_8),
+    enc("0b11110_x_1_0010_0_xxxx 0b0_xxx_xxxx_xxxxxxxx", "movw", NOT_V6M, movw, rd_11_8),
+    enc("0b11110_x_1_0101_0_1111 0b0_xxx_xxxx_xxxxxxxx", "adr", T2, adr_t2, rd_11_8),
+    enc("0b11110_x_1_0101_0_1101 0b0_xxx_xxxx_xxxxxxxx", "subw", T2, sub_sp_t3, rd_11_8),
+    enc("0b11110_x_1_0101_0_xxxx 0b0_xxx_xxxx_xxxxxxxx", "subw", T2, other, rd_11_8),
+    enc("0b11110_x_1_0110_0_xxxx 0b0_xxx_xxxx_xxxxxxxx", "movt", NOT_V6M, movt, rd_11_8),
+    enc("0b11110_0_1_1001_0_xxxx 0b0_000_xxxx_00_00_xxxx", "ssat16", T2, other, rd_11_8),
+    enc("0b11110_0_1_100_x_0_xxxx 0b0_xxx_xxxx_xx_0_xxxxx", "ssat", T2, other, rd_11_8),
+    enc("0b11110_x_1_1010_0_xxxx 0b0_xxx_xxxx_xxxxxxxx", "sbfx", T2, other, rd_11_8),
+    enc("0b11110_0_1_1011_0_1111 0b0_xxx_xxxx_xx_0_xxxxx", "bfc", T2, other, rd_11_8),
+    enc("0b11110_0_1_1011_0_xxxx 0b0_xxx_xxxx_xx_0_xxxxx", "bfi", T2, other, rd_11_8),
+    enc("0b11110_0_1_1101_0_xxxx 0b0_000_xxxx_00_00_xxxx", "usat16", T2, other, rd_11_8),
+    enc("0b11110_0_1_110_x_0_xxxx 0b0_xxx_xxxx_xx_0_xxxxx", "usat", T2, other, rd_11_8),
+    enc("0b11110_x_1_1110_0_xxxx 0b0_xxx_xxxx_xxxxxxxx", "ubfx", T2, other, rd_11_8),
+    /* A5.3.4 Branches and miscellaneous control */
+    enc("0b11110_011100_x_xxxx 0b10_x_0_xxxx_xxxxxxxx", "msr", ALL, other, none),
+    enc("0b11110_0111010_1111 0b10_x_0_x_000_00000000", "nop", T2, other, none),
+    enc("0b11110_0111010_1111 0b10_x_0_x_000_00000001", "yield", T2, other, none),
+    enc("0b11110_0111010_1111 0b10_x_0_x_000_00000010", "wfe", T2, other, none),
+    enc("0b11110_0111010_1111 0b10_x_0_x_000_00000011", "wfi", T2, other, none),
+    enc("0b11110_0111010_1111 0b10_x_0_x_000_00000100", "sev", T2, other, none),
+    enc("0b11110_0111010_1111 0b10_x_0_x_000_1111xxxx", "dbg", T2, other, none),
+    // unallocated hints execute as NOPs
+    enc("0b11110_0111010_1111 0b10_x_0_x_000_xxxxxxxx", "hint", T2, other, none),
+    enc("0b11110_0111011_1111 0b10_x_0_xxxx_0010_xxxx", "clrex", T2, other, none),
+    enc("0b11110_0111011_1111 0b10_x_0_xxxx_0100_xxxx", "dsb", ALL, other, none),
+    enc("0b11110_0111011_1111 0b10_x_0_xxxx_0101_xxxx", "dmb", ALL, other, none),
+    enc("0b11110_0111011_1111 0b10_x_0_xxxx_0110_xxxx", "isb", ALL, other, none),
+    enc("0b11110_011111_x_1111 0b10_x_0_xxxx_xxxxxxxx", "mrs", ALL, other, rd_11_8),
+    enc("0b11110_1111111_xxxx 0b1010_xxxxxxxxxxxx", "udf", ALL, other, none),
+    // `cond == 0b111x` is used to encode the instructions above
+    undefined("0b11110_x_111x_xxxxxx 0b10_x_0_xxxxxxxxxxxx"),
+    enc("0b11110_x_xxxxxxxxxx 0b10_x_0_xxxxxxxxxxxx", "b", T2, b_t3, none),
+    enc("0b11110_x_xxxxxxxxxx 0b10_x_1_xxxxxxxxxxxx", "b", NOT_V6M, b_t4, none),
+    enc("0b11110_x_xxxxxxxxxx 0b11_x_1_xxxxxxxxxxxx", "bl", ALL, bl, call),
+    /* A5.3.10 Store single data item */
+    undefined("0b11111_00_0_x_11_0_xxxx 0bxxxx_xxxx_xxxxxxxx"),
+    undefined("0b11111_00_0_x_xx_0_1111 0bxxxx_xxxx_xxxxxxxx"),
+    dyn_enc("0b11111_00_0_1_xx_0_xxxx 0bxxxx_xxxx_xxxxxxxx", store_name, T2, other, none),
+    dyn_enc("0b11111_00_0_0_xx_0_xxxx 0bxxxx_1110_xxxxxxxx", unprivileged_store_name, T2, other, none),
+    undefined("0b11111_00_0_0_xx_0_xxxx 0bxxxx_1_0_x_0_xxxxxxxx"),
+    dyn_enc("0b11111_00_0_0_xx_0_xxxx 0bxxxx_1_xxx_xxxxxxxx", store_name, T2, str_imm8, str_imm8_wb),
+    dyn_enc("0b11111_00_0_0_xx_0_xxxx 0bxxxx_0_00000_xx_xxxx", store_name, T2, other, none),
+    /* A5.3.7 Load word, A5.3.8 Load halfword, A5.3.9 Load byte, memory hints */
+    undefined("0b11111_00_x_x_11_1_xxxx 0bxxxx_xxxx_xxxxxxxx"),
+    undefined("0b11111_00_1_x_10_1_xxxx 0bxxxx_xxxx_xxxxxxxx"),
+    enc("0b11111_00_0_x_00_1_1111 0b1111_xxxx_xxxxxxxx", "pld", T2, other, none),
+    enc("0b11111_00_0_1_00_1_xxxx 0b1111_xxxx_xxxxxxxx", "pld", T2, other, none),
+    enc("0b11111_00_0_0_00_1_xxxx 0b1111_1100_xxxxxxxx", "pld", T2, other, none),
+    enc("0b11111_00_0_0_00_1_xxxx 0b1111_0_00000_xx_xxxx", "pld", T2, other, none),
+    enc("0b11111_00_1_x_00_1_1111 0b1111_xxxx_xxxxxxxx", "pli", T2, other, none),
+    enc("0b11111_00_1_1_00_1_xxxx 0b1111_xxxx_xxxxxxxx", "pli", T2, other, none),
+    enc("0b11111_00_1_0_00_1_xxxx 0b1111_1100_xxxxxxxx", "pli", T2, other, none),
+    enc("0b11111_00_1_0_00_1_xxxx 0b1111_0_00000_xx_xxxx", "pli", T2, other, none),
+    // unallocated memory hints execute as NOPs
+    enc("0b11111_00_x_x_01_1_1111 0b1111_xxxx_xxxxxxxx", "nop", T2, other, none),
+    enc("0b11111_00_x_1_01_1_xxxx 0b1111_xxxx_xxxxxxxx", "nop", T2, other, none),
+    enc("0b11111_00_x_0_01_1_xxxx 0b1111_1100_xxxxxxxx", "nop", T2, other, none),
+    enc("0b11111_00_x_0_01_1_xxxx 0b1111_0_00000_xx_xxxx", "nop", T2, other, none),
+    dyn_enc("0b11111_00_x_x_0x_1_1111 0bxxxx_xxxx_xxxxxxxx", load_name, T2, other, rt_15_12),
+    enc("0b11111_00_0_x_10_1_1111 0bxxxx_xxxx_xxxxxxxx", "ldr", T2, ldr_lit_t2, rt_15_12),
+    dyn_enc("0b11111_00_x_1_0x_1_xxxx 0bxxxx_xxxx_xxxxxxxx", load_name, T2, other, rt_15_12),
+    enc("0b11111_00_0_1_10_1_xxxx 0bxxxx_xxxx_xxxxxxxx", "ldr", T2, ldr_imm12, rt_15_12),
+    dyn_enc("0b11111_00_x_0_xx_1_xxxx 0bxxxx_1110_xxxxxxxx", unprivileged_load_name, T2, other, rt_15_12),
+    undefined("0b11111_00_x_0_xx_1_xxxx 0bxxxx_1_0_x_0_xxxxxxxx"),
+    dyn_enc("0b11111_00_x_0_0x_1_xxxx 0bxxxx_1_xxx_xxxxxxxx", load_name, T2, other, rt_imm8),
+    enc("0b11111_00_0_0_10_1_xxxx 0bxxxx_1_xxx_xxxxxxxx", "ldr", T2, ldr_imm8, rt_imm8),
+    dyn_enc("0b11111_00_x_0_0x_1_xxxx 0bxxxx_0_00000_xx_xxxx", load_name, T2, other, rt_15_12),
+    enc("0b11111_00_0_0_10_1_xxxx 0bxxxx_0_00000_xx_xxxx", "ldr", T2, ldr_imm12, rt_15_12),
+    /* A5.3.12 Data processing (register) */
+    dyn_enc("0b11111_010_0_xx_x_xxxx 0b1111_xxxx_0000_xxxx", shift_name, T2, other, rd_11_8),
+    undefined("0b11111_010_0_11x_xxxx 0b1111_xxxx_1xxx_xxxx"),
+    dyn_enc("0b11111_010_0_xxx_xxxx 0b1111_xxxx_1xxx_xxxx", extend_name, T2, other, rd_11_8),
+    undefined("0b11111_010_1_x11_xxxx 0b1111_xxxx_0xxx_xxxx"),
+    undefined("0b11111_010_1_xxx_xxxx 0b1111_xxxx_0x11_xxxx"),
+    dyn_enc("0b11111_010_1_xxx_xxxx 0b1111_xxxx_0xxx_xxxx", parallel_name, T2, other, rd_11_8),
+    undefined("0b11111_010_1_1xx_xxxx 0b1111_xxxx_10xx_xxxx"),
+    undefined("0b11111_010_1_01x_xxxx 0b1111_xxxx_10x1_xxxx"),
+    undefined("0b11111_010_1_01x_xxxx 0b1111_xxxx_101x_xxxx"),
+    dyn_enc("0b11111_010_1_0xx_xxxx 0b1111_xxxx_10xx_xxxx", misc_name, T2, other, rd_11_8),
+    /* A5.3.16 Multiply, multiply accumulate, and absolute difference */
+    undefined("0b11111_0110_000_xxxx 0bxxxx_xxxx_00_1x_xxxx"),
+    undefined("0b11111_0110_01x_xxxx 0bxxxx_xxxx_00_1x_xxxx"),
+    undefined("0b11111_0110_1xx_xxxx 0bxxxx_xxxx_00_1x_xxxx"),
+    undefined("0b11111_0110_111_xxxx 0bxxxx_xxxx_00_01_xxxx"),
+    dyn_enc("0b11111_0110_xxx_xxxx 0bxxxx_xxxx_00_xx_xxxx", multiply_name, T2, other, rd_11_8),
+    /* A5.3.17 Long multiply, long multiply accumulate, and divide */
+    enc("0b11111_0111_000_xxxx 0bxxxx_xxxx_0000_xxxx", "smull", T2, other, rdlo_rdhi),
+    enc("0b11111_0111_001_xxxx 0b1111_xxxx_1111_xxxx", "sdiv", NOT_V6M, other, rd_11_8),
+    enc("0b11111_0111_010_xxxx 0bxxxx_xxxx_0000_xxxx", "umull", T2, other, rdlo_rdhi),
+    enc("0b11111_0111_011_xxxx 0b1111_xxxx_1111_xxxx", "udiv", NOT_V6M, other, rd_11_8),
+    dyn_enc("0b11111_0111_100_xxxx 0bxxxx_xxxx_0000_xxxx", long_multiply_name, T2, other, rdlo_rdhi),
+    dyn_enc("0b11111_0111_100_xxxx 0bxxxx_xxxx_10xx_xxxx", long_multiply_name, T2, other, rdlo_rdhi),
+    dyn_enc("0b11111_0111_10x_xxxx 0bxxxx_xxxx_110x_xxxx", long_multiply_name, T2, other, rdlo_rdhi),
+    dyn_enc("0b11111_0111_110_xxxx 0bxxxx_xxxx_0000_xxxx", long_multiply_name, T2, other, rdlo_rdhi),
+    dyn_enc("0b11111_0111_110_xxxx 0bxxxx_xxxx_0110_xxxx", long_multiply_name, T2, other, rdlo_rdhi),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::Arch;
+
+    // Encodings checked against `llvm-mc --disassemble`; see the header of the file for the format
+    const CORPUS: &str = include_str!("decode/corpus.txt");
+
+    #[test]
+    fn corpus() {
+        for line in CORPUS.lines() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let arch = match words.next().unwrap() {
+                "v6m" => Arch::V6M,
+                "v7m" => Arch::V7M,
+                "v8mbase" => Arch::V8MBase,
+                "v8mmain" => Arch::V8MMain,
+                arch => panic!("unknown architecture: {}", arch),
+            };
+
+            let first = u16::from_str_radix(words.next().unwrap(), 16).unwrap();
+            let mut bytes = first.to_le_bytes().to_vec();
+            if super::is_32bit(u32::from(first)) {
+                let second = u16::from_str_radix(words.next().unwrap(), 16).unwrap();
+                bytes.extend_from_slice(&second.to_le_bytes());
+            }
+
+            let name = words.next().unwrap();
+            let op = words.collect::<Vec<_>>().join(" ");
+
+            let actual = super::decode(&bytes, arch);
+            if name == "-" {
+                assert_eq!(actual, None, "{}", line);
+            } else {
+                let actual = actual.unwrap_or_else(|| panic!("{}: not decoded", line));
+                assert_eq!(
+                    (actual.name, format!("{:?}", actual.op)),
+                    (name, op),
+                    "{}",
+                    line
+                );
+                assert_eq!(actual.size as usize, bytes.len(), "{}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn truncated() {
+        // first half of `BL`
+        assert_eq!(super::decode(&[0x00, 0xf0], Arch::V7M), None);
+        assert_eq!(super::decode(&[], Arch::V7M), None);
+    }
+}
@@ -0,0 +1,1040 @@
+use core::{cmp, fmt};
+use std::collections::HashSet;
+
+// Reference: Intel 64 and IA-32 Architectures Software Developer's Manual, Volume 2 - Chapter 2
+// (Instruction Format) and Appendix A (Opcode Map)
+
+const RCX: u8 = 1;
+const RSP: u8 = 4;
+const RBP: u8 = 5;
+
+// the maximum length of an instruction
+const MAX_LENGTH: usize = 15;
+
+/// Analyzes a subroutine and returns all the `CALL` and `JMP` instructions in it, plus whether this
+/// function performs an indirect function call or not
+///
+/// Encodings that can't be decoded are reported as an `Error`; the caller decides whether that's
+/// fatal or not
+pub fn analyze(bytes: &[u8], address: u64) -> Result<Analysis, Error> {
+    // we want to know if any of the instructions modifies the SP (stack pointer). We use this
+    // information to determine if the subroutine uses stack space or not. We want to detect the
+    // following instructions:
+    // - 55                    push    rbp
+    // - 48 83 ec 18           sub     rsp, 0x18
+    // - 48 81 ec 88 00 00 00  sub     rsp, 0x88
+    // - 48 29 c4              sub     rsp, rax
+    let mut modifies_sp = false;
+
+    // same as in the Thumb analysis: we only compute the stack usage of subroutines that contain
+    // no intra-branches. `sp` is the running offset of the SP from its value on entry; `stack` is
+    // the lowest offset seen so far, i.e. minus the peak stack usage
+    let mut sp = 0i64;
+    let mut stack = Some(0i64);
+
+    let mut calls = vec![];
+    let mut jmps = vec![];
+    let mut indirect = false;
+
+    // lightweight constant propagation used to resolve `lea rax, [rip + sym]; call rax` and to
+    // bound `mov eax, imm; sub rsp, rax` stack allocations. A register keeps its constant until an
+    // instruction writes to it; calls clobber all of them. Values known on one path may not hold
+    // on the others so the constants are also forgotten after unconditional jumps and returns, and
+    // at the targets of intra-function branches
+    let mut consts = [None; 16];
+    let mut resolved = vec![];
+
+    let mut insns = vec![];
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let start = address.wrapping_add(offset as u64);
+
+        let insn = match decode(&bytes[offset..]) {
+            Some(insn) => insn,
+            None => return Err(Error::unknown(&bytes[offset..], start)),
+        };
+
+        insns.push((offset, insn));
+        offset += insn.size;
+    }
+
+    let targets = insns
+        .iter()
+        .filter_map(|(offset, insn)| match insn.op {
+            Op::Jmp(rel) | Op::Jcc(rel) => Some((offset + insn.size) as i64 + i64::from(rel)),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    for (offset, insn) in insns {
+        let Instruction { op, writes, size } = insn;
+        let start = address.wrapping_add(offset as u64);
+
+        if targets.contains(&(offset as i64)) {
+            consts = [None; 16];
+        }
+
+        // offset of the next instruction, relative to the start of the subroutine. Relative
+        // branches and RIP-relative addresses are computed from it
+        let next = (offset + size) as i64;
+
+        let known = consts;
+        match writes {
+            Writes::None => {}
+            Writes::Reg(reg) => consts[usize::from(reg)] = None,
+            Writes::Unknown => consts = [None; 16],
+        }
+
+        // the value of the register written by a constant-producing instruction, if known
+        let constant = match op {
+            Op::Jmp(rel) | Op::Jcc(rel) => {
+                let target = next + i64::from(rel);
+
+                if target >= 0 && (target as usize) < bytes.len() {
+                    // this is an `if` or `loop`; give up the stack usage analysis
+                    stack = None;
+                }
+
+                jmps.push(target);
+                None
+            }
+
+            Op::Call(rel) => {
+                calls.push(next + i64::from(rel));
+                None
+            }
+
+            Op::CallRegister(reg) => {
+                if let Some(target) = known[usize::from(reg)] {
+                    resolved.push(target);
+                } else {
+                    indirect = true;
+                }
+                None
+            }
+
+            Op::JmpRegister(reg) => {
+                if let Some(target) = known[usize::from(reg)] {
+                    // tail call
+                    resolved.push(target);
+                } else {
+                    // either a jump table or an indirect tail call
+                    indirect = true;
+                    stack = None;
+                }
+                None
+            }
+
+            Op::CallMemory => {
+                // e.g. `call qword ptr [rip + foo@GOTPCREL]`
+                indirect = true;
+                None
+            }
+
+            Op::JmpMemory => {
+                indirect = true;
+                stack = None;
+                None
+            }
+
+            Op::Push(n) => {
+                modifies_sp = true;
+
+                sp -= i64::from(n);
+                if let Some(stack) = stack.as_mut() {
+                    *stack = cmp::min(*stack, sp);
+                }
+                None
+            }
+
+            Op::Pop(n) => {
+                sp += i64::from(n);
+                None
+            }
+
+            Op::AddSp(imm) => {
+                modifies_sp = true;
+
+                sp += i64::from(imm);
+                if let Some(stack) = stack.as_mut() {
+                    *stack = cmp::min(*stack, sp);
+                }
+                None
+            }
+
+            Op::SubSpRegister(reg) => {
+                modifies_sp = true;
+
+                match known[usize::from(reg)] {
+                    // a negative value deallocates stack
+                    Some(value) => {
+                        sp = sp.wrapping_sub(value as i64);
+                        if let Some(stack) = stack.as_mut() {
+                            *stack = cmp::min(*stack, sp);
+                        }
+                    }
+
+                    None => stack = None,
+                }
+                None
+            }
+
+            Op::WritesSp => {
+                modifies_sp = true;
+                None
+            }
+
+            Op::UnknownSp => {
+                modifies_sp = true;
+                stack = None;
+                None
+            }
+
+            Op::MovImm(reg, imm) => Some((reg, imm)),
+
+            Op::Lea(reg, disp) => Some((
+                reg,
+                start
+                    .wrapping_add(size as u64)
+                    .wrapping_add(i64::from(disp) as u64),
+            )),
+
+            Op::Return | Op::Other => None,
+        };
+
+        if let Some((reg, value)) = constant {
+            consts[usize::from(reg)] = Some(value);
+        }
+
+        // the next instruction can only be reached through a branch
+        if let Op::Jmp(_) | Op::JmpRegister(_) | Op::JmpMemory | Op::Return = op {
+            consts = [None; 16];
+        }
+    }
+
+    Ok(Analysis {
+        calls,
+        jmps,
+        resolved,
+        indirect,
+        modifies_sp,
+        stack: stack.map(|min| (-min) as u64),
+    })
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Analysis {
+    /// Offsets (relative to the start of the subroutine) of the targets of `CALL rel32`
+    /// instructions
+    pub calls: Vec<i64>,
+
+    /// Offsets (relative to the start of the subroutine) of the targets of `JMP` and `Jcc`
+    /// instructions
+    pub jmps: Vec<i64>,
+
+    /// Absolute addresses of the targets of `CALL reg` / `JMP reg` instructions that were resolved
+    /// using constant propagation (`lea reg, [rip + sym]` or `mov reg, imm`)
+    pub resolved: Vec<u64>,
+
+    /// Whether the subroutine performs an indirect function call we couldn't resolve
+    pub indirect: bool,
+
+    /// Whether the subroutine modifies the SP register
+    ///
+    /// NOTE the return address pushed by `CALL` is not included; this matches LLVM's stack usage
+    /// information
+    pub modifies_sp: bool,
+
+    /// Stack usage of the subroutine; `None` if the subroutine contains intra-branches
+    pub stack: Option<u64>,
+}
+
+// What an instruction does, as far as the analysis is concerned
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    // `JMP rel`; the displacement is relative to the next instruction
+    Jmp(i32),
+
+    // `Jcc rel`, `JRCXZ rel` and `LOOP rel`; the displacement is relative to the next instruction
+    Jcc(i32),
+
+    // `CALL rel32`; the displacement is relative to the next instruction
+    Call(i32),
+
+    // `CALL reg`
+    CallRegister(u8),
+
+    // `JMP reg`
+    JmpRegister(u8),
+
+    // `CALL [mem]`
+    CallMemory,
+
+    // `JMP [mem]`
+    JmpMemory,
+
+    // `RET`
+    Return,
+
+    // `PUSH`; number of bytes pushed onto the stack
+    Push(u32),
+
+    // `POP reg`; number of bytes popped off the stack
+    Pop(u32),
+
+    // `ADD rsp, imm` or `SUB rsp, imm`; the immediate is negated for `SUB`
+    AddSp(i32),
+
+    // `SUB rsp, reg`
+    SubSpRegister(u8),
+
+    // any other instruction that modifies RSP; e.g. `POP rbp` or `MOV rsp, rbp`
+    WritesSp,
+
+    // an instruction that modifies RSP by an unknown amount; e.g. `AND rsp, -32`
+    UnknownSp,
+
+    // `MOV r32, imm32` or `MOV r64, imm`
+    MovImm(u8, u64),
+
+    // `LEA reg, [rip + disp32]`
+    Lea(u8, i32),
+
+    Other,
+}
+
+// Integer registers written by an instruction
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Writes {
+    None,
+    Reg(u8),
+    // we don't track which registers this instruction writes to; assume all of them
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Instruction {
+    op: Op,
+    writes: Writes,
+    size: usize,
+}
+
+// size of the immediate operand of an instruction
+#[derive(Clone, Copy, PartialEq)]
+enum Imm {
+    None,
+    // 8 bits
+    B,
+    // 16 bits
+    W,
+    // 16 or 32 bits depending on the operand size
+    Z,
+    // 32 bits; the operand size prefix is ignored (`CALL rel32`, `JMP rel32` and `Jcc rel32`)
+    D,
+    // 16, 32 or 64 bits depending on the operand size (`MOV r64, imm64`)
+    V,
+    // `ENTER`: 16 + 8 bits
+    Enter,
+    // `MOV` to / from a 64-bit absolute address
+    Moffs,
+    // `TEST r/m, imm` in the `F6` and `F7` groups; only `/0` and `/1` have an immediate
+    Group3B,
+    Group3Z,
+}
+
+// attributes of an opcode: whether it's followed by a ModRM byte and the size of its immediate
+#[derive(Clone, Copy)]
+struct Attrs {
+    modrm: bool,
+    imm: Imm,
+}
+
+const fn attrs(modrm: bool, imm: Imm) -> Option<Attrs> {
+    Some(Attrs { modrm, imm })
+}
+
+const NONE: Option<Attrs> = attrs(false, Imm::None);
+const IB: Option<Attrs> = attrs(false, Imm::B);
+const IZ: Option<Attrs> = attrs(false, Imm::Z);
+const M: Option<Attrs> = attrs(true, Imm::None);
+const MIB: Option<Attrs> = attrs(true, Imm::B);
+const MIZ: Option<Attrs> = attrs(true, Imm::Z);
+// not valid in 64-bit mode, or a prefix handled before the opcode
+const INV: Option<Attrs> = None;
+
+// Table A-2 One-byte Opcode Map
+#[rustfmt::skip]
+const ONE_BYTE: [Option<Attrs>; 256] = [
+    // 0x00
+    M, M, M, M, IB, IZ, INV, INV, M, M, M, M, IB, IZ, INV, INV,
+    // 0x10
+    M, M, M, M, IB, IZ, INV, INV, M, M, M, M, IB, IZ, INV, INV,
+    // 0x20
+    M, M, M, M, IB, IZ, INV, INV, M, M, M, M, IB, IZ, INV, INV,
+    // 0x30
+    M, M, M, M, IB, IZ, INV, INV, M, M, M, M, IB, IZ, INV, INV,
+    // 0x40: REX prefixes
+    INV, INV, INV, INV, INV, INV, INV, INV, INV, INV, INV, INV, INV, INV, INV, INV,
+    // 0x50: PUSH / POP
+    NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE,
+    // 0x60
+    INV, INV, INV, M, INV, INV, INV, INV, IZ, MIZ, IB, MIB, NONE, NONE, NONE, NONE,
+    // 0x70: Jcc rel8
+    IB, IB, IB, IB, IB, IB, IB, IB, IB, IB, IB, IB, IB, IB, IB, IB,
+    // 0x80
+    MIB, MIZ, INV, MIB, M, M, M, M, M, M, M, M, M, M, M, M,
+    // 0x90
+    NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, INV, NONE, NONE, NONE, NONE, NONE,
+    // 0xa0
+    attrs(false, Imm::Moffs), attrs(false, Imm::Moffs), attrs(false, Imm::Moffs),
+    attrs(false, Imm::Moffs), NONE, NONE, NONE, NONE, IB, IZ, NONE, NONE, NONE, NONE, NONE, NONE,
+    // 0xb0: MOV reg, imm
+    IB, IB, IB, IB, IB, IB, IB, IB,
+    attrs(false, Imm::V), attrs(false, Imm::V), attrs(false, Imm::V), attrs(false, Imm::V),
+    attrs(false, Imm::V), attrs(false, Imm::V), attrs(false, Imm::V), attrs(false, Imm::V),
+    // 0xc0
+    MIB, MIB, attrs(false, Imm::W), NONE, INV, INV, MIB, MIZ,
+    attrs(false, Imm::Enter), NONE, attrs(false, Imm::W), NONE, NONE, IB, INV, NONE,
+    // 0xd0
+    M, M, M, M, INV, INV, INV, NONE, M, M, M, M, M, M, M, M,
+    // 0xe0: E8 and E9 always take a 32-bit displacement in 64-bit mode
+    IB, IB, IB, IB, IB, IB, IB, IB, attrs(false, Imm::D), attrs(false, Imm::D), INV, IB,
+    NONE, NONE, NONE, NONE,
+    // 0xf0
+    INV, NONE, INV, INV, NONE, NONE, attrs(true, Imm::Group3B), attrs(true, Imm::Group3Z),
+    NONE, NONE, NONE, NONE, NONE, NONE, M, M,
+];
+
+// Table A-3 Two-byte Opcode Map (first byte is 0FH)
+fn two_byte(opcode: u8) -> Option<Attrs> {
+    match opcode {
+        // SYSCALL, CLTS, SYSRET, INVD, WBINVD, UD2 and FEMMS
+        0x05..=0x09 | 0x0b | 0x0e => NONE,
+        // 3DNow!; the opcode is encoded as an immediate
+        0x0f => MIB,
+        // WRMSR, RDTSC, RDMSR, RDPMC, SYSENTER, SYSEXIT and GETSEC
+        0x30..=0x37 => NONE,
+        // three-byte opcode maps; handled by the caller
+        0x38 | 0x3a => INV,
+        // PSHUFx and the shift-by-immediate groups
+        0x70..=0x73 => MIB,
+        // EMMS
+        0x77 => NONE,
+        // Jcc rel32
+        0x80..=0x8f => attrs(false, Imm::D),
+        // PUSH / POP FS / GS and CPUID
+        0xa0..=0xa2 | 0xa8..=0xaa => NONE,
+        // SHLD / SHRD with an immediate count and the BT group
+        0xa4 | 0xac | 0xba => MIB,
+        // CMPPS, PINSRW, PEXTRW and SHUFPS
+        0xc2 | 0xc4..=0xc6 => MIB,
+        // BSWAP
+        0xc8..=0xcf => NONE,
+        _ => M,
+    }
+}
+
+fn decode(bytes: &[u8]) -> Option<Instruction> {
+    let mut i = 0;
+
+    // legacy prefixes
+    let mut opsize = false;
+    let mut addrsize = false;
+    loop {
+        match *bytes.get(i)? {
+            0x66 => opsize = true,
+            0x67 => addrsize = true,
+            0xf0 | 0xf2 | 0xf3 | 0x2e | 0x36 | 0x3e | 0x26 | 0x64 | 0x65 => {}
+            _ => break,
+        }
+
+        i += 1;
+
+        if i >= MAX_LENGTH {
+            return None;
+        }
+    }
+
+    // REX prefix
+    let mut rex = 0;
+    if let 0x40..=0x4f = *bytes.get(i)? {
+        rex = bytes[i];
+        i += 1;
+    }
+    let rex_w = rex & 0b1000 != 0;
+    let rex_r = (rex & 0b0100) << 1;
+    let rex_b = (rex & 0b0001) << 3;
+
+    let opcode = *bytes.get(i)?;
+    i += 1;
+
+    // opcode map: 0 = one-byte, 1 = `0F`, 2 = `0F 38`, 3 = `0F 3A`
+    let mut map = 0;
+    let attrs = match opcode {
+        // VEX and EVEX prefixes; these are always followed by an opcode and a ModRM byte
+        0xc4 | 0xc5 | 0x62 => {
+            let (map, len) = match opcode {
+                0xc4 => (bytes.get(i)? & 0b1_1111, 2),
+                0xc5 => (1, 1),
+                _ => (bytes.get(i)? & 0b111, 3),
+            };
+            i += len;
+
+            let opcode = *bytes.get(i)?;
+            i += 1;
+
+            let imm = match map {
+                1 => match two_byte(opcode) {
+                    Some(Attrs { imm: Imm::B, .. }) => Imm::B,
+                    _ => Imm::None,
+                },
+                3 => Imm::B,
+                _ => Imm::None,
+            };
+
+            // `VZEROUPPER` and `VZEROALL` are the only ones without a ModRM byte
+            let modrm = if map == 1 && opcode == 0x77 {
+                0
+            } else {
+                modrm_len(bytes.get(i..)?)?
+            };
+
+            // VEX / EVEX encoded instructions operate on vector registers with a few exceptions in
+            // the BMI extensions (e.g. `ANDN` and `SHLX`) so we don't track what they write to
+            let size = i + modrm + imm_len(imm, false, false, false);
+            return if size <= bytes.len() && size <= MAX_LENGTH {
+                Some(Instruction {
+                    op: Op::Other,
+                    writes: Writes::Unknown,
+                    size,
+                })
+            } else {
+                None
+            };
+        }
+
+        0x0f => {
+            let opcode = *bytes.get(i)?;
+            i += 1;
+
+            match opcode {
+                0x38 => {
+                    map = 2;
+                    i += 1;
+                    M
+                }
+
+                0x3a => {
+                    map = 3;
+                    i += 1;
+                    MIB
+                }
+
+                _ => {
+                    map = 1;
+                    two_byte(opcode)
+                }
+            }
+        }
+
+        _ => ONE_BYTE[usize::from(opcode)],
+    }?;
+    // the last opcode byte
+    let opcode = bytes[i - 1];
+
+    let modrm = if attrs.modrm {
+        let modrm = *bytes.get(i)?;
+        i += modrm_len(&bytes[i..])?;
+        Some(modrm)
+    } else {
+        None
+    };
+
+    let imm_start = i;
+    let imm = match attrs.imm {
+        // only `TEST` (`/0` and `/1`) has an immediate
+        Imm::Group3B | Imm::Group3Z if modrm? & 0b0011_0000 != 0 => Imm::None,
+        Imm::Group3B => Imm::B,
+        Imm::Group3Z => Imm::Z,
+        imm => imm,
+    };
+    i += imm_len(imm, opsize, addrsize, rex_w);
+
+    if i > bytes.len() || i > MAX_LENGTH {
+        return None;
+    }
+
+    // the immediate operand, sign extended
+    let imm = match &bytes[imm_start..i] {
+        [b] => i64::from(*b as i8),
+        [b0, b1] => i64::from(i16::from_le_bytes([*b0, *b1])),
+        [b0, b1, b2, b3] => i64::from(i32::from_le_bytes([*b0, *b1, *b2, *b3])),
+        [b0, b1, b2, b3, b4, b5, b6, b7] => {
+            i64::from_le_bytes([*b0, *b1, *b2, *b3, *b4, *b5, *b6, *b7])
+        }
+        // `ENTER`
+        _ => 0,
+    };
+
+    // fields of the ModRM byte, extended with the REX prefix
+    let (direct, reg, rm) = match modrm {
+        Some(modrm) => (
+            modrm >> 6 == 0b11,
+            ((modrm >> 3) & 0b111) | rex_r,
+            (modrm & 0b111) | rex_b,
+        ),
+        None => (false, 0, 0),
+    };
+    // the opcode extension of group opcodes (`/digit`)
+    let group = reg & 0b111;
+    // the register encoded in the low 3 bits of the opcode (e.g. `PUSH r64`)
+    let low = (opcode & 0b111) | rex_b;
+    // RIP-relative addressing: `[rip + disp32]`
+    let rip = modrm.map(|modrm| modrm & 0b1100_0111 == 0b0000_0101) == Some(true);
+
+    let (op, writes) = match (map, opcode) {
+        // JMP rel8 / rel32, Jcc rel8 and Jcc rel32
+        (0, 0xe9) | (0, 0xeb) => (Op::Jmp(imm as i32), Writes::None),
+        (0, 0x70..=0x7f) | (0, 0xe3) | (1, 0x80..=0x8f) => (Op::Jcc(imm as i32), Writes::None),
+
+        // LOOP, LOOPE and LOOPNE; these decrement RCX
+        (0, 0xe0..=0xe2) => (Op::Jcc(imm as i32), Writes::Reg(RCX)),
+
+        // CALL rel32; the callee may clobber any register
+        (0, 0xe8) => (Op::Call(imm as i32), Writes::Unknown),
+
+        // near and far RET
+        (0, 0xc2) | (0, 0xc3) | (0, 0xca) | (0, 0xcb) => (Op::Return, Writes::None),
+
+        // PUSH r64, PUSH imm and PUSHF
+        (0, 0x50..=0x57) | (0, 0x68) | (0, 0x6a) | (0, 0x9c) => {
+            (Op::Push(if opsize { 2 } else { 8 }), Writes::None)
+        }
+
+        // POP r64
+        (0, 0x58..=0x5f) if low == RSP => (Op::WritesSp, Writes::None),
+        (0, 0x58..=0x5f) => (Op::Pop(if opsize { 2 } else { 8 }), Writes::Reg(low)),
+
+        // ENTER
+        (0, 0xc8) => {
+            let size = u16::from_le_bytes([bytes[imm_start], bytes[imm_start + 1]]);
+            (Op::Push(8 + u32::from(size)), Writes::Reg(RBP))
+        }
+
+        // LEAVE
+        (0, 0xc9) => (Op::WritesSp, Writes::Reg(RBP)),
+
+        // ADD, OR, ADC, SBB, AND, SUB, XOR and CMP with a register operand
+        (0, 0x00..=0x3f) if attrs.modrm => {
+            let kind = opcode >> 3;
+            // `r/m, reg` or `reg, r/m`
+            let to_reg = opcode & 0b10 != 0;
+            let byte = opcode & 1 == 0;
+
+            let dst = if to_reg {
+                Some(reg)
+            } else if direct {
+                Some(rm)
+            } else {
+                None
+            };
+
+            match dst {
+                // CMP
+                _ if kind == 7 => (Op::Other, Writes::None),
+
+                // byte registers (e.g. `AH`) are not tracked
+                Some(_) if byte => (Op::Other, Writes::Unknown),
+
+                Some(RSP) => {
+                    let op = match kind {
+                        // SUB rsp, r64
+                        5 if direct => Op::SubSpRegister(if to_reg { rm } else { reg }),
+                        // ADD rsp, r64; deallocation
+                        0 => Op::WritesSp,
+                        // e.g. `AND rsp, rax`
+                        _ => Op::UnknownSp,
+                    };
+
+                    (op, Writes::None)
+                }
+
+                // XOR r32, r32; this zeroes the register
+                Some(dst) if kind == 6 && direct && reg == rm => {
+                    (Op::MovImm(dst, 0), Writes::Reg(dst))
+                }
+
+                Some(dst) => (Op::Other, Writes::Reg(dst)),
+
+                None => (Op::Other, Writes::None),
+            }
+        }
+
+        // group 1: ADD, OR, ADC, SBB, AND, SUB, XOR and CMP with an immediate operand
+        (0, 0x81) | (0, 0x83) if direct => match (group, rm) {
+            // CMP
+            (7, _) => (Op::Other, Writes::None),
+            (0, RSP) if rex_w => (Op::AddSp(imm as i32), Writes::None),
+            (5, RSP) if rex_w => (Op::AddSp((imm as i32).wrapping_neg()), Writes::None),
+            // e.g. `AND rsp, -32` used to realign the stack
+            (_, RSP) => (Op::UnknownSp, Writes::None),
+            _ => (Op::Other, Writes::Reg(rm)),
+        },
+        (0, 0x80..=0x83) => (
+            Op::Other,
+            if direct {
+                Writes::Unknown
+            } else {
+                Writes::None
+            },
+        ),
+
+        // MOV r/m64, r64 and MOV r64, r/m64
+        (0, 0x89) | (0, 0x8b) => {
+            let dst = if opcode == 0x8b {
+                Some(reg)
+            } else if direct {
+                Some(rm)
+            } else {
+                None
+            };
+
+            match dst {
+                // e.g. `MOV rsp, rbp`
+                Some(RSP) => (Op::WritesSp, Writes::None),
+                Some(dst) => (Op::Other, Writes::Reg(dst)),
+                None => (Op::Other, Writes::None),
+            }
+        }
+
+        // MOV r/m8, r8 and MOV r8, r/m8
+        (0, 0x88) | (0, 0x8a) => (
+            Op::Other,
+            if opcode == 0x8a || direct {
+                Writes::Unknown
+            } else {
+                Writes::None
+            },
+        ),
+
+        // LEA
+        (0, 0x8d) if reg == RSP => (Op::WritesSp, Writes::None),
+        (0, 0x8d) if rip => {
+            let b = &bytes[imm_start - 4..imm_start];
+            let disp = i32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+
+            (Op::Lea(reg, disp), Writes::Reg(reg))
+        }
+        (0, 0x8d) => (Op::Other, Writes::Reg(reg)),
+
+        // MOV r32, imm32 and MOV r64, imm64
+        (0, 0xb8..=0xbf) if low == RSP => (Op::WritesSp, Writes::None),
+        (0, 0xb8..=0xbf) if !opsize => {
+            let imm = if rex_w {
+                imm as u64
+            } else {
+                // 32-bit operations zero extend the result
+                u64::from(imm as u32)
+            };
+
+            (Op::MovImm(low, imm), Writes::Reg(low))
+        }
+
+        // MOV r/m, imm32
+        (0, 0xc7) if direct && rm == RSP => (Op::WritesSp, Writes::None),
+        (0, 0xc7) if direct => {
+            if !opsize {
+                // the 64-bit operation sign extends the immediate, the 32-bit one zero extends
+                // the result
+                let imm = if rex_w {
+                    imm as u64
+                } else {
+                    u64::from(imm as u32)
+                };
+                (Op::MovImm(rm, imm), Writes::Reg(rm))
+            } else {
+                (Op::Other, Writes::Reg(rm))
+            }
+        }
+        (0, 0xc7) => (Op::Other, Writes::None),
+
+        // group 5: INC, DEC, CALL, CALLF, JMP, JMPF and PUSH
+        (0, 0xff) => match group {
+            2 if direct => (Op::CallRegister(rm), Writes::Unknown),
+            2 | 3 => (Op::CallMemory, Writes::Unknown),
+            4 if direct => (Op::JmpRegister(rm), Writes::None),
+            4 | 5 => (Op::JmpMemory, Writes::None),
+            6 => (Op::Push(8), Writes::None),
+            _ if direct && rm == RSP => (Op::UnknownSp, Writes::None),
+            _ if direct => (Op::Other, Writes::Reg(rm)),
+            _ => (Op::Other, Writes::None),
+        },
+
+        // `INT3`, `HLT`, `NOP` and `UD2`
+        (0, 0xcc) | (0, 0xf4) | (0, 0x90) | (1, 0x0b) => (Op::Other, Writes::None),
+
+        // multi-byte NOP and the `ENDBR64` hint
+        (1, 0x1e) | (1, 0x1f) => (Op::Other, Writes::None),
+
+        // any other instruction; conservatively assume it clobbers all registers
+        _ => (Op::Other, Writes::Unknown),
+    };
+
+    Some(Instruction {
+        op,
+        writes,
+        size: i,
+    })
+}
+
+// length of the ModRM byte plus the SIB byte and the displacement, if present
+fn modrm_len(bytes: &[u8]) -> Option<usize> {
+    let modrm = *bytes.first()?;
+    let mode = modrm >> 6;
+    let rm = modrm & 0b111;
+
+    if mode == 0b11 {
+        return Some(1);
+    }
+
+    let mut len = 1;
+    let mut base = rm;
+    if rm == 0b100 {
+        // SIB byte
+        base = *bytes.get(1)? & 0b111;
+        len += 1;
+    }
+
+    len += match mode {
+        // `[rip + disp32]` or, with a SIB byte, `[index * scale + disp32]`
+        0b00 if base == 0b101 => 4,
+        0b00 => 0,
+        0b01 => 1,
+        _ => 4,
+    };
+
+    Some(len)
+}
+
+fn imm_len(imm: Imm, opsize: bool, addrsize: bool, rex_w: bool) -> usize {
+    match imm {
+        Imm::None | Imm::Group3B | Imm::Group3Z => 0,
+        Imm::B => 1,
+        Imm::W => 2,
+        Imm::Z if opsize => 2,
+        Imm::Z | Imm::D => 4,
+        Imm::V if rex_w => 8,
+        Imm::V if opsize => 2,
+        Imm::V => 4,
+        Imm::Enter => 3,
+        Imm::Moffs if addrsize => 4,
+        Imm::Moffs => 8,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    // the instruction at `address` could not be decoded
+    UnknownInstruction { address: u64, encoding: String },
+}
+
+impl Error {
+    fn unknown(bytes: &[u8], address: u64) -> Self {
+        let encoding = bytes
+            .iter()
+            .take(4)
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Error::UnknownInstruction { address, encoding }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownInstruction { address, encoding } => write!(
+                f,
+                "unknown instruction {} at address {:#010x}",
+                encoding, address
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn sanity() {
+        // e8 10 00 00 00          call    0x15
+        // e9 f0 ff ff ff          jmp     -0x6
+        // 75 e0                   jne     -0x14
+        // c3                      ret
+        let branches = super::analyze(
+            &[
+                0xe8, 0x10, 0x00, 0x00, 0x00, 0xe9, 0xf0, 0xff, 0xff, 0xff, 0x75, 0xe0, 0xc3,
+            ],
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            branches,
+            super::Analysis {
+                calls: vec![5 + 0x10],
+                jmps: vec![10 - 0x10, 12 - 0x20],
+                resolved: vec![],
+                indirect: false,
+                modifies_sp: false,
+                stack: Some(0),
+            }
+        );
+
+        // 90                      nop
+        // 90                      nop
+        // eb fc                   jmp     0x0
+        let looping = super::analyze(&[0x90, 0x90, 0xeb, 0xfc], 0).unwrap();
+        assert_eq!(looping.jmps, vec![0]);
+        assert_eq!(looping.stack, None);
+
+        // `06` (`PUSH ES`) is not valid in 64-bit mode
+        assert_eq!(
+            super::analyze(&[0x90, 0x06, 0xc3], 0x100),
+            Err(super::Error::UnknownInstruction {
+                address: 0x101,
+                encoding: "06 c3".to_owned(),
+            })
+        );
+
+        // truncated `CALL rel32`
+        assert!(super::analyze(&[0xe8, 0x00, 0x00], 0).is_err());
+    }
+
+    #[test]
+    fn constants() {
+        // 48 8d 05 00 01 00 00    lea     rax, [rip + 0x100]
+        // ff d0                   call    rax
+        let lea = super::analyze(
+            &[0x48, 0x8d, 0x05, 0x00, 0x01, 0x00, 0x00, 0xff, 0xd0],
+            0x1000,
+        )
+        .unwrap();
+        assert!(!lea.indirect);
+        assert_eq!(lea.resolved, vec![0x1107]);
+
+        // the same code above 4 GiB (e.g. a position independent executable)
+        let high = super::analyze(
+            &[0x48, 0x8d, 0x05, 0x00, 0x01, 0x00, 0x00, 0xff, 0xd0],
+            0x5555_5555_1000,
+        )
+        .unwrap();
+        assert_eq!(high.resolved, vec![0x5555_5555_1107]);
+
+        // b8 00 20 00 00          mov     eax, 0x2000
+        // ff d0                   call    rax
+        // ff d0                   call    rax
+        let clobbered =
+            super::analyze(&[0xb8, 0x00, 0x20, 0x00, 0x00, 0xff, 0xd0, 0xff, 0xd0], 0).unwrap();
+        assert_eq!(clobbered.resolved, vec![0x2000]);
+        // the first call may have modified `rax`
+        assert!(clobbered.indirect);
+
+        // b8 00 20 00 00          mov     eax, 0x2000
+        // 85 ff                   test    edi, edi
+        // 74 05                   je      0xe
+        // b8 00 30 00 00          mov     eax, 0x3000
+        // ff d0                   call    rax
+        let join = super::analyze(
+            &[
+                0xb8, 0x00, 0x20, 0x00, 0x00, 0x85, 0xff, 0x74, 0x05, 0xb8, 0x00, 0x30, 0x00, 0x00,
+                0xff, 0xd0,
+            ],
+            0,
+        )
+        .unwrap();
+        assert!(join.indirect);
+        assert!(join.resolved.is_empty());
+
+        // 48 8b 07                mov     rax, qword ptr [rdi]
+        // ff d0                   call    rax
+        let load = super::analyze(&[0x48, 0x8b, 0x07, 0xff, 0xd0], 0).unwrap();
+        assert!(load.indirect);
+        assert!(load.resolved.is_empty());
+
+        // ff 15 10 00 00 00       call    qword ptr [rip + 0x10]
+        let memory = super::analyze(&[0xff, 0x15, 0x10, 0x00, 0x00, 0x00], 0).unwrap();
+        assert!(memory.indirect);
+
+        // ff e0                   jmp     rax
+        let jump_table = super::analyze(&[0xff, 0xe0], 0).unwrap();
+        assert!(jump_table.indirect);
+        assert_eq!(jump_table.stack, None);
+    }
+
+    #[test]
+    fn modifies_sp() {
+        // 90                      nop
+        // 48 89 e5                mov     rbp, rsp
+        let nop = super::analyze(&[0x90, 0x48, 0x89, 0xe5], 0).unwrap();
+        assert!(!nop.modifies_sp);
+        assert_eq!(nop.stack, Some(0));
+
+        // 55                      push    rbp
+        // 41 57                   push    r15
+        // 48 83 ec 18             sub     rsp, 0x18
+        // 48 83 c4 18             add     rsp, 0x18
+        // 41 5f                   pop     r15
+        // 5d                      pop     rbp
+        // c3                      ret
+        let frame = super::analyze(
+            &[
+                0x55, 0x41, 0x57, 0x48, 0x83, 0xec, 0x18, 0x48, 0x83, 0xc4, 0x18, 0x41, 0x5f, 0x5d,
+                0xc3,
+            ],
+            0,
+        )
+        .unwrap();
+        assert!(frame.modifies_sp);
+        assert_eq!(frame.stack, Some(8 + 8 + 0x18));
+
+        // 55                      push    rbp
+        // 5d                      pop     rbp
+        // 48 83 ec 18             sub     rsp, 0x18
+        // 48 83 c4 18             add     rsp, 0x18
+        // 48 83 ec 08             sub     rsp, 0x8
+        let peak = super::analyze(
+            &[
+                0x55, 0x5d, 0x48, 0x83, 0xec, 0x18, 0x48, 0x83, 0xc4, 0x18, 0x48, 0x83, 0xec, 0x08,
+            ],
+            0,
+        )
+        .unwrap();
+        assert_eq!(peak.stack, Some(0x18));
+
+        // 48 81 ec 88 00 00 00    sub     rsp, 0x88
+        let imm32 = super::analyze(&[0x48, 0x81, 0xec, 0x88, 0x00, 0x00, 0x00], 0).unwrap();
+        assert_eq!(imm32.stack, Some(0x88));
+
+        // b8 00 20 00 00          mov     eax, 0x2000
+        // 48 29 c4                sub     rsp, rax
+        let register =
+            super::analyze(&[0xb8, 0x00, 0x20, 0x00, 0x00, 0x48, 0x29, 0xc4], 0).unwrap();
+        assert_eq!(register.stack, Some(0x2000));
+
+        // 48 29 c4                sub     rsp, rax
+        let dynamic = super::analyze(&[0x48, 0x29, 0xc4], 0).unwrap();
+        assert!(dynamic.modifies_sp);
+        assert_eq!(dynamic.stack, None);
+
+        // 48 83 e4 e0             and     rsp, -0x20
+        let realign = super::analyze(&[0x48, 0x83, 0xe4, 0xe0], 0).unwrap();
+        assert!(realign.modifies_sp);
+        assert_eq!(realign.stack, None);
+
+        // 48 89 ec                mov     rsp, rbp
+        // c9                      leave
+        let epilogue = super::analyze(&[0x48, 0x89, 0xec, 0xc9], 0).unwrap();
+        assert!(epilogue.modifies_sp);
+        assert_eq!(epilogue.stack, Some(0));
+    }
+}